@@ -0,0 +1,11 @@
+//! Compute Web Worker: CSV parsing and widget data transforms
+//!
+//! Built by Trunk as a worker (`data-type="worker"` in index.html) and
+//! started by the app as `compute_worker_loader.js`.
+
+fn main() {
+    _ = console_log::init_with_level(log::Level::Debug);
+    console_error_panic_hook::set_once();
+
+    dashboard_studio_rs::run_compute_worker();
+}
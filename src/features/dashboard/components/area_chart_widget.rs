@@ -1,6 +1,5 @@
 use crate::features::dashboard::{
-    config::builders::AreaConfig, config::style::AreaStyleOptions,
-    config::traits::WidgetConfigBuilder, DashboardContext,
+    compute::use_chart_options, config::builders::AreaConfig, DashboardContext, WidgetType,
 };
 use crate::ui::molecules::EChartsWrapper;
 use leptos::prelude::*;
@@ -39,38 +38,23 @@ pub fn AreaChartWidget(
             .find(|w| w.id == widget_id)
     });
 
-    // Generate ECharts options using AreaConfig builder
-    let options = Memo::new(move |_| {
-        widget.get().and_then(|w| {
-            // Get active dataset
-            let active_dataset = dashboard.get_datasets().into_iter().find(|ds| ds.active)?;
-
-            // Parse style options from JSON
-            let style_options: AreaStyleOptions =
-                serde_json::from_str(&w.chart_config.style_options)
-                    .unwrap_or_else(|_| AreaStyleOptions::default());
-
-            // Build ECharts options using AreaConfig
-            AreaConfig
-                .build_echarts_options(
-                    &active_dataset,
-                    &w.chart_config.data_mapping,
-                    &style_options,
-                )
-                .ok()
-        })
-    });
+    // Build ECharts options; the data is computed in the compute worker
+    let chart = use_chart_options(AreaConfig, WidgetType::Area, widget);
+    let options = chart.options;
 
     view! {
         {move || {
             let has_widget = widget.get().is_some();
-            let has_options = options.get().is_some();
+            let has_options = chart.has_chart();
             if has_widget && has_options {
                 Some(
                     view! {
                         <EChartsWrapper
                             options=Signal::derive(move || options.get().unwrap_or_default())
                             class="".to_string()
+                            loading=chart.loading
+                            on_event=chart.on_event
+                            selected=chart.selected
                         />
                     }
                         .into_any(),
@@ -0,0 +1,263 @@
+use crate::features::dashboard::{
+    data::filter::{Filter, FilterOperator},
+    data::transform::value_label,
+    DashboardContext, WidgetType,
+};
+use leptos::prelude::*;
+use serde_json::Value;
+
+/// What a control offers, read from its field's column
+#[derive(Clone, Debug, PartialEq)]
+enum ControlDomain {
+    /// Distinct values, sorted by label
+    Values(Vec<(String, Value)>),
+    /// Smallest and largest number
+    Numbers { min: f64, max: f64 },
+    /// First and last `YYYY-MM-DD` date
+    Dates { min: String, max: String },
+    /// Free text
+    Text,
+}
+
+/// Filter Control Widget - dashboard-wide filter input (no ECharts)
+///
+/// Dropdown, date range, range slider or search box on the field chosen
+/// in the Data tab. Its selection is stored in the dashboard's global
+/// filters and narrows every widget whose dataset has that field.
+///
+/// # Example
+/// ```rust
+/// view! {
+///     <FilterControlWidget widget_id="widget_region_filter" />
+/// }
+/// ```
+#[component]
+pub fn FilterControlWidget(
+    /// Widget ID to fetch from DashboardContext
+    #[prop(into)]
+    widget_id: String,
+) -> impl IntoView {
+    let dashboard = DashboardContext::use_context();
+    let control_id = StoredValue::new(widget_id.clone());
+
+    // Get widget configuration from context
+    let widget = Memo::new(move |_| {
+        dashboard
+            .get_widgets()
+            .into_iter()
+            .find(|w| w.id == widget_id)
+    });
+
+    // Control type and field
+    let control = Memo::new(move |_| {
+        widget.with(|w| {
+            w.as_ref()
+                .and_then(|w| Some((w.widget_type, w.chart_config.data_mapping.filter_field.clone()?)))
+        })
+    });
+
+    // Values offered, from the control's dataset
+    let domain = Memo::new(move |_| {
+        let (widget_type, field) = control.get()?;
+        let config = widget.with(|w| w.as_ref().map(|w| w.chart_config.clone()))?;
+        dashboard.datasets.with(|datasets| {
+            let dataset = config.dataset(datasets)?;
+            let idx = dataset.fields.iter().position(|f| f.name == field)?;
+            let column = dataset.data.column(idx);
+            let domain = match widget_type {
+                WidgetType::FilterDropdown => {
+                    let mut values: Vec<(String, Value)> = column
+                        .group_rows()
+                        .keys
+                        .into_iter()
+                        .filter_map(|value| Some((value_label(&value)?, value)))
+                        .collect();
+                    values.sort_by(|a, b| a.0.cmp(&b.0));
+                    ControlDomain::Values(values)
+                }
+                WidgetType::FilterRange => {
+                    let numbers = (0..column.len()).filter_map(|row| column.number(row));
+                    let (min, max) = numbers.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), n| {
+                        (min.min(n), max.max(n))
+                    });
+                    if min > max {
+                        return None;
+                    }
+                    ControlDomain::Numbers { min, max }
+                }
+                WidgetType::FilterDateRange => {
+                    let mut dates = (0..column.len())
+                        .filter_map(|row| column.value(row).as_str().and_then(|date| date.get(..10)).map(str::to_string));
+                    let first = dates.next()?;
+                    let (min, max) = dates.fold((first.clone(), first), |(min, max), date| {
+                        (min.min(date.clone()), max.max(date))
+                    });
+                    ControlDomain::Dates { min, max }
+                }
+                _ => ControlDomain::Text,
+            };
+            Some(domain)
+        })
+    });
+
+    // Current condition of this control
+    let current = Memo::new(move |_| {
+        dashboard
+            .global_filters
+            .with(|filters| control_id.with_value(|id| filters.get(id).cloned()))
+    });
+    let operand = move |idx: usize| current.with(|filter| filter.as_ref().and_then(|f| f.values.get(idx).cloned()));
+
+    let set_filter = move |operator: FilterOperator, values: Option<Vec<Value>>| {
+        let filter = control.get_untracked().zip(values).map(|((_, field), values)| Filter {
+            field,
+            operator,
+            values,
+        });
+        control_id.with_value(|id| dashboard.set_global_filter(id, filter));
+    };
+
+    view! {
+        <div class="w-full h-full flex flex-col justify-center gap-2 p-4">
+            {move || match domain.get() {
+                None => {
+                    view! {
+                        <div class="text-center">
+                            <p class="text-sm text-base-content/60 font-medium">"No field selected"</p>
+                            <p class="text-xs text-base-content/40 mt-1">
+                                "Choose the field to filter in the Data tab"
+                            </p>
+                        </div>
+                    }
+                        .into_any()
+                }
+                Some(ControlDomain::Values(values)) => {
+                    let selected = operand(0);
+                    let options = values.clone();
+                    view! {
+                        <select
+                            class="select select-bordered select-sm w-full"
+                            on:change=move |ev| {
+                                let value = event_target_value(&ev)
+                                    .parse::<usize>()
+                                    .ok()
+                                    .and_then(|idx| options.get(idx))
+                                    .map(|(_, value)| vec![value.clone()]);
+                                set_filter(FilterOperator::In, value);
+                            }
+                        >
+                            <option value="" selected=selected.is_none()>"All"</option>
+                            {values
+                                .into_iter()
+                                .enumerate()
+                                .map(|(idx, (label, value))| {
+                                    view! {
+                                        <option value=idx.to_string() selected=selected.as_ref() == Some(&value)>
+                                            {label}
+                                        </option>
+                                    }
+                                })
+                                .collect::<Vec<_>>()}
+                        </select>
+                    }
+                        .into_any()
+                }
+                Some(ControlDomain::Numbers { min, max }) => {
+                    let low = operand(0).and_then(|v| v.as_f64()).unwrap_or(min);
+                    let high = operand(1).and_then(|v| v.as_f64()).unwrap_or(max);
+                    let step = if max > min { (max - min) / 100.0 } else { 1.0 };
+                    // Moving either handle back to the bounds clears the filter
+                    let update = move |low: f64, high: f64| {
+                        let values = (low > min || high < max)
+                            .then(|| vec![Value::from(low.min(high)), Value::from(high.max(low))]);
+                        set_filter(FilterOperator::Between, values);
+                    };
+                    view! {
+                        <div class="flex justify-between text-xs text-base-content/70">
+                            <span>{format!("{}", low)}</span>
+                            <span>{format!("{}", high)}</span>
+                        </div>
+                        <input
+                            type="range"
+                            class="range range-xs range-primary"
+                            min=min
+                            max=max
+                            step=step
+                            value=low
+                            on:change=move |ev| {
+                                let low = event_target_value(&ev).parse().unwrap_or(min);
+                                update(low, high);
+                            }
+                        />
+                        <input
+                            type="range"
+                            class="range range-xs range-primary"
+                            min=min
+                            max=max
+                            step=step
+                            value=high
+                            on:change=move |ev| {
+                                let high = event_target_value(&ev).parse().unwrap_or(max);
+                                update(low, high);
+                            }
+                        />
+                    }
+                        .into_any()
+                }
+                Some(ControlDomain::Dates { min, max }) => {
+                    let date = move |idx: usize| operand(idx).and_then(|v| v.as_str().map(str::to_string)).unwrap_or_default();
+                    let (from, to) = (date(0), date(1));
+                    let update = move |from: String, to: String| {
+                        let bound = |date: &str| if date.is_empty() { Value::Null } else { Value::from(date) };
+                        let values = (!from.is_empty() || !to.is_empty()).then(|| vec![bound(&from), bound(&to)]);
+                        set_filter(FilterOperator::DateRange, values);
+                    };
+                    let to_for_from = to.clone();
+                    let from_for_to = from.clone();
+                    view! {
+                        <div class="flex items-center gap-2">
+                            <input
+                                type="date"
+                                class="input input-bordered input-sm flex-1 min-w-0"
+                                min=min.clone()
+                                max=max.clone()
+                                value=from
+                                on:change=move |ev| update(event_target_value(&ev), to_for_from.clone())
+                            />
+                            <span class="text-xs text-base-content/50">"to"</span>
+                            <input
+                                type="date"
+                                class="input input-bordered input-sm flex-1 min-w-0"
+                                min=min
+                                max=max
+                                value=to
+                                on:change=move |ev| update(from_for_to.clone(), event_target_value(&ev))
+                            />
+                        </div>
+                    }
+                        .into_any()
+                }
+                Some(ControlDomain::Text) => {
+                    let text = operand(0).and_then(|v| v.as_str().map(str::to_string)).unwrap_or_default();
+                    view! {
+                        <label class="input input-bordered input-sm flex items-center gap-2 w-full">
+                            <span class="icon-[lucide--search] w-4 h-4 text-base-content/40"></span>
+                            <input
+                                type="search"
+                                class="grow"
+                                placeholder="Search..."
+                                value=text
+                                on:change=move |ev| {
+                                    let text = event_target_value(&ev);
+                                    let values = (!text.trim().is_empty()).then(|| vec![Value::from(text.trim())]);
+                                    set_filter(FilterOperator::Contains, values);
+                                }
+                            />
+                        </label>
+                    }
+                        .into_any()
+                }
+            }}
+        </div>
+    }
+}
@@ -1,8 +1,7 @@
 use crate::features::dashboard::{
     config::style::KpiStyleOptions,
     DashboardContext,
-    kpi_aggregation::calculate_kpi,
-    models::KpiAggregation,
+    kpi_aggregation::{calculate_kpi, calculate_window_kpi},
 };
 use crate::ui::atoms::{Icon, IconName};
 use leptos::prelude::*;
@@ -44,16 +43,23 @@ pub fn KpiWidget(
     // Calculate KPI value from real data
     let kpi_result = Memo::new(move |_| {
         widget.get().and_then(|w| {
-            // Get active dataset
-            let active_dataset = dashboard.get_datasets().into_iter().find(|ds| ds.active)?;
+            // Get the widget's dataset
+            let datasets = dashboard.get_datasets();
+            let dataset = w.chart_config.dataset(&datasets)?;
 
             // Get KPI field and aggregation from data mapping
             let kpi_field = w.chart_config.data_mapping.kpi_field.as_ref()?;
-            let aggregation = w.chart_config.data_mapping.kpi_aggregation
-                .unwrap_or(KpiAggregation::Sum);
+            let aggregation = w.chart_config.data_mapping.kpi_aggregation.clone().unwrap_or_default();
 
-            // Calculate KPI value
-            calculate_kpi(&active_dataset, kpi_field, aggregation)
+            // Calculate KPI value over the rows passing the widget's and the
+            // dashboard's filters; window calculations run over the periods
+            // of the x-axis field
+            let mapping = w.chart_config.data_mapping.with_global_filters(&dashboard.dashboard_filters(&w.id));
+            let filtered = mapping.filters.apply(dataset);
+            match mapping.calculation(kpi_field).filter(|_| mapping.x_axis.is_some()) {
+                Some(calculation) => calculate_window_kpi(&filtered, &mapping, kpi_field, aggregation, calculation),
+                None => calculate_kpi(&filtered, kpi_field, aggregation),
+            }
         })
     });
 
@@ -13,6 +13,7 @@ pub mod scatter_widget;
 pub mod radar_widget;
 pub mod heatmap_widget;
 pub mod treemap_widget;
+pub mod filter_control_widget;
 
 // Sidebar components (modularized)
 pub mod sidebar;
@@ -28,4 +29,5 @@ pub use scatter_widget::*;
 pub use radar_widget::*;
 pub use heatmap_widget::*;
 pub use treemap_widget::*;
+pub use filter_control_widget::*;
 pub use sidebar::LeftSidebar;
@@ -1,6 +1,5 @@
 use crate::features::dashboard::{
-    config::builders::RadarConfig, config::style::RadarStyleOptions,
-    config::traits::WidgetConfigBuilder, DashboardContext,
+    compute::use_chart_options, config::builders::RadarConfig, DashboardContext, WidgetType,
 };
 use crate::ui::molecules::EChartsWrapper;
 use leptos::prelude::*;
@@ -30,32 +29,19 @@ pub fn RadarWidget(
             .find(|w| w.id == widget_id)
     });
 
-    let options = Memo::new(move |_| {
-        widget.get().and_then(|w| {
-            let active_dataset = dashboard.get_datasets().into_iter().find(|ds| ds.active)?;
-
-            let style_options: RadarStyleOptions =
-                serde_json::from_str(&w.chart_config.style_options)
-                    .unwrap_or_else(|_| RadarStyleOptions::default());
-
-            RadarConfig
-                .build_echarts_options(
-                    &active_dataset,
-                    &w.chart_config.data_mapping,
-                    &style_options,
-                )
-                .ok()
-        })
-    });
+    // Build ECharts options; the data is computed in the compute worker
+    let chart = use_chart_options(RadarConfig, WidgetType::Radar, widget);
+    let options = chart.options;
 
     view! {
         {move || {
-            if widget.get().is_some() && options.get().is_some() {
+            if widget.get().is_some() && chart.has_chart() {
                 Some(
                     view! {
                         <EChartsWrapper
                             options=Signal::derive(move || options.get().unwrap_or_default())
                             class="".to_string()
+                            loading=chart.loading
                         />
                     }.into_any(),
                 )
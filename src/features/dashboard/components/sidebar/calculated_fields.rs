@@ -0,0 +1,117 @@
+use crate::features::dashboard::DashboardContext;
+use crate::ui::atoms::{Icon, IconName};
+use leptos::ev::MouseEvent;
+use leptos::prelude::*;
+
+/// Calculated fields section component
+///
+/// Lists the calculated fields of the active dataset and adds new ones
+/// from an expression (e.g. `Revenue - Cost`). Expression errors are shown
+/// under the editor with the position they occur at.
+///
+/// # Example
+/// ```rust
+/// view! {
+///     <CalculatedFieldsSection />
+/// }
+/// ```
+#[component]
+pub fn CalculatedFieldsSection() -> impl IntoView {
+    let dashboard = DashboardContext::use_context();
+
+    let (name, set_name) = signal(String::new());
+    let (expression, set_expression) = signal(String::new());
+    let (error, set_error) = signal(None::<String>);
+
+    let active_dataset = Memo::new(move |_| dashboard.get_active_dataset());
+
+    let on_add = move |_: MouseEvent| {
+        let Some(dataset) = active_dataset.get_untracked() else {
+            return;
+        };
+        match dashboard.add_calculated_field(&dataset.id, &name.get_untracked(), &expression.get_untracked()) {
+            Ok(()) => {
+                set_name.set(String::new());
+                set_expression.set(String::new());
+                set_error.set(None);
+            }
+            Err(e) => set_error.set(Some(e.to_string())),
+        }
+    };
+
+    view! {
+        {move || {
+            active_dataset.get().map(|dataset| {
+                let dataset_id = dataset.id.clone();
+                view! {
+                    <div class="flex flex-col gap-2">
+                        <h3 class="px-1 text-xs font-bold text-base-content/60 uppercase tracking-wider">
+                            "Calculated Fields"
+                        </h3>
+
+                        // Existing fields
+                        {dataset
+                            .calculated_fields
+                            .iter()
+                            .map(|field| {
+                                let field_name = field.name.clone();
+                                let dataset_id = dataset_id.clone();
+                                let field_type = dataset
+                                    .fields
+                                    .iter()
+                                    .find(|f| f.name == field.name)
+                                    .map(|f| f.field_type.display_name())
+                                    .unwrap_or_default();
+                                view! {
+                                    <div class="flex items-start gap-2 px-2 py-1 rounded hover:bg-base-200 group">
+                                        <div class="flex flex-col flex-1 min-w-0">
+                                            <span class="text-sm font-medium text-base-content truncate">
+                                                {field.name.clone()}
+                                                <span class="ml-1 text-xs text-base-content/40">{field_type}</span>
+                                            </span>
+                                            <code class="text-xs text-base-content/60 truncate">
+                                                {field.expression.clone()}
+                                            </code>
+                                        </div>
+                                        <button
+                                            class="opacity-0 group-hover:opacity-100 text-base-content/40 hover:text-error transition-opacity"
+                                            title="Remove calculated field"
+                                            on:click=move |_| dashboard.remove_calculated_field(&dataset_id, &field_name)
+                                        >
+                                            <Icon name=IconName::Delete class="w-4 h-4" />
+                                        </button>
+                                    </div>
+                                }
+                            })
+                            .collect::<Vec<_>>()}
+
+                        // New field
+                        <input
+                            class="input input-sm input-bordered w-full"
+                            placeholder="Field name"
+                            prop:value=move || name.get()
+                            on:input=move |ev| set_name.set(event_target_value(&ev))
+                        />
+                        <textarea
+                            class="textarea textarea-bordered textarea-sm w-full font-mono"
+                            rows="2"
+                            placeholder="e.g. Revenue - Cost"
+                            prop:value=move || expression.get()
+                            on:input=move |ev| {
+                                set_expression.set(event_target_value(&ev));
+                                set_error.set(None);
+                            }
+                        ></textarea>
+                        {move || error.get().map(|message| view! {
+                            <p class="text-xs text-error">{message}</p>
+                        })}
+                        <button class="btn btn-sm btn-ghost gap-2 justify-start" on:click=on_add>
+                            <Icon name=IconName::Add class="w-4 h-4" />
+                            <span class="text-xs font-semibold">"Add Calculated Field"</span>
+                        </button>
+                    </div>
+                }
+            })
+        }}
+    }
+}
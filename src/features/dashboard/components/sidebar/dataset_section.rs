@@ -1,4 +1,5 @@
 use crate::features::dashboard::csv_upload::CsvUploadManager;
+use crate::features::dashboard::data::replace::ReplaceReport;
 use crate::features::dashboard::DashboardContext;
 use crate::ui::atoms::{Icon, IconName};
 use crate::ui::molecules::{DatasetItem, UploadZone};
@@ -9,6 +10,9 @@ use leptos::prelude::*;
 /// Dataset section component
 ///
 /// Displays CSV upload zone and list of uploaded datasets with activation.
+/// The data of an imported dataset can be replaced by a new file; the
+/// schema changes and the widgets left with missing fields are then
+/// reported.
 ///
 /// # Example
 /// ```rust
@@ -50,7 +54,7 @@ pub fn DatasetSection(
                 upload_manager.as_ref().map(|manager| {
                     view! {
                         <UploadZone
-                            title=String::from("Upload CSV")
+                            title=String::from("Upload CSV, JSON, Excel or Parquet")
                             subtitle=String::from("or drag and drop")
                             upload_manager=Some(*manager)
                         />
@@ -58,6 +62,12 @@ pub fn DatasetSection(
                 })
             }}
 
+            // Outcome of the latest data replacement
+            {move || {
+                let manager = upload_manager?;
+                manager.replace_report().map(|report| view! { <ReplaceReportCard report=report manager=manager /> })
+            }}
+
             // Dataset items list
             <div class="flex flex-col gap-1 mt-1">
                 {move || {
@@ -69,6 +79,14 @@ pub fn DatasetSection(
                             let on_delete = Callback::new(move |_: MouseEvent| {
                                 // TODO: Delete dataset
                             });
+                            // Derived datasets are computed from their sources
+                            let imported = dashboard.datasets.with_untracked(|datasets| {
+                                datasets.iter().any(|d| d.id == dataset.id && d.derived_from.is_none())
+                            });
+                            let replace_id = dataset.id.clone();
+                            let on_replace = upload_manager.filter(|_| imported).map(|manager| {
+                                Callback::new(move |_: MouseEvent| manager.select_replacement(replace_id.clone()))
+                            });
                             let on_click = Callback::new(move |_: MouseEvent| {
                                 dashboard.set_active_dataset(Some(dataset_id.clone()));
                             });
@@ -81,6 +99,7 @@ pub fn DatasetSection(
                                         active=dataset.active
                                         show_delete=dataset.active
                                         on_delete=on_delete
+                                        on_replace=on_replace
                                         on_click=on_click
                                     />
                                 </div>
@@ -92,3 +111,52 @@ pub fn DatasetSection(
         </div>
     }
 }
+
+/// Schema changes and broken widgets after replacing a dataset's data
+#[component]
+fn ReplaceReportCard(report: ReplaceReport, manager: CsvUploadManager) -> impl IntoView {
+    let diff = &report.diff;
+    let mut changes: Vec<String> = Vec::new();
+    if !diff.added.is_empty() {
+        changes.push(format!("Added: {}", diff.added.join(", ")));
+    }
+    if !diff.removed.is_empty() {
+        changes.push(format!("Removed: {}", diff.removed.join(", ")));
+    }
+    if !diff.renamed.is_empty() {
+        let renamed: Vec<String> = diff.renamed.iter().map(|r| format!("{} → {}", r.from, r.to)).collect();
+        changes.push(format!("Renamed: {}", renamed.join(", ")));
+    }
+    if !diff.retyped.is_empty() {
+        let retyped: Vec<String> = diff
+            .retyped
+            .iter()
+            .map(|r| format!("{} ({} → {})", r.name, r.from.display_name(), r.to.display_name()))
+            .collect();
+        changes.push(format!("Retyped: {}", retyped.join(", ")));
+    }
+    if changes.is_empty() {
+        changes.push("Same fields".to_string());
+    }
+    if !report.repaired.is_empty() {
+        changes.push(format!("Repaired: {}", report.repaired.join(", ")));
+    }
+    let alert = if report.broken.is_empty() { "alert alert-success" } else { "alert alert-warning" };
+
+    view! {
+        <div class=format!("{} flex flex-col items-start gap-1 text-xs py-2", alert)>
+            <div class="flex items-center w-full">
+                <span class="font-semibold flex-1 truncate">{format!("Data of {} replaced", report.dataset_name)}</span>
+                <button class="opacity-60 hover:opacity-100" title="Dismiss" on:click=move |_| manager.dismiss_report()>
+                    <Icon name=IconName::Close class="w-4 h-4" />
+                </button>
+            </div>
+            {changes.into_iter().map(|change| view! { <span>{change}</span> }).collect::<Vec<_>>()}
+            {report
+                .broken
+                .into_iter()
+                .map(|breakage| view! { <span class="font-medium">{breakage.to_string()}</span> })
+                .collect::<Vec<_>>()}
+        </div>
+    }
+}
@@ -0,0 +1,273 @@
+use crate::features::dashboard::data::derived::DerivedFrom;
+use crate::features::dashboard::data::join::{Join, JoinKey, JoinKind, JoinStats};
+use crate::features::dashboard::{DashboardContext, Dataset};
+use crate::ui::atoms::{Icon, IconName};
+use leptos::ev::MouseEvent;
+use leptos::prelude::*;
+
+/// Join section component
+///
+/// Joins the active dataset (left) with another one (right) on one or
+/// more key pairs. Row and unmatched counts are previewed while the join
+/// is set up, and shown for the active dataset when it is a join. The
+/// joined dataset follows its inputs when they change.
+///
+/// # Example
+/// ```rust
+/// view! {
+///     <JoinSection />
+/// }
+/// ```
+#[component]
+pub fn JoinSection() -> impl IntoView {
+    let dashboard = DashboardContext::use_context();
+
+    let (right_id, set_right_id) = signal(String::new());
+    let (kind, set_kind) = signal(JoinKind::default());
+    let (keys, set_keys) = signal(Vec::<JoinKey>::new());
+    let (dataset_name, set_dataset_name) = signal(String::new());
+    let (error, set_error) = signal(None::<String>);
+
+    let active_dataset = Memo::new(move |_| dashboard.get_active_dataset());
+    let find = move |id: &str| dashboard.datasets.with(|datasets| datasets.iter().find(|d| d.id == id).cloned());
+
+    let join = move || {
+        let left = active_dataset.get()?;
+        Some(Join { left: left.id, right: right_id.get(), kind: kind.get(), keys: keys.get() })
+    };
+
+    // Counts of the join being set up
+    let preview = Memo::new(move |_| {
+        let join = join()?;
+        let left = active_dataset.get()?;
+        let right = find(&join.right)?;
+        Some(join.stats(&left, &right).map_err(|e| e.to_string()))
+    });
+
+    // Counts of the active dataset, when it is a join
+    let active_stats = Memo::new(move |_| {
+        let Some(DerivedFrom::Join(join)) = active_dataset.get()?.derived_from else {
+            return None;
+        };
+        let (left, right) = (find(&join.left)?, find(&join.right)?);
+        Some((join.stats(&left, &right).ok()?, left.name, right.name))
+    });
+
+    let on_create = move |_: MouseEvent| {
+        let Some(join) = join() else {
+            return;
+        };
+        if join.right.is_empty() {
+            set_error.set(Some("Choose the dataset to join with".to_string()));
+            return;
+        }
+        match dashboard.add_derived_dataset(DerivedFrom::Join(join), &dataset_name.get_untracked()) {
+            Ok(id) => {
+                set_keys.set(Vec::new());
+                set_dataset_name.set(String::new());
+                set_error.set(None);
+                dashboard.set_active_dataset(Some(id));
+            }
+            Err(e) => set_error.set(Some(e.to_string())),
+        }
+    };
+
+    // A new key pair: the first left field, with the right field of the same name if any
+    let on_add_key = move |_: MouseEvent| {
+        let Some(left) = active_dataset.get_untracked().and_then(|d| d.fields.first().map(|f| f.name.clone())) else {
+            return;
+        };
+        let right = find(&right_id.get_untracked())
+            .and_then(|right| {
+                let same = right.fields.iter().find(|f| f.name == left);
+                same.or(right.fields.first()).map(|f| f.name.clone())
+            })
+            .unwrap_or_default();
+        set_keys.update(|keys| keys.push(JoinKey { left, right }));
+    };
+
+    view! {
+        {move || {
+            active_dataset.get().map(|dataset| {
+                let others: Vec<Dataset> = dashboard.datasets.with(|datasets| datasets.clone());
+                let left_fields: Vec<String> = dataset.fields.iter().map(|f| f.name.clone()).collect();
+                let left_name = dataset.name.clone();
+                view! {
+                    <div class="flex flex-col gap-2">
+                        <h3 class="px-1 text-xs font-bold text-base-content/60 uppercase tracking-wider">
+                            "Join"
+                        </h3>
+
+                        {move || active_stats.get().map(|(stats, left, right)| view! {
+                            <p class="px-1 text-xs text-base-content/60">
+                                {stats_text(&stats, &left, &right)}
+                            </p>
+                        })}
+
+                        <div class="flex gap-2">
+                            <select
+                                class="select select-bordered select-sm w-28"
+                                on:change=move |ev| {
+                                    if let Some(chosen) = JoinKind::all()
+                                        .into_iter()
+                                        .find(|kind| kind.label() == event_target_value(&ev))
+                                    {
+                                        set_kind.set(chosen);
+                                    }
+                                }
+                            >
+                                {JoinKind::all()
+                                    .into_iter()
+                                    .map(|option| view! {
+                                        <option value=option.label() selected=move || kind.get() == option>
+                                            {option.label()}
+                                        </option>
+                                    })
+                                    .collect::<Vec<_>>()}
+                            </select>
+                            <select
+                                class="select select-bordered select-sm flex-1 min-w-0"
+                                on:change=move |ev| {
+                                    set_right_id.set(event_target_value(&ev));
+                                    set_keys.set(Vec::new());
+                                    set_error.set(None);
+                                }
+                            >
+                                <option value="" selected=move || right_id.get().is_empty()>"With dataset"</option>
+                                {others
+                                    .into_iter()
+                                    .map(|other| {
+                                        let id = other.id.clone();
+                                        view! {
+                                            <option value=other.id selected=move || right_id.get() == id>
+                                                {other.name}
+                                            </option>
+                                        }
+                                    })
+                                    .collect::<Vec<_>>()}
+                            </select>
+                        </div>
+
+                        // Key pairs: left field = right field
+                        {move || {
+                            let right_fields: Vec<String> = find(&right_id.get())
+                                .map(|right| right.fields.iter().map(|f| f.name.clone()).collect())
+                                .unwrap_or_default();
+                            keys.get()
+                                .into_iter()
+                                .enumerate()
+                                .map(|(idx, key)| {
+                                    view! {
+                                        <div class="flex items-center gap-1">
+                                            <KeyFieldSelect
+                                                fields=left_fields.clone()
+                                                selected=key.left
+                                                on_change=Callback::new(move |name: String| {
+                                                    set_keys.update(|keys| keys[idx].left = name);
+                                                })
+                                            />
+                                            <span class="text-xs text-base-content/50">"="</span>
+                                            <KeyFieldSelect
+                                                fields=right_fields.clone()
+                                                selected=key.right
+                                                on_change=Callback::new(move |name: String| {
+                                                    set_keys.update(|keys| keys[idx].right = name);
+                                                })
+                                            />
+                                            <button
+                                                class="text-base-content/40 hover:text-error"
+                                                title="Remove key"
+                                                on:click=move |_| set_keys.update(|keys| {
+                                                    keys.remove(idx);
+                                                })
+                                            >
+                                                <Icon name=IconName::Close class="w-4 h-4" />
+                                            </button>
+                                        </div>
+                                    }
+                                })
+                                .collect::<Vec<_>>()
+                        }}
+                        <button
+                            class="btn btn-xs btn-ghost gap-1 justify-start"
+                            disabled=move || right_id.get().is_empty()
+                            on:click=on_add_key
+                        >
+                            <Icon name=IconName::Add class="w-3 h-3" />
+                            <span class="text-xs">"Add key"</span>
+                        </button>
+
+                        {move || match preview.get() {
+                            Some(Ok(stats)) if !keys.with(Vec::is_empty) => {
+                                let right = find(&right_id.get()).map(|d| d.name).unwrap_or_default();
+                                Some(view! {
+                                    <p class="px-1 text-xs text-base-content/60">
+                                        {stats_text(&stats, &left_name, &right)}
+                                    </p>
+                                }.into_any())
+                            }
+                            Some(Err(message)) if !keys.with(Vec::is_empty) => Some(view! {
+                                <p class="text-xs text-error">{message}</p>
+                            }.into_any()),
+                            _ => None,
+                        }}
+
+                        <input
+                            class="input input-sm input-bordered w-full"
+                            placeholder="Joined dataset name"
+                            prop:value=move || dataset_name.get()
+                            on:input=move |ev| set_dataset_name.set(event_target_value(&ev))
+                        />
+                        {move || error.get().map(|message| view! {
+                            <p class="text-xs text-error">{message}</p>
+                        })}
+                        <button class="btn btn-sm btn-ghost gap-2 justify-start" on:click=on_create>
+                            <Icon name=IconName::Add class="w-4 h-4" />
+                            <span class="text-xs font-semibold">"Create Joined Dataset"</span>
+                        </button>
+                    </div>
+                }
+            })
+        }}
+    }
+}
+
+/// Row and unmatched counts of a join, e.g. `12 rows · 2 of orders.csv
+/// and 1 of customers.csv unmatched`
+fn stats_text(stats: &JoinStats, left: &str, right: &str) -> String {
+    format!(
+        "{} rows · {} of {} and {} of {} unmatched",
+        stats.rows, stats.unmatched_left, left, stats.unmatched_right, right
+    )
+}
+
+/// Field picker of one side of a key pair
+#[component]
+fn KeyFieldSelect(
+    /// Fields of the dataset
+    fields: Vec<String>,
+    /// Field currently chosen
+    selected: String,
+    /// Callback with the chosen field
+    on_change: Callback<String>,
+) -> impl IntoView {
+    view! {
+        <select
+            class="select select-bordered select-xs flex-1 min-w-0"
+            on:change=move |ev| on_change.run(event_target_value(&ev))
+        >
+            {fields
+                .into_iter()
+                .map(|name| {
+                    let is_selected = name == selected;
+                    let label = name.clone();
+                    view! {
+                        <option value=name selected=is_selected>
+                            {label}
+                        </option>
+                    }
+                })
+                .collect::<Vec<_>>()}
+        </select>
+    }
+}
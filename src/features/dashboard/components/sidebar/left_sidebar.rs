@@ -3,8 +3,11 @@ use crate::ui::atoms::{Button, ButtonSize, ButtonVariant, Divider, Icon, IconNam
 use leptos::ev::MouseEvent;
 use leptos::prelude::*;
 
+use super::calculated_fields::CalculatedFieldsSection;
 use super::dataset_section::DatasetSection;
 use super::export_actions::ExportActions;
+use super::join::JoinSection;
+use super::reshape::ReshapeSection;
 use super::template_section::TemplateSection;
 use super::workspace_actions::WorkspaceActions;
 use crate::ui::organisms::data::DatasetItemData;
@@ -13,7 +16,8 @@ use crate::ui::organisms::data::TemplateData;
 /// Left sidebar organism component
 ///
 /// Main left panel containing datasets and templates sections.
-/// Composes DatasetSection, TemplateSection, ExportActions, and documentation footer.
+/// Composes DatasetSection, CalculatedFieldsSection, ReshapeSection, JoinSection,
+/// TemplateSection, ExportActions, and documentation footer.
 ///
 /// # Example
 /// ```rust
@@ -57,6 +61,15 @@ pub fn LeftSidebar(
                 upload_manager=upload_manager
             />
 
+            // Calculated fields of the active dataset
+            <CalculatedFieldsSection />
+
+            // Pivot / unpivot of the active dataset
+            <ReshapeSection />
+
+            // Joins of the active dataset with another
+            <JoinSection />
+
             // Divider
             <Divider class="h-px bg-base-300 w-full" />
 
@@ -2,11 +2,17 @@
 //!
 //! Modular sidebar implementation with separate components for:
 //! - Dataset management
+//! - Calculated fields of the active dataset
+//! - Datasets reshaped (pivoted or unpivoted) from the active one
+//! - Datasets joining the active one with another
 //! - Template library with categories
 //! - Template export functionality
 //! - Workspace (Dashboard) export/import functionality
 
 pub mod dataset_section;
+pub mod calculated_fields;
+pub mod reshape;
+pub mod join;
 pub mod template_section;
 pub mod export_actions;
 pub mod workspace_actions;
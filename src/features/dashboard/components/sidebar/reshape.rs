@@ -0,0 +1,263 @@
+use crate::features::dashboard::data::derived::DerivedFrom;
+use crate::features::dashboard::data::reshape::Reshape;
+use crate::features::dashboard::data::AggregationFunction;
+use crate::features::dashboard::{DashboardContext, FieldType};
+use crate::ui::atoms::{Icon, IconName};
+use leptos::ev::MouseEvent;
+use leptos::prelude::*;
+
+/// Reshape section component
+///
+/// Creates a dataset reshaped from the active one: unpivoting turns the
+/// chosen columns into name/value rows (wide to long), pivoting spreads
+/// the values of a field into aggregated columns (long to wide). The new
+/// dataset follows its source when the source changes.
+///
+/// # Example
+/// ```rust
+/// view! {
+///     <ReshapeSection />
+/// }
+/// ```
+#[component]
+pub fn ReshapeSection() -> impl IntoView {
+    let dashboard = DashboardContext::use_context();
+
+    let (pivot, set_pivot) = signal(false);
+    // Unpivoted columns, or the row fields of a pivot
+    let (chosen, set_chosen) = signal(Vec::<String>::new());
+    let (name_field, set_name_field) = signal("Name".to_string());
+    let (value_field, set_value_field) = signal("Value".to_string());
+    let (column_field, set_column_field) = signal(String::new());
+    let (aggregation, set_aggregation) = signal(AggregationFunction::Sum);
+    let (dataset_name, set_dataset_name) = signal(String::new());
+    let (error, set_error) = signal(None::<String>);
+
+    let active_dataset = Memo::new(move |_| dashboard.get_active_dataset());
+
+    let on_create = move |_: MouseEvent| {
+        let Some(dataset) = active_dataset.get_untracked() else {
+            return;
+        };
+        // In field order, whatever order they were ticked in
+        let chosen: Vec<String> = chosen.with_untracked(|chosen| {
+            dataset.fields.iter().map(|f| f.name.clone()).filter(|name| chosen.contains(name)).collect()
+        });
+        let reshape = if pivot.get_untracked() {
+            if column_field.with_untracked(String::is_empty) || value_field.with_untracked(String::is_empty) {
+                set_error.set(Some("Choose the field spread into columns and the measure".to_string()));
+                return;
+            }
+            Reshape::Pivot {
+                row_fields: chosen,
+                column_field: column_field.get_untracked(),
+                value_field: value_field.get_untracked(),
+                aggregation: aggregation.get_untracked(),
+            }
+        } else {
+            Reshape::Unpivot {
+                columns: chosen,
+                name_field: name_field.get_untracked().trim().to_string(),
+                value_field: value_field.get_untracked().trim().to_string(),
+            }
+        };
+        let derived_from = DerivedFrom::Reshape { source: dataset.id.clone(), reshape };
+        match dashboard.add_derived_dataset(derived_from, &dataset_name.get_untracked()) {
+            Ok(id) => {
+                set_chosen.set(Vec::new());
+                set_dataset_name.set(String::new());
+                set_error.set(None);
+                dashboard.set_active_dataset(Some(id));
+            }
+            Err(e) => set_error.set(Some(e.to_string())),
+        }
+    };
+
+    let set_mode = move |is_pivot: bool| {
+        set_pivot.set(is_pivot);
+        set_chosen.set(Vec::new());
+        set_value_field.set(if is_pivot { String::new() } else { "Value".to_string() });
+        set_error.set(None);
+    };
+
+    view! {
+        {move || {
+            active_dataset.get().map(|dataset| {
+                let field_names: Vec<String> = dataset.fields.iter().map(|f| f.name.clone()).collect();
+                let numeric_names: Vec<String> = dataset
+                    .fields
+                    .iter()
+                    .filter(|f| f.field_type == FieldType::Numeric)
+                    .map(|f| f.name.clone())
+                    .collect();
+                view! {
+                    <div class="flex flex-col gap-2">
+                        <h3 class="px-1 text-xs font-bold text-base-content/60 uppercase tracking-wider">
+                            "Reshape"
+                        </h3>
+
+                        <select
+                            class="select select-bordered select-sm w-full"
+                            on:change=move |ev| set_mode(event_target_value(&ev) == "pivot")
+                        >
+                            <option value="unpivot" selected=move || !pivot.get()>
+                                "Unpivot: columns to rows"
+                            </option>
+                            <option value="pivot" selected=move || pivot.get()>
+                                "Pivot: values to columns"
+                            </option>
+                        </select>
+
+                        // Unpivoted columns, or pivot row fields
+                        <span class="px-1 text-xs text-base-content/60">
+                            {move || if pivot.get() { "Row fields" } else { "Columns to unpivot" }}
+                        </span>
+                        <div class="flex flex-col gap-1 max-h-32 overflow-y-auto px-1">
+                            {field_names
+                                .iter()
+                                .map(|name| {
+                                    let name = name.clone();
+                                    let label = name.clone();
+                                    let checked_name = name.clone();
+                                    view! {
+                                        <label class="flex items-center gap-2 text-xs cursor-pointer">
+                                            <input
+                                                type="checkbox"
+                                                class="checkbox checkbox-xs"
+                                                prop:checked=move || chosen.with(|chosen| chosen.contains(&checked_name))
+                                                on:change=move |ev| {
+                                                    let checked = event_target_checked(&ev);
+                                                    set_chosen.update(|chosen| {
+                                                        chosen.retain(|chosen| chosen != &name);
+                                                        if checked {
+                                                            chosen.push(name.clone());
+                                                        }
+                                                    });
+                                                }
+                                            />
+                                            <span class="truncate">{label}</span>
+                                        </label>
+                                    }
+                                })
+                                .collect::<Vec<_>>()}
+                        </div>
+
+                        {move || {
+                            if pivot.get() {
+                                let field_names = field_names.clone();
+                                let numeric_names = numeric_names.clone();
+                                view! {
+                                    <select
+                                        class="select select-bordered select-sm w-full"
+                                        on:change=move |ev| set_column_field.set(event_target_value(&ev))
+                                    >
+                                        <option value="" selected=move || column_field.get().is_empty()>
+                                            "Field spread into columns"
+                                        </option>
+                                        {field_names
+                                            .into_iter()
+                                            .map(|name| {
+                                                let selected_name = name.clone();
+                                                view! {
+                                                    <option
+                                                        value=selected_name.clone()
+                                                        selected=move || column_field.get() == selected_name
+                                                    >
+                                                        {name}
+                                                    </option>
+                                                }
+                                            })
+                                            .collect::<Vec<_>>()}
+                                    </select>
+                                    <div class="flex gap-2">
+                                        <select
+                                            class="select select-bordered select-sm w-24"
+                                            on:change=move |ev| {
+                                                if let Some(function) = AggregationFunction::common()
+                                                    .into_iter()
+                                                    .find(|function| function.display_name() == event_target_value(&ev))
+                                                {
+                                                    set_aggregation.set(function);
+                                                }
+                                            }
+                                        >
+                                            {AggregationFunction::common()
+                                                .into_iter()
+                                                .map(|function| {
+                                                    let name = function.display_name();
+                                                    view! {
+                                                        <option
+                                                            value=name.clone()
+                                                            selected=move || aggregation.get() == function
+                                                        >
+                                                            {name.clone()}
+                                                        </option>
+                                                    }
+                                                })
+                                                .collect::<Vec<_>>()}
+                                        </select>
+                                        <select
+                                            class="select select-bordered select-sm flex-1 min-w-0"
+                                            on:change=move |ev| set_value_field.set(event_target_value(&ev))
+                                        >
+                                            <option value="" selected=move || value_field.get().is_empty()>
+                                                "Measure"
+                                            </option>
+                                            {numeric_names
+                                                .into_iter()
+                                                .map(|name| {
+                                                    let selected_name = name.clone();
+                                                    view! {
+                                                        <option
+                                                            value=selected_name.clone()
+                                                            selected=move || value_field.get() == selected_name
+                                                        >
+                                                            {name}
+                                                        </option>
+                                                    }
+                                                })
+                                                .collect::<Vec<_>>()}
+                                        </select>
+                                    </div>
+                                }
+                                    .into_any()
+                            } else {
+                                view! {
+                                    <div class="flex gap-2">
+                                        <input
+                                            class="input input-sm input-bordered flex-1 min-w-0"
+                                            placeholder="Name field"
+                                            prop:value=move || name_field.get()
+                                            on:input=move |ev| set_name_field.set(event_target_value(&ev))
+                                        />
+                                        <input
+                                            class="input input-sm input-bordered flex-1 min-w-0"
+                                            placeholder="Value field"
+                                            prop:value=move || value_field.get()
+                                            on:input=move |ev| set_value_field.set(event_target_value(&ev))
+                                        />
+                                    </div>
+                                }
+                                    .into_any()
+                            }
+                        }}
+
+                        <input
+                            class="input input-sm input-bordered w-full"
+                            placeholder=format!("{} (reshaped)", dataset.name)
+                            prop:value=move || dataset_name.get()
+                            on:input=move |ev| set_dataset_name.set(event_target_value(&ev))
+                        />
+                        {move || error.get().map(|message| view! {
+                            <p class="text-xs text-error">{message}</p>
+                        })}
+                        <button class="btn btn-sm btn-ghost gap-2 justify-start" on:click=on_create>
+                            <Icon name=IconName::Add class="w-4 h-4" />
+                            <span class="text-xs font-semibold">"Create Reshaped Dataset"</span>
+                        </button>
+                    </div>
+                }
+            })
+        }}
+    }
+}
@@ -1,5 +1,5 @@
 use crate::context::ToastContext;
-use crate::features::dashboard::{DashboardContext, KpiAggregation};
+use crate::features::dashboard::{data::AggregationFunction, DashboardContext};
 use crate::ui::molecules::{CategoryTabs as MoleculeCategoryTabs, KpiQuickActions, Modal, ModalTemplateGallery, PreviewType, TemplateCard};
 use crate::ui::organisms::data::TemplateData;
 use leptos::ev::MouseEvent;
@@ -122,7 +122,7 @@ pub fn TemplateSection(
     });
 
     // KPI quick actions select handler
-    let on_kpi_select = Callback::new(move |(field_name, aggregation, _label): (String, KpiAggregation, String)| {
+    let on_kpi_select = Callback::new(move |(field_name, aggregation, _label): (String, AggregationFunction, String)| {
         log::info!("KPI selected: {:?} - {:?}", field_name, aggregation);
 
         // Get pending template
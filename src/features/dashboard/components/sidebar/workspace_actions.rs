@@ -47,17 +47,17 @@ pub fn WorkspaceActions() -> impl IntoView {
     // Import dashboard - trigger file input click
     let on_import_click = move |_: MouseEvent| {
         log::info!("Import clicked - opening file picker");
-        if let Some(window) = web_sys::window() {
-            if let Some(document) = window.document() {
-                if let Some(input) = document
-                    .get_element_by_id("import-dashboard-file-input")
-                    .and_then(|el| el.dyn_into::<web_sys::HtmlInputElement>().ok())
-                {
-                    input.click();
-                } else {
-                    log::error!("Failed to find import file input");
-                    toast.show_error("Error", "File input not found");
-                }
+        if let Some(window) = web_sys::window()
+            && let Some(document) = window.document()
+        {
+            if let Some(input) = document
+                .get_element_by_id("import-dashboard-file-input")
+                .and_then(|el| el.dyn_into::<web_sys::HtmlInputElement>().ok())
+            {
+                input.click();
+            } else {
+                log::error!("Failed to find import file input");
+                toast.show_error("Error", "File input not found");
             }
         }
     };
@@ -82,28 +82,27 @@ pub fn WorkspaceActions() -> impl IntoView {
 
             // Create closure for onload event
             let onload = wasm_bindgen::closure::Closure::wrap(Box::new(move |ev: Event| {
-                if let Some(target) = ev.target() {
-                    if let Some(reader) = target.dyn_ref::<web_sys::FileReader>() {
-                        if let Some(content) = reader.result().ok().and_then(|v| v.as_string()) {
-                            log::info!("File loaded, {} bytes", content.len());
-
-                            // Import dashboard
-                            match ExportService::import_dashboard(&dashboard_clone, &content, &filename_clone) {
-                                Ok(()) => {
-                                    log::info!("Import successful");
-                                    toast_clone.show_success(
-                                        "Dashboard Imported!",
-                                        &format!("Loaded '{}'", filename_clone),
-                                    );
-                                }
-                                Err(e) => {
-                                    log::error!("Import failed: {}", e);
-                                    toast_clone.show_error(
-                                        "Import Failed",
-                                        &format!("Could not import: {}", e),
-                                    );
-                                }
-                            }
+                if let Some(target) = ev.target()
+                    && let Some(reader) = target.dyn_ref::<web_sys::FileReader>()
+                    && let Some(content) = reader.result().ok().and_then(|v| v.as_string())
+                {
+                    log::info!("File loaded, {} bytes", content.len());
+
+                    // Import dashboard
+                    match ExportService::import_dashboard(&dashboard_clone, &content, &filename_clone) {
+                        Ok(()) => {
+                            log::info!("Import successful");
+                            toast_clone.show_success(
+                                "Dashboard Imported!",
+                                &format!("Loaded '{}'", filename_clone),
+                            );
+                        }
+                        Err(e) => {
+                            log::error!("Import failed: {}", e);
+                            toast_clone.show_error(
+                                "Import Failed",
+                                &format!("Could not import: {}", e),
+                            );
                         }
                     }
                 }
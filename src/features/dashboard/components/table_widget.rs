@@ -1,6 +1,10 @@
 use crate::features::dashboard::{
     config::style::{TableStyleOptions, TableRowHeight},
-    DashboardContext,
+    data::aggregation::aggregate_field,
+    data::transform::{compare_values, rank_items, value_label, SortOrder, OTHER_SERIES},
+    data::window::WindowCalculation,
+    data::Column,
+    DashboardContext, FieldType,
 };
 use leptos::prelude::*;
 
@@ -15,6 +19,7 @@ use leptos::prelude::*;
 /// - Sorting support (style option)
 /// - Pagination support (style option)
 /// - Hover row effects
+/// - Summary row (style option)
 /// - Dark mode support
 /// - Clean borders
 ///
@@ -40,11 +45,21 @@ pub fn TableWidget(
             .find(|w| w.id == widget_id)
     });
 
+    // Parse style options
+    let style_options = Memo::new(move |_| {
+        widget
+            .get()
+            .and_then(|w| serde_json::from_str::<TableStyleOptions>(&w.chart_config.style_options).ok())
+            .unwrap_or_default()
+    });
+
     // Get active dataset and selected columns
     let table_data = Memo::new(move |_| {
         widget.get().and_then(|w| {
-            // Get active dataset
-            let dataset = dashboard.get_datasets().into_iter().find(|ds| ds.active)?;
+            // Get the widget's dataset
+            let datasets = dashboard.get_datasets();
+            let mapping = w.chart_config.data_mapping.with_global_filters(&dashboard.dashboard_filters(&w.id));
+            let dataset = mapping.filters.apply(w.chart_config.dataset(&datasets)?);
 
             // Get selected columns from data mapping
             let selected_columns = &w.chart_config.data_mapping.columns;
@@ -69,48 +84,129 @@ pub fn TableWidget(
                 return None;
             }
 
-            // Extract headers and data
-            let headers: Vec<String> = column_indexes.iter().map(|(name, _)| name.clone()).collect();
+            let columns: Vec<_> = column_indexes
+                .iter()
+                .map(|(_, idx)| dataset.data.column(*idx))
+                .collect();
 
-            let rows: Vec<Vec<String>> = dataset
-                .data
+            // Rows shown: in the dataset's order until a sort or top-N limit
+            // is chosen. The first column is the dimension and the first
+            // numeric column the measure; rows beyond the limit are merged
+            // into an "Other" row, after the others, summing numeric columns.
+            let mut order: Vec<usize> = (0..dataset.data.len()).collect();
+            let mut merged = Vec::new();
+            if mapping.sort != SortOrder::Dimension || mapping.top_n.is_some() {
+                order.sort_by(|&a, &b| compare_values(&columns[0].value(a), &columns[0].value(b)));
+                let measure = column_indexes
+                    .iter()
+                    .position(|(_, idx)| dataset.fields[*idx].field_type == FieldType::Numeric);
+                let labels: Vec<Option<String>> = order.iter().map(|&row| value_label(&columns[0].value(row))).collect();
+                let values: Vec<Option<f64>> =
+                    order.iter().map(|&row| measure.and_then(|measure| columns[measure].number(row))).collect();
+                let ranked = rank_items(&labels, &values, &mapping.sort, mapping.top_n);
+                merged = ranked.others.iter().map(|&idx| order[idx]).collect();
+                order = ranked.shown.iter().map(|&idx| order[idx]).collect();
+            }
+            let other_row = (!merged.is_empty()).then_some(order.len());
+            let row_count = order.len() + usize::from(other_row.is_some());
+
+            // Numbers of a column on each row shown
+            let numbers = |column: &Column| -> Vec<Option<f64>> {
+                let mut numbers: Vec<Option<f64>> = order.iter().map(|&row| column.number(row)).collect();
+                if other_row.is_some() {
+                    numbers.push(merged.iter().filter_map(|&row| column.number(row)).reduce(|total, n| total + n));
+                }
+                numbers
+            };
+
+            // Window calculations of the columns that have one, down the rows
+            // in table order; last year's periods are looked up in the first
+            // date column
+            let periods: Vec<serde_json::Value> = match column_indexes
+                .iter()
+                .zip(&columns)
+                .find(|((_, idx), _)| dataset.fields[*idx].field_type == FieldType::Date)
+            {
+                Some((_, column)) => (0..row_count)
+                    .map(|row| order.get(row).map_or(serde_json::Value::Null, |&row| column.value(row)))
+                    .collect(),
+                None => vec![serde_json::Value::Null; row_count],
+            };
+            let calculated: Vec<Option<(WindowCalculation, Vec<Option<f64>>)>> = column_indexes
+                .iter()
+                .zip(&columns)
+                .map(|((name, _), column)| {
+                    let calculation = mapping.calculation(name)?;
+                    Some((calculation, calculation.apply(&periods, &numbers(column), None)))
+                })
+                .collect();
+
+            // Extract headers and data
+            let headers: Vec<String> = column_indexes
                 .iter()
+                .zip(&calculated)
+                .map(|((name, _), calculated)| match calculated {
+                    Some((calculation, _)) => format!("{} ({})", name, calculation.label()),
+                    None => name.clone(),
+                })
+                .collect();
+
+            let format_number = |value: Option<f64>| match value {
+                Some(value) if value.fract() == 0.0 => format!("{}", value),
+                Some(value) => format!("{:.2}", value),
+                None => "—".to_string(),
+            };
+            let rows: Vec<Vec<String>> = (0..row_count)
                 .map(|row| {
-                    column_indexes
+                    columns
                         .iter()
-                        .map(|(_, idx)| {
-                            row.get(*idx)
-                                .map(|val| {
-                                    // Format value based on type
-                                    match val {
-                                        serde_json::Value::String(s) => s.clone(),
-                                        serde_json::Value::Number(n) => n.to_string(),
-                                        serde_json::Value::Bool(b) => b.to_string(),
-                                        serde_json::Value::Null => "—".to_string(),
-                                        _ => val.to_string(),
-                                    }
-                                })
-                                .unwrap_or_else(|| "—".to_string())
+                        .zip(&column_indexes)
+                        .zip(&calculated)
+                        .enumerate()
+                        .map(|(position, ((column, (_, idx)), calculated))| {
+                            if let Some((calculation, results)) = calculated {
+                                let percent = calculation.output_format(None).is_some_and(|format| format.percent);
+                                return match results[row] {
+                                    Some(value) if percent => format!("{:.1}%", value),
+                                    value => format_number(value),
+                                };
+                            }
+                            if other_row == Some(row) {
+                                return match dataset.fields[*idx].field_type {
+                                    _ if position == 0 => OTHER_SERIES.to_string(),
+                                    FieldType::Numeric => format_number(numbers(column)[row]),
+                                    _ => "—".to_string(),
+                                };
+                            }
+                            // Format value based on type
+                            match column.value(order[row]) {
+                                serde_json::Value::String(s) => s,
+                                serde_json::Value::Number(n) => n.to_string(),
+                                serde_json::Value::Bool(b) => b.to_string(),
+                                serde_json::Value::Null => "—".to_string(),
+                                val => val.to_string(),
+                            }
                         })
                         .collect()
                 })
                 .collect();
 
-            Some((headers, rows))
-        })
-    });
+            // Summary row: each column aggregated over all rows
+            let summary = style_options.get().summary.map(|agg| {
+                let cells: Vec<String> = column_indexes
+                    .iter()
+                    .map(|(_, idx)| format_number(aggregate_field(&dataset, *idx, 0..dataset.data.len(), &agg)))
+                    .collect();
+                (agg.display_name(), cells)
+            });
 
-    // Parse style options
-    let style_options = Memo::new(move |_| {
-        widget
-            .get()
-            .and_then(|w| serde_json::from_str::<TableStyleOptions>(&w.chart_config.style_options).ok())
-            .unwrap_or_default()
+            Some((headers, rows, summary))
+        })
     });
 
     view! {
         {move || {
-            if let Some((headers, rows)) = table_data.get() {
+            if let Some((headers, rows, summary)) = table_data.get() {
                 let style = style_options.get();
 
                 // Determine row height class
@@ -198,6 +294,31 @@ pub fn TableWidget(
                                         })
                                         .collect::<Vec<_>>()}
                                 </tbody>
+                                {summary
+                                    .map(|(name, cells)| {
+                                        view! {
+                                            <tfoot class="sticky bottom-0 bg-base-100 border-t-2 border-base-300">
+                                                <tr>
+                                                    {cells
+                                                        .into_iter()
+                                                        .map(|cell| {
+                                                            view! {
+                                                                <td class=format!(
+                                                                    "{} px-4 font-semibold text-base-content",
+                                                                    row_height_class,
+                                                                )>
+                                                                    <span class="text-xs text-base-content/50 mr-1">
+                                                                        {name.clone()}
+                                                                    </span>
+                                                                    {cell}
+                                                                </td>
+                                                            }
+                                                        })
+                                                        .collect::<Vec<_>>()}
+                                                </tr>
+                                            </tfoot>
+                                        }
+                                    })}
                             </table>
 
                             // Pagination footer (if enabled)
@@ -0,0 +1,152 @@
+//! Chart widget options computed through the compute worker
+
+use super::client::{transform_locally, ComputeClient};
+use crate::features::dashboard::config::traits::WidgetConfigBuilder;
+use crate::features::dashboard::cross_filter::{enable_selection, selected_elements, supports_cross_filter, ChartEvent};
+use crate::features::dashboard::{DashboardContext, DataMapping, Widget, WidgetType};
+use leptos::prelude::*;
+use serde_json::Value;
+
+/// ECharts options of a chart widget
+#[derive(Clone, Copy)]
+pub struct ChartOptions {
+    /// Options JSON; `None` until the widget is configured and its data computed
+    pub options: Memo<Option<String>>,
+    /// Whether the widget's data is being computed
+    pub loading: ReadSignal<bool>,
+    /// Handles the chart's click and brush events by cross-filtering
+    pub on_event: Callback<ChartEvent>,
+    /// Names of the chart's elements selected for cross-filtering
+    pub selected: Signal<Vec<String>>,
+}
+
+impl ChartOptions {
+    /// Whether there is a chart to show (already built, or on its way)
+    pub fn has_chart(&self) -> bool {
+        self.loading.get() || self.options.with(Option::is_some)
+    }
+}
+
+/// Build a chart widget's options, with the data transform off the main thread
+///
+/// The data is recomputed (in the compute worker when there is one) when the
+/// widget's dataset, the field mapping or the dashboard's filters change;
+/// style and theme changes only rebuild the options from the data already
+/// computed. While a transform is pending the previous chart stays up and
+/// `loading` is set.
+///
+/// Clicking or brushing elements of the chart selects them, filtering the
+/// other widgets (see `cross_filter`); the chart itself isn't filtered by
+/// its own selection.
+///
+/// # Arguments
+/// * `builder` - Config builder of the widget type
+/// * `widget_type` - Widget type, telling the worker which builder to use
+/// * `widget` - The widget, `None` once it is removed
+pub fn use_chart_options<B>(builder: B, widget_type: WidgetType, widget: Memo<Option<Widget>>) -> ChartOptions
+where
+    B: WidgetConfigBuilder + 'static,
+    B::Data: Send + Sync + 'static,
+{
+    let dashboard = DashboardContext::use_context();
+    let compute = ComputeClient::use_context();
+
+    let (data, set_data) = signal(None::<(DataMapping, B::Data)>);
+    let (loading, set_loading) = signal(false);
+    // Only the latest request may store its result
+    let latest_request = StoredValue::new(0u64);
+
+    // What the data depends on: the widget's dataset (and its rows'
+    // revision), mapping and the dashboard's filters
+    let input = Memo::new(move |_| {
+        let (config, mapping) = widget.with(|w| {
+            w.as_ref().map(|w| {
+                let mapping = w.chart_config.data_mapping.with_global_filters(&dashboard.dashboard_filters(&w.id));
+                (w.chart_config.clone(), mapping)
+            })
+        })?;
+        let (dataset_id, revision) = dashboard.datasets.with(|datasets| {
+            config.dataset(datasets).map(|ds| (ds.id.clone(), ds.data.revision()))
+        })?;
+        Some((dataset_id, revision, mapping))
+    });
+
+    Effect::new(move |_| {
+        latest_request.update_value(|id| *id += 1);
+        let request = latest_request.get_value();
+
+        let Some((dataset_id, _revision, mapping)) = input.get() else {
+            set_data.set(None);
+            set_loading.set(false);
+            return;
+        };
+
+        let computed_for = mapping.clone();
+        let on_result = move |result: Result<Value, String>| {
+            if latest_request.get_value() != request {
+                return;
+            }
+            set_loading.set(false);
+            let decoded = result.and_then(|value| {
+                serde_json::from_value::<B::Data>(value).map_err(|e| e.to_string())
+            });
+            match decoded {
+                Ok(decoded) => set_data.set(Some((computed_for, decoded))),
+                Err(e) => {
+                    log::debug!("{} data unavailable: {}", widget_type.display_name(), e);
+                    set_data.set(None);
+                }
+            }
+        };
+
+        set_loading.set(true);
+        match compute {
+            Some(client) => client.transform(widget_type, dataset_id, mapping, on_result),
+            None => on_result(transform_locally(dashboard, widget_type, &dataset_id, &mapping)),
+        }
+    });
+
+    let options = Memo::new(move |_| {
+        let style = widget.with(|w| w.as_ref().map(|w| w.chart_config.style_options.clone()))?;
+        let style: B::StyleOptions =
+            serde_json::from_str(&style).unwrap_or_else(|_| builder.default_style());
+
+        let options = data.with(|data| {
+            let (mapping, data) = data.as_ref()?;
+            builder.build_options_from_data(data, mapping, &style).ok()
+        })?;
+        if !supports_cross_filter(widget_type) {
+            return Some(options);
+        }
+        let mut options: Value = serde_json::from_str(&options).ok()?;
+        enable_selection(&mut options, widget_type);
+        Some(options.to_string())
+    });
+
+    let on_event = Callback::new(move |event: ChartEvent| {
+        let Some((widget_id, mapping)) =
+            widget.with_untracked(|w| w.as_ref().map(|w| (w.id.clone(), w.chart_config.data_mapping.clone())))
+        else {
+            return;
+        };
+        let elements = selected_elements(widget_type, &mapping, &event);
+        match event {
+            // Clicks add to the selection, or remove already selected elements
+            ChartEvent::Click(_) if !elements.is_empty() => dashboard.select_chart_elements(&widget_id, elements, true),
+            ChartEvent::Click(_) => {}
+            ChartEvent::Brush(_) => dashboard.select_chart_elements(&widget_id, elements, false),
+        }
+    });
+
+    let selected = Signal::derive(move || {
+        dashboard.chart_selection.with(|selection| {
+            selection
+                .as_ref()
+                .filter(|selection| widget.with(|w| w.as_ref().is_some_and(|w| w.id == selection.source)))
+                .map(|selection| selection.names())
+                .unwrap_or_default()
+        })
+    });
+
+    ChartOptions { options, loading, on_event, selected }
+}
@@ -0,0 +1,323 @@
+//! Main-thread side of the compute subsystem
+
+use super::engine::transform_widget;
+use super::protocol::{from_js_message, to_js_message, ComputeRequest, ComputeResponse};
+use crate::config;
+use crate::features::dashboard::csv_upload::ImportOptions;
+use crate::features::dashboard::{
+    CalculatedField, DashboardContext, DataMapping, Dataset, DatasetId, Field, WidgetType,
+};
+use futures::channel::mpsc::{self, UnboundedReceiver};
+use leptos::prelude::*;
+use serde_json::Value;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{ErrorEvent, MessageEvent, Worker};
+
+/// Loader script Trunk generates for the `compute_worker` binary
+const WORKER_SCRIPT: &str = "/compute_worker_loader.js";
+
+/// A running compute worker, terminated when dropped
+pub struct ComputeWorker {
+    worker: Worker,
+    _on_message: Closure<dyn FnMut(MessageEvent)>,
+    _on_error: Closure<dyn FnMut(ErrorEvent)>,
+}
+
+impl ComputeWorker {
+    /// Start a worker; every response it posts is passed to `on_response`
+    ///
+    /// A worker whose script fails to load is reported asynchronously, as a
+    /// `ComputeResponse::WorkerError`.
+    pub fn spawn(on_response: impl FnMut(ComputeResponse) + 'static) -> Result<Self, String> {
+        let worker = Worker::new(&config::path(WORKER_SCRIPT)).map_err(|e| format!("{:?}", e))?;
+        let on_response = Rc::new(RefCell::new(on_response));
+
+        let handler = on_response.clone();
+        let on_message = Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+            let response = from_js_message(&event.data()).and_then(|(json, _)| ComputeResponse::from_message(&json));
+            match response {
+                Ok(response) => (handler.borrow_mut())(response),
+                Err(e) => log::error!("Compute worker sent an invalid response: {}", e),
+            }
+        });
+
+        let on_error = Closure::<dyn FnMut(ErrorEvent)>::new(move |event: ErrorEvent| {
+            event.prevent_default();
+            (on_response.borrow_mut())(ComputeResponse::WorkerError { message: event.message() });
+        });
+
+        worker.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+        worker.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+
+        Ok(Self {
+            worker,
+            _on_message: on_message,
+            _on_error: on_error,
+        })
+    }
+
+    /// Post a request to the worker
+    pub fn send(&self, request: &ComputeRequest) -> Result<(), String> {
+        let (json, payload) = request.to_message()?;
+        let (message, transfer) = to_js_message(&json, payload);
+        self.worker
+            .post_message_with_transfer(&message, &transfer)
+            .map_err(|e| format!("{:?}", e))
+    }
+}
+
+impl Drop for ComputeWorker {
+    fn drop(&mut self) {
+        self.worker.terminate();
+    }
+}
+
+/// Start a worker parsing delimited text
+///
+/// # Returns
+/// The worker and the stream of its responses (progress, then the parsed
+/// Dataset). Dropping the worker terminates it and ends the stream.
+pub fn spawn_parse_worker(
+    text: Arc<String>,
+    filename: &str,
+    file_size: u64,
+    options: &ImportOptions,
+) -> Result<(ComputeWorker, UnboundedReceiver<ComputeResponse>), String> {
+    let (sender, receiver) = mpsc::unbounded();
+    let worker = ComputeWorker::spawn(move |response| {
+        let _ = sender.unbounded_send(response);
+    })?;
+
+    worker.send(&ComputeRequest::ParseCsv {
+        text,
+        filename: filename.to_string(),
+        file_size,
+        options: options.clone(),
+    })?;
+    Ok((worker, receiver))
+}
+
+/// A transform waiting for the worker's answer
+struct PendingTransform {
+    widget_type: WidgetType,
+    dataset_id: DatasetId,
+    mapping: DataMapping,
+    callback: Box<dyn FnOnce(Result<Value, String>)>,
+}
+
+/// What the worker holds of a dataset
+///
+/// A change of name, fields (calculated ones included) or rows (told by
+/// the table's revision) means it must be sent again.
+#[derive(Clone, Debug, PartialEq)]
+struct DatasetStamp {
+    name: String,
+    fields: Vec<Field>,
+    calculated_fields: Vec<CalculatedField>,
+    revision: u64,
+}
+
+impl DatasetStamp {
+    fn of(dataset: &Dataset) -> Self {
+        Self {
+            name: dataset.name.clone(),
+            fields: dataset.fields.clone(),
+            calculated_fields: dataset.calculated_fields.clone(),
+            revision: dataset.data.revision(),
+        }
+    }
+}
+
+/// Dashboard-wide client of the compute worker
+///
+/// Keeps a copy of the dashboard's datasets in the worker and routes widget
+/// transforms to it. When no worker can be started (or it crashes),
+/// transforms run on the main thread instead.
+#[derive(Clone, Copy)]
+pub struct ComputeClient {
+    dashboard: DashboardContext,
+    worker: StoredValue<Option<ComputeWorker>, LocalStorage>,
+    pending: StoredValue<HashMap<u64, PendingTransform>, LocalStorage>,
+    synced: StoredValue<HashMap<DatasetId, DatasetStamp>, LocalStorage>,
+    next_request_id: StoredValue<u64>,
+}
+
+impl ComputeClient {
+    /// Start the compute worker and provide the client to child components
+    pub fn provide(dashboard: DashboardContext) -> Self {
+        let client = Self {
+            dashboard,
+            worker: StoredValue::new_local(None),
+            pending: StoredValue::new_local(HashMap::new()),
+            synced: StoredValue::new_local(HashMap::new()),
+            next_request_id: StoredValue::new(0),
+        };
+
+        match ComputeWorker::spawn(move |response| client.on_response(response)) {
+            Ok(worker) => client.worker.set_value(Some(worker)),
+            Err(e) => log::warn!("Compute worker unavailable, computing on the main thread: {}", e),
+        }
+
+        // Keep the worker's copy of the datasets up to date
+        Effect::new(move |_| {
+            dashboard.datasets.with(|datasets| client.sync_datasets(datasets));
+        });
+
+        provide_context(client);
+        client
+    }
+
+    /// Get the client, if a dashboard provided one
+    pub fn use_context() -> Option<Self> {
+        use_context::<Self>()
+    }
+
+    /// Compute the data of a widget from a dataset
+    ///
+    /// `callback` receives the builder's serialized `Data`. It is called
+    /// later when the worker computes it, or right away on the main thread
+    /// when there is no worker.
+    pub fn transform(
+        &self,
+        widget_type: WidgetType,
+        dataset_id: DatasetId,
+        mapping: DataMapping,
+        callback: impl FnOnce(Result<Value, String>) + 'static,
+    ) {
+        if self.worker.with_value(Option::is_none) {
+            callback(transform_locally(self.dashboard, widget_type, &dataset_id, &mapping));
+            return;
+        }
+
+        // The datasets effect may not have run yet for a dataset added this tick
+        self.dashboard
+            .datasets
+            .with_untracked(|datasets| self.sync_datasets(datasets));
+
+        self.next_request_id.update_value(|id| *id += 1);
+        let request_id = self.next_request_id.get_value();
+        let request = ComputeRequest::Transform {
+            request_id,
+            widget_type,
+            dataset_id: dataset_id.clone(),
+            mapping: Box::new(mapping.clone()),
+        };
+        self.pending.update_value(|pending| {
+            pending.insert(
+                request_id,
+                PendingTransform {
+                    widget_type,
+                    dataset_id,
+                    mapping,
+                    callback: Box::new(callback),
+                },
+            );
+        });
+
+        if let Err(e) = self.send(&request) {
+            log::warn!("Compute worker unreachable ({}), computing on the main thread", e);
+            self.fall_back();
+        }
+    }
+
+    fn send(&self, request: &ComputeRequest) -> Result<(), String> {
+        self.worker.with_value(|worker| match worker {
+            Some(worker) => worker.send(request),
+            None => Err("worker stopped".to_string()),
+        })
+    }
+
+    /// Send new and changed datasets to the worker and drop removed ones
+    fn sync_datasets(&self, datasets: &[Dataset]) {
+        if self.worker.with_value(Option::is_none) {
+            return;
+        }
+
+        let mut requests = Vec::new();
+        self.synced.update_value(|synced| {
+            synced.retain(|id, _| {
+                let keep = datasets.iter().any(|dataset| &dataset.id == id);
+                if !keep {
+                    requests.push(ComputeRequest::RemoveDataset { dataset_id: id.clone() });
+                }
+                keep
+            });
+
+            for dataset in datasets {
+                let stamp = DatasetStamp::of(dataset);
+                if synced.get(&dataset.id) != Some(&stamp) {
+                    requests.push(ComputeRequest::LoadDataset { dataset: Box::new(dataset.clone()) });
+                    synced.insert(dataset.id.clone(), stamp);
+                }
+            }
+        });
+
+        for request in &requests {
+            if let Err(e) = self.send(request) {
+                log::warn!("Failed to send dataset to the compute worker: {}", e);
+            }
+        }
+    }
+
+    fn on_response(&self, response: ComputeResponse) {
+        match response {
+            ComputeResponse::Transformed { request_id, result } => {
+                let pending = self
+                    .pending
+                    .try_update_value(|pending| pending.remove(&request_id))
+                    .flatten();
+                if let Some(pending) = pending {
+                    (pending.callback)(result);
+                }
+            }
+            ComputeResponse::WorkerError { message } => {
+                log::warn!("Compute worker failed ({}), computing on the main thread", message);
+                self.fall_back();
+            }
+            ComputeResponse::ParseProgress { .. } | ComputeResponse::Parsed { .. } => {}
+        }
+    }
+
+    /// Stop using the worker and compute pending transforms locally
+    fn fall_back(&self) {
+        let worker = self.worker.try_update_value(Option::take).flatten();
+        // We may be inside one of the worker's own handlers: drop it later
+        leptos::task::spawn_local(async move { drop(worker) });
+        self.synced.update_value(HashMap::clear);
+
+        let pending = self
+            .pending
+            .try_update_value(std::mem::take)
+            .unwrap_or_default();
+        for (_, request) in pending {
+            let result = transform_locally(
+                self.dashboard,
+                request.widget_type,
+                &request.dataset_id,
+                &request.mapping,
+            );
+            (request.callback)(result);
+        }
+    }
+}
+
+/// Compute the data of a widget on the main thread
+pub fn transform_locally(
+    dashboard: DashboardContext,
+    widget_type: WidgetType,
+    dataset_id: &DatasetId,
+    mapping: &DataMapping,
+) -> Result<Value, String> {
+    dashboard.datasets.with_untracked(|datasets| {
+        let dataset = datasets
+            .iter()
+            .find(|dataset| &dataset.id == dataset_id)
+            .ok_or_else(|| format!("Dataset not found: {}", dataset_id))?;
+        transform_widget(widget_type, dataset, mapping).map_err(|e| e.to_string())
+    })
+}
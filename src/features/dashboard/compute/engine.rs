@@ -0,0 +1,235 @@
+//! Compute engine: the work done on behalf of the main thread
+//!
+//! Plain Rust with no DOM access, so it runs unchanged in the worker and,
+//! as a fallback, on the main thread.
+
+use super::protocol::{ComputeRequest, ComputeResponse};
+use crate::features::dashboard::config::builders::{
+    AreaConfig, BarConfig, CandlestickConfig, HeatmapConfig, LineConfig, PieConfig, RadarConfig,
+    ScatterConfig, TreemapConfig,
+};
+use crate::features::dashboard::config::traits::{ConfigError, WidgetConfigBuilder};
+use crate::features::dashboard::csv_upload::{ChunkedCsvParser, ImportOptions, PARSE_CHUNK_ROWS};
+use crate::features::dashboard::{CsvError, DataMapping, Dataset, DatasetId, WidgetType};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Compute the data of a chart widget
+///
+/// The mapping's filters are applied first, so builders only see the rows
+/// passing them.
+///
+/// # Returns
+/// The builder's `Data` serialized as JSON, or an error for widgets that
+/// aren't built from transformed data (KPI, table, filter controls)
+pub fn transform_widget(
+    widget_type: WidgetType,
+    dataset: &Dataset,
+    mapping: &DataMapping,
+) -> Result<Value, ConfigError> {
+    let filtered = mapping.filters.apply(dataset);
+    let dataset = filtered.as_ref();
+    match widget_type {
+        WidgetType::Line => transform_with(LineConfig, dataset, mapping),
+        WidgetType::Bar => transform_with(BarConfig, dataset, mapping),
+        WidgetType::Pie => transform_with(PieConfig, dataset, mapping),
+        WidgetType::Scatter => transform_with(ScatterConfig, dataset, mapping),
+        WidgetType::Area => transform_with(AreaConfig, dataset, mapping),
+        WidgetType::Radar => transform_with(RadarConfig, dataset, mapping),
+        WidgetType::Candlestick => transform_with(CandlestickConfig, dataset, mapping),
+        WidgetType::Heatmap => transform_with(HeatmapConfig, dataset, mapping),
+        WidgetType::Treemap => transform_with(TreemapConfig, dataset, mapping),
+        WidgetType::Kpi
+        | WidgetType::Table
+        | WidgetType::FilterDropdown
+        | WidgetType::FilterDateRange
+        | WidgetType::FilterRange
+        | WidgetType::FilterSearch => Err(ConfigError::InvalidValue(format!(
+            "{} has no chart data",
+            widget_type.display_name()
+        ))),
+    }
+}
+
+fn transform_with<B: WidgetConfigBuilder>(
+    builder: B,
+    dataset: &Dataset,
+    mapping: &DataMapping,
+) -> Result<Value, ConfigError> {
+    let data = builder.transform_data(dataset, mapping)?;
+    serde_json::to_value(data).map_err(|e| ConfigError::SerializationError(e.to_string()))
+}
+
+/// Parse delimited text chunk by chunk, reporting progress after each chunk
+pub fn parse_csv_in_chunks(
+    text: &str,
+    filename: &str,
+    file_size: u64,
+    options: &ImportOptions,
+    mut on_progress: impl FnMut(f64, u64),
+) -> Result<Dataset, CsvError> {
+    let mut parser = ChunkedCsvParser::from_text(text, options)?;
+    while !parser.parse_chunk(PARSE_CHUNK_ROWS)? {
+        on_progress(parser.progress(), parser.rows_processed());
+    }
+    let (dataset, _fields) = parser.finish(filename, file_size)?;
+    Ok(dataset)
+}
+
+/// Worker-side state: the datasets transforms are computed from
+#[derive(Default)]
+pub struct ComputeEngine {
+    datasets: HashMap<DatasetId, Dataset>,
+}
+
+impl ComputeEngine {
+    /// Handle one request
+    ///
+    /// # Arguments
+    /// * `request` - Request posted by the main thread
+    /// * `respond` - Called with every response (progress and results)
+    pub fn handle(&mut self, request: ComputeRequest, respond: &mut dyn FnMut(ComputeResponse)) {
+        match request {
+            ComputeRequest::LoadDataset { dataset } => {
+                self.datasets.insert(dataset.id.clone(), *dataset);
+            }
+            ComputeRequest::RemoveDataset { dataset_id } => {
+                self.datasets.remove(&dataset_id);
+            }
+            ComputeRequest::Transform { request_id, widget_type, dataset_id, mapping } => {
+                let result = match self.datasets.get(&dataset_id) {
+                    Some(dataset) => {
+                        transform_widget(widget_type, dataset, &mapping).map_err(|e| e.to_string())
+                    }
+                    None => Err(format!("Dataset not loaded: {}", dataset_id)),
+                };
+                respond(ComputeResponse::Transformed { request_id, result });
+            }
+            ComputeRequest::ParseCsv { text, filename, file_size, options } => {
+                let result = parse_csv_in_chunks(&text, &filename, file_size, &options, |progress, rows_processed| {
+                    respond(ComputeResponse::ParseProgress { progress, rows_processed })
+                });
+                respond(ComputeResponse::Parsed { result: result.map(Box::new) });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::dashboard::data::DataTable;
+    use crate::features::dashboard::{CsvDialect, Field, FieldType};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn dataset() -> Dataset {
+        let fields = vec![
+            Field { name: "Month".to_string(), field_type: FieldType::Text, format: None },
+            Field { name: "Sales".to_string(), field_type: FieldType::Numeric, format: None },
+        ];
+        Dataset {
+            id: "sales".to_string(),
+            name: "sales.csv".to_string(),
+            size: "0 MB".to_string(),
+            uploaded_at: "Today".to_string(),
+            data: DataTable::from_rows(&fields, vec![
+                vec![json!("Jan"), json!(10)],
+                vec![json!("Jan"), json!(5)],
+                vec![json!("Feb"), json!(7)],
+            ]),
+            fields,
+            active: true,
+            dialect: None,
+            calculated_fields: Vec::new(),
+            derived_from: None,
+        }
+    }
+
+    fn mapping() -> DataMapping {
+        DataMapping {
+            x_axis: Some("Month".to_string()),
+            y_axis: vec!["Sales".to_string()],
+            ..Default::default()
+        }
+    }
+
+    fn run(engine: &mut ComputeEngine, request: ComputeRequest) -> Vec<ComputeResponse> {
+        let mut responses = Vec::new();
+        engine.handle(request, &mut |response| responses.push(response));
+        responses
+    }
+
+    #[test]
+    fn test_transform_matches_builder_data() {
+        let mut engine = ComputeEngine::default();
+        run(&mut engine, ComputeRequest::LoadDataset { dataset: Box::new(dataset()) });
+
+        let responses = run(&mut engine, ComputeRequest::Transform {
+            request_id: 1,
+            widget_type: WidgetType::Bar,
+            dataset_id: "sales".to_string(),
+            mapping: Box::new(mapping()),
+        });
+
+        let expected = BarConfig.transform_data(&dataset(), &mapping()).unwrap();
+        let [ComputeResponse::Transformed { request_id: 1, result: Ok(value) }] = responses.as_slice() else {
+            panic!("unexpected responses: {:?}", responses);
+        };
+        assert_eq!(*value, serde_json::to_value(expected).unwrap());
+    }
+
+    #[test]
+    fn test_transform_unknown_dataset_and_kpi_fail() {
+        let mut engine = ComputeEngine::default();
+        let responses = run(&mut engine, ComputeRequest::Transform {
+            request_id: 2,
+            widget_type: WidgetType::Line,
+            dataset_id: "missing".to_string(),
+            mapping: Box::new(mapping()),
+        });
+        assert!(matches!(responses[0], ComputeResponse::Transformed { result: Err(_), .. }));
+
+        assert!(transform_widget(WidgetType::Kpi, &dataset(), &mapping()).is_err());
+    }
+
+    #[test]
+    fn test_transform_applies_filters() {
+        use crate::features::dashboard::data::filter::{Filter, FilterNode, FilterOperator};
+
+        let mut filtered = mapping();
+        filtered.filters.filters.push(FilterNode::Condition(Filter {
+            field: "Sales".to_string(),
+            operator: FilterOperator::GreaterThan,
+            values: vec![json!(6)],
+        }));
+        let value = transform_widget(WidgetType::Bar, &dataset(), &filtered).unwrap();
+
+        let expected_rows = DataTable::from_rows(&dataset().fields, vec![
+            vec![json!("Jan"), json!(10)],
+            vec![json!("Feb"), json!(7)],
+        ]);
+        let expected = BarConfig.transform_data(&Dataset { data: expected_rows, ..dataset() }, &mapping()).unwrap();
+        assert_eq!(value, serde_json::to_value(expected).unwrap());
+    }
+
+    #[test]
+    fn test_parse_csv_reports_progress() {
+        let text: String = std::iter::once("id,value\n".to_string())
+            .chain((0..PARSE_CHUNK_ROWS * 2).map(|i| format!("{},{}\n", i, i * 2)))
+            .collect();
+        let mut engine = ComputeEngine::default();
+        let responses = run(&mut engine, ComputeRequest::ParseCsv {
+            text: Arc::new(text),
+            filename: "big.csv".to_string(),
+            file_size: 1024,
+            options: ImportOptions::new(CsvDialect::default()),
+        });
+
+        assert!(matches!(responses[0], ComputeResponse::ParseProgress { .. }));
+        let Some(ComputeResponse::Parsed { result: Ok(dataset) }) = responses.last() else {
+            panic!("missing parse result");
+        };
+        assert_eq!(dataset.data.len(), PARSE_CHUNK_ROWS * 2);
+    }
+}
@@ -0,0 +1,20 @@
+//! Compute subsystem
+//!
+//! CSV parsing and widget data transforms run in a Web Worker (the
+//! `compute_worker` binary) so large datasets don't freeze the UI. The
+//! main thread and the worker exchange JSON messages: the worker keeps a
+//! copy of the dashboard's datasets and answers transform requests with
+//! the widget data, which the main thread turns into ECharts options.
+//!
+//! Everything falls back to the main thread when no worker is available.
+
+pub mod chart_options;
+pub mod client;
+pub mod engine;
+pub mod protocol;
+pub mod worker;
+
+pub use chart_options::use_chart_options;
+pub use client::{spawn_parse_worker, ComputeClient, ComputeWorker};
+pub use protocol::ComputeResponse;
+pub use worker::run_compute_worker;
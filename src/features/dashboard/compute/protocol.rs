@@ -0,0 +1,157 @@
+//! Messages exchanged between the main thread and the compute worker
+//!
+//! Messages cross the worker boundary as JSON in an `ArrayBuffer` that is
+//! transferred rather than copied. The text of a `ParseCsv` request follows
+//! in a second buffer as raw bytes instead of being escaped into the JSON.
+
+use crate::features::dashboard::csv_upload::ImportOptions;
+use crate::features::dashboard::{CsvError, DataMapping, Dataset, DatasetId, WidgetType};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+
+/// Request posted to the compute worker
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ComputeRequest {
+    /// Store (or replace) a dataset in the worker
+    LoadDataset { dataset: Box<Dataset> },
+    /// Forget a dataset
+    RemoveDataset { dataset_id: DatasetId },
+    /// Compute the data of a widget from a loaded dataset
+    Transform {
+        request_id: u64,
+        widget_type: WidgetType,
+        dataset_id: DatasetId,
+        mapping: Box<DataMapping>,
+    },
+    /// Parse delimited text into a Dataset, reporting progress per chunk
+    ParseCsv {
+        /// Sent alongside the JSON (see `to_message`)
+        #[serde(skip)]
+        text: Arc<String>,
+        filename: String,
+        file_size: u64,
+        options: ImportOptions,
+    },
+}
+
+impl ComputeRequest {
+    /// Encode the request for `postMessage`
+    ///
+    /// # Returns
+    /// The JSON-encoded request and the raw bytes posted with it (the text
+    /// of a `ParseCsv` request)
+    pub fn to_message(&self) -> Result<(Vec<u8>, Option<&[u8]>), String> {
+        let json = serde_json::to_vec(self).map_err(|e| e.to_string())?;
+        let payload = match self {
+            Self::ParseCsv { text, .. } => Some(text.as_bytes()),
+            _ => None,
+        };
+        Ok((json, payload))
+    }
+
+    /// Decode a request posted to the worker
+    pub fn from_message(json: &[u8], payload: Option<Vec<u8>>) -> Result<Self, String> {
+        let mut request: Self = serde_json::from_slice(json).map_err(|e| e.to_string())?;
+        if let Self::ParseCsv { text, .. } = &mut request {
+            let bytes = payload.ok_or_else(|| "missing text of ParseCsv".to_string())?;
+            *text = Arc::new(String::from_utf8(bytes).map_err(|e| e.to_string())?);
+        }
+        Ok(request)
+    }
+}
+
+/// Response posted back by the compute worker
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ComputeResponse {
+    /// Widget data of a `Transform` request, as the builder's serialized `Data`
+    Transformed {
+        request_id: u64,
+        result: Result<Value, String>,
+    },
+    /// A chunk of a `ParseCsv` request has been parsed
+    ParseProgress { progress: f64, rows_processed: u64 },
+    /// Result of a `ParseCsv` request
+    Parsed { result: Result<Box<Dataset>, CsvError> },
+    /// The worker failed to start or crashed (raised on the main thread)
+    WorkerError { message: String },
+}
+
+impl ComputeResponse {
+    /// Encode the response for `postMessage`
+    pub fn to_message(&self) -> Result<Vec<u8>, String> {
+        serde_json::to_vec(self).map_err(|e| e.to_string())
+    }
+
+    /// Decode a message posted by the worker
+    pub fn from_message(json: &[u8]) -> Result<Self, String> {
+        serde_json::from_slice(json).map_err(|e| e.to_string())
+    }
+}
+
+/// Wrap a message's buffers for `postMessage`
+///
+/// # Returns
+/// The message (an array of `ArrayBuffer`s) and the list of buffers to
+/// transfer with it
+pub fn to_js_message(json: &[u8], payload: Option<&[u8]>) -> (js_sys::Array, js_sys::Array) {
+    let buffers: js_sys::Array = std::iter::once(json)
+        .chain(payload)
+        .map(|bytes| js_sys::Uint8Array::from(bytes).buffer())
+        .collect();
+    (buffers.clone(), buffers)
+}
+
+/// Unwrap the buffers of a message built by `to_js_message`
+///
+/// # Returns
+/// The JSON and the payload that followed it, if any
+pub fn from_js_message(data: &wasm_bindgen::JsValue) -> Result<(Vec<u8>, Option<Vec<u8>>), String> {
+    use wasm_bindgen::JsCast;
+
+    let buffers = data.dyn_ref::<js_sys::Array>().ok_or_else(|| "not a message array".to_string())?;
+    let mut bytes = buffers.iter().map(|buffer| js_sys::Uint8Array::new(&buffer).to_vec());
+    let json = bytes.next().ok_or_else(|| "empty message".to_string())?;
+    Ok((json, bytes.next()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::dashboard::CsvDialect;
+
+    #[test]
+    fn test_messages_round_trip() {
+        let request = ComputeRequest::Transform {
+            request_id: 7,
+            widget_type: WidgetType::Bar,
+            dataset_id: "ds_1".to_string(),
+            mapping: Box::default(),
+        };
+        let (json, payload) = request.to_message().unwrap();
+        assert_eq!(payload, None);
+        assert_eq!(ComputeRequest::from_message(&json, None).unwrap(), request);
+
+        let response = ComputeResponse::Parsed { result: Err(CsvError::EmptyFile) };
+        assert_eq!(ComputeResponse::from_message(&response.to_message().unwrap()).unwrap(), response);
+    }
+
+    #[test]
+    fn test_parse_text_travels_outside_the_json() {
+        let request = ComputeRequest::ParseCsv {
+            text: Arc::new("name,\"quoted\"\nA,1\n".to_string()),
+            filename: "a.csv".to_string(),
+            file_size: 14,
+            options: ImportOptions::new(CsvDialect::default()),
+        };
+        let (json, payload) = request.to_message().unwrap();
+        assert!(!String::from_utf8_lossy(&json).contains("quoted"));
+        assert_eq!(payload, Some("name,\"quoted\"\nA,1\n".as_bytes()));
+
+        let decoded = ComputeRequest::from_message(&json, payload.map(<[u8]>::to_vec)).unwrap();
+        assert_eq!(decoded, request);
+        assert!(ComputeRequest::from_message(&json, None).is_err());
+    }
+}
@@ -0,0 +1,40 @@
+//! Worker side of the compute subsystem
+
+use super::engine::ComputeEngine;
+use super::protocol::{from_js_message, to_js_message, ComputeRequest, ComputeResponse};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{DedicatedWorkerGlobalScope, MessageEvent};
+
+/// Serve compute requests posted to this worker until it is terminated
+///
+/// Called from the `compute_worker` binary.
+pub fn run_compute_worker() {
+    let scope: DedicatedWorkerGlobalScope = js_sys::global().unchecked_into();
+    let reply_scope = scope.clone();
+    let mut engine = ComputeEngine::default();
+
+    let on_message = Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+        let request = from_js_message(&event.data())
+            .and_then(|(json, payload)| ComputeRequest::from_message(&json, payload));
+        match request {
+            Ok(request) => engine.handle(request, &mut |response| post(&reply_scope, &response)),
+            Err(e) => log::error!("Compute worker: invalid request: {}", e),
+        }
+    });
+    scope.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+    // The handler lives as long as the worker
+    on_message.forget();
+}
+
+fn post(scope: &DedicatedWorkerGlobalScope, response: &ComputeResponse) {
+    match response.to_message() {
+        Ok(json) => {
+            let (message, transfer) = to_js_message(&json, None);
+            if let Err(e) = scope.post_message_with_transfer(&message, &transfer) {
+                log::error!("Compute worker: failed to post response: {:?}", e);
+            }
+        }
+        Err(e) => log::error!("Compute worker: failed to encode response: {}", e),
+    }
+}
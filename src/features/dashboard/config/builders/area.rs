@@ -4,7 +4,7 @@ use crate::features::dashboard::config::traits::{WidgetConfigBuilder, FieldRequi
 use crate::features::dashboard::config::style::AreaStyleOptions;
 use crate::features::dashboard::config::theme_colors::{ChartColors, lighten_color};
 use crate::features::dashboard::models::{Dataset, DataMapping, FieldType};
-use crate::features::dashboard::data::{dataset_to_echarts_format, AggregationFunction};
+use crate::features::dashboard::data::{series_data, SeriesData};
 use serde_json::json;
 
 /// Area widget configuration builder
@@ -14,25 +14,28 @@ pub struct AreaConfig;
 impl WidgetConfigBuilder for AreaConfig {
     type StyleOptions = AreaStyleOptions;
 
-    fn build_echarts_options(
+    type Data = SeriesData;
+
+    fn transform_data(&self, dataset: &Dataset, mapping: &DataMapping) -> Result<Self::Data, ConfigError> {
+        series_data(dataset, mapping)
+            .map_err(|e| ConfigError::DataTransformationError(e.to_string()))
+    }
+
+    fn build_options_from_data(
         &self,
-        dataset: &Dataset,
-        mapping: &DataMapping,
+        data: &Self::Data,
+        _mapping: &DataMapping,
         style: &Self::StyleOptions,
     ) -> Result<String, ConfigError> {
-        // Transform dataset to ECharts format
-        let echarts_data = dataset_to_echarts_format(
-            dataset,
-            mapping,
-            AggregationFunction::Sum,
-        ).map_err(|e| ConfigError::DataTransformationError(e.to_string()))?;
+        let echarts_data = &data.source;
 
         // Read colors from active DaisyUI theme
         let colors = ChartColors::from_daisyui_theme();
         let color_palette = colors.to_palette();
 
-        // Build series array based on y_axis fields
-        let num_series = mapping.y_axis.len();
+        // One series per column of the table: a measure, or a measure's
+        // category when the category field splits them
+        let num_series = data.series_count();
         let series: Vec<serde_json::Value> = (0..num_series).map(|i| {
             let series_color = color_palette[i % color_palette.len()].clone();
 
@@ -42,7 +45,7 @@ impl WidgetConfigBuilder for AreaConfig {
             json!({
                 "type": "line",
                 "smooth": style.smooth,
-                "stack": if style.stacked { "total" } else { "" },
+                "stack": style.stacked.then(|| data.stack(i)),
                 "lineStyle": {
                     "width": style.border_width as u32,
                     "color": series_color.clone()
@@ -104,9 +107,9 @@ impl WidgetConfigBuilder for AreaConfig {
             })
         }).collect();
 
-        // Build xAxis
+        // Build xAxis (a time axis for dates)
         let x_axis = json!({
-            "type": "category",
+            "type": if data.temporal { "time" } else { "category" },
             "boundaryGap": false,
             "axisLine": {
                 "lineStyle": { "color": colors.grid }
@@ -118,7 +121,7 @@ impl WidgetConfigBuilder for AreaConfig {
         });
 
         // Build yAxis
-        let y_axis = json!({
+        let mut y_axis = json!({
             "type": "value",
             "axisLine": {
                 "lineStyle": { "color": colors.grid }
@@ -135,6 +138,11 @@ impl WidgetConfigBuilder for AreaConfig {
             }
         });
 
+        // Currency / percent notation of the imported measures
+        if let Some(format) = &data.format {
+            y_axis["axisLabel"]["formatter"] = json!(format.axis_label_template());
+        }
+
         // Build complete ECharts options
         let options = json!({
             "dataset": {
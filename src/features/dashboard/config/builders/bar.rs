@@ -4,7 +4,7 @@ use crate::features::dashboard::config::traits::{WidgetConfigBuilder, FieldRequi
 use crate::features::dashboard::config::style::BarStyleOptions;
 use crate::features::dashboard::config::theme_colors::{ChartColors, lighten_color};
 use crate::features::dashboard::models::{Dataset, DataMapping, FieldType};
-use crate::features::dashboard::data::{dataset_to_echarts_format, AggregationFunction};
+use crate::features::dashboard::data::{series_data, SeriesData};
 use serde_json::json;
 
 /// Bar widget configuration builder
@@ -14,26 +14,29 @@ pub struct BarConfig;
 impl WidgetConfigBuilder for BarConfig {
     type StyleOptions = BarStyleOptions;
 
-    fn build_echarts_options(
+    type Data = SeriesData;
+
+    fn transform_data(&self, dataset: &Dataset, mapping: &DataMapping) -> Result<Self::Data, ConfigError> {
+        series_data(dataset, mapping)
+            .map_err(|e| ConfigError::DataTransformationError(e.to_string()))
+    }
+
+    fn build_options_from_data(
         &self,
-        dataset: &Dataset,
-        mapping: &DataMapping,
+        data: &Self::Data,
+        _mapping: &DataMapping,
         style: &Self::StyleOptions,
     ) -> Result<String, ConfigError> {
-        // Transform dataset to ECharts format
-        let echarts_data = dataset_to_echarts_format(
-            dataset,
-            mapping,
-            AggregationFunction::Sum,
-        ).map_err(|e| ConfigError::DataTransformationError(e.to_string()))?;
+        let echarts_data = &data.source;
 
         // Read colors from active DaisyUI theme
         let colors = ChartColors::from_daisyui_theme();
         let color_palette = colors.to_palette();
 
-        // Determine axis orientation
-        let x_axis_type = if style.horizontal { "value" } else { "category" };
-        let y_axis_type = if style.horizontal { "category" } else { "value" };
+        // Determine axis orientation; dates go on a time axis
+        let dimension_axis_type = if data.temporal { "time" } else { "category" };
+        let x_axis_type = if style.horizontal { "value" } else { dimension_axis_type };
+        let y_axis_type = if style.horizontal { dimension_axis_type } else { "value" };
 
         // SVG patterns for accessibility (colorblind support)
         let patterns = [
@@ -47,8 +50,9 @@ impl WidgetConfigBuilder for BarConfig {
             "circle",                               // 8. Dots
         ];
 
-        // Build series array based on y_axis fields
-        let num_series = mapping.y_axis.len();
+        // One series per column of the table: a measure, or a measure's
+        // category when the category field splits them
+        let num_series = data.series_count();
         let series: Vec<serde_json::Value> = (0..num_series).map(|i| {
             // Use colors from palette, cycling if more series than colors
             let series_color = color_palette[i % color_palette.len()].clone();
@@ -68,7 +72,7 @@ impl WidgetConfigBuilder for BarConfig {
 
             json!({
                 "type": "bar",
-                "stack": if style.stacked { serde_json::json!("total") } else { serde_json::Value::Null },
+                "stack": style.stacked.then(|| data.stack(i)),
                 "barMinWidth": if style.horizontal { Some(0) } else { None::<i32> },
                 "barMaxWidth": if style.horizontal { None::<i32> } else { Some(style.bar_width as i32) },
                 "itemStyle": {
@@ -171,6 +175,12 @@ impl WidgetConfigBuilder for BarConfig {
             y_axis["nameGap"] = json!(50);
         }
 
+        // Currency / percent notation of the imported measures goes on the value axis
+        if let Some(format) = &data.format {
+            let value_axis = if style.horizontal { &mut x_axis } else { &mut y_axis };
+            value_axis["axisLabel"]["formatter"] = json!(format.axis_label_template());
+        }
+
         // Build title option
         let title_option = style.title.as_ref().map(|t| json!({
             "text": t,
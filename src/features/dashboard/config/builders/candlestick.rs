@@ -54,21 +54,11 @@ impl CandlestickConfig {
         let mut echarts_data = vec![header];
 
         // Transform data rows
-        for row in &dataset.data {
-            if let (Some(date), Some(open), Some(close), Some(low), Some(high)) = (
-                row.get(date_idx),
-                row.get(open_idx),
-                row.get(close_idx),
-                row.get(low_idx),
-                row.get(high_idx),
-            ) {
-                echarts_data.push(vec![
-                    date.clone(),
-                    open.clone(),
-                    close.clone(),
-                    low.clone(),
-                    high.clone(),
-                ]);
+        // Rows with a missing date or price are skipped instead of drawn at 0
+        let columns = [date_idx, open_idx, close_idx, low_idx, high_idx].map(|idx| dataset.data.column(idx));
+        for row in 0..dataset.data.len() {
+            if columns.iter().all(|column| !column.is_null(row)) {
+                echarts_data.push(columns.iter().map(|column| column.value(row)).collect());
             }
         }
 
@@ -79,14 +69,19 @@ impl CandlestickConfig {
 impl WidgetConfigBuilder for CandlestickConfig {
     type StyleOptions = CandlestickStyleOptions;
 
-    fn build_echarts_options(
+    type Data = Vec<Vec<Value>>;
+
+    fn transform_data(&self, dataset: &Dataset, mapping: &DataMapping) -> Result<Self::Data, ConfigError> {
+        Self::dataset_to_candlestick_format(dataset, mapping)
+    }
+
+    fn build_options_from_data(
         &self,
-        dataset: &Dataset,
-        mapping: &DataMapping,
+        data: &Self::Data,
+        _mapping: &DataMapping,
         style: &Self::StyleOptions,
     ) -> Result<String, ConfigError> {
-        // Transform dataset to candlestick format
-        let echarts_data = Self::dataset_to_candlestick_format(dataset, mapping)?;
+        let echarts_data = data;
 
         // Read colors from active DaisyUI theme
         let colors = ChartColors::from_daisyui_theme();
@@ -3,9 +3,15 @@
 use crate::features::dashboard::config::traits::{WidgetConfigBuilder, FieldRequirement, ConfigError};
 use crate::features::dashboard::config::style::HeatmapStyleOptions;
 use crate::features::dashboard::config::theme_colors::ChartColors;
+use crate::features::dashboard::cross_filter::element_name;
 use crate::features::dashboard::models::{Dataset, DataMapping, FieldType};
-use crate::features::dashboard::data::transform::find_field_index;
+use crate::features::dashboard::data::aggregation::aggregate_field;
+use crate::features::dashboard::data::transform::{find_field_index, value_label};
 use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Heatmap cells plus the x and y category labels
+type HeatmapData = (Vec<Vec<Value>>, Vec<String>, Vec<String>);
 
 /// Heatmap widget configuration builder
 #[derive(Clone, Copy)]
@@ -18,7 +24,7 @@ impl HeatmapConfig {
     fn dataset_to_heatmap_format(
         dataset: &Dataset,
         mapping: &DataMapping,
-    ) -> Result<(Vec<Vec<Value>>, Vec<String>, Vec<String>), ConfigError> {
+    ) -> Result<HeatmapData, ConfigError> {
         // For heatmap, we need x_axis (x categories), category (y categories), and y_axis[0] (values)
         let x_idx = find_field_index(&dataset.fields, &mapping.x_axis)
             .map_err(|e| ConfigError::DataTransformationError(e.to_string()))?;
@@ -33,63 +39,79 @@ impl HeatmapConfig {
             return Err(ConfigError::MissingField("Value field is required".to_string()));
         };
 
-        // Collect unique categories for both axes
-        let mut x_categories: Vec<String> = Vec::new();
-        let mut y_categories: Vec<String> = Vec::new();
+        // Collect unique categories for both axes, in order of appearance;
+        // null categories are skipped rather than collapsed into ""
+        let x_groups = dataset.data.column(x_idx).group_rows();
+        let y_groups = dataset.data.column(y_category_idx).group_rows();
+        let (x_categories, x_positions) = category_positions(&x_groups.keys);
+        let (y_categories, y_positions) = category_positions(&y_groups.keys);
 
-        for row in &dataset.data {
-            if let Some(x_val) = row.get(x_idx) {
-                let x_str = x_val.as_str().unwrap_or("").to_string();
-                if !x_categories.contains(&x_str) {
-                    x_categories.push(x_str);
-                }
-            }
-            if let Some(y_val) = row.get(y_category_idx) {
-                let y_str = y_val.as_str().unwrap_or("").to_string();
-                if !y_categories.contains(&y_str) {
-                    y_categories.push(y_str);
-                }
+        // Group the rows of each cell, in order of appearance
+        let mut cells: Vec<((usize, usize), Vec<usize>)> = Vec::new();
+        let mut cell_index: HashMap<(usize, usize), usize> = HashMap::new();
+
+        for row in 0..dataset.data.len() {
+            if let (Some(x_i), Some(y_i)) = (
+                x_positions[x_groups.codes[row] as usize],
+                y_positions[y_groups.codes[row] as usize],
+            ) {
+                let index = *cell_index.entry((x_i, y_i)).or_insert_with(|| {
+                    cells.push(((x_i, y_i), Vec::new()));
+                    cells.len() - 1
+                });
+                cells[index].1.push(row);
             }
         }
 
         // Build heatmap data: [[x_index, y_index, value], ...]
-        let mut heatmap_data: Vec<Vec<Value>> = Vec::new();
-
-        for row in &dataset.data {
-            if let (Some(x_val), Some(y_val), Some(value)) =
-                (row.get(x_idx), row.get(y_category_idx), row.get(value_idx)) {
-                let x_str = x_val.as_str().unwrap_or("");
-                let y_str = y_val.as_str().unwrap_or("");
-
-                if let (Some(x_i), Some(y_i)) = (
-                    x_categories.iter().position(|c| c == x_str),
-                    y_categories.iter().position(|c| c == y_str),
-                ) {
-                    heatmap_data.push(vec![
-                        json!(x_i),
-                        json!(y_i),
-                        value.clone(),
-                    ]);
-                }
-            }
-        }
+        // Cells whose values are all null are left empty
+        let agg_fn = mapping.aggregation(&mapping.y_axis[0]);
+        let heatmap_data: Vec<Vec<Value>> = cells
+            .into_iter()
+            .filter_map(|((x_i, y_i), rows)| {
+                let value = aggregate_field(dataset, value_idx, rows, &agg_fn)?;
+                Some(vec![json!(x_i), json!(y_i), json!(value)])
+            })
+            .collect();
 
         Ok((heatmap_data, x_categories, y_categories))
     }
 }
 
+/// Category labels of the distinct values of a column, and the position
+/// of each distinct value among them (`None` for null)
+fn category_positions(keys: &[Value]) -> (Vec<String>, Vec<Option<usize>>) {
+    let mut categories: Vec<String> = Vec::new();
+    let positions = keys
+        .iter()
+        .map(|key| {
+            let label = value_label(key)?;
+            // Distinct values can share a label (e.g. `1` and `"1"`)
+            Some(categories.iter().position(|c| *c == label).unwrap_or_else(|| {
+                categories.push(label);
+                categories.len() - 1
+            }))
+        })
+        .collect();
+    (categories, positions)
+}
+
 impl WidgetConfigBuilder for HeatmapConfig {
     type StyleOptions = HeatmapStyleOptions;
 
-    fn build_echarts_options(
+    type Data = HeatmapData;
+
+    fn transform_data(&self, dataset: &Dataset, mapping: &DataMapping) -> Result<Self::Data, ConfigError> {
+        Self::dataset_to_heatmap_format(dataset, mapping)
+    }
+
+    fn build_options_from_data(
         &self,
-        dataset: &Dataset,
-        mapping: &DataMapping,
+        data: &Self::Data,
+        _mapping: &DataMapping,
         style: &Self::StyleOptions,
     ) -> Result<String, ConfigError> {
-        // Transform dataset to heatmap format
-        let (heatmap_data, x_categories, y_categories) =
-            Self::dataset_to_heatmap_format(dataset, mapping)?;
+        let (heatmap_data, x_categories, y_categories) = data;
 
         // Read colors from active DaisyUI theme
         let colors = ChartColors::from_daisyui_theme();
@@ -101,10 +123,22 @@ impl WidgetConfigBuilder for HeatmapConfig {
             vec!["#313695".to_string(), "#a50026".to_string()]  // Blue to Red
         };
 
+        // Cells are named by their categories, so selected ones can be highlighted
+        let label = |categories: &[String], idx: &Value| {
+            idx.as_u64().and_then(|i| categories.get(i as usize)).map_or(Value::Null, |label| json!(label))
+        };
+        let cells: Vec<Value> = heatmap_data
+            .iter()
+            .map(|cell| {
+                let name = element_name([&label(x_categories, &cell[0]), &label(y_categories, &cell[1])]);
+                json!({ "name": name, "value": cell })
+            })
+            .collect();
+
         // Build series
         let series = json!({
             "type": "heatmap",
-            "data": heatmap_data,
+            "data": cells,
             "label": if style.show_values {
                 Some(json!({
                     "show": true,
@@ -4,7 +4,7 @@ use crate::features::dashboard::config::traits::{WidgetConfigBuilder, FieldRequi
 use crate::features::dashboard::config::style::LineStyleOptions;
 use crate::features::dashboard::config::theme_colors::{ChartColors, lighten_color};
 use crate::features::dashboard::models::{Dataset, DataMapping, FieldType};
-use crate::features::dashboard::data::{dataset_to_echarts_format, AggregationFunction};
+use crate::features::dashboard::data::{series_data, SeriesData};
 use serde_json::json;
 
 /// Line widget configuration builder
@@ -14,18 +14,20 @@ pub struct LineConfig;
 impl WidgetConfigBuilder for LineConfig {
     type StyleOptions = LineStyleOptions;
 
-    fn build_echarts_options(
+    type Data = SeriesData;
+
+    fn transform_data(&self, dataset: &Dataset, mapping: &DataMapping) -> Result<Self::Data, ConfigError> {
+        series_data(dataset, mapping)
+            .map_err(|e| ConfigError::DataTransformationError(e.to_string()))
+    }
+
+    fn build_options_from_data(
         &self,
-        dataset: &Dataset,
-        mapping: &DataMapping,
+        data: &Self::Data,
+        _mapping: &DataMapping,
         style: &Self::StyleOptions,
     ) -> Result<String, ConfigError> {
-        // Transform dataset to ECharts format
-        let echarts_data = dataset_to_echarts_format(
-            dataset,
-            mapping,
-            AggregationFunction::Sum, // Default aggregation
-        ).map_err(|e| ConfigError::DataTransformationError(e.to_string()))?;
+        let echarts_data = &data.source;
 
         // Read colors from active DaisyUI theme
         let colors = ChartColors::from_daisyui_theme();
@@ -43,8 +45,9 @@ impl WidgetConfigBuilder for LineConfig {
             "circle",                               // 8. Dots
         ];
 
-        // Build series array based on y_axis fields
-        let num_series = mapping.y_axis.len();
+        // One series per column of the table: a measure, or a measure's
+        // category when the category field splits them
+        let num_series = data.series_count();
         let series: Vec<serde_json::Value> = (0..num_series).map(|i| {
             let is_first = i == 0;
             // Use colors from palette, cycling if more series than colors
@@ -54,6 +57,7 @@ impl WidgetConfigBuilder for LineConfig {
             json!({
                 "type": "line",
                 "smooth": style.smooth,
+                "stack": style.stacked.then(|| data.stack(i)),
                 "lineStyle": {
                     "width": style.line_width as u32,
                     "color": series_color.clone()
@@ -136,9 +140,9 @@ impl WidgetConfigBuilder for LineConfig {
             })
         }).collect();
 
-        // Build xAxis with optional title
+        // Build xAxis with optional title (a time axis for dates)
         let mut x_axis = json!({
-            "type": "category",
+            "type": if data.temporal { "time" } else { "category" },
             "boundaryGap": false,
             "axisLine": {
                 "lineStyle": { "color": colors.grid }
@@ -176,6 +180,10 @@ impl WidgetConfigBuilder for LineConfig {
                 "fontSize": 11
             }
         });
+        // Currency / percent notation of the imported measures
+        if let Some(format) = &data.format {
+            y_axis["axisLabel"]["formatter"] = json!(format.axis_label_template());
+        }
         if let Some(ref title) = style.y_axis_title {
             y_axis["name"] = json!(title);
             y_axis["nameLocation"] = json!("middle");
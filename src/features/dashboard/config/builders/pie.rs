@@ -4,7 +4,7 @@ use crate::features::dashboard::config::traits::{WidgetConfigBuilder, FieldRequi
 use crate::features::dashboard::config::style::PieStyleOptions;
 use crate::features::dashboard::config::theme_colors::ChartColors;
 use crate::features::dashboard::models::{Dataset, DataMapping, FieldType};
-use crate::features::dashboard::data::{dataset_to_echarts_format, AggregationFunction};
+use crate::features::dashboard::data::{series_data, SeriesData};
 use serde_json::json;
 
 /// Pie widget configuration builder
@@ -14,18 +14,22 @@ pub struct PieConfig;
 impl WidgetConfigBuilder for PieConfig {
     type StyleOptions = PieStyleOptions;
 
-    fn build_echarts_options(
+    type Data = SeriesData;
+
+    fn transform_data(&self, dataset: &Dataset, mapping: &DataMapping) -> Result<Self::Data, ConfigError> {
+        // Slices are the x values: a category doesn't split a pie
+        let mapping = DataMapping { category: None, ..mapping.clone() };
+        series_data(dataset, &mapping)
+            .map_err(|e| ConfigError::DataTransformationError(e.to_string()))
+    }
+
+    fn build_options_from_data(
         &self,
-        dataset: &Dataset,
-        mapping: &DataMapping,
+        data: &Self::Data,
+        _mapping: &DataMapping,
         style: &Self::StyleOptions,
     ) -> Result<String, ConfigError> {
-        // Transform dataset to ECharts format
-        let echarts_data = dataset_to_echarts_format(
-            dataset,
-            mapping,
-            AggregationFunction::Sum,
-        ).map_err(|e| ConfigError::DataTransformationError(e.to_string()))?;
+        let echarts_data = &data.source;
 
         // Read colors from active DaisyUI theme
         let colors = ChartColors::from_daisyui_theme();
@@ -4,7 +4,8 @@ use crate::features::dashboard::config::traits::{WidgetConfigBuilder, FieldRequi
 use crate::features::dashboard::config::style::RadarStyleOptions;
 use crate::features::dashboard::config::theme_colors::ChartColors;
 use crate::features::dashboard::models::{Dataset, DataMapping, FieldType};
-use crate::features::dashboard::data::transform::find_field_index;
+use crate::features::dashboard::data::aggregation::{aggregate_field, number_or_null};
+use crate::features::dashboard::data::transform::{find_field_index, rank_items, OTHER_SERIES};
 use serde_json::{json, Value};
 
 /// Radar widget configuration builder
@@ -27,19 +28,31 @@ impl RadarConfig {
             .collect::<Result<Vec<_>, _>>()
             .map_err(|e| ConfigError::DataTransformationError(e.to_string()))?;
 
-        // Build radar indicator configuration
-        let indicators: Vec<Value> = mapping.y_axis.iter()
-            .map(|name| json!({ "name": name }))
+        if dataset.data.is_empty() {
+            let indicators = mapping.y_axis.iter().map(|name| json!({ "name": name })).collect();
+            return Ok((indicators, vec![]));
+        }
+
+        // Aggregate each indicator over all rows, with its own function
+        let values: Vec<Option<f64>> = indicator_indexes.iter().zip(&mapping.y_axis)
+            .map(|(&idx, name)| aggregate_field(dataset, idx, 0..dataset.data.len(), &mapping.aggregation(name)))
             .collect();
 
-        // Get first row of data for demo (or aggregate multiple rows)
-        let data_values: Vec<Value> = if let Some(row) = dataset.data.first() {
-            indicator_indexes.iter()
-                .filter_map(|&idx| row.get(idx).cloned())
-                .collect()
-        } else {
-            vec![]
-        };
+        // Indicators in the mapping's order; those beyond its top-N limit
+        // are summed into an "Other" indicator
+        let labels: Vec<Option<String>> = mapping.y_axis.iter().cloned().map(Some).collect();
+        let ranked = rank_items(&labels, &values, &mapping.sort, mapping.top_n);
+        let mut indicators: Vec<Value> = ranked.shown.iter()
+            .map(|&idx| json!({ "name": mapping.y_axis[idx] }))
+            .collect();
+        let mut data_values: Vec<Value> = ranked.shown.iter()
+            .map(|&idx| number_or_null(values[idx]))
+            .collect();
+        if !ranked.others.is_empty() {
+            let others = ranked.others.iter().filter_map(|&idx| values[idx]);
+            indicators.push(json!({ "name": OTHER_SERIES }));
+            data_values.push(number_or_null(others.reduce(|total, value| total + value)));
+        }
 
         Ok((indicators, data_values))
     }
@@ -48,14 +61,19 @@ impl RadarConfig {
 impl WidgetConfigBuilder for RadarConfig {
     type StyleOptions = RadarStyleOptions;
 
-    fn build_echarts_options(
+    type Data = (Vec<Value>, Vec<Value>);
+
+    fn transform_data(&self, dataset: &Dataset, mapping: &DataMapping) -> Result<Self::Data, ConfigError> {
+        Self::dataset_to_radar_format(dataset, mapping)
+    }
+
+    fn build_options_from_data(
         &self,
-        dataset: &Dataset,
-        mapping: &DataMapping,
+        data: &Self::Data,
+        _mapping: &DataMapping,
         style: &Self::StyleOptions,
     ) -> Result<String, ConfigError> {
-        // Transform dataset to radar format
-        let (indicators, data_values) = Self::dataset_to_radar_format(dataset, mapping)?;
+        let (indicators, data_values) = data;
 
         // Read colors from active DaisyUI theme
         let colors = ChartColors::from_daisyui_theme();
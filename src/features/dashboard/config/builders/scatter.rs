@@ -3,9 +3,12 @@
 use crate::features::dashboard::config::traits::{WidgetConfigBuilder, FieldRequirement, ConfigError};
 use crate::features::dashboard::config::style::ScatterStyleOptions;
 use crate::features::dashboard::config::theme_colors::{ChartColors, lighten_color};
+use crate::features::dashboard::cross_filter::scatter_point_name;
 use crate::features::dashboard::models::{Dataset, DataMapping, FieldType};
+use crate::features::dashboard::data::aggregation::{aggregate_field, number_or_null, AggregationFunction};
 use crate::features::dashboard::data::transform::find_field_index;
 use serde_json::{json, Value};
+use std::collections::HashMap;
 
 /// Scatter widget configuration builder
 #[derive(Clone, Copy)]
@@ -21,6 +24,10 @@ impl ScatterConfig {
     ///   ...
     /// ]
     /// ```
+    ///
+    /// Each row is a point, unless an aggregation was chosen for the Y
+    /// measure: then there is one point per X value (and color), with Y
+    /// and size aggregated.
     fn dataset_to_scatter_format(
         dataset: &Dataset,
         mapping: &DataMapping,
@@ -45,26 +52,66 @@ impl ScatterConfig {
         // Transform data rows
         let mut scatter_data: Vec<Vec<Value>> = Vec::new();
 
-        for row in &dataset.data {
-            if let (Some(x_val), Some(y_val)) = (row.get(x_idx), row.get(y_idx)) {
-                let mut data_point = vec![x_val.clone(), y_val.clone()];
+        let x_column = dataset.data.column(x_idx);
+        let y_column = dataset.data.column(y_idx);
+        let size_column = size_idx.map(|idx| dataset.data.column(idx));
+        let color_column = color_idx.map(|idx| dataset.data.column(idx));
 
-                // Add size dimension if specified
-                if let Some(size_i) = size_idx {
-                    if let Some(size_val) = row.get(size_i) {
-                        data_point.push(size_val.clone());
-                    }
-                }
+        if let Some(agg_fn) = mapping.aggregations.get(&mapping.y_axis[0])
+            && *agg_fn != AggregationFunction::None
+        {
+            let x_groups = x_column.group_rows();
+            let color_groups = color_column.map(|column| column.group_rows());
 
-                // Add color dimension if specified
-                if let Some(color_i) = color_idx {
-                    if let Some(color_val) = row.get(color_i) {
-                        data_point.push(color_val.clone());
-                    }
+            // Rows of each (x, color) point, in order of appearance
+            let mut points: Vec<((u32, u32), Vec<usize>)> = Vec::new();
+            let mut point_index: HashMap<(u32, u32), usize> = HashMap::new();
+            for row in 0..dataset.data.len() {
+                if x_column.is_null(row) {
+                    continue;
                 }
+                let key = (x_groups.codes[row], color_groups.as_ref().map_or(0, |groups| groups.codes[row]));
+                let index = *point_index.entry(key).or_insert_with(|| {
+                    points.push((key, Vec::new()));
+                    points.len() - 1
+                });
+                points[index].1.push(row);
+            }
 
+            for ((x_code, color_code), rows) in points {
+                let Some(y) = aggregate_field(dataset, y_idx, rows.iter().copied(), agg_fn) else {
+                    continue;
+                };
+                let mut data_point = vec![x_groups.keys[x_code as usize].clone(), json!(y)];
+                if let Some(size_idx) = size_idx {
+                    data_point.push(number_or_null(aggregate_field(dataset, size_idx, rows.iter().copied(), agg_fn)));
+                }
+                if let Some(groups) = &color_groups {
+                    data_point.push(groups.keys[color_code as usize].clone());
+                }
                 scatter_data.push(data_point);
             }
+            return Ok(scatter_data);
+        }
+
+        for row in 0..dataset.data.len() {
+            // Points without both coordinates can't be plotted
+            if x_column.is_null(row) || y_column.is_null(row) {
+                continue;
+            }
+            let mut data_point = vec![x_column.value(row), y_column.value(row)];
+
+            // Add size dimension if specified
+            if let Some(size_column) = size_column {
+                data_point.push(size_column.value(row));
+            }
+
+            // Add color dimension if specified
+            if let Some(color_column) = color_column {
+                data_point.push(color_column.value(row));
+            }
+
+            scatter_data.push(data_point);
         }
 
         Ok(scatter_data)
@@ -74,14 +121,19 @@ impl ScatterConfig {
 impl WidgetConfigBuilder for ScatterConfig {
     type StyleOptions = ScatterStyleOptions;
 
-    fn build_echarts_options(
+    type Data = Vec<Vec<Value>>;
+
+    fn transform_data(&self, dataset: &Dataset, mapping: &DataMapping) -> Result<Self::Data, ConfigError> {
+        Self::dataset_to_scatter_format(dataset, mapping)
+    }
+
+    fn build_options_from_data(
         &self,
-        dataset: &Dataset,
+        data: &Self::Data,
         mapping: &DataMapping,
         style: &Self::StyleOptions,
     ) -> Result<String, ConfigError> {
-        // Transform dataset to scatter format
-        let scatter_data = Self::dataset_to_scatter_format(dataset, mapping)?;
+        let scatter_data = data;
 
         // Read colors from active DaisyUI theme
         let colors = ChartColors::from_daisyui_theme();
@@ -96,10 +148,16 @@ impl WidgetConfigBuilder for ScatterConfig {
             json!(style.point_size_min as u32)
         };
 
+        // Points are named by what selects them, so selected ones can be highlighted
+        let points: Vec<Value> = scatter_data
+            .iter()
+            .map(|point| json!({ "name": scatter_point_name(mapping, point), "value": point }))
+            .collect();
+
         // Build series
         let series = json!({
             "type": "scatter",
-            "data": scatter_data,
+            "data": points,
             "symbolSize": symbol_size,
             "itemStyle": {
                 "color": primary_color.clone(),
@@ -4,9 +4,19 @@ use crate::features::dashboard::config::traits::{WidgetConfigBuilder, FieldRequi
 use crate::features::dashboard::config::style::TreemapStyleOptions;
 use crate::features::dashboard::config::theme_colors::ChartColors;
 use crate::features::dashboard::models::{Dataset, DataMapping, FieldType};
-use crate::features::dashboard::data::transform::find_field_index;
+use crate::features::dashboard::data::aggregation::aggregate_field;
+use crate::features::dashboard::data::transform::{find_field_index, rank_items, value_label, SortOrder, OTHER_SERIES};
 use serde_json::{json, Value};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+
+/// Node of the treemap being built
+struct TreeNode {
+    name: String,
+    /// Rows below the node
+    rows: Vec<usize>,
+    /// Child nodes, already converted (`None` for leaves)
+    children: Option<Vec<Value>>,
+}
 
 /// Treemap widget configuration builder
 #[derive(Clone, Copy)]
@@ -36,65 +46,56 @@ impl TreemapConfig {
             None
         };
 
-        // Build hierarchical structure
-        let mut root_children: HashMap<String, HashMap<String, Vec<Value>>> = HashMap::new();
+        // Group rows into leaves: level0 -> level1 -> leaf, names in order
+        let mut root_children: BTreeMap<String, BTreeMap<String, BTreeMap<String, Vec<usize>>>> = BTreeMap::new();
 
-        for row in &dataset.data {
-            // Extract hierarchy values
-            let hierarchy_values: Vec<String> = hierarchy_indexes.iter()
-                .filter_map(|&idx| {
-                    row.get(idx).and_then(|v| v.as_str()).map(|s| s.to_string())
-                })
-                .collect();
+        let hierarchy_columns: Vec<_> = hierarchy_indexes.iter()
+            .map(|&idx| dataset.data.column(idx))
+            .collect();
+
+        for row in 0..dataset.data.len() {
+            // Extract hierarchy values (rows with a null level are skipped)
+            let Some(hierarchy_values) = hierarchy_columns.iter()
+                .map(|column| value_label(&column.value(row)))
+                .collect::<Option<Vec<String>>>()
+            else {
+                continue;
+            };
 
             if hierarchy_values.len() < 2 {
                 continue;  // Need at least 2 levels
             }
 
-            // Get value (or default to 1 for counting)
-            let value = if let Some(v_idx) = value_idx {
-                row.get(v_idx)
-                    .and_then(|v| v.as_f64())
-                    .unwrap_or(1.0)
-            } else {
-                1.0
-            };
-
-            // Build hierarchy: level0 -> level1 -> level2...
+            // Leaves are the third level, or the second one repeated
             let level0 = &hierarchy_values[0];
             let level1 = &hierarchy_values[1];
+            let leaf = hierarchy_values.get(2).unwrap_or(level1);
 
-            let level0_map = root_children.entry(level0.clone()).or_insert_with(HashMap::new);
-            let level1_items = level0_map.entry(level1.clone()).or_insert_with(Vec::new);
-
-            // Add leaf node
-            if hierarchy_values.len() > 2 {
-                level1_items.push(json!({
-                    "name": hierarchy_values[2],
-                    "value": value
-                }));
-            } else {
-                level1_items.push(json!({
-                    "name": level1,
-                    "value": value
-                }));
-            }
+            root_children.entry(level0.clone()).or_default()
+                .entry(level1.clone()).or_default()
+                .entry(leaf.clone()).or_default()
+                .push(row);
         }
 
+        // Node value: the measure aggregated with its function, or the row
+        // count without a measure; leaves with no value are left out
+        let node_value = |rows: &[usize]| match value_idx {
+            Some(idx) => aggregate_field(dataset, idx, rows.iter().copied(), &mapping.aggregation(&mapping.y_axis[0])),
+            None => Some(rows.len() as f64),
+        };
+
         // Convert to ECharts tree structure
-        let children: Vec<Value> = root_children.into_iter().map(|(level0_name, level1_map)| {
-            let level1_children: Vec<Value> = level1_map.into_iter().map(|(level1_name, items)| {
-                json!({
-                    "name": level1_name,
-                    "children": items
-                })
+        let children = Self::ranked_nodes(root_children.into_iter().map(|(level0_name, level1_map)| {
+            let level1_nodes: Vec<TreeNode> = level1_map.into_iter().map(|(level1_name, leaves)| {
+                let leaf_nodes: Vec<TreeNode> = leaves.into_iter()
+                    .map(|(name, rows)| TreeNode { name, rows, children: None })
+                    .collect();
+                let rows = Self::node_rows(&leaf_nodes);
+                TreeNode { name: level1_name, rows, children: Some(Self::ranked_nodes(leaf_nodes, &node_value, mapping)) }
             }).collect();
-
-            json!({
-                "name": level0_name,
-                "children": level1_children
-            })
-        }).collect();
+            let rows = Self::node_rows(&level1_nodes);
+            TreeNode { name: level0_name, rows, children: Some(Self::ranked_nodes(level1_nodes, &node_value, mapping)) }
+        }).collect(), &node_value, mapping);
 
         Ok(json!({
             "name": "Root",
@@ -103,17 +104,72 @@ impl TreemapConfig {
     }
 }
 
+impl TreemapConfig {
+    /// Rows below a list of sibling nodes
+    fn node_rows(nodes: &[TreeNode]) -> Vec<usize> {
+        nodes.iter().flat_map(|node| node.rows.iter().copied()).collect()
+    }
+
+    /// Sibling nodes in the mapping's order, as ECharts tree nodes
+    ///
+    /// Siblings beyond the top-N limit are merged into an "Other" node:
+    /// leaves into one leaf aggregating their rows, inner nodes under it
+    /// as its children.
+    fn ranked_nodes(
+        nodes: Vec<TreeNode>,
+        node_value: &dyn Fn(&[usize]) -> Option<f64>,
+        mapping: &DataMapping,
+    ) -> Vec<Value> {
+        let labels: Vec<Option<String>> = nodes.iter().map(|node| Some(node.name.clone())).collect();
+        let values: Vec<Option<f64>> = nodes.iter().map(|node| node_value(&node.rows)).collect();
+        let ranked = rank_items(&labels, &values, &mapping.sort, mapping.top_n);
+
+        let to_json = |idx: usize| {
+            let node = &nodes[idx];
+            match &node.children {
+                Some(children) => Some(json!({ "name": node.name, "children": children })),
+                None => Some(json!({ "name": node.name, "value": values[idx]? })),
+            }
+        };
+        let mut converted: Vec<Value> = ranked.shown.iter().filter_map(|&idx| to_json(idx)).collect();
+        if let Some(&first_other) = ranked.others.first() {
+            let other = if nodes[first_other].children.is_none() {
+                let rows: Vec<usize> = ranked.others.iter().flat_map(|&idx| nodes[idx].rows.iter().copied()).collect();
+                node_value(&rows).map(|value| json!({ "name": OTHER_SERIES, "value": value }))
+            } else {
+                let children: Vec<Value> = ranked.others.iter().filter_map(|&idx| to_json(idx)).collect();
+                Some(json!({ "name": OTHER_SERIES, "children": children }))
+            };
+            converted.extend(other);
+        }
+        converted
+    }
+}
+
 impl WidgetConfigBuilder for TreemapConfig {
     type StyleOptions = TreemapStyleOptions;
 
-    fn build_echarts_options(
+    type Data = Value;
+
+    fn transform_data(&self, dataset: &Dataset, mapping: &DataMapping) -> Result<Self::Data, ConfigError> {
+        Self::dataset_to_treemap_format(dataset, mapping)
+    }
+
+    fn build_options_from_data(
         &self,
-        dataset: &Dataset,
+        data: &Self::Data,
         mapping: &DataMapping,
         style: &Self::StyleOptions,
     ) -> Result<String, ConfigError> {
-        // Transform dataset to treemap format
-        let tree_data = Self::dataset_to_treemap_format(dataset, mapping)?;
+        let tree_data = data;
+
+        // The squarified layout reads best largest first, so ECharts sorts
+        // nodes by size unless they are ordered ascending or by hand
+        let node_sort = match mapping.sort {
+            SortOrder::MeasureAscending => json!("asc"),
+            SortOrder::Manual(_) => Value::Null,
+            SortOrder::Dimension | SortOrder::MeasureDescending => json!("desc"),
+        };
 
         // Read colors from active DaisyUI theme
         let colors = ChartColors::from_daisyui_theme();
@@ -123,6 +179,7 @@ impl WidgetConfigBuilder for TreemapConfig {
         let series = json!({
             "type": "treemap",
             "data": [tree_data],
+            "sort": node_sort,
             "leafDepth": style.leaf_depth as u32,
             "roam": false,
             "breadcrumb": {
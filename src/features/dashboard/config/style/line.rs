@@ -25,6 +25,10 @@ pub struct LineStyleOptions {
     #[serde(default)]
     pub area_fill: bool,
 
+    /// Stack series on top of each other
+    #[serde(default)]
+    pub stacked: bool,
+
     /// Line thickness in pixels (1-10)
     #[serde(default = "default_line_width")]
     pub line_width: u8,
@@ -68,6 +72,7 @@ impl Default for LineStyleOptions {
             y_axis_title: None,
             smooth: default_smooth(),
             area_fill: false,
+            stacked: false,
             line_width: default_line_width(),
             show_points: false,
             point_size: default_point_size(),
@@ -1,5 +1,6 @@
 //! Style options for Table widget
 
+use crate::features::dashboard::data::AggregationFunction;
 use serde::{Deserialize, Serialize};
 
 /// Style options for Table widgets
@@ -32,6 +33,10 @@ pub struct TableStyleOptions {
     /// Show borders
     #[serde(default)]
     pub show_borders: bool,
+
+    /// Summary row under the table, aggregating every column
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<AggregationFunction>,
 }
 
 /// Row height options
@@ -69,6 +74,7 @@ impl Default for TableStyleOptions {
             hover: default_hover(),
             row_height: default_row_height(),
             show_borders: true,
+            summary: None,
         }
     }
 }
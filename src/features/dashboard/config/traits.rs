@@ -98,8 +98,41 @@ pub trait WidgetConfigBuilder: Clone + Copy + Send + Sync {
     /// Style options type for this widget
     type StyleOptions: Clone + Serialize + for<'de> Deserialize<'de>;
 
+    /// Data computed from the dataset, independent of theme and style
+    ///
+    /// Serializable so it can be computed off the main thread and sent back.
+    type Data: Clone + Serialize + for<'de> Deserialize<'de>;
+
+    /// Compute the widget data from the dataset (aggregation, grouping, ...)
+    ///
+    /// This is the expensive part of building a chart. It doesn't touch the
+    /// DOM, so it can run in the compute worker.
+    ///
+    /// # Arguments
+    /// * `dataset` - The active dataset with field definitions
+    /// * `mapping` - Field mapping configuration
+    fn transform_data(&self, dataset: &Dataset, mapping: &DataMapping) -> Result<Self::Data, ConfigError>;
+
+    /// Build ECharts options JSON from already-transformed data
+    ///
+    /// # Arguments
+    /// * `data` - Result of `transform_data`
+    /// * `mapping` - Field mapping configuration
+    /// * `style` - Widget-specific style options
+    ///
+    /// # Returns
+    /// ECharts option JSON string, or error if configuration is invalid
+    fn build_options_from_data(
+        &self,
+        data: &Self::Data,
+        mapping: &DataMapping,
+        style: &Self::StyleOptions,
+    ) -> Result<String, ConfigError>;
+
     /// Build ECharts options JSON from dataset and configuration
     ///
+    /// Runs both phases on the calling thread.
+    ///
     /// # Arguments
     /// * `dataset` - The active dataset with field definitions
     /// * `mapping` - Field mapping configuration
@@ -107,12 +140,16 @@ pub trait WidgetConfigBuilder: Clone + Copy + Send + Sync {
     ///
     /// # Returns
     /// ECharts option JSON string, or error if configuration is invalid
+    #[allow(dead_code)]
     fn build_echarts_options(
         &self,
         dataset: &Dataset,
         mapping: &DataMapping,
         style: &Self::StyleOptions,
-    ) -> Result<String, ConfigError>;
+    ) -> Result<String, ConfigError> {
+        let data = self.transform_data(dataset, mapping)?;
+        self.build_options_from_data(&data, mapping, style)
+    }
 
     /// Validate if the current data mapping is sufficient for this widget
     ///
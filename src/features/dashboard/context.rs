@@ -1,4 +1,12 @@
 use super::models::*;
+use super::data::expression::ExpressionError;
+use super::cross_filter::{ChartSelection, SelectedElement};
+use super::data::filter::{Filter, FilterGroup, FilterNode, GlobalFilters};
+use super::data::derived::{
+    derived_ids, refresh_all_derived, refresh_derived, refresh_renamed, DeriveError, DerivedFrom,
+};
+use super::data::replace::{missing_fields, Breakage, ReplaceReport};
+use super::data::DataTable;
 use super::history::{Command, HistoryManager};
 use leptos::prelude::*;
 
@@ -44,6 +52,14 @@ pub struct DashboardContext {
     pub selected_widget_id: ReadSignal<Option<WidgetId>>,
     set_selected_widget_id: WriteSignal<Option<WidgetId>>,
 
+    /// Selections of the dashboard's filter controls (saved with the dashboard)
+    pub global_filters: ReadSignal<GlobalFilters>,
+    set_global_filters: WriteSignal<GlobalFilters>,
+
+    /// Chart elements selected for cross-filtering (not saved)
+    pub chart_selection: ReadSignal<Option<ChartSelection>>,
+    set_chart_selection: WriteSignal<Option<ChartSelection>>,
+
     /// Widget type selected for creation (from WidgetSelector)
     pub pending_widget_type: ReadSignal<WidgetType>,
     pub set_pending_widget_type: WriteSignal<WidgetType>,
@@ -68,6 +84,18 @@ pub struct DashboardContext {
     pub storage_key: ReadSignal<String>,
     pub set_storage_key: WriteSignal<String>,
 
+    /// Whether the saved dashboard is still being loaded (editing is blocked)
+    pub restoring: ReadSignal<bool>,
+    set_restoring: WriteSignal<bool>,
+
+    /// Whether a save is being written to storage
+    saving: ReadSignal<bool>,
+    set_saving: WriteSignal<bool>,
+
+    /// Whether another save was requested while one was being written
+    save_pending: ReadSignal<bool>,
+    set_save_pending: WriteSignal<bool>,
+
     // ===== History Management (Undo/Redo) =====
 
     /// History manager for undo/redo operations
@@ -98,6 +126,8 @@ impl DashboardContext {
         let (templates, set_templates) = signal(Vec::new());
         let (active_dataset_id, set_active_dataset_id) = signal(None);
         let (selected_widget_id, set_selected_widget_id) = signal(None);
+        let (global_filters, set_global_filters) = signal(GlobalFilters::default());
+        let (chart_selection, set_chart_selection) = signal(None);
         let (pending_widget_type, set_pending_widget_type) = signal(WidgetType::Line);
         let (grid_view_active, set_grid_view_active) = signal(true); // Default to true
         let (title, set_title) = signal(String::from("Untitled Dashboard"));
@@ -106,6 +136,9 @@ impl DashboardContext {
 
         // Storage management
         let (storage_key, set_storage_key) = signal(String::new());
+        let (restoring, set_restoring) = signal(false);
+        let (saving, set_saving) = signal(false);
+        let (save_pending, set_save_pending) = signal(false);
 
         // History management
         let (history, set_history) = signal(HistoryManager::new(50));
@@ -125,6 +158,10 @@ impl DashboardContext {
             set_active_dataset_id,
             selected_widget_id,
             set_selected_widget_id,
+            global_filters,
+            set_global_filters,
+            chart_selection,
+            set_chart_selection,
             pending_widget_type,
             set_pending_widget_type,
             grid_view_active,
@@ -137,6 +174,12 @@ impl DashboardContext {
             set_auto_saved,
             storage_key,
             set_storage_key,
+            restoring,
+            set_restoring,
+            saving,
+            set_saving,
+            save_pending,
+            set_save_pending,
             history,
             set_history,
             can_undo,
@@ -206,6 +249,183 @@ impl DashboardContext {
         self.datasets.get().into_iter().find(|d| d.id == dataset_id)
     }
 
+    /// Add a calculated field to a dataset
+    ///
+    /// The dataset is left unchanged when the expression doesn't check.
+    pub fn add_calculated_field(
+        &self,
+        dataset_id: &str,
+        name: &str,
+        expression: &str,
+    ) -> Result<(), ExpressionError> {
+        let mut dataset = self
+            .datasets
+            .with_untracked(|datasets| datasets.iter().find(|d| d.id == dataset_id).cloned())
+            .ok_or_else(|| ExpressionError::new(format!("dataset not found: {}", dataset_id), 0))?;
+        dataset.add_calculated_field(name, expression)?;
+
+        self.set_datasets.update(|datasets| {
+            if let Some(existing) = datasets.iter_mut().find(|d| d.id == dataset_id) {
+                *existing = dataset;
+            }
+            refresh_derived(datasets, dataset_id);
+        });
+        Ok(())
+    }
+
+    /// Remove a calculated field (and those computed from it) from a dataset
+    pub fn remove_calculated_field(&self, dataset_id: &str, name: &str) {
+        self.set_datasets.update(|datasets| {
+            if let Some(dataset) = datasets.iter_mut().find(|d| d.id == dataset_id) {
+                dataset.remove_calculated_field(name);
+            }
+            refresh_derived(datasets, dataset_id);
+        });
+    }
+
+    /// Add a dataset computed from others: a pivot or unpivot of one
+    /// dataset, or a join of two
+    ///
+    /// The new dataset remembers how it was made and is recomputed
+    /// whenever one of its sources changes.
+    ///
+    /// # Returns
+    /// The ID of the new dataset
+    pub fn add_derived_dataset(&self, derived_from: DerivedFrom, name: &str) -> Result<DatasetId, DeriveError> {
+        let id = format!("ds_{}", uuid::Uuid::new_v4());
+        let dataset = self.datasets.with_untracked(|datasets| {
+            let name = match name.trim() {
+                "" => derived_from.default_name(datasets),
+                name => name.to_string(),
+            };
+            Dataset::derived(derived_from, id.clone(), &name, datasets)
+        })?;
+        self.add_dataset(dataset);
+        Ok(id)
+    }
+
+    /// Replace the data of a dataset with a new import of the same source
+    ///
+    /// The dataset keeps its id, so the widgets showing it update in place,
+    /// and the datasets derived from it are computed again. Renamed fields
+    /// are followed by calculated fields, by how derived datasets are
+    /// computed, and by the mappings and filter controls of the widgets
+    /// showing any of those datasets. Undoable as one step.
+    ///
+    /// # Returns
+    /// The schema changes, and the widgets and calculated fields left
+    /// broken; `None` if the dataset doesn't exist or is derived (its rows
+    /// follow its sources)
+    pub fn replace_dataset_data(&self, id: &str, replacement: Dataset) -> Option<ReplaceReport> {
+        let previous = self.datasets.get_untracked();
+        let mut dataset = previous.iter().find(|d| d.id == id && d.derived_from.is_none())?.clone();
+        let diff = dataset.replace_data(replacement);
+
+        // Datasets derived from it read the renamed fields under their new
+        // names, along the whole chain
+        let affected: Vec<DatasetId> = std::iter::once(id.to_string()).chain(derived_ids(&previous, id)).collect();
+        self.set_datasets.update(|datasets| {
+            if let Some(existing) = datasets.iter_mut().find(|d| d.id == id) {
+                *existing = dataset.clone();
+            }
+            refresh_renamed(datasets, id, &diff);
+        });
+        let datasets = self.datasets.get_untracked();
+
+        let mut commands = Vec::new();
+        let mut report = ReplaceReport {
+            dataset_name: dataset.name.clone(),
+            diff,
+            repaired: Vec::new(),
+            broken: Vec::new(),
+        };
+        for affected_id in &affected {
+            let (Some(old), Some(new)) = (
+                previous.iter().find(|d| &d.id == affected_id),
+                datasets.iter().find(|d| &d.id == affected_id),
+            ) else {
+                continue;
+            };
+            let dropped: Vec<String> = old
+                .calculated_fields
+                .iter()
+                .filter(|calculated| !new.calculated_fields.iter().any(|kept| kept.name == calculated.name))
+                .map(|calculated| calculated.name.clone())
+                .collect();
+            if !dropped.is_empty() {
+                report.broken.push(Breakage::CalculatedFields { dataset_name: new.name.clone(), names: dropped });
+            }
+            // Derived datasets whose definition didn't change follow their sources on undo
+            if new.id == id || new.derived_from != old.derived_from || new.calculated_fields != old.calculated_fields {
+                commands.push(Command::ReplaceDataset { previous: Box::new(old.clone()), new: Box::new(new.clone()) });
+            }
+        }
+
+        let previous_global_filters = self.global_filters.get_untracked();
+        let mut global_filters = previous_global_filters.clone();
+        self.set_widgets.update(|widgets| {
+            for widget in widgets.iter_mut() {
+                let Some(shown) = widget.chart_config.dataset(&datasets).filter(|d| affected.contains(&d.id)) else {
+                    continue;
+                };
+                let diff = report.diff.renames_in(&shown.fields);
+                let previous_mapping = widget.chart_config.data_mapping.clone();
+                if diff.repair(&mut widget.chart_config.data_mapping) {
+                    report.repaired.push(widget.title.clone());
+                    commands.push(Command::UpdateDataMapping {
+                        widget_id: widget.id.clone(),
+                        previous_mapping: Box::new(previous_mapping),
+                        new_mapping: Box::new(widget.chart_config.data_mapping.clone()),
+                    });
+                }
+                // The condition a filter control set on a renamed field
+                if let Some(mut filter) = global_filters.get(&widget.id).cloned()
+                    && let Some(to) = diff.renamed_to(&filter.field)
+                {
+                    filter.field = to.to_string();
+                    global_filters.set(&widget.id, Some(filter));
+                }
+                let missing = missing_fields(&widget.chart_config.data_mapping, shown);
+                if !missing.is_empty() {
+                    report.broken.push(Breakage::Widget {
+                        title: widget.title.clone(),
+                        derived_dataset: (shown.id != id).then(|| shown.name.clone()),
+                        missing,
+                    });
+                }
+            }
+        });
+        if global_filters != previous_global_filters {
+            self.set_global_filters.set(global_filters.clone());
+            commands.push(Command::SetGlobalFilters {
+                previous: Box::new(previous_global_filters),
+                new: Box::new(global_filters),
+            });
+        }
+
+        let command = Command::Batch { commands, description: format!("Replace data of {}", dataset.name) };
+        self.set_history.update(|history| {
+            history.execute(command);
+        });
+        self.update_history_availability();
+        self.mark_as_edited();
+
+        log::info!("Replaced data of {}: {} widgets repaired", dataset.name, report.repaired.len());
+        Some(report)
+    }
+
+    /// Put a dataset in place of the one with the same id, and recompute
+    /// the datasets derived from it
+    fn put_dataset(&self, dataset: Dataset) {
+        self.set_datasets.update(|datasets| {
+            let id = dataset.id.clone();
+            if let Some(existing) = datasets.iter_mut().find(|d| d.id == id) {
+                *existing = dataset;
+                refresh_derived(datasets, &id);
+            }
+        });
+    }
+
     // ===== Widget Methods =====
 
     /// Get all widgets
@@ -214,7 +434,10 @@ impl DashboardContext {
     }
 
     /// Add a new widget
-    pub fn add_widget(&self, widget: Widget) {
+    ///
+    /// Widgets not bound to a dataset are bound to the active one.
+    pub fn add_widget(&self, mut widget: Widget) {
+        self.bind_to_active_dataset(&mut widget.chart_config);
         let widget_id = widget.id.clone();
         let widget_title = widget.title.clone();
         let widget_type = widget.widget_type;
@@ -300,6 +523,20 @@ impl DashboardContext {
         self.mark_as_edited();
     }
 
+    /// Bind a widget to the dataset it shows
+    pub fn set_widget_dataset(&self, id: &str, dataset_id: DatasetId) {
+        self.update_widget(id, |widget| {
+            widget.chart_config.dataset_id = Some(dataset_id);
+        });
+    }
+
+    /// Bind an unbound widget configuration to the active dataset
+    fn bind_to_active_dataset(&self, config: &mut ChartConfig) {
+        if config.dataset_id.is_none() {
+            config.dataset_id = self.active_dataset_id.get_untracked();
+        }
+    }
+
     /// Remove widget by ID
     pub fn remove_widget(&self, id: &str) {
         // CAPTURE STATE BEFORE MUTATION
@@ -326,6 +563,14 @@ impl DashboardContext {
             layers.retain(|l| l.widget_id != id);
         });
 
+        // A removed filter control or selection source stops filtering
+        if widget.widget_type.is_filter_control() {
+            self.set_global_filter(id, None);
+        }
+        if self.chart_selection.with_untracked(|selection| selection.as_ref().is_some_and(|s| s.source == id)) {
+            self.clear_chart_selection();
+        }
+
         // RECORD COMMAND
         let command = Command::RemoveWidget {
             widget: Box::new(widget.clone()),
@@ -339,6 +584,60 @@ impl DashboardContext {
         self.mark_as_edited();
     }
 
+    /// Set or clear the condition of a filter control
+    pub fn set_global_filter(&self, control_id: &str, filter: Option<Filter>) {
+        if self.global_filters.with_untracked(|filters| filters.get(control_id) == filter.as_ref()) {
+            return;
+        }
+        self.set_global_filters.update(|filters| filters.set(control_id, filter));
+        self.mark_as_edited();
+    }
+
+    /// Select chart elements of a widget, replacing any other selection
+    ///
+    /// # Arguments
+    /// * `source` - Widget the elements were selected on
+    /// * `elements` - The elements; none clears the widget's selection, and
+    ///   leaves one made on another widget alone
+    /// * `toggle` - Whether the elements are added to (or, when already
+    ///   selected, removed from) the widget's current selection
+    pub fn select_chart_elements(&self, source: &str, elements: Vec<SelectedElement>, toggle: bool) {
+        let current = self.chart_selection.get_untracked().filter(|selection| selection.source == source);
+        if elements.is_empty() && current.is_none() {
+            return;
+        }
+        let mut selection = current
+            .filter(|_| toggle)
+            .unwrap_or_else(|| ChartSelection { source: source.to_string(), elements: Vec::new() });
+        if toggle {
+            elements.into_iter().for_each(|element| selection.toggle(element));
+        } else {
+            selection.elements = elements;
+        }
+        self.set_chart_selection.set((!selection.elements.is_empty()).then_some(selection));
+    }
+
+    /// Clear the cross-filtering selection
+    pub fn clear_chart_selection(&self) {
+        if self.chart_selection.with_untracked(Option::is_some) {
+            self.set_chart_selection.set(None);
+        }
+    }
+
+    /// Dashboard filters applying to a widget: the filter controls' conditions
+    /// and the chart selection made on any other widget
+    pub fn dashboard_filters(&self, widget_id: &str) -> FilterGroup {
+        let mut filters = self.global_filters.with(GlobalFilters::group);
+        self.chart_selection.with(|selection| {
+            if let Some(selection) = selection
+                && selection.source != widget_id
+            {
+                filters.filters.push(FilterNode::Group(selection.group()));
+            }
+        });
+        filters
+    }
+
     /// Set widget editing state
     pub fn set_widget_editing(&self, id: &str, editing: bool) {
         self.update_widget(id, |widget| {
@@ -494,7 +793,7 @@ impl DashboardContext {
         &self,
         template_id: &str,
         kpi_field: Option<String>,
-        kpi_aggregation: Option<crate::features::dashboard::KpiAggregation>,
+        kpi_aggregation: Option<crate::features::dashboard::data::AggregationFunction>,
     ) -> usize {
         let template = self
             .templates
@@ -518,12 +817,12 @@ impl DashboardContext {
                 let widget_id = format!("widget_{}", uuid::Uuid::new_v4());
 
                 // Apply KPI configuration if this is a KPI widget and KPI config was provided
-                let chart_config = if template_widget.widget_type == WidgetType::Kpi {
+                let mut chart_config = if template_widget.widget_type == WidgetType::Kpi {
                     if let (Some(field), Some(aggr)) = (&kpi_field, &kpi_aggregation) {
                         // Clone and modify the chart_config with KPI settings
                         let mut config = template_widget.chart_config.clone();
                         config.data_mapping.kpi_field = Some(field.clone());
-                        config.data_mapping.kpi_aggregation = Some(*aggr);
+                        config.data_mapping.kpi_aggregation = Some(aggr.clone());
                         config
                     } else {
                         template_widget.chart_config.clone()
@@ -531,6 +830,7 @@ impl DashboardContext {
                 } else {
                     template_widget.chart_config.clone()
                 };
+                self.bind_to_active_dataset(&mut chart_config);
 
                 let widget = Widget {
                     id: widget_id.clone(),
@@ -567,11 +867,11 @@ impl DashboardContext {
             let widget_id = format!("widget_{}", uuid::Uuid::new_v4());
 
             // Apply KPI configuration if this is a KPI widget and KPI config was provided
-            let chart_config = if template.preview_type == WidgetType::Kpi {
+            let mut chart_config = if template.preview_type == WidgetType::Kpi {
                 if let (Some(field), Some(aggr)) = (&kpi_field, &kpi_aggregation) {
                     let mut config = template.default_config.clone();
                     config.data_mapping.kpi_field = Some(field.clone());
-                    config.data_mapping.kpi_aggregation = Some(*aggr);
+                    config.data_mapping.kpi_aggregation = Some(aggr.clone());
                     config
                 } else {
                     template.default_config.clone()
@@ -579,6 +879,7 @@ impl DashboardContext {
             } else {
                 template.default_config.clone()
             };
+            self.bind_to_active_dataset(&mut chart_config);
 
             let widget = Widget {
                 id: widget_id.clone(),
@@ -635,9 +936,23 @@ impl DashboardContext {
         format!("{}_{}", timestamp, title_slug)
     }
 
-    /// Save to localStorage (Generic template, no CSV data)
+    /// Save to browser storage (Complete template, datasets included)
+    ///
+    /// Falls back to the layout alone (Generic template) when the data
+    /// exceeds the storage quota. Only one save is written at a time, so an
+    /// older snapshot can't overwrite a newer one; a save requested meanwhile
+    /// (or while the saved dashboard is being restored) runs afterwards.
     pub fn save_to_storage(&self) {
-        let template = self.export_template(crate::features::dashboard::export::TemplateType::Generic);
+        use crate::features::dashboard::export::TemplateType;
+
+        if self.saving.get_untracked() || self.restoring.get_untracked() {
+            self.set_save_pending.set(true);
+            return;
+        }
+
+        // Both snapshots are taken now, not when the fallback is needed
+        let complete = self.export_template(TemplateType::Complete);
+        let generic = self.export_template(TemplateType::Generic);
 
         // Reuse existing key if available, otherwise generate new one
         let key = {
@@ -649,26 +964,49 @@ impl DashboardContext {
             }
         };
 
-        match crate::features::dashboard::io::storage::save_template(&key, &template) {
-            Ok(_) => {
-                log::info!("Dashboard auto-saved: {}", key);
-                self.set_storage_key.set(key.clone());
-                self.set_auto_saved.set(true);
-            }
-            Err(e) => {
-                log::error!("Failed to auto-save: {:?}", e);
-                self.set_auto_saved.set(false);
+        self.set_saving.set(true);
+        let ctx = *self;
+        leptos::task::spawn_local(async move {
+            match crate::features::dashboard::io::storage::save_template(&key, &complete, &generic).await {
+                Ok(saved) => {
+                    log::info!("Dashboard auto-saved ({:?}): {}", saved, key);
+                    ctx.set_storage_key.set(key);
+                    ctx.set_auto_saved.set(true);
+                }
+                Err(e) => {
+                    log::error!("Failed to auto-save: {}", e);
+                    ctx.set_auto_saved.set(false);
+                }
             }
+            ctx.set_saving.set(false);
+            ctx.save_if_pending();
+        });
+    }
+
+    /// Run the save requested while another one was being written
+    fn save_if_pending(&self) {
+        if self.save_pending.get_untracked() {
+            self.set_save_pending.set(false);
+            self.save_to_storage();
         }
     }
 
-    /// Load dashboard from localStorage by key
+    /// Load dashboard from browser storage by key
     pub fn load_from_storage(&self, key: &str) {
-        match crate::features::dashboard::io::storage::load_template(key) {
+        let ctx = *self;
+        let key = key.to_string();
+        leptos::task::spawn_local(async move {
+            ctx.restore(key).await;
+        });
+    }
+
+    /// Load a saved dashboard and replace the current one with it
+    async fn restore(self, key: String) {
+        match crate::features::dashboard::io::storage::load_template(&key).await {
             Ok(template) => {
                 self.import_template(template);
-                self.set_storage_key.set(key.to_string());
                 log::info!("Dashboard loaded: {}", key);
+                self.set_storage_key.set(key);
             }
             Err(e) => log::error!("Failed to load: {}", e),
         }
@@ -683,25 +1021,35 @@ impl DashboardContext {
     }
 
     /// Import template and restore dashboard state
-    pub fn import_template(&self, template: crate::features::dashboard::export::DashboardTemplate) {
+    pub fn import_template(&self, mut template: crate::features::dashboard::export::DashboardTemplate) {
         log::info!("DashboardContext::import_template - starting import");
 
+        template.bind_unbound_widgets();
+        let active_dataset_id = template.datasets.iter().find(|ds| ds.active).map(|ds| ds.id.clone());
+
         use crate::features::dashboard::models::Dataset;
 
         // Convert DatasetExport → Dataset
-        let datasets = template.datasets.into_iter().map(|ds_export| {
+        let mut datasets = template.datasets.into_iter().map(|ds_export| {
             log::debug!("Converting dataset: {} ({} fields)", ds_export.name, ds_export.fields.len());
 
+            let data = DataTable::from_rows(&ds_export.fields, ds_export.data.unwrap_or_default());
             Dataset {
                 id: ds_export.id,
                 name: ds_export.name.clone(),
                 size: "Imported".into(),
                 uploaded_at: chrono::Utc::now().format("%Y-%m-%d %H:%M").to_string(),
                 fields: ds_export.fields,
-                active: false,
-                data: ds_export.data.unwrap_or_default(),
+                active: ds_export.active,
+                data,
+                dialect: ds_export.dialect,
+                calculated_fields: Vec::new(),
+                derived_from: ds_export.derived_from,
             }
+            .with_calculated_fields(ds_export.calculated_fields)
         }).collect::<Vec<_>>();
+        // Generic templates carry no rows: derived datasets are computed here
+        refresh_all_derived(&mut datasets);
 
         log::info!(
             "Importing {} widgets, {} datasets, {} layers",
@@ -714,6 +1062,9 @@ impl DashboardContext {
         self.set_widgets.set(template.widgets);
         self.set_layers.set(template.layers);
         self.set_datasets.set(datasets);
+        self.set_active_dataset_id.set(active_dataset_id);
+        self.set_global_filters.set(template.global_filters);
+        self.set_chart_selection.set(None);
         self.set_title.set(template.metadata.title);
 
         // Clear history and reset to fresh state
@@ -817,11 +1168,22 @@ impl DashboardContext {
                 log::info!("Undo: Restored mapping for widget {}", widget_id);
             }
 
-            Command::Batch { commands, .. } => {
+            Command::ReplaceDataset { previous, .. } => {
+                // Inverse: Restore the previous data
+                self.put_dataset(*previous);
+            }
+
+            Command::SetGlobalFilters { previous, .. } => {
+                // Inverse: Restore the previous conditions
+                self.set_global_filters.set(*previous);
+            }
+
+            Command::Batch { commands, description } => {
                 // Undo batch in reverse order
                 for cmd in commands.into_iter().rev() {
                     self.execute_undo_command(cmd);
                 }
+                log::info!("Undo: {}", description);
             }
         }
     }
@@ -881,11 +1243,22 @@ impl DashboardContext {
                 log::info!("Redo: Updated mapping for widget {}", widget_id);
             }
 
-            Command::Batch { commands, .. } => {
+            Command::ReplaceDataset { new, .. } => {
+                // Redo: Apply the new data
+                self.put_dataset(*new);
+            }
+
+            Command::SetGlobalFilters { new, .. } => {
+                // Redo: Apply the new conditions
+                self.set_global_filters.set(*new);
+            }
+
+            Command::Batch { commands, description } => {
                 // Redo batch in original order
                 for cmd in commands {
                     self.execute_redo_command(cmd);
                 }
+                log::info!("Redo: {}", description);
             }
         }
     }
@@ -910,21 +1283,28 @@ impl DashboardContext {
                     Field {
                         name: "Product Category".into(),
                         field_type: FieldType::Text,
+                        format: None,
                     },
                     Field {
                         name: "Total Revenue".into(),
                         field_type: FieldType::Numeric,
+                        format: None,
                     },
                     Field {
                         name: "Units Sold".into(),
                         field_type: FieldType::Numeric,
+                        format: None,
                     },
                     Field {
                         name: "Date".into(),
                         field_type: FieldType::Date,
+                        format: None,
                     },
                 ],
-                data: Vec::new(),
+                data: DataTable::default(),
+                dialect: None,
+                calculated_fields: Vec::new(),
+                derived_from: None,
             },
             Dataset {
                 id: "ds_churn".into(),
@@ -933,7 +1313,10 @@ impl DashboardContext {
                 uploaded_at: "Yesterday".into(),
                 active: false,
                 fields: vec![],
-                data: Vec::new(),
+                data: DataTable::default(),
+                dialect: None,
+                calculated_fields: Vec::new(),
+                derived_from: None,
             },
         ];
 
@@ -946,10 +1329,18 @@ impl DashboardContext {
                 widget_type: WidgetType::Line,
                 chart_config: ChartConfig {
                     chart_type: Some(WidgetType::Line),
+                    dataset_id: Some("ds_q3_sales".into()),
                     data_mapping: DataMapping {
                         x_axis: Some("Date".into()),
+                        time_bucketing: None,
                         y_axis: vec!["Total Revenue".into()],
+                        aggregations: Default::default(),
+                        calculations: Default::default(),
                         category: None,
+                        series_limit: None,
+                        missing_values: Default::default(),
+                        sort: Default::default(),
+                        top_n: None,
                         size: None,
                         color: None,
                         open: None,
@@ -960,6 +1351,9 @@ impl DashboardContext {
                         columns: Vec::new(),
                         kpi_field: None,
                         kpi_aggregation: None,
+                        filters: Default::default(),
+                        ignore_global_filters: false,
+                        filter_field: None,
                     },
                     style_options: "{}".into(),
                 },
@@ -978,6 +1372,7 @@ impl DashboardContext {
                 widget_type: WidgetType::Kpi,
                 chart_config: ChartConfig {
                     chart_type: Some(WidgetType::Kpi),
+                    dataset_id: Some("ds_q3_sales".into()),
                     data_mapping: DataMapping::default(),
                     style_options: "{}".into(),
                 },
@@ -996,10 +1391,18 @@ impl DashboardContext {
                 widget_type: WidgetType::Pie,
                 chart_config: ChartConfig {
                     chart_type: Some(WidgetType::Pie),
+                    dataset_id: Some("ds_q3_sales".into()),
                     data_mapping: DataMapping {
                         x_axis: Some("Product Category".into()),
+                        time_bucketing: None,
                         y_axis: vec!["Total Revenue".into()],
+                        aggregations: Default::default(),
+                        calculations: Default::default(),
                         category: None,
+                        series_limit: None,
+                        missing_values: Default::default(),
+                        sort: Default::default(),
+                        top_n: None,
                         size: None,
                         color: None,
                         open: None,
@@ -1010,6 +1413,9 @@ impl DashboardContext {
                         columns: Vec::new(),
                         kpi_field: None,
                         kpi_aggregation: None,
+                        filters: Default::default(),
+                        ignore_global_filters: false,
+                        filter_field: None,
                     },
                     style_options: "{}".into(),
                 },
@@ -1063,6 +1469,7 @@ impl DashboardContext {
                 widgets: vec![],
                 default_config: ChartConfig {
                     chart_type: Some(WidgetType::Bar),
+                    dataset_id: None,
                     data_mapping: DataMapping::default(),
                     style_options: "{}".into(),
                 },
@@ -1075,6 +1482,7 @@ impl DashboardContext {
                 widgets: vec![],
                 default_config: ChartConfig {
                     chart_type: Some(WidgetType::Kpi),
+                    dataset_id: None,
                     data_mapping: DataMapping::default(),
                     style_options: "{}".into(),
                 },
@@ -1094,18 +1502,26 @@ impl DashboardContext {
     /// This is the production-ready initialization that starts with a clean slate.
     /// Users must upload their own CSV files.
     ///
-    /// Auto-loads the most recent dashboard from localStorage if available.
+    /// The most recent saved dashboard, if any, is then loaded from browser
+    /// storage in the background; `restoring` is set until it has loaded so
+    /// that edits can't be made (and then overwritten) in the meantime.
     pub fn init_empty(&self) {
-        // Try to load the most recent dashboard from localStorage
-        if let Ok(templates_list) = crate::features::dashboard::io::storage::list_templates()
-            && let Some(most_recent_key) = templates_list.into_iter().max()
-        {
-            log::info!("Found saved dashboard: {}", most_recent_key);
-            self.load_from_storage(&most_recent_key);
-            return;
-        }
-
-        log::info!("No saved dashboard found, initializing empty");
+        self.set_restoring.set(true);
+        let ctx = *self;
+        leptos::task::spawn_local(async move {
+            match crate::features::dashboard::io::storage::list_dashboards().await {
+                Ok(summaries) => match summaries.into_iter().map(|summary| summary.key).max() {
+                    Some(most_recent_key) => {
+                        log::info!("Found saved dashboard: {}", most_recent_key);
+                        ctx.restore(most_recent_key).await;
+                    }
+                    None => log::info!("No saved dashboard found"),
+                },
+                Err(e) => log::warn!("Failed to list saved dashboards: {}", e),
+            }
+            ctx.set_restoring.set(false);
+            ctx.save_if_pending();
+        });
 
         // Set default title for new dashboard
         self.set_title.set(String::from("Untitled Dashboard"));
@@ -1133,6 +1549,7 @@ impl DashboardContext {
                 widgets: vec![],
                 default_config: ChartConfig {
                     chart_type: Some(WidgetType::Bar),
+                    dataset_id: None,
                     data_mapping: DataMapping::default(),
                     style_options: "{}".into(),
                 },
@@ -1145,6 +1562,7 @@ impl DashboardContext {
                 widgets: vec![],
                 default_config: ChartConfig {
                     chart_type: Some(WidgetType::Line),
+                    dataset_id: None,
                     data_mapping: DataMapping::default(),
                     style_options: "{}".into(),
                 },
@@ -1157,6 +1575,7 @@ impl DashboardContext {
                 widgets: vec![],
                 default_config: ChartConfig {
                     chart_type: Some(WidgetType::Pie),
+                    dataset_id: None,
                     data_mapping: DataMapping::default(),
                     style_options: "{}".into(),
                 },
@@ -1169,6 +1588,7 @@ impl DashboardContext {
                 widgets: vec![],
                 default_config: ChartConfig {
                     chart_type: Some(WidgetType::Kpi),
+                    dataset_id: None,
                     data_mapping: DataMapping::default(),
                     style_options: "{}".into(),
                 },
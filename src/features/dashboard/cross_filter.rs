@@ -0,0 +1,539 @@
+//! Cross-filtering: chart elements selected by clicking or brushing
+//!
+//! Selecting elements of one chart narrows every other widget on the
+//! dataset to the rows behind them. The selection lives in the dashboard
+//! context next to the filter controls' conditions, but isn't saved.
+
+use crate::features::dashboard::data::filter::{Filter, FilterCombinator, FilterGroup, FilterNode, FilterOperator};
+use crate::features::dashboard::data::transform::{value_label, OTHER_SERIES};
+use crate::features::dashboard::{DataMapping, WidgetId, WidgetType};
+use serde_json::{json, Value};
+
+/// Event delivered by a chart through the ECharts event bridge
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChartEvent {
+    /// An element was clicked
+    Click(ChartElement),
+    /// Category names or periods inside the brush areas (empty once the
+    /// brush is cleared)
+    Brush(Vec<String>),
+}
+
+/// Chart element under a click
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ChartElement {
+    /// Name of the series
+    pub series_name: Option<String>,
+    /// Data name: category label or period, slice or node name
+    pub name: Option<String>,
+    /// Data value as plotted (`[x, y, ...]` for scatter and heatmap points)
+    pub value: Value,
+    /// Category labels of the element on the X and Y axes, for charts whose
+    /// two axes are categories (heatmap)
+    pub axis_names: Vec<String>,
+    /// Names of the node's ancestors and the node itself, below the root (treemap)
+    pub tree_path: Vec<String>,
+}
+
+impl ChartEvent {
+    /// Decode the parameters of an ECharts `click` event
+    ///
+    /// # Arguments
+    /// * `params` - The event's `seriesName`, `name`, `value` and `treePathInfo`
+    /// * `options` - Options the chart was drawn with, to resolve axis indexes
+    pub fn from_click(params: &Value, options: &Value) -> Self {
+        let text = |key: &str| params[key].as_str().filter(|text| !text.is_empty()).map(str::to_string);
+        let value = params["value"].clone();
+
+        let axis_names = match (category_axis(options, "xAxis"), category_axis(options, "yAxis")) {
+            (Some(x_names), Some(y_names)) => {
+                let name = |names: &[Value], idx: usize| {
+                    value[idx].as_u64().and_then(|i| names.get(i as usize)).and_then(Value::as_str).map(str::to_string)
+                };
+                name(x_names, 0).zip(name(y_names, 1)).map(|(x, y)| vec![x, y]).unwrap_or_default()
+            }
+            _ => Vec::new(),
+        };
+
+        // The first entry is the tree's root, which isn't a data node
+        let tree_path = params["treePathInfo"]
+            .as_array()
+            .map(|path| path.iter().skip(1).filter_map(|node| node["name"].as_str().map(str::to_string)).collect())
+            .unwrap_or_default();
+
+        // Points of a dataset on a time axis aren't named: their period is
+        // the row's first column
+        let name = text("name").or_else(|| {
+            let dimension = value[0].as_str().filter(|_| !options["dataset"].is_null());
+            dimension.map(str::to_string)
+        });
+
+        ChartEvent::Click(ChartElement {
+            series_name: text("seriesName"),
+            name,
+            value,
+            axis_names,
+            tree_path,
+        })
+    }
+
+    /// Decode the parameters of an ECharts `brushSelected` event
+    ///
+    /// Brushed data indexes are resolved to names on the category axis, or
+    /// to the first column of the chart's dataset.
+    ///
+    /// # Returns
+    /// `None` when no brush area is drawn
+    pub fn from_brush(params: &Value, options: &Value) -> Option<Self> {
+        let batches = params["batch"].as_array()?;
+        if batches.iter().all(|batch| batch["areas"].as_array().is_none_or(Vec::is_empty)) {
+            return None;
+        }
+        let names: Vec<&str> = match category_axis(options, "xAxis").or_else(|| category_axis(options, "yAxis")) {
+            Some(names) => names.iter().map(|name| name.as_str().unwrap_or_default()).collect(),
+            // The dataset's first row is its header
+            None => options["dataset"]["source"]
+                .as_array()?
+                .iter()
+                .skip(1)
+                .map(|row| row[0].as_str().unwrap_or_default())
+                .collect(),
+        };
+
+        let mut selected: Vec<String> = Vec::new();
+        let indexes = batches
+            .iter()
+            .filter_map(|batch| batch["selected"].as_array())
+            .flatten()
+            .filter_map(|series| series["dataIndex"].as_array())
+            .flatten()
+            .filter_map(Value::as_u64);
+        for idx in indexes {
+            if let Some(&name) = names.get(idx as usize)
+                && !name.is_empty()
+                && !selected.iter().any(|selected| selected == name)
+            {
+                selected.push(name.to_string());
+            }
+        }
+        Some(ChartEvent::Brush(selected))
+    }
+}
+
+/// First axis of `key` (`xAxis` or `yAxis`)
+fn first_axis<'a>(options: &'a Value, key: &str) -> &'a Value {
+    match &options[key] {
+        Value::Array(axes) => axes.first().unwrap_or(&Value::Null),
+        axis => axis,
+    }
+}
+
+/// Category names of the first axis of `key`, if it is a category axis
+/// listing them
+fn category_axis<'a>(options: &'a Value, key: &str) -> Option<&'a [Value]> {
+    let axis = first_axis(options, key);
+    (axis["type"] == "category").then(|| axis["data"].as_array().map(Vec::as_slice))?
+}
+
+/// Whether the chart's dimension (category or time axis) is its y-axis
+fn dimension_on_y(options: &Value) -> bool {
+    matches!(first_axis(options, "yAxis")["type"].as_str(), Some("category" | "time"))
+}
+
+/// One selected chart element
+#[derive(Clone, Debug, PartialEq)]
+pub struct SelectedElement {
+    /// Name of the element on the chart, used to highlight it
+    pub name: String,
+    /// Conditions the rows behind the element meet, ANDed
+    pub conditions: Vec<Filter>,
+}
+
+/// Elements selected on one chart
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChartSelection {
+    /// Widget the elements were selected on
+    pub source: WidgetId,
+    pub elements: Vec<SelectedElement>,
+}
+
+impl ChartSelection {
+    /// Names of the selected elements, to highlight them on the source chart
+    pub fn names(&self) -> Vec<String> {
+        self.elements
+            .iter()
+            .filter(|element| !element.name.is_empty())
+            .map(|element| element.name.clone())
+            .collect()
+    }
+
+    /// Add an element, or remove it if it is already selected
+    pub fn toggle(&mut self, element: SelectedElement) {
+        match self.elements.iter().position(|selected| selected.conditions == element.conditions) {
+            Some(idx) => {
+                self.elements.remove(idx);
+            }
+            None => self.elements.push(element),
+        }
+    }
+
+    /// Rows behind any of the selected elements
+    pub fn group(&self) -> FilterGroup {
+        let element_group = |element: &SelectedElement| FilterGroup {
+            combinator: FilterCombinator::And,
+            filters: element.conditions.iter().cloned().map(FilterNode::Condition).collect(),
+        };
+        FilterGroup {
+            combinator: FilterCombinator::Or,
+            filters: self.elements.iter().map(|element| FilterNode::Group(element_group(element))).collect(),
+        }
+    }
+}
+
+/// Whether elements of a widget type can be selected to cross-filter
+pub fn supports_cross_filter(widget_type: WidgetType) -> bool {
+    matches!(
+        widget_type,
+        WidgetType::Bar
+            | WidgetType::Line
+            | WidgetType::Area
+            | WidgetType::Pie
+            | WidgetType::Candlestick
+            | WidgetType::Scatter
+            | WidgetType::Heatmap
+            | WidgetType::Treemap
+    )
+}
+
+/// Whether a widget type draws its dimension on a category or time axis,
+/// so ranges of it can be brushed
+fn has_dimension_axis(widget_type: WidgetType) -> bool {
+    matches!(widget_type, WidgetType::Bar | WidgetType::Line | WidgetType::Area | WidgetType::Candlestick)
+}
+
+/// Chart elements an event selects, identified by the widget's fields
+///
+/// A click selects one element and a brush the categories in its areas.
+/// Periods of a bucketed date select the date range they cover. Elements of
+/// charts without a dimension (radar), the "Other" item merging those
+/// beyond a top-N limit, and clicks the mapping can't resolve, select
+/// nothing.
+pub fn selected_elements(widget_type: WidgetType, mapping: &DataMapping, event: &ChartEvent) -> Vec<SelectedElement> {
+    let equals = |field: &str, value: Value| Filter {
+        field: field.to_string(),
+        operator: FilterOperator::Equals,
+        values: vec![value],
+    };
+    let dimension = |field: &str, name: &str| match mapping.time_bucketing.and_then(|bucketing| bucketing.period_bounds(name)) {
+        Some((first, last)) => Filter {
+            field: field.to_string(),
+            operator: FilterOperator::DateRange,
+            values: vec![Value::from(first), Value::from(last)],
+        },
+        None => equals(field, Value::from(name)),
+    };
+    let element = |name: &str, conditions: Vec<Filter>| SelectedElement { name: name.to_string(), conditions };
+    let merged = |name: &str| mapping.top_n.is_some() && name == OTHER_SERIES;
+
+    match event {
+        ChartEvent::Brush(names) => match (&mapping.x_axis, has_dimension_axis(widget_type)) {
+            (Some(x_axis), true) => names
+                .iter()
+                .filter(|name| !merged(name))
+                .map(|name| element(name, vec![dimension(x_axis, name)]))
+                .collect(),
+            _ => Vec::new(),
+        },
+        ChartEvent::Click(clicked) => {
+            let selected = match widget_type {
+                WidgetType::Bar | WidgetType::Line | WidgetType::Area | WidgetType::Pie | WidgetType::Candlestick => {
+                    let name = clicked.name.as_ref().filter(|name| !merged(name));
+                    mapping.x_axis.as_ref().zip(name).map(|(x_axis, name)| {
+                        let mut conditions = vec![dimension(x_axis, name)];
+                        if let Some(category) = split_category(widget_type, mapping, clicked) {
+                            conditions.push(category);
+                        }
+                        element(name, conditions)
+                    })
+                }
+                WidgetType::Heatmap => match (&mapping.x_axis, &mapping.category, clicked.axis_names.as_slice()) {
+                    (Some(x_axis), Some(category), [x, y]) => {
+                        let (x, y) = (Value::from(x.as_str()), Value::from(y.as_str()));
+                        Some(element(&element_name([&x, &y]), vec![equals(x_axis, x), equals(category, y)]))
+                    }
+                    _ => None,
+                },
+                WidgetType::Treemap => {
+                    // "Other" nodes add a level above the nodes they merge
+                    let conditions: Vec<Filter> = mapping
+                        .hierarchy
+                        .iter()
+                        .zip(clicked.tree_path.iter().filter(|name| !merged(name)))
+                        .map(|(field, name)| equals(field, Value::from(name.as_str())))
+                        .collect();
+                    let name = clicked.tree_path.last().map(String::as_str).unwrap_or_default();
+                    (!conditions.is_empty()).then(|| element(name, conditions))
+                }
+                WidgetType::Scatter => {
+                    let point = clicked.value.as_array().map(Vec::as_slice).unwrap_or_default();
+                    mapping.x_axis.as_ref().zip(point.first()).map(|(x_axis, x)| {
+                        let mut conditions = vec![equals(x_axis, x.clone())];
+                        if let Some(color) = &mapping.color
+                            && let Some(value) = scatter_color(mapping, point)
+                        {
+                            conditions.push(equals(color, value.clone()));
+                        }
+                        element(&scatter_point_name(mapping, point), conditions)
+                    })
+                }
+                _ => None,
+            };
+            selected.into_iter().collect()
+        }
+    }
+}
+
+/// Name of a chart element drawn from several values (heatmap cell,
+/// scatter point): the values joined with " · "
+///
+/// Builders name the data items of those charts with it, so that the
+/// selected ones can be highlighted by name.
+pub fn element_name<'a>(values: impl IntoIterator<Item = &'a Value>) -> String {
+    values.into_iter().filter_map(value_label).collect::<Vec<_>>().join(" · ")
+}
+
+/// Name of a scatter point: its X value and color, the values clicking it
+/// selects by (points sharing them are selected together)
+pub fn scatter_point_name(mapping: &DataMapping, point: &[Value]) -> String {
+    element_name(point.first().into_iter().chain(scatter_color(mapping, point)))
+}
+
+/// Color value of a scatter point, when the mapping has a color field
+///
+/// Points are `[x, y, size?, color?]`.
+fn scatter_color<'a>(mapping: &DataMapping, point: &'a [Value]) -> Option<&'a Value> {
+    let color_idx = if mapping.size.is_some() { 3 } else { 2 };
+    mapping.color.as_ref().and_then(|_| point.get(color_idx))
+}
+
+/// Condition on the category of a clicked series, when the category field
+/// splits the chart's measures into series (the "Other" series merges
+/// several categories and adds no condition)
+fn split_category(widget_type: WidgetType, mapping: &DataMapping, clicked: &ChartElement) -> Option<Filter> {
+    if !matches!(widget_type, WidgetType::Bar | WidgetType::Line | WidgetType::Area) {
+        return None;
+    }
+    let category = mapping.category.as_ref()?;
+    let series = clicked.series_name.as_deref()?;
+    // With several measures, series are named "<category> · <measure>"
+    let label = match mapping.y_axis.as_slice() {
+        [_] => series,
+        _ => series.rsplit_once(" · ").map_or(series, |(label, _)| label),
+    };
+    (label != OTHER_SERIES).then(|| Filter {
+        field: category.clone(),
+        operator: FilterOperator::Equals,
+        values: vec![Value::from(label)],
+    })
+}
+
+/// Let the elements of a chart be selected
+///
+/// Series get multiple selection, so selected elements can be highlighted,
+/// and charts with a category or time axis get a brush along it.
+pub fn enable_selection(options: &mut Value, widget_type: WidgetType) {
+    let enable = |series: &mut Value| {
+        if let Some(series) = series.as_object_mut() {
+            series.entry("selectedMode").or_insert(json!("multiple"));
+        }
+    };
+    match &mut options["series"] {
+        Value::Array(series) => series.iter_mut().for_each(enable),
+        series => enable(series),
+    }
+
+    if !has_dimension_axis(widget_type) {
+        return;
+    }
+    let (brush_type, axis_index) = if dimension_on_y(options) {
+        ("lineY", "yAxisIndex")
+    } else {
+        ("lineX", "xAxisIndex")
+    };
+    options["brush"] = json!({
+        "toolbox": [brush_type, "clear"],
+        axis_index: 0,
+        "throttleType": "debounce",
+        "throttleDelay": 300,
+    });
+    options["toolbox"] = json!({
+        "right": 8,
+        "feature": { "brush": { "type": [brush_type, "clear"] } },
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::dashboard::data::time::{TimeBucketing, TimeGranularity};
+
+    fn bar_options() -> Value {
+        json!({
+            "xAxis": { "type": "category", "data": ["Jan", "Feb", "Mar"] },
+            "yAxis": { "type": "value" },
+            "series": [{ "type": "bar", "data": [1, 2, 3] }],
+        })
+    }
+
+    #[test]
+    fn test_click_and_brush_decoding() {
+        let click = ChartEvent::from_click(&json!({ "seriesName": "Sales", "name": "Feb", "value": 2 }), &bar_options());
+        let ChartEvent::Click(element) = &click else { panic!("expected a click") };
+        assert_eq!(element.name.as_deref(), Some("Feb"));
+        assert!(element.axis_names.is_empty());
+
+        let heatmap = json!({
+            "xAxis": { "type": "category", "data": ["Mon", "Tue"] },
+            "yAxis": { "type": "category", "data": ["AM", "PM"] },
+        });
+        let ChartEvent::Click(cell) = ChartEvent::from_click(&json!({ "value": [1, 0, 7] }), &heatmap) else {
+            panic!("expected a click")
+        };
+        assert_eq!(cell.axis_names, vec!["Tue", "AM"]);
+
+        let treemap = json!({ "treePathInfo": [{ "name": "" }, { "name": "Europe" }, { "name": "Italy" }] });
+        let ChartEvent::Click(node) = ChartEvent::from_click(&treemap, &json!({})) else { panic!("expected a click") };
+        assert_eq!(node.tree_path, vec!["Europe", "Italy"]);
+
+        let brush = json!({ "batch": [{
+            "areas": [{ "brushType": "lineX" }],
+            "selected": [{ "dataIndex": [2, 0] }, { "dataIndex": [0] }],
+        }] });
+        assert_eq!(
+            ChartEvent::from_brush(&brush, &bar_options()),
+            Some(ChartEvent::Brush(vec!["Mar".to_string(), "Jan".to_string()]))
+        );
+        assert_eq!(ChartEvent::from_brush(&json!({ "batch": [{ "areas": [] }] }), &bar_options()), None);
+
+        // Dataset charts on a time axis name points by their first column
+        let timeline = json!({
+            "dataset": { "source": [["Date", "Sales"], ["2024-01-01", 5], ["2024-02-01", 7]] },
+            "xAxis": { "type": "time" },
+        });
+        let ChartEvent::Click(point) = ChartEvent::from_click(&json!({ "name": "", "value": ["2024-02-01", 7] }), &timeline)
+        else {
+            panic!("expected a click")
+        };
+        assert_eq!(point.name.as_deref(), Some("2024-02-01"));
+        let brush = json!({ "batch": [{ "areas": [{}], "selected": [{ "dataIndex": [0] }] }] });
+        assert_eq!(
+            ChartEvent::from_brush(&brush, &timeline),
+            Some(ChartEvent::Brush(vec!["2024-01-01".to_string()]))
+        );
+    }
+
+    #[test]
+    fn test_selected_elements_by_widget_type() {
+        let mapping = DataMapping {
+            x_axis: Some("Month".to_string()),
+            category: Some("Shift".to_string()),
+            color: Some("Region".to_string()),
+            hierarchy: vec!["Continent".to_string(), "Country".to_string()],
+            ..Default::default()
+        };
+        let click = |element: ChartElement| ChartEvent::Click(element);
+        let conditions = |widget_type, event: &ChartEvent| -> Vec<(String, Value)> {
+            selected_elements(widget_type, &mapping, event)
+                .into_iter()
+                .flat_map(|element| element.conditions)
+                .map(|filter| (filter.field, filter.values[0].clone()))
+                .collect()
+        };
+
+        let bar = click(ChartElement { name: Some("Feb".to_string()), ..Default::default() });
+        assert_eq!(conditions(WidgetType::Pie, &bar), vec![("Month".to_string(), json!("Feb"))]);
+        assert!(conditions(WidgetType::Radar, &bar).is_empty());
+
+        // Series split by category also select their category
+        let split_bar = click(ChartElement {
+            series_name: Some("North".to_string()),
+            name: Some("Feb".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(
+            conditions(WidgetType::Bar, &split_bar),
+            vec![("Month".to_string(), json!("Feb")), ("Shift".to_string(), json!("North"))]
+        );
+
+        let cell = click(ChartElement { axis_names: vec!["Tue".to_string(), "AM".to_string()], ..Default::default() });
+        assert_eq!(
+            conditions(WidgetType::Heatmap, &cell),
+            vec![("Month".to_string(), json!("Tue")), ("Shift".to_string(), json!("AM"))]
+        );
+        assert_eq!(selected_elements(WidgetType::Heatmap, &mapping, &cell)[0].name, "Tue · AM");
+
+        let node = click(ChartElement { tree_path: vec!["Europe".to_string()], ..Default::default() });
+        assert_eq!(conditions(WidgetType::Treemap, &node), vec![("Continent".to_string(), json!("Europe"))]);
+
+        let point = click(ChartElement { value: json!([3, 10, "North"]), ..Default::default() });
+        assert_eq!(
+            conditions(WidgetType::Scatter, &point),
+            vec![("Month".to_string(), json!(3)), ("Region".to_string(), json!("North"))]
+        );
+        assert_eq!(selected_elements(WidgetType::Scatter, &mapping, &point)[0].name, "3 · North");
+
+        let brush = ChartEvent::Brush(vec!["Jan".to_string(), "Mar".to_string()]);
+        assert_eq!(selected_elements(WidgetType::Line, &mapping, &brush).len(), 2);
+        assert!(selected_elements(WidgetType::Pie, &mapping, &brush).is_empty());
+
+        // Monthly periods select the month's dates
+        let monthly = DataMapping {
+            x_axis: Some("Date".to_string()),
+            time_bucketing: Some(TimeBucketing { granularity: TimeGranularity::Month, ..Default::default() }),
+            ..Default::default()
+        };
+        let period = click(ChartElement { name: Some("2024-02-01".to_string()), ..Default::default() });
+        let selected = selected_elements(WidgetType::Line, &monthly, &period);
+        assert_eq!(selected[0].conditions[0].operator, FilterOperator::DateRange);
+        assert_eq!(selected[0].conditions[0].values, vec![json!("2024-02"), json!("2024-02")]);
+    }
+
+    #[test]
+    fn test_selection_toggles_and_ors_elements() {
+        let element = |name: &str| SelectedElement {
+            name: name.to_string(),
+            conditions: vec![Filter {
+                field: "Month".to_string(),
+                operator: FilterOperator::Equals,
+                values: vec![json!(name)],
+            }],
+        };
+        let mut selection = ChartSelection { source: "bar".to_string(), elements: Vec::new() };
+        selection.toggle(element("Jan"));
+        selection.toggle(element("Feb"));
+        selection.toggle(element("Jan"));
+        assert_eq!(selection.names(), vec!["Feb"]);
+
+        selection.toggle(element("Mar"));
+        let group = selection.group();
+        assert_eq!(group.combinator, FilterCombinator::Or);
+        assert_eq!(group.filters.len(), 2);
+    }
+
+    #[test]
+    fn test_enable_selection_adds_brush_on_dimension_axis() {
+        let mut options = bar_options();
+        enable_selection(&mut options, WidgetType::Bar);
+        assert_eq!(options["series"][0]["selectedMode"], "multiple");
+        assert_eq!(options["brush"]["xAxisIndex"], 0);
+
+        let mut horizontal = json!({ "xAxis": { "type": "value" }, "yAxis": { "type": "time" }, "series": [] });
+        enable_selection(&mut horizontal, WidgetType::Bar);
+        assert_eq!(horizontal["brush"]["yAxisIndex"], 0);
+
+        let mut pie = json!({ "series": [{ "type": "pie" }] });
+        enable_selection(&mut pie, WidgetType::Pie);
+        assert_eq!(pie["series"][0]["selectedMode"], "multiple");
+        assert!(pie.get("brush").is_none());
+    }
+}
@@ -0,0 +1,316 @@
+use crate::features::dashboard::{Field, FieldType, Dataset, CsvError};
+use crate::features::dashboard::data::{table::ColumnBuilder, DataTable};
+use crate::features::dashboard::csv_upload::{
+    csv_parser::{self, TYPE_SAMPLE_ROWS},
+    dialect,
+    import_options::ImportOptions,
+    number_format,
+    schema_validator::{self, ValidationConfig},
+    value_converter::ColumnConverter,
+};
+use csv::StringRecord;
+use std::io::{Cursor, Read};
+use std::sync::Arc;
+
+/// Number of rows parsed per chunk before yielding to the event loop
+pub const PARSE_CHUNK_ROWS: usize = 5_000;
+
+/// UTF-8 byte order mark some exporters put at the start of the file
+const UTF8_BOM: &str = "\u{feff}";
+
+/// File text shared with the pending import, readable without a copy
+#[derive(Clone, Debug)]
+pub struct SharedText(Arc<String>);
+
+impl AsRef<[u8]> for SharedText {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_bytes()
+    }
+}
+
+/// Incremental parser for delimited text
+///
+/// The header and the type sample are read up front; the remaining rows
+/// are read in chunks with [`ChunkedCsvParser::parse_chunk`] and converted
+/// straight into typed dataset columns, so no intermediate table of strings
+/// is kept and the caller can report progress (or stop) between chunks.
+///
+/// Column types, currency / percent formats and date layouts are decided
+/// from the first `TYPE_SAMPLE_ROWS` rows.
+pub struct ChunkedCsvParser<R: Read> {
+    reader: csv::Reader<R>,
+    record: StringRecord,
+    /// Bytes before the reader's start (a skipped byte order mark)
+    offset: u64,
+    total_bytes: u64,
+    /// Records read so far, including skipped rows and the header
+    records_read: usize,
+    width: usize,
+    /// Source column index and converter of every included column
+    columns: Vec<(usize, ColumnConverter)>,
+    fields: Vec<Field>,
+    /// One builder per included column
+    data: Vec<ColumnBuilder>,
+    rows: usize,
+    options: ImportOptions,
+    finished: bool,
+}
+
+impl ChunkedCsvParser<Cursor<SharedText>> {
+    /// Start parsing text shared with the pending import
+    pub fn from_shared_text(text: Arc<String>, options: &ImportOptions) -> Result<Self, CsvError> {
+        let total_bytes = text.len() as u64;
+        let offset = if text.starts_with(UTF8_BOM) { UTF8_BOM.len() as u64 } else { 0 };
+        let mut cursor = Cursor::new(SharedText(text));
+        cursor.set_position(offset);
+        Self::new(cursor, offset, total_bytes, options)
+    }
+}
+
+impl<'a> ChunkedCsvParser<&'a [u8]> {
+    /// Start parsing borrowed text
+    pub fn from_text(text: &'a str, options: &ImportOptions) -> Result<Self, CsvError> {
+        let body = text.strip_prefix(UTF8_BOM).unwrap_or(text);
+        let offset = (text.len() - body.len()) as u64;
+        Self::new(body.as_bytes(), offset, text.len() as u64, options)
+    }
+}
+
+impl<R: Read> ChunkedCsvParser<R> {
+    /// Read skipped rows, the header and the type sample, and set up the columns
+    ///
+    /// # Arguments
+    /// * `input` - Delimited text, positioned after any byte order mark
+    /// * `offset` - Bytes already skipped before `input`
+    /// * `total_bytes` - Size of the whole text, for progress
+    /// * `options` - Dialect, skipped rows and per-column overrides
+    fn new(input: R, offset: u64, total_bytes: u64, options: &ImportOptions) -> Result<Self, CsvError> {
+        let reader = dialect::reader_builder(&options.dialect)
+            .flexible(true)
+            .from_reader(input);
+
+        let mut parser = Self {
+            reader,
+            record: StringRecord::new(),
+            offset,
+            total_bytes,
+            records_read: 0,
+            width: 0,
+            columns: Vec::new(),
+            fields: Vec::new(),
+            data: Vec::new(),
+            rows: 0,
+            options: options.clone(),
+            finished: false,
+        };
+
+        for _ in 0..options.skip_rows {
+            if !parser.read_next()? {
+                return Err(CsvError::EmptyFile);
+            }
+        }
+
+        let header = if options.dialect.has_header {
+            if !parser.read_next()? {
+                return Err(CsvError::EmptyFile);
+            }
+            Some(record_cells(&parser.record))
+        } else {
+            None
+        };
+
+        let mut sample: Vec<Vec<String>> = Vec::new();
+        while sample.len() < TYPE_SAMPLE_ROWS && parser.read_next()? {
+            sample.push(record_cells(&parser.record));
+        }
+        if header.is_none() && sample.is_empty() {
+            return Err(CsvError::EmptyFile);
+        }
+
+        let headers = header.unwrap_or_else(|| {
+            let width = sample.iter().map(|row| row.len()).max().unwrap_or(0);
+            (1..=width).map(|i| format!("Column {}", i)).collect()
+        });
+        parser.width = headers.len();
+
+        // Every data row must line up with the header
+        let first_sample_row = parser.records_read - sample.len();
+        for (idx, row) in sample.iter().enumerate() {
+            parser.check_width(row.len(), first_sample_row + idx + 1)?;
+        }
+
+        // Apply user overrides to the detected types
+        let number_format = options.dialect.number_format;
+        let detected = csv_parser::detect_field_types(&headers, &sample, &number_format)?;
+        let columns = options.resolve_columns(&headers, &detected);
+
+        let names: Vec<String> = columns.iter().map(|(_, field)| field.name.clone()).collect();
+        schema_validator::validate_structure(&names, sample.len(), &ValidationConfig::default())?;
+
+        // Numeric columns keep the currency / percent notation of the sample
+        for (idx, mut field) in columns {
+            let cells: Vec<&str> = sample.iter().map(|row| row[idx].as_str()).collect();
+            if field.field_type == FieldType::Numeric {
+                field.format = number_format::detect_field_format(&cells, &number_format);
+            }
+            parser.columns.push((idx, ColumnConverter::new(field.field_type, &cells, &number_format)));
+            parser.data.push(ColumnBuilder::new(field.field_type));
+            parser.fields.push(field);
+        }
+
+        for row in &sample {
+            let cells: Vec<&str> = row.iter().map(String::as_str).collect();
+            push_cells(&parser.columns, &mut parser.data, &cells);
+        }
+        parser.rows = sample.len();
+
+        Ok(parser)
+    }
+
+    /// Read and convert up to `max_rows` more rows
+    ///
+    /// # Returns
+    /// `true` once the end of the file has been reached
+    pub fn parse_chunk(&mut self, max_rows: usize) -> Result<bool, CsvError> {
+        for _ in 0..max_rows {
+            if !self.read_next()? {
+                self.finished = true;
+                break;
+            }
+            self.check_width(self.record.len(), self.records_read)?;
+
+            let cells: Vec<&str> = self.record.iter().collect();
+            push_cells(&self.columns, &mut self.data, &cells);
+            self.rows += 1;
+        }
+        Ok(self.finished)
+    }
+
+    /// Number of data rows parsed so far
+    pub fn rows_processed(&self) -> u64 {
+        self.rows as u64
+    }
+
+    /// Share of the text read so far, as a percentage (0-100)
+    pub fn progress(&self) -> f64 {
+        if self.finished || self.total_bytes == 0 {
+            return 100.0;
+        }
+        let read = self.offset + self.reader.position().byte();
+        (read as f64 / self.total_bytes as f64 * 100.0).min(100.0)
+    }
+
+    /// Parse whatever is left and build the Dataset
+    ///
+    /// # Arguments
+    /// * `filename` - Name of the file
+    /// * `file_size` - Size of the file in bytes
+    pub fn finish(mut self, filename: &str, file_size: u64) -> Result<(Dataset, Vec<Field>), CsvError> {
+        while !self.parse_chunk(PARSE_CHUNK_ROWS)? {}
+        if self.rows == 0 {
+            return Err(CsvError::EmptyFile);
+        }
+
+        let data = DataTable::from_columns(self.data.into_iter().map(ColumnBuilder::finish).collect());
+        let dataset = csv_parser::dataset_from_table(
+            self.fields.clone(),
+            data,
+            filename,
+            file_size,
+            &self.options,
+        );
+        Ok((dataset, self.fields))
+    }
+
+    /// Read the next record into `self.record`
+    fn read_next(&mut self) -> Result<bool, CsvError> {
+        let more = self
+            .reader
+            .read_record(&mut self.record)
+            .map_err(|e| CsvError::ParseError {
+                row: self.records_read + 1,
+                message: e.to_string(),
+            })?;
+        if more {
+            self.records_read += 1;
+        }
+        Ok(more)
+    }
+
+    fn check_width(&self, found: usize, row: usize) -> Result<(), CsvError> {
+        if found == self.width {
+            return Ok(());
+        }
+        Err(CsvError::ParseError {
+            row,
+            message: format!("expected {} fields, found {}", self.width, found),
+        })
+    }
+
+}
+
+/// Convert the included cells of a row into the column builders
+fn push_cells(columns: &[(usize, ColumnConverter)], data: &mut [ColumnBuilder], cells: &[&str]) {
+    for ((idx, converter), builder) in columns.iter().zip(data) {
+        builder.push(converter.convert(cells[*idx]));
+    }
+}
+
+fn record_cells(record: &StringRecord) -> Vec<String> {
+    record.iter().map(str::to_string).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use crate::features::dashboard::CsvDialect;
+
+    fn options() -> ImportOptions {
+        ImportOptions::new(CsvDialect::default())
+    }
+
+    #[test]
+    fn test_chunks_report_progress() {
+        let text: String = std::iter::once("id,name\n".to_string())
+            .chain((0..250).map(|i| format!("{},row {}\n", i, i)))
+            .collect();
+        let mut parser = ChunkedCsvParser::from_shared_text(Arc::new(text), &options()).unwrap();
+
+        // The type sample is converted up front
+        assert_eq!(parser.rows_processed(), TYPE_SAMPLE_ROWS as u64);
+        assert!(parser.progress() < 50.0);
+
+        assert!(!parser.parse_chunk(100).unwrap());
+        assert_eq!(parser.rows_processed(), 200);
+        assert!(parser.parse_chunk(100).unwrap());
+        assert_eq!(parser.progress(), 100.0);
+
+        let (dataset, fields) = parser.finish("big.csv", 2048).unwrap();
+        assert_eq!(dataset.data.len(), 250);
+        assert_eq!(fields[0].field_type, FieldType::Numeric);
+        assert_eq!(dataset.data.row(249), vec![Value::from(249.0), Value::from("row 249")]);
+    }
+
+    #[test]
+    fn test_ragged_row_after_sample_reports_line() {
+        let text: String = std::iter::once("a,b\n".to_string())
+            .chain((0..150).map(|i| format!("{},{}\n", i, i)))
+            .chain(std::iter::once("1,2,3\n".to_string()))
+            .collect();
+        let parser = ChunkedCsvParser::from_text(&text, &options()).unwrap();
+
+        let result = parser.finish("ragged.csv", 100);
+        assert!(matches!(result, Err(CsvError::ParseError { row: 152, .. })));
+    }
+
+    #[test]
+    fn test_bom_and_header_only_file() {
+        let parser = ChunkedCsvParser::from_text("\u{feff}a,b\n1,2\n", &options()).unwrap();
+        let (_, fields) = parser.finish("bom.csv", 10).unwrap();
+        assert_eq!(fields[0].name, "a");
+
+        let parser = ChunkedCsvParser::from_text("a,b\n", &options()).unwrap();
+        assert!(matches!(parser.finish("empty.csv", 4), Err(CsvError::EmptyFile)));
+    }
+}
@@ -0,0 +1,454 @@
+use crate::features::dashboard::{CsvError, Dataset, Field, FieldType, NumberFormat};
+use crate::features::dashboard::data::{Column, DataTable};
+use crate::features::dashboard::csv_upload::{
+    csv_parser,
+    import_options::{ImportOptions, ImportPreview, SourceFormat},
+    schema_validator::{self, ValidationConfig},
+    value_converter,
+};
+use arrow_array::cast::AsArray;
+use arrow_array::types::{Date32Type, Float64Type, TimestampMicrosecondType};
+use arrow_array::{Array, ArrayRef, RecordBatch};
+use arrow_cast::cast;
+use arrow_cast::display::{ArrayFormatter, FormatOptions};
+use arrow_ipc::reader::{FileReader, StreamReader};
+use arrow_schema::{ArrowError, DataType, SchemaRef, TimeUnit};
+use bytes::Bytes;
+use parquet::arrow::ProjectionMask;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use serde_json::Value;
+use std::io::Cursor;
+
+/// Magic bytes at the start of an Arrow IPC *file* (streams have none)
+const ARROW_FILE_MAGIC: &[u8] = b"ARROW1";
+
+/// Parse a Parquet or Arrow IPC file and create a Dataset
+///
+/// Column types come from the file's schema rather than from the text
+/// of the cells: integers, floats and decimals become numeric, dates and
+/// timestamps become dates, booleans stay booleans and everything else is
+/// text. Only the columns kept in the import wizard are decoded.
+///
+/// # Arguments
+/// * `bytes` - The file content
+/// * `filename` - Name of the file
+/// * `file_size` - Size of the file in bytes
+/// * `options` - Format and per-column overrides from the import wizard
+///
+/// # Returns
+/// A tuple of (Dataset, Vec<Field>) or a CsvError
+pub fn parse_columnar_with_options(
+    bytes: Bytes,
+    filename: &str,
+    file_size: u64,
+    options: &ImportOptions,
+) -> Result<(Dataset, Vec<Field>), CsvError> {
+    let schema = read_schema(&bytes, options.format)?;
+    let headers = column_names(&schema);
+    let native = native_types(&schema);
+    let columns = options.resolve_columns(&headers, &native);
+
+    let names: Vec<String> = columns.iter().map(|(_, field)| field.name.clone()).collect();
+    schema_validator::validate_structure(&names, 0, &ValidationConfig::default())?;
+
+    // Decoded batches hold the projected columns in schema order
+    let mut projection: Vec<usize> = columns.iter().map(|(idx, _)| *idx).collect();
+    projection.sort_unstable();
+    let batches = read_batches(bytes, options.format, Some(projection.clone()), None)?;
+
+    let row_count: usize = batches.iter().map(RecordBatch::num_rows).sum();
+    if row_count == 0 {
+        return Err(CsvError::EmptyFile);
+    }
+
+    // Columns keep their native values unless the wizard changed the
+    // type, in which case they are converted like text cells
+    let converted: Vec<Column> = columns
+        .iter()
+        .map(|(idx, field)| {
+            let position = projection.binary_search(idx).unwrap_or_default();
+            let values = column_values(&batches, position)?;
+            if field.field_type == native[*idx] {
+                return Ok(Column::from_values(field.field_type, values));
+            }
+            let cells: Vec<String> = values.iter().map(value_text).collect();
+            let cells: Vec<&str> = cells.iter().map(String::as_str).collect();
+            let values = value_converter::convert_column(&cells, field.field_type, &NumberFormat::default());
+            Ok(Column::from_values(field.field_type, values))
+        })
+        .collect::<Result<_, CsvError>>()?;
+
+    let fields: Vec<Field> = columns.into_iter().map(|(_, field)| field).collect();
+    let data = DataTable::from_columns(converted);
+    let dataset = csv_parser::dataset_from_table(fields.clone(), data, filename, file_size, options);
+
+    Ok((dataset, fields))
+}
+
+/// Build the import wizard preview of a Parquet or Arrow IPC file
+///
+/// Only the first `limit` rows are decoded.
+///
+/// # Arguments
+/// * `bytes` - The file content
+/// * `options` - Current wizard settings
+/// * `limit` - Number of rows to include in the preview
+pub fn preview_columnar(
+    bytes: Bytes,
+    options: &ImportOptions,
+    limit: usize,
+) -> Result<ImportPreview, CsvError> {
+    let schema = read_schema(&bytes, options.format)?;
+    let headers = column_names(&schema);
+    let types = native_types(&schema);
+
+    let batches = read_batches(bytes, options.format, None, Some(limit))?;
+    let columns = (0..headers.len())
+        .map(|col| column_values(&batches, col))
+        .collect::<Result<Vec<_>, CsvError>>()?;
+
+    let row_count: usize = batches.iter().map(RecordBatch::num_rows).sum();
+    if row_count == 0 {
+        return Err(CsvError::EmptyFile);
+    }
+
+    let rows: Vec<Vec<String>> = (0..row_count)
+        .map(|row| columns.iter().map(|column| value_text(&column[row])).collect())
+        .collect();
+    let raw_rows = std::iter::once(headers.clone()).chain(rows.iter().cloned()).collect();
+
+    Ok(csv_parser::build_preview(raw_rows, headers, rows, types, &NumberFormat::default(), limit))
+}
+
+/// Wrap a reader error with the kind of file being read
+fn unreadable(format: SourceFormat, error: impl std::fmt::Display) -> CsvError {
+    CsvError::InvalidCsvFormat(format!("unreadable {} file: {}", format.display_name(), error))
+}
+
+/// Whether the bytes are an Arrow IPC file rather than a stream
+fn is_arrow_file(bytes: &[u8]) -> bool {
+    bytes.starts_with(ARROW_FILE_MAGIC)
+}
+
+/// Read the schema without decoding any data
+fn read_schema(bytes: &Bytes, format: SourceFormat) -> Result<SchemaRef, CsvError> {
+    let schema = match format {
+        SourceFormat::Parquet => {
+            ParquetRecordBatchReaderBuilder::try_new(bytes.clone()).map(|builder| builder.schema().clone())
+        }
+        _ if is_arrow_file(bytes) => FileReader::try_new(Cursor::new(&bytes[..]), None)
+            .map(|reader| reader.schema())
+            .map_err(Into::into),
+        _ => StreamReader::try_new(Cursor::new(&bytes[..]), None)
+            .map(|reader| reader.schema())
+            .map_err(Into::into),
+    };
+    schema.map_err(|e| unreadable(format, e))
+}
+
+/// Decode record batches, optionally restricted to some columns and rows
+///
+/// # Arguments
+/// * `projection` - Indices of the top-level columns to decode (ascending)
+/// * `limit` - Maximum number of rows to decode
+fn read_batches(
+    bytes: Bytes,
+    format: SourceFormat,
+    projection: Option<Vec<usize>>,
+    limit: Option<usize>,
+) -> Result<Vec<RecordBatch>, CsvError> {
+    let batches = match format {
+        SourceFormat::Parquet => {
+            let mut builder = ParquetRecordBatchReaderBuilder::try_new(bytes)
+                .map_err(|e| unreadable(format, e))?;
+            if let Some(projection) = projection {
+                let mask = ProjectionMask::roots(builder.parquet_schema(), projection);
+                builder = builder.with_projection(mask);
+            }
+            if let Some(limit) = limit {
+                builder = builder.with_limit(limit);
+            }
+            let reader = builder.build().map_err(|e| unreadable(format, e))?;
+            collect_batches(reader, limit)
+        }
+        _ if is_arrow_file(&bytes) => FileReader::try_new(Cursor::new(&bytes[..]), projection)
+            .and_then(|reader| collect_batches(reader, limit)),
+        _ => StreamReader::try_new(Cursor::new(&bytes[..]), projection)
+            .and_then(|reader| collect_batches(reader, limit)),
+    };
+    batches.map_err(|e| unreadable(format, e))
+}
+
+/// Collect batches until `limit` rows have been read
+fn collect_batches(
+    reader: impl Iterator<Item = Result<RecordBatch, ArrowError>>,
+    limit: Option<usize>,
+) -> Result<Vec<RecordBatch>, ArrowError> {
+    let mut remaining = limit.unwrap_or(usize::MAX);
+    let mut batches = Vec::new();
+    for batch in reader {
+        if remaining == 0 {
+            break;
+        }
+        let batch = batch?;
+        let rows = batch.num_rows().min(remaining);
+        remaining -= rows;
+        batches.push(batch.slice(0, rows));
+    }
+    Ok(batches)
+}
+
+/// Names of the top-level columns
+fn column_names(schema: &SchemaRef) -> Vec<String> {
+    schema.fields().iter().map(|field| field.name().clone()).collect()
+}
+
+/// Field types of the top-level columns
+fn native_types(schema: &SchemaRef) -> Vec<FieldType> {
+    schema.fields().iter().map(|field| field_type_of(field.data_type())).collect()
+}
+
+/// Map an Arrow type onto the dashboard's field types
+fn field_type_of(data_type: &DataType) -> FieldType {
+    match data_type {
+        DataType::Boolean => FieldType::Boolean,
+        DataType::Date32 | DataType::Date64 | DataType::Timestamp(..) => FieldType::Date,
+        DataType::Dictionary(_, values) => field_type_of(values),
+        other if other.is_numeric() => FieldType::Numeric,
+        _ => FieldType::Text,
+    }
+}
+
+/// Values of one column across all batches
+fn column_values(batches: &[RecordBatch], column: usize) -> Result<Vec<Value>, CsvError> {
+    let mut values = Vec::with_capacity(batches.iter().map(RecordBatch::num_rows).sum());
+    for batch in batches {
+        values.extend(array_values(batch.column(column))?);
+    }
+    Ok(values)
+}
+
+/// Convert an Arrow array to JSON values following its field type
+///
+/// Dates are written as `YYYY-MM-DD` and timestamps as
+/// `YYYY-MM-DDTHH:MM:SS` (UTC with a `Z` suffix when the column has a time
+/// zone), matching what the CSV importer normalizes to.
+fn array_values(array: &ArrayRef) -> Result<Vec<Value>, CsvError> {
+    let cast_err = |e: ArrowError| CsvError::InvalidCsvFormat(format!("unsupported column: {}", e));
+    let non_null = |i: usize, value: Value| if array.is_null(i) { Value::Null } else { value };
+
+    let values = match array.data_type() {
+        DataType::Boolean => array
+            .as_boolean()
+            .iter()
+            .map(|b| b.map(Value::Bool).unwrap_or(Value::Null))
+            .collect(),
+        DataType::Date32 | DataType::Date64 => {
+            let days = cast(array, &DataType::Date32).map_err(cast_err)?;
+            let days = days.as_primitive::<Date32Type>();
+            (0..days.len())
+                .map(|i| {
+                    let date = days.value_as_date(i).map(|d| d.format("%Y-%m-%d").to_string());
+                    non_null(i, date.map(Value::String).unwrap_or(Value::Null))
+                })
+                .collect()
+        }
+        DataType::Timestamp(_, tz) => {
+            let micros = cast(array, &DataType::Timestamp(TimeUnit::Microsecond, tz.clone())).map_err(cast_err)?;
+            let micros = micros.as_primitive::<TimestampMicrosecondType>();
+            let layout = if tz.is_some() { "%Y-%m-%dT%H:%M:%SZ" } else { "%Y-%m-%dT%H:%M:%S" };
+            (0..micros.len())
+                .map(|i| {
+                    let ts = micros.value_as_datetime(i).map(|dt| dt.format(layout).to_string());
+                    non_null(i, ts.map(Value::String).unwrap_or(Value::Null))
+                })
+                .collect()
+        }
+        DataType::Dictionary(_, values) => {
+            let unpacked = cast(array, values).map_err(cast_err)?;
+            return array_values(&unpacked);
+        }
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => {
+            let text = cast(array, &DataType::Utf8).map_err(cast_err)?;
+            text.as_string::<i32>()
+                .iter()
+                .map(|s| s.map(|s| Value::String(s.to_string())).unwrap_or(Value::Null))
+                .collect()
+        }
+        other if other.is_numeric() => {
+            let floats = cast(array, &DataType::Float64).map_err(cast_err)?;
+            floats
+                .as_primitive::<Float64Type>()
+                .iter()
+                .map(|n| n.and_then(serde_json::Number::from_f64).map(Value::Number).unwrap_or(Value::Null))
+                .collect()
+        }
+        // Lists, structs, binary, times...: shown as Arrow displays them
+        _ => {
+            let formatter = ArrayFormatter::try_new(array.as_ref(), &FormatOptions::default()).map_err(cast_err)?;
+            (0..array.len())
+                .map(|i| non_null(i, Value::String(formatter.value(i).to_string())))
+                .collect()
+        }
+    };
+
+    Ok(values)
+}
+
+/// Render a value as the text of a cell (whole numbers without `.0`)
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Number(n) => match n.as_f64() {
+            Some(f) if f.fract() == 0.0 && f.abs() < 1e15 => format!("{}", f as i64),
+            _ => n.to_string(),
+        },
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::dashboard::CsvDialect;
+    use crate::features::dashboard::csv_upload::import_options::ColumnOverride;
+    use arrow_array::{BooleanArray, Date32Array, Float64Array, Int64Array, StringArray, TimestampMillisecondArray};
+    use arrow_schema::{Field as ArrowField, Schema};
+    use std::sync::Arc;
+
+    fn options(format: SourceFormat) -> ImportOptions {
+        ImportOptions {
+            format,
+            ..ImportOptions::new(CsvDialect::default())
+        }
+    }
+
+    fn sample_batch() -> RecordBatch {
+        let schema = Schema::new(vec![
+            ArrowField::new("id", DataType::Int64, false),
+            ArrowField::new("price", DataType::Float64, true),
+            ArrowField::new("city", DataType::Utf8, true),
+            ArrowField::new("paid", DataType::Boolean, true),
+            ArrowField::new("day", DataType::Date32, true),
+            ArrowField::new("at", DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())), true),
+        ]);
+        RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2])),
+                Arc::new(Float64Array::from(vec![Some(9.5), None])),
+                Arc::new(StringArray::from(vec![Some("Rome"), None])),
+                Arc::new(BooleanArray::from(vec![Some(true), Some(false)])),
+                // 19723 days after the epoch is 2024-01-01
+                Arc::new(Date32Array::from(vec![Some(19723), None])),
+                Arc::new(
+                    TimestampMillisecondArray::from(vec![Some(1_704_103_200_000), None]).with_timezone("UTC"),
+                ),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn parquet_bytes(batch: &RecordBatch) -> Bytes {
+        let mut buffer = Vec::new();
+        let mut writer = parquet::arrow::ArrowWriter::try_new(&mut buffer, batch.schema(), None).unwrap();
+        writer.write(batch).unwrap();
+        writer.close().unwrap();
+        Bytes::from(buffer)
+    }
+
+    #[test]
+    fn test_parse_parquet_maps_schema_types() {
+        let bytes = parquet_bytes(&sample_batch());
+        let (dataset, fields) =
+            parse_columnar_with_options(bytes, "sales.parquet", 1024, &options(SourceFormat::Parquet)).unwrap();
+
+        let types: Vec<FieldType> = fields.iter().map(|f| f.field_type).collect();
+        assert_eq!(
+            types,
+            vec![
+                FieldType::Numeric,
+                FieldType::Numeric,
+                FieldType::Text,
+                FieldType::Boolean,
+                FieldType::Date,
+                FieldType::Date
+            ]
+        );
+        assert_eq!(
+            dataset.data.row(0),
+            vec![
+                Value::from(1.0),
+                Value::from(9.5),
+                Value::from("Rome"),
+                Value::Bool(true),
+                Value::from("2024-01-01"),
+                Value::from("2024-01-01T10:00:00Z")
+            ]
+        );
+        assert_eq!(dataset.data.value(1, 1), Value::Null);
+        assert_eq!(dataset.data.value(1, 5), Value::Null);
+        assert_eq!(dataset.dialect, None);
+    }
+
+    #[test]
+    fn test_parse_parquet_with_column_selection() {
+        let bytes = parquet_bytes(&sample_batch());
+        let mut opts = options(SourceFormat::Parquet);
+        opts.columns = column_names(&sample_batch().schema())
+            .into_iter()
+            .zip(native_types(&sample_batch().schema()))
+            .map(|(source, field_type)| ColumnOverride {
+                name: source.clone(),
+                include: source == "city" || source == "id",
+                source,
+                field_type,
+            })
+            .collect();
+        opts.columns[0].name = "order".to_string();
+        opts.columns[0].field_type = FieldType::Text;
+
+        let (dataset, fields) = parse_columnar_with_options(bytes, "sales.parquet", 1024, &opts).unwrap();
+        let names: Vec<&str> = fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["order", "city"]);
+        assert_eq!(dataset.data.row(1), vec![Value::from("2"), Value::Null]);
+    }
+
+    #[test]
+    fn test_parse_arrow_file_and_stream() {
+        let batch = sample_batch();
+
+        let mut file = Vec::new();
+        let mut writer = arrow_ipc::writer::FileWriter::try_new(&mut file, &batch.schema()).unwrap();
+        writer.write(&batch).unwrap();
+        writer.finish().unwrap();
+        drop(writer);
+
+        let mut stream = Vec::new();
+        let mut writer = arrow_ipc::writer::StreamWriter::try_new(&mut stream, &batch.schema()).unwrap();
+        writer.write(&batch).unwrap();
+        writer.finish().unwrap();
+        drop(writer);
+
+        for bytes in [file, stream] {
+            let (dataset, fields) =
+                parse_columnar_with_options(Bytes::from(bytes), "sales.arrow", 512, &options(SourceFormat::ArrowIpc))
+                    .unwrap();
+            assert_eq!(fields.len(), 6);
+            assert_eq!(dataset.data.len(), 2);
+            assert_eq!(dataset.data.value(0, 2), Value::from("Rome"));
+        }
+    }
+
+    #[test]
+    fn test_preview_and_unreadable_file() {
+        let preview = preview_columnar(parquet_bytes(&sample_batch()), &options(SourceFormat::Parquet), 1).unwrap();
+        assert_eq!(preview.headers, vec!["id", "price", "city", "paid", "day", "at"]);
+        assert_eq!(preview.rows, vec![vec!["1", "9.5", "Rome", "true", "2024-01-01", "2024-01-01T10:00:00Z"]]);
+        assert_eq!(preview.raw_rows.len(), 2);
+
+        let result = preview_columnar(Bytes::from_static(b"not parquet"), &options(SourceFormat::Parquet), 5);
+        assert!(matches!(result, Err(CsvError::InvalidCsvFormat(_))));
+    }
+}
@@ -1,106 +1,233 @@
-use crate::features::dashboard::{Field, Dataset, CsvError};
+use crate::features::dashboard::{Field, FieldType, Dataset, CsvDialect, CsvError, NumberFormat};
+use crate::features::dashboard::data::{Column, DataTable};
 use crate::features::dashboard::csv_upload::{
+    chunked_parser::ChunkedCsvParser,
+    dialect,
+    import_options::{ImportOptions, ImportPreview, SourceFormat},
+    number_format,
     type_detector,
+    value_converter,
     schema_validator::{self, ValidationConfig},
 };
 use uuid::Uuid;
-use serde_json::Value;
 
-/// Parse CSV and create Dataset with Fields
+/// Parse CSV with the settings confirmed in the import wizard
+///
+/// Leading rows are skipped, the header row is taken (or names generated),
+/// then column renames, exclusions and type overrides are applied. Runs
+/// the chunked parser to completion in one go.
 ///
 /// # Arguments
 /// * `csv_text` - The CSV file content as text
 /// * `filename` - Name of the CSV file
 /// * `file_size` - Size of the file in bytes
+/// * `options` - Dialect, skipped rows and per-column overrides
 ///
 /// # Returns
 /// A tuple of (Dataset, Vec<Field>) or a CsvError
-pub fn parse_csv_to_dataset(
+pub fn parse_csv_with_options(
     csv_text: &str,
     filename: &str,
     file_size: u64,
+    options: &ImportOptions,
 ) -> Result<(Dataset, Vec<Field>), CsvError> {
-    // Parse CSV using csv crate
-    let mut rdr = csv::ReaderBuilder::new()
-        .has_headers(true)
-        .trim(csv::Trim::All)
-        .from_reader(csv_text.as_bytes());
-
-    // Get headers
-    let headers = rdr
-        .headers()
-        .map_err(|e| CsvError::InvalidCsvFormat(format!("Failed to read headers: {}", e)))?
-        .iter()
-        .map(|s| s.to_string())
-        .collect::<Vec<_>>();
-
-    // Validate structure
-    schema_validator::validate_structure(&headers, 0, &ValidationConfig::default())?;
-
-    // Read all rows for type detection and data storage
-    let all_rows: Vec<Vec<String>> = rdr
-        .records()
-        .map(|result| {
-            result
-                .map(|record| record.iter().map(|s| s.to_string()).collect())
-                .map_err(|e| CsvError::ParseError {
-                    row: 0,
-                    message: e.to_string(),
-                })
-        })
-        .collect::<Result<Vec<_>, _>>()?;
+    ChunkedCsvParser::from_text(csv_text, options)?.finish(filename, file_size)
+}
 
+/// Turn a table of raw cells into a Dataset
+///
+/// Shared by every reader (CSV, JSON, spreadsheets): applies the wizard
+/// overrides to the detected column types, validates the structure and
+/// converts the cells. Rows must already have one cell per header.
+pub(super) fn build_dataset(
+    headers: Vec<String>,
+    all_rows: Vec<Vec<String>>,
+    detected: &[FieldType],
+    filename: &str,
+    file_size: u64,
+    options: &ImportOptions,
+) -> Result<(Dataset, Vec<Field>), CsvError> {
     if all_rows.is_empty() {
         return Err(CsvError::EmptyFile);
     }
 
-    // Sample first 100 rows for type detection
-    let sample_rows = all_rows.iter().take(100).cloned().collect::<Vec<_>>();
+    // Apply user overrides to the detected types
+    let number_format = &options.dialect.number_format;
+    let mut columns = options.resolve_columns(&headers, detected);
 
-    // Detect column types
-    let field_types = type_detector::detect_types(headers.clone(), sample_rows)
-        .map_err(CsvError::TypeInferenceFailed)?;
+    // Validate structure of the resulting columns
+    let names: Vec<String> = columns.iter().map(|(_, field)| field.name.clone()).collect();
+    schema_validator::validate_structure(&names, all_rows.len(), &ValidationConfig::default())?;
 
-    // Create Field objects
-    let fields: Vec<Field> = field_types
-        .iter()
-        .map(|(name, field_type)| Field {
-            name: name.clone(),
-            field_type: *field_type,
+    // Convert CSV data column by column into typed columns, following the
+    // final field types (included columns only). Numeric columns also keep
+    // the currency / percent notation they were written in.
+    let converted: Vec<Column> = columns
+        .iter_mut()
+        .map(|(idx, field)| {
+            let cells: Vec<&str> = all_rows.iter().map(|row| row[*idx].as_str()).collect();
+            if field.field_type == FieldType::Numeric {
+                field.format = number_format::detect_field_format(&cells, number_format);
+            }
+            let values = value_converter::convert_column(&cells, field.field_type, number_format);
+            Column::from_values(field.field_type, values)
         })
         .collect();
 
-    // Convert CSV data to JSON values
-    let data: Vec<Vec<Value>> = all_rows
-        .iter()
-        .map(|row| {
-            row.iter()
-                .map(|val| {
-                    // Try to parse as number first
-                    if let Ok(num) = val.parse::<f64>() {
-                        Value::Number(serde_json::Number::from_f64(num).unwrap_or(serde_json::Number::from(0)))
-                    } else if let Ok(bool_val) = val.parse::<bool>() {
-                        Value::Bool(bool_val)
-                    } else {
-                        Value::String(val.clone())
-                    }
-                })
-                .collect()
-        })
-        .collect();
+    let fields: Vec<Field> = columns.into_iter().map(|(_, field)| field).collect();
+    let data = DataTable::from_columns(converted);
+    let dataset = dataset_from_table(fields.clone(), data, filename, file_size, options);
+
+    Ok((dataset, fields))
+}
 
-    // Create Dataset
-    let dataset = Dataset {
+/// Wrap converted columns into a new (inactive) Dataset
+///
+/// # Arguments
+/// * `fields` - Final fields, one per column
+/// * `data` - Converted rows, one column per field
+/// * `filename` - Dataset name
+/// * `file_size` - Size of the source file in bytes
+/// * `options` - Confirmed import options
+pub(super) fn dataset_from_table(
+    fields: Vec<Field>,
+    data: DataTable,
+    filename: &str,
+    file_size: u64,
+    options: &ImportOptions,
+) -> Dataset {
+    Dataset {
         id: format!("ds_{}", Uuid::new_v4()),
         name: filename.to_string(),
         size: format_size(file_size),
         uploaded_at: "Today".to_string(),
         active: false, // Will be activated by context
-        fields: fields.clone(),
+        fields,
         data,
+        // Only delimited files have a dialect worth remembering
+        dialect: (options.format == SourceFormat::Delimited).then(|| options.dialect.clone()),
+        calculated_fields: Vec::new(),
+        derived_from: None,
+    }
+}
+
+/// Build the import wizard preview for a set of options
+///
+/// Only the first rows are parsed, so this is cheap enough to re-run on
+/// every change in the wizard.
+///
+/// # Arguments
+/// * `csv_text` - The CSV file content as text
+/// * `options` - Current wizard settings
+/// * `limit` - Number of data rows to include in the preview
+pub fn preview_csv(
+    csv_text: &str,
+    options: &ImportOptions,
+    limit: usize,
+) -> Result<ImportPreview, CsvError> {
+    // Read enough rows for the preview and for a representative type sample
+    let wanted = options.skip_rows + 1 + limit.max(TYPE_SAMPLE_ROWS);
+    let records = read_records(csv_text, &options.dialect, Some(wanted))?;
+    let raw_rows: Vec<Vec<String>> = records
+        .iter()
+        .take(options.skip_rows + 1 + limit)
+        .cloned()
+        .collect();
+
+    let (headers, rows) = split_header(records, options)?;
+    let detected_types = detect_field_types(&headers, &rows, &options.dialect.number_format)?;
+    Ok(build_preview(raw_rows, headers, rows, detected_types, &options.dialect.number_format, limit))
+}
+
+/// Assemble an import preview from already-read rows
+pub(super) fn build_preview(
+    raw_rows: Vec<Vec<String>>,
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+    detected_types: Vec<FieldType>,
+    number_format: &NumberFormat,
+    limit: usize,
+) -> ImportPreview {
+    let sample: Vec<Vec<String>> = rows.into_iter().take(limit.max(TYPE_SAMPLE_ROWS)).collect();
+    ImportPreview {
+        raw_rows,
+        headers,
+        detected_types,
+        rows: sample.iter().take(limit).cloned().collect(),
+        sample,
+        number_format: *number_format,
+    }
+}
+
+/// Number of data rows sampled for type detection
+pub(super) const TYPE_SAMPLE_ROWS: usize = 100;
+
+/// Read records with a dialect, optionally stopping after `limit` records
+///
+/// The reader is flexible so that title rows above the header (which the
+/// user may skip) don't abort parsing.
+fn read_records(
+    csv_text: &str,
+    dialect: &CsvDialect,
+    limit: Option<usize>,
+) -> Result<Vec<Vec<String>>, CsvError> {
+    let mut rdr = dialect::reader_builder(dialect)
+        .flexible(true)
+        .from_reader(csv_text.trim_start_matches('\u{feff}').as_bytes());
+
+    rdr.records()
+        .take(limit.unwrap_or(usize::MAX))
+        .enumerate()
+        .map(|(idx, result)| {
+            result
+                .map(|record| record.iter().map(|s| s.to_string()).collect())
+                .map_err(|e| CsvError::ParseError {
+                    row: idx + 1,
+                    message: e.to_string(),
+                })
+        })
+        .collect()
+}
+
+/// Drop skipped rows and split off the header row
+///
+/// Headerless files get generated "Column N" names.
+fn split_header(
+    mut records: Vec<Vec<String>>,
+    options: &ImportOptions,
+) -> Result<(Vec<String>, Vec<Vec<String>>), CsvError> {
+    if records.len() <= options.skip_rows {
+        return Err(CsvError::EmptyFile);
+    }
+    records.drain(..options.skip_rows);
+
+    let headers = if options.dialect.has_header {
+        records.remove(0)
+    } else {
+        let width = records.iter().map(|row| row.len()).max().unwrap_or(0);
+        (1..=width).map(|i| format!("Column {}", i)).collect()
     };
 
-    Ok((dataset, fields))
+    Ok((headers, records))
+}
+
+/// Detect the type of each column from the first rows
+///
+/// Columns of a table without data rows are treated as text.
+pub(super) fn detect_field_types(
+    headers: &[String],
+    rows: &[Vec<String>],
+    number_format: &NumberFormat,
+) -> Result<Vec<FieldType>, CsvError> {
+    if rows.is_empty() {
+        return Ok(vec![FieldType::Text; headers.len()]);
+    }
+
+    let sample_rows = rows.iter().take(TYPE_SAMPLE_ROWS).cloned().collect::<Vec<_>>();
+
+    type_detector::detect_types(headers.to_vec(), sample_rows, number_format)
+        .map(|types| types.into_iter().map(|(_, field_type)| field_type).collect())
+        .map_err(CsvError::TypeInferenceFailed)
 }
 
 /// Format file size for display
@@ -117,6 +244,90 @@ fn format_size(bytes: u64) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serde_json::Value;
+
+    /// Parse with the sniffed dialect, as an upload confirmed unchanged
+    fn parse(text: &str, filename: &str, file_size: u64) -> Result<(Dataset, Vec<Field>), CsvError> {
+        let dialect = dialect::sniff_dialect(text, &ValidationConfig::default().allowed_delimiters);
+        parse_csv_with_options(text, filename, file_size, &ImportOptions::new(dialect))
+    }
+
+    #[test]
+    fn test_parse_semicolon_file() {
+        let text = "region;revenue\nNorth;100\nSouth;250\n";
+        let (dataset, fields) = parse(text, "sales.csv", 40).unwrap();
+
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[0].name, "region");
+        assert_eq!(dataset.data.len(), 2);
+        assert_eq!(dataset.dialect.unwrap().delimiter, ';');
+    }
+
+    #[test]
+    fn test_parse_headerless_file_generates_names() {
+        let text = "1|2\n3|4\n5|6\n";
+        let (dataset, fields) = parse(text, "dump.txt", 12).unwrap();
+
+        assert_eq!(fields[0].name, "Column 1");
+        assert_eq!(fields[1].name, "Column 2");
+        assert_eq!(dataset.data.len(), 3);
+    }
+
+    #[test]
+    fn test_parse_with_skipped_rows_and_overrides() {
+        let text = "Quarterly export\nzip,flag,amount\n00123,1,10\n04567,0,20\n";
+        let dialect = CsvDialect { has_header: true, ..CsvDialect::default() };
+        let mut options = ImportOptions::new(dialect);
+        options.skip_rows = 1;
+
+        let preview = preview_csv(text, &options, 5).unwrap();
+        assert_eq!(preview.headers, vec!["zip", "flag", "amount"]);
+        assert_eq!(preview.detected_types[0], FieldType::Numeric);
+        assert_eq!(preview.detected_types[1], FieldType::Boolean);
+        assert_eq!(preview.rows.len(), 2);
+
+        options.columns = preview.default_columns();
+        options.columns[0].field_type = FieldType::Text;
+        options.columns[1].include = false;
+        options.columns[2].name = "Amount".into();
+
+        let (dataset, fields) = parse_csv_with_options(text, "export.csv", 60, &options).unwrap();
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[1].name, "Amount");
+        assert_eq!(dataset.data.value(0, 0), Value::String("00123".into()));
+    }
+
+    #[test]
+    fn test_preview_counts_rejected_cells() {
+        // One unit-suffixed amount among numbers is detected as numeric but dropped
+        let mut text = "code,amount\n".to_string();
+        for idx in 0..20 {
+            let amount = if idx == 5 { "12 kg".to_string() } else { idx.to_string() };
+            text.push_str(&format!("C{},{}\n", idx, amount));
+        }
+        let preview = preview_csv(&text, &ImportOptions::new(CsvDialect::default()), 5).unwrap();
+
+        assert_eq!(preview.rows.len(), 5);
+        assert_eq!(preview.sample.len(), 20);
+        assert_eq!(preview.detected_types[1], FieldType::Numeric);
+        assert_eq!(preview.rejected_cells(1, FieldType::Numeric), 1);
+        assert_eq!(preview.rejected_cells(1, FieldType::Text), 0);
+        assert_eq!(preview.rejected_cells(0, FieldType::Numeric), 20);
+    }
+
+    #[test]
+    fn test_parse_localized_numbers_keeps_format() {
+        let text = "item;price;share\nA;€ 1.200,50;12,5%\nB;€ 3.400;7%\nC;(€ 50);n/a\n";
+        let (dataset, fields) = parse(text, "prices.csv", 60).unwrap();
+
+        assert_eq!(fields[1].field_type, FieldType::Numeric);
+        assert_eq!(fields[1].format.as_ref().unwrap().currency.as_deref(), Some("€"));
+        assert!(fields[2].format.as_ref().unwrap().percent);
+        assert_eq!(dataset.data.value(0, 1), Value::from(1200.5));
+        assert_eq!(dataset.data.value(2, 1), Value::from(-50.0));
+        assert_eq!(dataset.data.value(0, 2), Value::from(0.125));
+        assert_eq!(dataset.data.value(2, 2), Value::Null);
+    }
 
     #[test]
     fn test_format_size() {
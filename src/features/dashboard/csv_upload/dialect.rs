@@ -0,0 +1,367 @@
+use crate::features::dashboard::{CsvDialect, FieldType, NumberFormat};
+use crate::features::dashboard::csv_upload::{number_format, type_detector};
+use std::collections::HashSet;
+
+/// Number of bytes inspected when sniffing (first few KB of the file)
+const SNIFF_BYTES: usize = 8 * 1024;
+
+/// Maximum number of records used to score a candidate dialect
+const SNIFF_ROWS: usize = 50;
+
+/// Quote characters considered during sniffing
+const QUOTE_CANDIDATES: [char; 2] = ['"', '\''];
+
+/// Detect the dialect of a delimited text file
+///
+/// Only the first few KB are inspected. The delimiter is picked among
+/// `allowed_delimiters` by how consistently it splits rows into the same
+/// number of fields; quote and escape characters are inferred from how
+/// fields are wrapped; the number format (decimal and thousands separators)
+/// is the one that reads the most cells; the header is detected by
+/// comparing the first row against the types of the rows below it.
+///
+/// # Arguments
+/// * `text` - The file content as text
+/// * `allowed_delimiters` - Candidate delimiters, in order of preference
+///
+/// # Returns
+/// The detected dialect, or the default (comma, double quote, header) when
+/// the sample is empty or ambiguous
+pub fn sniff_dialect(text: &str, allowed_delimiters: &[char]) -> CsvDialect {
+    let sample = sample_text(text);
+    if sample.trim().is_empty() {
+        return CsvDialect::default();
+    }
+
+    let quote = detect_quote(sample, allowed_delimiters);
+    let escape = detect_escape(sample, quote, allowed_delimiters);
+    let delimiter = detect_delimiter(sample, allowed_delimiters, quote, escape);
+
+    let mut dialect = CsvDialect {
+        delimiter,
+        quote,
+        escape,
+        has_header: true,
+        number_format: Default::default(),
+    };
+    dialect.number_format = detect_number_format(sample, &dialect);
+    dialect.has_header = detect_header(sample, &dialect);
+    dialect
+}
+
+/// Build a `csv::ReaderBuilder` configured for a dialect
+///
+/// Headers are always read as a regular record; callers decide what to do
+/// with the first row based on `dialect.has_header`.
+pub fn reader_builder(dialect: &CsvDialect) -> csv::ReaderBuilder {
+    let mut builder = csv::ReaderBuilder::new();
+    builder
+        .has_headers(false)
+        .trim(csv::Trim::All)
+        .delimiter(ascii_byte(dialect.delimiter, b','))
+        .quote(ascii_byte(dialect.quote, b'"'))
+        .escape(dialect.escape.map(|c| ascii_byte(c, b'\\')))
+        .double_quote(dialect.escape.is_none());
+    builder
+}
+
+/// Convert a dialect character to the byte the csv crate expects
+fn ascii_byte(c: char, fallback: u8) -> u8 {
+    if c.is_ascii() { c as u8 } else { fallback }
+}
+
+/// Take the first `SNIFF_BYTES` of the text, cut at a line boundary
+fn sample_text(text: &str) -> &str {
+    let text = text.trim_start_matches('\u{feff}');
+    if text.len() <= SNIFF_BYTES {
+        return text;
+    }
+
+    let mut end = SNIFF_BYTES;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let sample = &text[..end];
+
+    // Drop the trailing partial line so it doesn't skew field counts
+    match sample.rfind('\n') {
+        Some(pos) if pos > 0 => &sample[..pos],
+        _ => sample,
+    }
+}
+
+/// Pick the quote character that most often wraps whole fields
+fn detect_quote(sample: &str, delimiters: &[char]) -> char {
+    let is_boundary = |c: Option<char>| c.is_none_or(|c| delimiters.contains(&c));
+
+    let mut best = ('"', 0usize);
+    for quote in QUOTE_CANDIDATES {
+        let mut opening = 0;
+        let mut closing = 0;
+
+        for line in sample.lines() {
+            let chars: Vec<char> = line.chars().collect();
+            for (i, &c) in chars.iter().enumerate() {
+                if c != quote {
+                    continue;
+                }
+                let prev = chars[..i].iter().rev().find(|c| **c != ' ').copied();
+                let next = chars[i + 1..].iter().find(|c| **c != ' ').copied();
+                if is_boundary(prev) {
+                    opening += 1;
+                } else if is_boundary(next) {
+                    closing += 1;
+                }
+            }
+        }
+
+        let score = opening.min(closing);
+        if score > best.1 {
+            best = (quote, score);
+        }
+    }
+
+    best.0
+}
+
+/// Detect backslash-escaped quotes (`\"`) as opposed to doubled quotes (`""`)
+fn detect_escape(sample: &str, quote: char, delimiters: &[char]) -> Option<char> {
+    let is_boundary = |c: Option<char>| {
+        c.is_none_or(|c| delimiters.contains(&c) || c == '\n' || c == '\r')
+    };
+
+    let chars: Vec<char> = sample.chars().collect();
+    let mut backslashed = 0;
+    let mut doubled = 0;
+
+    for i in 1..chars.len() {
+        if chars[i] != quote {
+            continue;
+        }
+        let next = chars.get(i + 1).copied();
+        if chars[i - 1] == '\\' && !is_boundary(next) {
+            backslashed += 1;
+        } else if chars[i - 1] == quote {
+            // `""` between two boundaries is just an empty quoted field
+            let before = if i >= 2 { Some(chars[i - 2]) } else { None };
+            if !(is_boundary(before) && is_boundary(next)) {
+                doubled += 1;
+            }
+        }
+    }
+
+    if backslashed > doubled { Some('\\') } else { None }
+}
+
+/// Read up to `SNIFF_ROWS` non-empty records of the sample with a dialect
+fn sample_records(sample: &str, dialect: &CsvDialect) -> Option<Vec<Vec<String>>> {
+    let mut reader = reader_builder(dialect)
+        .flexible(true)
+        .from_reader(sample.as_bytes());
+
+    let mut records = Vec::new();
+    for result in reader.records().take(SNIFF_ROWS) {
+        let record = result.ok()?;
+        if record.iter().all(|field| field.is_empty()) {
+            continue;
+        }
+        records.push(record.iter().map(|s| s.to_string()).collect());
+    }
+
+    Some(records)
+}
+
+/// Pick the delimiter that splits rows into the most consistent field count
+fn detect_delimiter(
+    sample: &str,
+    delimiters: &[char],
+    quote: char,
+    escape: Option<char>,
+) -> char {
+    // (delimiter, consistency, field count)
+    let mut best: Option<(char, f64, usize)> = None;
+
+    for &delimiter in delimiters {
+        let dialect = CsvDialect {
+            delimiter,
+            quote,
+            escape,
+            has_header: false,
+            number_format: Default::default(),
+        };
+        let Some(records) = sample_records(sample, &dialect) else {
+            continue;
+        };
+        if records.is_empty() {
+            continue;
+        }
+
+        // Most frequent field count and how many rows share it
+        let mut counts: Vec<(usize, usize)> = Vec::new();
+        for record in &records {
+            match counts.iter_mut().find(|(len, _)| *len == record.len()) {
+                Some((_, n)) => *n += 1,
+                None => counts.push((record.len(), 1)),
+            }
+        }
+        let Some(&(field_count, rows)) = counts
+            .iter()
+            .max_by(|a, b| a.1.cmp(&b.1).then(a.0.cmp(&b.0)))
+        else {
+            continue;
+        };
+
+        if field_count < 2 {
+            continue;
+        }
+
+        let consistency = rows as f64 / records.len() as f64;
+        let better = match best {
+            None => true,
+            Some((_, best_consistency, best_count)) => {
+                consistency > best_consistency
+                    || (consistency == best_consistency && field_count > best_count)
+            }
+        };
+        if better {
+            best = Some((delimiter, consistency, field_count));
+        }
+    }
+
+    best.map(|(delimiter, _, _)| delimiter)
+        .or_else(|| delimiters.first().copied())
+        .unwrap_or(',')
+}
+
+/// Detect the number format from every cell of the sample
+fn detect_number_format(sample: &str, dialect: &CsvDialect) -> NumberFormat {
+    let records = sample_records(sample, dialect).unwrap_or_default();
+    number_format::detect_number_format(records.iter().flatten().map(String::as_str))
+}
+
+/// Decide whether the first row is a header
+///
+/// Each column votes: if the values below the first row share a type (or a
+/// fixed length) that the first cell does not, the first row looks like a
+/// header; if the first cell matches, it looks like data. Ties favour a
+/// header, which is by far the most common layout.
+fn detect_header(sample: &str, dialect: &CsvDialect) -> bool {
+    let Some(records) = sample_records(sample, dialect) else {
+        return true;
+    };
+    if records.len() < 2 {
+        return true;
+    }
+
+    let first = &records[0];
+    let rest = &records[1..];
+
+    // Duplicate names are a strong hint that the first row is data
+    let non_empty: Vec<&String> = first.iter().filter(|c| !c.is_empty()).collect();
+    let unique: HashSet<&String> = non_empty.iter().copied().collect();
+    if unique.len() < non_empty.len() {
+        return false;
+    }
+
+    let mut votes: i32 = 0;
+    for (idx, cell) in first.iter().enumerate() {
+        let column: Vec<String> = rest
+            .iter()
+            .filter_map(|row| row.get(idx))
+            .filter(|v| !v.is_empty())
+            .cloned()
+            .collect();
+        if column.is_empty() || cell.is_empty() {
+            continue;
+        }
+
+        let Ok(column_type) = type_detector::detect_column_type("", &column, &dialect.number_format) else {
+            continue;
+        };
+
+        if column_type != FieldType::Text {
+            if type_detector::value_matches_type(cell, column_type, &dialect.number_format) {
+                votes -= 1;
+            } else {
+                votes += 1;
+            }
+            continue;
+        }
+
+        // Text columns: fixed-length codes (e.g. country codes) are telling
+        let len = column[0].chars().count();
+        if column.iter().all(|v| v.chars().count() == len) {
+            if cell.chars().count() == len {
+                votes -= 1;
+            } else {
+                votes += 1;
+            }
+        }
+    }
+
+    votes >= 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DELIMITERS: [char; 4] = [',', '\t', ';', '|'];
+
+    #[test]
+    fn test_sniff_comma() {
+        let text = "name,revenue,region\nAcme,100.5,North\nGlobex,200,South\n";
+        let dialect = sniff_dialect(text, &DELIMITERS);
+        assert_eq!(dialect.delimiter, ',');
+        assert_eq!(dialect.quote, '"');
+        assert!(dialect.has_header);
+    }
+
+    #[test]
+    fn test_sniff_semicolon_with_decimal_commas() {
+        let text = "Produkt;Umsatz;Menge\nApfel;1,50;10\nBirne;2,75;3\nKiwi;0,99;7\n";
+        let dialect = sniff_dialect(text, &DELIMITERS);
+        assert_eq!(dialect.delimiter, ';');
+        assert_eq!(dialect.number_format.decimal_separator, ',');
+    }
+
+    #[test]
+    fn test_sniff_pipe_and_tab() {
+        let pipe = "id|name|score\n1|alpha|3.5\n2|beta|4.0\n";
+        assert_eq!(sniff_dialect(pipe, &DELIMITERS).delimiter, '|');
+
+        let tab = "id\tname\tscore\n1\talpha\t3.5\n2\tbeta\t4.0\n";
+        assert_eq!(sniff_dialect(tab, &DELIMITERS).delimiter, '\t');
+    }
+
+    #[test]
+    fn test_sniff_quoted_fields_containing_delimiter() {
+        let text = "name,city\n\"Doe, John\",\"Paris\"\n\"Roe, Jane\",\"Rome\"\n";
+        let dialect = sniff_dialect(text, &DELIMITERS);
+        assert_eq!(dialect.delimiter, ',');
+        assert_eq!(dialect.quote, '"');
+        assert_eq!(dialect.escape, None);
+    }
+
+    #[test]
+    fn test_sniff_single_quote_and_backslash_escape() {
+        let text = "name;note\n'Bob';'said \\'hi\\''\n'Ann';'ok'\n";
+        let dialect = sniff_dialect(text, &DELIMITERS);
+        assert_eq!(dialect.delimiter, ';');
+        assert_eq!(dialect.quote, '\'');
+        assert_eq!(dialect.escape, Some('\\'));
+    }
+
+    #[test]
+    fn test_sniff_headerless_numeric_file() {
+        let text = "1,2.5,3\n4,5.5,6\n7,8.5,9\n";
+        let dialect = sniff_dialect(text, &DELIMITERS);
+        assert_eq!(dialect.delimiter, ',');
+        assert!(!dialect.has_header);
+    }
+
+    #[test]
+    fn test_sniff_empty_text_uses_default() {
+        assert_eq!(sniff_dialect("", &DELIMITERS), CsvDialect::default());
+    }
+}
@@ -1,20 +1,67 @@
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use web_sys::{File, FileReader, ProgressEvent};
+use bytes::Bytes;
+use std::borrow::Cow;
 use std::sync::Arc;
 
-/// Read file as text with progress tracking
+/// Content of a file read in the browser
+#[derive(Clone, Debug)]
+pub enum FileContent {
+    /// Text formats (CSV, JSON), decoded as UTF-8
+    Text(Arc<String>),
+    /// Binary formats (spreadsheets, Parquet, Arrow), as raw bytes
+    Binary(Bytes),
+}
+
+impl FileContent {
+    /// Content as text (binary content is decoded lossily)
+    pub fn as_text(&self) -> Cow<'_, str> {
+        match self {
+            Self::Text(text) => Cow::Borrowed(text.as_str()),
+            Self::Binary(bytes) => String::from_utf8_lossy(bytes),
+        }
+    }
+
+    /// Content as raw bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Self::Text(text) => text.as_bytes(),
+            Self::Binary(bytes) => bytes,
+        }
+    }
+
+    /// Content as shared text (binary content is decoded lossily)
+    pub fn to_shared_text(&self) -> Arc<String> {
+        match self {
+            Self::Text(text) => text.clone(),
+            Self::Binary(bytes) => Arc::new(String::from_utf8_lossy(bytes).into_owned()),
+        }
+    }
+
+    /// Content as a shared buffer (free for binary content)
+    pub fn to_shared_bytes(&self) -> Bytes {
+        match self {
+            Self::Text(text) => Bytes::copy_from_slice(text.as_bytes()),
+            Self::Binary(bytes) => bytes.clone(),
+        }
+    }
+}
+
+/// Read file with progress tracking
 ///
 /// # Arguments
 /// * `file` - The file to read
+/// * `binary` - Read raw bytes instead of UTF-8 text
 /// * `on_progress` - Callback for progress updates (bytes_read, total_bytes)
 /// * `on_complete` - Callback for completion (result)
 ///
 /// This version doesn't use channels and is non-blocking
 pub fn read_file_with_progress(
     file: File,
+    binary: bool,
     on_progress: impl Fn(u64, u64) + 'static,
-    on_complete: impl FnOnce(Result<String, String>) + 'static + Clone,
+    on_complete: impl FnOnce(Result<FileContent, String>) + 'static + Clone,
 ) {
     let file_size = file.size() as u64;
     let file_name = file.name();
@@ -62,9 +109,13 @@ pub fn read_file_with_progress(
         let reader: &FileReader = target.unchecked_ref();
 
         let result = reader.result().unwrap();
-        let text = result.as_string().unwrap();
+        let content = if binary {
+            FileContent::Binary(Bytes::from(js_sys::Uint8Array::new(&result).to_vec()))
+        } else {
+            FileContent::Text(Arc::new(result.as_string().unwrap_or_default()))
+        };
 
-        on_complete_clone(Ok(text));
+        on_complete_clone(Ok(content));
     });
 
     reader.set_onload(Some(load_callback.as_ref().unchecked_ref()));
@@ -80,7 +131,12 @@ pub fn read_file_with_progress(
     error_callback.forget();
 
     // Start reading
-    if let Err(e) = reader.read_as_text(&file) {
+    let started = if binary {
+        reader.read_as_array_buffer(&file)
+    } else {
+        reader.read_as_text(&file)
+    };
+    if let Err(e) = started {
         // Can't call on_complete here as it's already moved, so we log instead
         log::error!("Failed to start reading file: {:?}", e);
     }
@@ -0,0 +1,248 @@
+use crate::features::dashboard::{CsvDialect, Field, FieldType, NumberFormat};
+use crate::features::dashboard::csv_upload::value_converter;
+use serde::{Deserialize, Serialize};
+
+/// Per-column settings chosen in the import wizard
+///
+/// Columns are matched by position against the parsed header row;
+/// `source` keeps the original header so stale overrides can be detected
+/// when the layout (delimiter, skipped rows, header row) changes.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ColumnOverride {
+    /// Header as found in the file (or generated for headerless files)
+    pub source: String,
+    /// Name the column will have in the dataset
+    pub name: String,
+    /// Whether the column is imported at all
+    pub include: bool,
+    /// Field type to use instead of the detected one
+    pub field_type: FieldType,
+}
+
+/// Kind of file being imported
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum SourceFormat {
+    /// Delimited text (CSV, TSV, ...), read with the dialect
+    #[default]
+    Delimited,
+    /// JSON array of objects
+    Json,
+    /// Newline-delimited JSON (one object per line)
+    Ndjson,
+    /// Spreadsheet workbook (xlsx, xls, ods); each selected sheet is a dataset
+    Spreadsheet,
+    /// Apache Parquet file
+    Parquet,
+    /// Arrow IPC file or stream (`.arrow`, `.feather` v2, `.arrows`)
+    ArrowIpc,
+}
+
+impl SourceFormat {
+    /// Guess the format from the file extension (delimited text by default)
+    pub fn from_filename(filename: &str) -> Self {
+        let lower = filename.to_lowercase();
+        if lower.ends_with(".json") {
+            Self::Json
+        } else if lower.ends_with(".ndjson") || lower.ends_with(".jsonl") {
+            Self::Ndjson
+        } else if [".xlsx", ".xlsm", ".xls", ".ods"].iter().any(|ext| lower.ends_with(ext)) {
+            Self::Spreadsheet
+        } else if lower.ends_with(".parquet") {
+            Self::Parquet
+        } else if [".arrow", ".feather", ".ipc", ".arrows"].iter().any(|ext| lower.ends_with(ext)) {
+            Self::ArrowIpc
+        } else {
+            Self::Delimited
+        }
+    }
+
+    /// Get display name for UI
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::Delimited => "Delimited text",
+            Self::Json => "JSON",
+            Self::Ndjson => "NDJSON",
+            Self::Spreadsheet => "Spreadsheet",
+            Self::Parquet => "Parquet",
+            Self::ArrowIpc => "Arrow IPC",
+        }
+    }
+
+    /// Whether files of this format are read as raw bytes
+    pub fn is_binary(&self) -> bool {
+        matches!(self, Self::Spreadsheet | Self::Parquet | Self::ArrowIpc)
+    }
+
+    /// Whether files of this format carry a typed columnar schema
+    pub fn is_columnar(&self) -> bool {
+        matches!(self, Self::Parquet | Self::ArrowIpc)
+    }
+}
+
+/// Import settings confirmed by the user before a file becomes a Dataset
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ImportOptions {
+    /// Kind of file; the dialect and skipped rows only apply to delimited text
+    pub format: SourceFormat,
+    /// Delimiter, quoting and header settings
+    pub dialect: CsvDialect,
+    /// Number of leading rows discarded before the header (or first data row)
+    pub skip_rows: usize,
+    /// Column renames, exclusions and type overrides.
+    /// Empty (or out of date) means "use the detected columns as-is".
+    pub columns: Vec<ColumnOverride>,
+    /// Workbook sheets to import, in workbook order. The first one is
+    /// previewed; overrides apply to every sheet with the same header row.
+    pub sheets: Vec<String>,
+}
+
+impl ImportOptions {
+    /// Options that import a delimited file as detected
+    pub fn new(dialect: CsvDialect) -> Self {
+        Self {
+            format: SourceFormat::Delimited,
+            dialect,
+            skip_rows: 0,
+            columns: Vec::new(),
+            sheets: Vec::new(),
+        }
+    }
+
+    /// Check whether the overrides were made for this header row
+    pub fn overrides_match(&self, headers: &[String]) -> bool {
+        self.columns.len() == headers.len()
+            && self
+                .columns
+                .iter()
+                .zip(headers)
+                .all(|(column, header)| &column.source == header)
+    }
+
+    /// Resolve the final columns: (source column index, resulting Field)
+    ///
+    /// Excluded columns are dropped; stale overrides are ignored.
+    pub fn resolve_columns(&self, headers: &[String], detected: &[FieldType]) -> Vec<(usize, Field)> {
+        if !self.overrides_match(headers) {
+            return headers
+                .iter()
+                .zip(detected)
+                .enumerate()
+                .map(|(idx, (name, field_type))| {
+                    (idx, Field { name: name.clone(), field_type: *field_type, format: None })
+                })
+                .collect();
+        }
+
+        self.columns
+            .iter()
+            .enumerate()
+            .filter(|(_, column)| column.include)
+            .map(|(idx, column)| {
+                let name = column.name.trim();
+                let name = if name.is_empty() { column.source.clone() } else { name.to_string() };
+                (idx, Field { name, field_type: column.field_type, format: None })
+            })
+            .collect()
+    }
+}
+
+/// Live preview of a file under a given set of import options
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImportPreview {
+    /// First records exactly as parsed (including skipped and header rows)
+    pub raw_rows: Vec<Vec<String>>,
+    /// Header row after skipping (generated names for headerless files)
+    pub headers: Vec<String>,
+    /// Type detected for each header
+    pub detected_types: Vec<FieldType>,
+    /// First data rows below the header
+    pub rows: Vec<Vec<String>>,
+    /// Data rows read for type detection (starting with `rows`)
+    pub sample: Vec<Vec<String>>,
+    /// Decimal / thousands separators the cells are read with
+    pub number_format: NumberFormat,
+}
+
+impl ImportPreview {
+    /// One override per column, pre-filled with the detected settings
+    pub fn default_columns(&self) -> Vec<ColumnOverride> {
+        self.headers
+            .iter()
+            .zip(&self.detected_types)
+            .map(|(header, field_type)| ColumnOverride {
+                source: header.clone(),
+                name: header.clone(),
+                include: true,
+                field_type: *field_type,
+            })
+            .collect()
+    }
+
+    /// Count the sampled cells of a column that don't fit a type and would
+    /// be imported as empty
+    pub fn rejected_cells(&self, column: usize, field_type: FieldType) -> usize {
+        let cells: Vec<&str> = self
+            .sample
+            .iter()
+            .map(|row| row.get(column).map(String::as_str).unwrap_or(""))
+            .collect();
+        value_converter::count_rejected(&cells, field_type, &self.number_format)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers() -> Vec<String> {
+        vec!["zip".to_string(), "amount".to_string()]
+    }
+
+    #[test]
+    fn test_resolve_columns_without_overrides() {
+        let options = ImportOptions::new(CsvDialect::default());
+        let columns = options.resolve_columns(&headers(), &[FieldType::Numeric, FieldType::Numeric]);
+
+        assert_eq!(columns.len(), 2);
+        assert_eq!(columns[0].1.field_type, FieldType::Numeric);
+    }
+
+    #[test]
+    fn test_resolve_columns_applies_overrides() {
+        let mut options = ImportOptions::new(CsvDialect::default());
+        options.columns = vec![
+            ColumnOverride {
+                source: "zip".into(),
+                name: "Postal Code".into(),
+                include: true,
+                field_type: FieldType::Text,
+            },
+            ColumnOverride {
+                source: "amount".into(),
+                name: "amount".into(),
+                include: false,
+                field_type: FieldType::Numeric,
+            },
+        ];
+
+        let columns = options.resolve_columns(&headers(), &[FieldType::Numeric, FieldType::Numeric]);
+        assert_eq!(columns.len(), 1);
+        assert_eq!(columns[0].0, 0);
+        assert_eq!(columns[0].1.name, "Postal Code");
+        assert_eq!(columns[0].1.field_type, FieldType::Text);
+    }
+
+    #[test]
+    fn test_stale_overrides_are_ignored() {
+        let mut options = ImportOptions::new(CsvDialect::default());
+        options.columns = vec![ColumnOverride {
+            source: "other".into(),
+            name: "x".into(),
+            include: false,
+            field_type: FieldType::Text,
+        }];
+
+        let columns = options.resolve_columns(&headers(), &[FieldType::Text, FieldType::Numeric]);
+        assert_eq!(columns.len(), 2);
+    }
+}
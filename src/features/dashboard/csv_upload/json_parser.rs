@@ -0,0 +1,247 @@
+use crate::features::dashboard::{Field, Dataset, CsvError};
+use crate::features::dashboard::csv_upload::{
+    csv_parser::{self, TYPE_SAMPLE_ROWS},
+    import_options::{ImportOptions, ImportPreview, SourceFormat},
+};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// Parse a JSON or NDJSON file and create a Dataset
+///
+/// Nested objects are flattened into dotted column names (`user.address.city`)
+/// and the columns are the union of the keys of all records, in the order
+/// they first appear. Records missing a key get a null cell. Arrays are kept
+/// as JSON text.
+///
+/// # Arguments
+/// * `text` - The file content as text
+/// * `filename` - Name of the file
+/// * `file_size` - Size of the file in bytes
+/// * `options` - Format and per-column overrides from the import wizard
+///
+/// # Returns
+/// A tuple of (Dataset, Vec<Field>) or a CsvError
+pub fn parse_json_with_options(
+    text: &str,
+    filename: &str,
+    file_size: u64,
+    options: &ImportOptions,
+) -> Result<(Dataset, Vec<Field>), CsvError> {
+    let records = read_json_records(text, options.format)?;
+    let (headers, rows) = flatten_records(&records);
+    let detected = csv_parser::detect_field_types(&headers, &rows, &options.dialect.number_format)?;
+    csv_parser::build_dataset(headers, rows, &detected, filename, file_size, options)
+}
+
+/// Build the import wizard preview of a JSON or NDJSON file
+///
+/// # Arguments
+/// * `text` - The file content as text
+/// * `options` - Current wizard settings
+/// * `limit` - Number of records to include in the preview
+pub fn preview_json(
+    text: &str,
+    options: &ImportOptions,
+    limit: usize,
+) -> Result<ImportPreview, CsvError> {
+    let records = read_json_records(text, options.format)?;
+    if records.is_empty() {
+        return Err(CsvError::EmptyFile);
+    }
+
+    // Union keys over the type sample so late-appearing keys still show up
+    let sample = &records[..records.len().min(limit.max(TYPE_SAMPLE_ROWS))];
+    let (headers, rows) = flatten_records(sample);
+
+    let raw_rows = std::iter::once(headers.clone())
+        .chain(rows.iter().take(limit).cloned())
+        .collect();
+
+    let detected = csv_parser::detect_field_types(&headers, &rows, &options.dialect.number_format)?;
+    Ok(csv_parser::build_preview(raw_rows, headers, rows, detected, &options.dialect.number_format, limit))
+}
+
+/// Read the records of a JSON array or an NDJSON stream
+///
+/// A `.json` file holding one object per line (or a single object) is read
+/// as a stream as well, since many exporters use the extension for both.
+fn read_json_records(text: &str, format: SourceFormat) -> Result<Vec<Map<String, Value>>, CsvError> {
+    let text = text.trim_start_matches('\u{feff}').trim();
+    if text.is_empty() {
+        return Err(CsvError::EmptyFile);
+    }
+
+    let values = if format == SourceFormat::Json && text.starts_with('[') {
+        serde_json::from_str::<Vec<Value>>(text)
+            .map_err(|e| CsvError::InvalidJson(e.to_string()))?
+    } else {
+        read_json_stream(text)?
+    };
+
+    values
+        .into_iter()
+        .enumerate()
+        .map(|(idx, value)| match value {
+            Value::Object(map) => Ok(map),
+            other => Err(CsvError::InvalidJson(format!(
+                "record {} is {}, expected an object",
+                idx + 1,
+                json_kind(&other)
+            ))),
+        })
+        .collect()
+}
+
+/// Read whitespace-separated JSON values (one per line in NDJSON)
+fn read_json_stream(text: &str) -> Result<Vec<Value>, CsvError> {
+    let mut stream = serde_json::Deserializer::from_str(text).into_iter::<Value>();
+    let mut values = Vec::new();
+
+    while let Some(result) = stream.next() {
+        match result {
+            Ok(value) => values.push(value),
+            Err(e) => {
+                // Report the line the bad record starts on
+                let rest = &text[stream.byte_offset()..];
+                let start = text.len() - rest.trim_start().len();
+                return Err(CsvError::ParseError {
+                    row: text[..start].matches('\n').count() + 1,
+                    message: e.to_string(),
+                });
+            }
+        }
+    }
+
+    Ok(values)
+}
+
+/// Name of a JSON value's kind, for error messages
+fn json_kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+/// Flatten records into a header row and rows of raw cells
+///
+/// Cells are rendered as text so they go through the same type detection
+/// and conversion as CSV cells; missing keys and nulls become empty cells.
+fn flatten_records(records: &[Map<String, Value>]) -> (Vec<String>, Vec<Vec<String>>) {
+    let mut headers: Vec<String> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    let mut rows: Vec<Vec<String>> = Vec::with_capacity(records.len());
+
+    for record in records {
+        let mut cells = Vec::new();
+        flatten_object("", record, &mut cells);
+
+        let mut row = vec![String::new(); headers.len()];
+        for (key, cell) in cells {
+            let idx = *positions.entry(key.clone()).or_insert_with(|| {
+                headers.push(key);
+                headers.len() - 1
+            });
+            if idx >= row.len() {
+                row.resize(idx + 1, String::new());
+            }
+            row[idx] = cell;
+        }
+        rows.push(row);
+    }
+
+    // Earlier rows predate keys introduced later
+    for row in &mut rows {
+        row.resize(headers.len(), String::new());
+    }
+
+    (headers, rows)
+}
+
+/// Collect (dotted key, cell text) pairs of an object, recursing into
+/// nested objects
+fn flatten_object(prefix: &str, object: &Map<String, Value>, out: &mut Vec<(String, String)>) {
+    for (key, value) in object {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+
+        match value {
+            Value::Object(nested) => flatten_object(&path, nested, out),
+            Value::Null => out.push((path, String::new())),
+            Value::String(s) => out.push((path, s.clone())),
+            Value::Bool(b) => out.push((path, b.to_string())),
+            Value::Number(n) => out.push((path, n.to_string())),
+            Value::Array(_) => out.push((path, value.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::dashboard::{CsvDialect, FieldType};
+
+    fn options(format: SourceFormat) -> ImportOptions {
+        ImportOptions {
+            format,
+            ..ImportOptions::new(CsvDialect::default())
+        }
+    }
+
+    #[test]
+    fn test_parse_json_array_flattens_and_unions_keys() {
+        let text = r#"[
+            {"id": 1, "user": {"name": "Ann", "address": {"city": "Rome"}}, "tags": ["a", "b"]},
+            {"id": 2, "user": {"name": "Bob"}, "active": true}
+        ]"#;
+        let (dataset, fields) =
+            parse_json_with_options(text, "users.json", 120, &options(SourceFormat::Json)).unwrap();
+
+        let names: Vec<&str> = fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["id", "user.name", "user.address.city", "tags", "active"]);
+        assert_eq!(fields[0].field_type, FieldType::Numeric);
+        assert_eq!(dataset.data.value(0, 3), Value::from(r#"["a","b"]"#));
+        assert_eq!(dataset.data.value(1, 2), Value::Null);
+        assert_eq!(dataset.data.value(0, 4), Value::Null);
+        assert_eq!(dataset.dialect, None);
+    }
+
+    #[test]
+    fn test_parse_ndjson() {
+        let text = "{\"level\":\"info\",\"ms\":12}\n{\"level\":\"warn\",\"ms\":40.5}\n\n";
+        let (dataset, fields) =
+            parse_json_with_options(text, "app.ndjson", 60, &options(SourceFormat::Ndjson)).unwrap();
+
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[1].field_type, FieldType::Numeric);
+        assert_eq!(dataset.data.len(), 2);
+        assert_eq!(dataset.data.value(1, 1), Value::from(40.5));
+    }
+
+    #[test]
+    fn test_ndjson_error_reports_line() {
+        let text = "{\"a\":1}\n{\"a\":\n";
+        let result = parse_json_with_options(text, "bad.ndjson", 20, &options(SourceFormat::Ndjson));
+        assert!(matches!(result, Err(CsvError::ParseError { row: 2, .. })));
+
+        let result = parse_json_with_options("[1, 2]", "nums.json", 6, &options(SourceFormat::Json));
+        assert!(matches!(result, Err(CsvError::InvalidJson(_))));
+    }
+
+    #[test]
+    fn test_preview_json() {
+        let text = r#"[{"a": 1}, {"a": 2, "b": "x"}]"#;
+        let preview = preview_json(text, &options(SourceFormat::Json), 1).unwrap();
+
+        assert_eq!(preview.headers, vec!["a", "b"]);
+        assert_eq!(preview.rows.len(), 1);
+        assert_eq!(preview.raw_rows[0], vec!["a", "b"]);
+    }
+}
@@ -1,28 +1,173 @@
 mod file_reader;
+mod dialect;
 mod csv_parser;
+mod chunked_parser;
+mod json_parser;
+mod spreadsheet_parser;
+mod columnar_parser;
+mod import_options;
+mod number_format;
 mod type_detector;
+mod value_converter;
 mod schema_validator;
 mod upload_progress;
 
 pub use upload_progress::{UploadState, UploadProgress};
-pub use file_reader::read_file_with_progress;
-pub use csv_parser::parse_csv_to_dataset;
+pub use file_reader::{read_file_with_progress, FileContent};
+pub use csv_parser::{parse_csv_with_options, preview_csv};
+pub use chunked_parser::{ChunkedCsvParser, PARSE_CHUNK_ROWS};
+pub use json_parser::{parse_json_with_options, preview_json};
+pub use spreadsheet_parser::{list_sheets, parse_workbook_with_options, preview_workbook};
+pub use columnar_parser::{parse_columnar_with_options, preview_columnar};
+pub use dialect::sniff_dialect;
+pub use import_options::{ImportOptions, ImportPreview, SourceFormat};
 
 use leptos::prelude::*;
-use crate::features::dashboard::DashboardContext;
+use crate::features::dashboard::data::replace::ReplaceReport;
+use crate::features::dashboard::{CsvDialect, CsvError, DashboardContext, Dataset, DatasetId};
+use crate::features::dashboard::compute::{spawn_parse_worker, ComputeResponse, ComputeWorker};
+use futures::StreamExt;
 use wasm_bindgen::JsCast;
 use wasm_bindgen::closure::Closure;
 use web_sys::{File, FileList, HtmlInputElement};
 use send_wrapper::SendWrapper;
 
+/// Number of data rows shown in the import wizard preview
+pub const PREVIEW_ROWS: usize = 20;
+
+/// Delimiters offered by the import wizard (same as the sniffer candidates)
+pub fn delimiter_candidates() -> Vec<char> {
+    schema_validator::ValidationConfig::default().allowed_delimiters
+}
+
+/// Parse a file with the confirmed options, using the reader for its format
+///
+/// Workbooks produce one Dataset per selected sheet; other formats exactly one.
+pub fn parse_with_options(
+    content: &FileContent,
+    filename: &str,
+    file_size: u64,
+    options: &ImportOptions,
+) -> Result<Vec<Dataset>, CsvError> {
+    let single = |(dataset, _fields)| vec![dataset];
+    match options.format {
+        SourceFormat::Delimited => {
+            parse_csv_with_options(&content.as_text(), filename, file_size, options).map(single)
+        }
+        SourceFormat::Json | SourceFormat::Ndjson => {
+            parse_json_with_options(&content.as_text(), filename, file_size, options).map(single)
+        }
+        SourceFormat::Spreadsheet => {
+            parse_workbook_with_options(content.as_bytes(), filename, file_size, options)
+        }
+        SourceFormat::Parquet | SourceFormat::ArrowIpc => {
+            parse_columnar_with_options(content.to_shared_bytes(), filename, file_size, options).map(single)
+        }
+    }
+}
+
+/// Build the import wizard preview, using the reader for the file's format
+pub fn preview_with_options(
+    content: &FileContent,
+    options: &ImportOptions,
+    limit: usize,
+) -> Result<ImportPreview, CsvError> {
+    match options.format {
+        SourceFormat::Delimited => preview_csv(&content.as_text(), options, limit),
+        SourceFormat::Json | SourceFormat::Ndjson => preview_json(&content.as_text(), options, limit),
+        SourceFormat::Spreadsheet => preview_workbook(content.as_bytes(), options, limit),
+        SourceFormat::Parquet | SourceFormat::ArrowIpc => {
+            preview_columnar(content.to_shared_bytes(), options, limit)
+        }
+    }
+}
+
+/// Initial import options and sheet list for a freshly read file
+///
+/// Delimited text gets its dialect sniffed; workbooks list their sheets
+/// and preselect the first one; other formats are recognised by their
+/// extension.
+fn initial_options(
+    filename: &str,
+    content: &FileContent,
+) -> Result<(ImportOptions, Vec<String>), CsvError> {
+    let format = SourceFormat::from_filename(filename);
+    let dialect = match format {
+        SourceFormat::Delimited => {
+            let config = schema_validator::ValidationConfig::default();
+            sniff_dialect(&content.as_text(), &config.allowed_delimiters)
+        }
+        _ => CsvDialect::default(),
+    };
+    let sheets = match format {
+        SourceFormat::Spreadsheet => list_sheets(content.as_bytes())?,
+        _ => Vec::new(),
+    };
+
+    let options = ImportOptions {
+        format,
+        sheets: sheets.first().cloned().into_iter().collect(),
+        ..ImportOptions::new(dialect)
+    };
+    Ok((options, sheets))
+}
+
+/// Let the browser render and handle input before the next chunk
+async fn yield_to_event_loop() {
+    let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+        let scheduled = web_sys::window().and_then(|window| {
+            window
+                .set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, 0)
+                .ok()
+        });
+        if scheduled.is_none() {
+            let _ = resolve.call0(&wasm_bindgen::JsValue::NULL);
+        }
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
+
+/// A file that has been read and is waiting for the user to confirm
+/// how it should be imported
+#[derive(Clone, Debug)]
+pub struct PendingImport {
+    /// Name of the uploaded file
+    pub filename: String,
+    /// Size of the file in bytes
+    pub file_size: u64,
+    /// File content (shared so previews don't copy it)
+    pub content: FileContent,
+    /// Initial settings (format, sniffed dialect, preselected sheet)
+    pub options: ImportOptions,
+    /// All sheets of a workbook, in workbook order (empty for other formats)
+    pub sheets: Vec<String>,
+}
+
 /// CSV Upload Manager - Smart Component
 ///
 /// Handles file selection, upload progress, parsing, and integration with DashboardContext.
 /// This is the main interface for CSV upload functionality.
+///
+/// Files are read and sniffed, then held as a `PendingImport` until the
+/// import wizard confirms (or cancels) them. A file chosen with
+/// `select_replacement` replaces the data of an existing dataset instead
+/// of adding one.
 #[derive(Clone, Copy)]
 pub struct CsvUploadManager {
     progress: ReadSignal<UploadProgress>,
     set_progress: WriteSignal<UploadProgress>,
+    pending: ReadSignal<Option<PendingImport>>,
+    set_pending: WriteSignal<Option<PendingImport>>,
+    /// Dataset whose data the next import replaces
+    replacing: ReadSignal<Option<DatasetId>>,
+    set_replacing: WriteSignal<Option<DatasetId>>,
+    /// Outcome of the latest replacement, until dismissed
+    report: ReadSignal<Option<ReplaceReport>>,
+    set_report: WriteSignal<Option<ReplaceReport>>,
+    /// Id of the latest parse; bumping it stops the running one
+    parse_job: StoredValue<u64>,
+    /// Worker parsing for the given job; dropping it terminates the worker
+    parse_worker: StoredValue<Option<(u64, ComputeWorker)>, LocalStorage>,
     dashboard: DashboardContext,
 }
 
@@ -30,9 +175,20 @@ impl CsvUploadManager {
     /// Create new upload manager
     pub fn new(dashboard: DashboardContext) -> Self {
         let (progress, set_progress) = signal(UploadProgress::default());
+        let (pending, set_pending) = signal(None);
+        let (replacing, set_replacing) = signal(None);
+        let (report, set_report) = signal(None);
         Self {
             progress,
             set_progress,
+            pending,
+            set_pending,
+            replacing,
+            set_replacing,
+            report,
+            set_report,
+            parse_job: StoredValue::new(0),
+            parse_worker: StoredValue::new_local(None),
             dashboard,
         }
     }
@@ -42,6 +198,223 @@ impl CsvUploadManager {
         self.progress.get()
     }
 
+    /// Get the import waiting for confirmation, if any (reactive)
+    pub fn pending_import(&self) -> Option<PendingImport> {
+        self.pending.get()
+    }
+
+    /// Dataset whose data the pending import replaces, if any (reactive)
+    pub fn replacing(&self) -> Option<DatasetId> {
+        self.replacing.get()
+    }
+
+    /// Outcome of the latest data replacement (reactive)
+    pub fn replace_report(&self) -> Option<ReplaceReport> {
+        self.report.get()
+    }
+
+    /// Hide the replacement report
+    pub fn dismiss_report(&self) {
+        self.set_report.set(None);
+    }
+
+    /// Choose a file whose data replaces that of an existing dataset
+    pub fn select_replacement(&self, dataset_id: DatasetId) {
+        self.set_replacing.set(Some(dataset_id));
+        self.select_file();
+    }
+
+    /// Parse the pending file with the confirmed options and add it to the dashboard
+    ///
+    /// When replacing a dataset, its data is replaced by the file's (the
+    /// first selected sheet of a workbook) and the report is kept.
+    ///
+    /// Parsing runs in the background: delimited text is parsed in chunks
+    /// of `PARSE_CHUNK_ROWS` rows, reporting `UploadState::Parsing` progress
+    /// and yielding to the event loop between chunks, until it finishes or
+    /// is stopped with `stop_parsing`. On error the import stays pending
+    /// and the state becomes `Failed` so the wizard can show the message
+    /// and let the user adjust the settings.
+    pub fn confirm_import(&self, options: ImportOptions) {
+        let Some(pending) = self.pending.get_untracked() else {
+            return;
+        };
+
+        let job = self.start_job();
+        self.set_progress.update(|p| {
+            p.state = UploadState::Parsing {
+                progress: 0.0,
+                rows_processed: 0,
+            };
+        });
+
+        let manager = *self;
+        leptos::task::spawn_local(async move {
+            let result = manager.parse_pending(&pending, &options, job).await;
+            if !manager.is_current_job(job) {
+                // Stopped or cancelled while parsing
+                return;
+            }
+
+            let datasets = match result {
+                Ok(datasets) => datasets,
+                Err(e) => {
+                    manager.set_progress.update(|p| {
+                        p.state = UploadState::Failed(e.to_string());
+                    });
+                    return;
+                }
+            };
+
+            if let Some(dataset_id) = manager.replacing.get_untracked() {
+                let report = datasets
+                    .into_iter()
+                    .next()
+                    .and_then(|dataset| manager.dashboard.replace_dataset_data(&dataset_id, dataset));
+                manager.set_report.set(report);
+                manager.set_replacing.set(None);
+            } else {
+                // Add to dashboard context; the first one becomes active (by ID, not name)
+                let first_id = datasets.first().map(|ds| ds.id.clone());
+                for dataset in datasets {
+                    manager.dashboard.add_dataset(dataset);
+                }
+                manager.dashboard.set_active_dataset(first_id);
+            }
+
+            manager.set_pending.set(None);
+            manager.set_progress.update(|p| {
+                p.state = UploadState::Completed;
+            });
+
+            log::info!("Successfully imported: {}", pending.filename);
+        });
+    }
+
+    /// Stop a running parse (or clear a parse error) and go back to the wizard
+    pub fn stop_parsing(&self) {
+        self.start_job();
+        self.set_progress.update(|p| {
+            if matches!(p.state, UploadState::Parsing { .. } | UploadState::Failed(_)) {
+                p.state = UploadState::AwaitingConfirmation;
+            }
+        });
+    }
+
+    /// Discard the pending file without importing it
+    pub fn cancel_import(&self) {
+        self.start_job();
+        self.set_pending.set(None);
+        self.set_replacing.set(None);
+        self.set_progress.set(UploadProgress::default());
+    }
+
+    /// Parse the pending file
+    ///
+    /// Delimited text is parsed chunk by chunk in a compute worker, or on
+    /// the main thread (yielding between chunks) when no worker can run.
+    async fn parse_pending(
+        &self,
+        pending: &PendingImport,
+        options: &ImportOptions,
+        job: u64,
+    ) -> Result<Vec<Dataset>, CsvError> {
+        if options.format != SourceFormat::Delimited {
+            // Other readers parse in one go; let the progress bar show first
+            yield_to_event_loop().await;
+            return parse_with_options(&pending.content, &pending.filename, pending.file_size, options);
+        }
+
+        if let Some(result) = self.parse_in_worker(pending, options, job).await {
+            return result.map(|dataset| vec![dataset]);
+        }
+
+        let mut parser = ChunkedCsvParser::from_shared_text(pending.content.to_shared_text(), options)?;
+        loop {
+            let done = parser.parse_chunk(PARSE_CHUNK_ROWS)?;
+            self.set_progress.update(|p| {
+                p.state = UploadState::Parsing {
+                    progress: parser.progress(),
+                    rows_processed: parser.rows_processed(),
+                };
+            });
+            if done {
+                break;
+            }
+
+            yield_to_event_loop().await;
+            if !self.is_current_job(job) {
+                return Err(CsvError::Cancelled);
+            }
+        }
+
+        let (dataset, _fields) = parser.finish(&pending.filename, pending.file_size)?;
+        Ok(vec![dataset])
+    }
+
+    /// Parse delimited text in a compute worker
+    ///
+    /// # Returns
+    /// `None` when the worker can't be started or fails, so the caller parses
+    /// on the main thread instead
+    async fn parse_in_worker(
+        &self,
+        pending: &PendingImport,
+        options: &ImportOptions,
+        job: u64,
+    ) -> Option<Result<Dataset, CsvError>> {
+        // Shared with the pending import; copied once, into the buffer posted to the worker
+        let text = pending.content.to_shared_text();
+        let (worker, mut responses) =
+            match spawn_parse_worker(text, &pending.filename, pending.file_size, options) {
+                Ok(started) => started,
+                Err(e) => {
+                    log::warn!("Parse worker unavailable, parsing on the main thread: {}", e);
+                    return None;
+                }
+            };
+        self.parse_worker.set_value(Some((job, worker)));
+
+        let result = loop {
+            match responses.next().await {
+                Some(ComputeResponse::ParseProgress { progress, rows_processed }) => {
+                    self.set_progress.update(|p| {
+                        p.state = UploadState::Parsing { progress, rows_processed };
+                    });
+                }
+                Some(ComputeResponse::Parsed { result }) => break Some(result.map(|dataset| *dataset)),
+                Some(ComputeResponse::WorkerError { message }) => {
+                    log::warn!("Parse worker failed, parsing on the main thread: {}", message);
+                    break None;
+                }
+                Some(ComputeResponse::Transformed { .. }) => {}
+                // The worker was dropped: the parse was stopped
+                None => break Some(Err(CsvError::Cancelled)),
+            }
+        };
+
+        // Terminate the worker unless a newer parse already replaced it
+        self.parse_worker.update_value(|worker| {
+            if matches!(worker, Some((worker_job, _)) if *worker_job == job) {
+                *worker = None;
+            }
+        });
+        result
+    }
+
+    /// Invalidate any running parse and return the id of a new one
+    fn start_job(&self) -> u64 {
+        // Terminates the parse worker, if any
+        self.parse_worker.set_value(None);
+        self.parse_job.update_value(|job| *job += 1);
+        self.parse_job.get_value()
+    }
+
+    /// Whether `job` is still the latest parse (not stopped or replaced)
+    fn is_current_job(&self, job: u64) -> bool {
+        self.parse_job.get_value() == job
+    }
+
     /// Trigger file selection dialog
     pub fn select_file(&self) {
         self.set_progress.update(|p| {
@@ -58,13 +431,13 @@ impl CsvUploadManager {
                     .unwrap();
 
                 input.set_type("file");
-                input.set_accept(".csv,.txt,.tsv");
+                input.set_accept(&schema_validator::ACCEPTED_EXTENSIONS.join(","));
                 input.set_multiple(false);
 
                 // Store closure to prevent GC
                 #[allow(clippy::clone_on_copy)]
                 let set_progress = SendWrapper::new(self.set_progress.clone());
-                let dashboard = self.dashboard;
+                let set_pending = SendWrapper::new(self.set_pending);
 
                 let on_change = Closure::wrap(Box::new(move |ev: web_sys::Event| {
                     if let Some(target) = ev.target()
@@ -73,7 +446,7 @@ impl CsvUploadManager {
                         && files.length() > 0 {
                             let file = files.get(0).unwrap();
                             let set_progress = set_progress.clone();
-                            Self::process_file(file, set_progress, dashboard);
+                            Self::process_file(file, set_progress, set_pending.clone());
                         }
                 }) as Box<dyn Fn(web_sys::Event)>);
 
@@ -84,23 +457,28 @@ impl CsvUploadManager {
             }
     }
 
-    /// Handle drag & drop file
+    /// Handle drag & drop file (always added as a new dataset)
     pub fn handle_drop(&self, files: FileList) {
         if files.length() > 0 {
+            self.set_replacing.set(None);
             let file = files.get(0).unwrap();
             #[allow(clippy::clone_on_copy)]
             let set_progress = SendWrapper::new(self.set_progress.clone());
-            let dashboard = self.dashboard;
+            let set_pending = SendWrapper::new(self.set_pending);
 
-            Self::process_file(file, set_progress, dashboard);
+            Self::process_file(file, set_progress, set_pending);
         }
     }
 
     /// Process uploaded file
+    ///
+    /// Reads the file and works out its format (sniffing the dialect of
+    /// delimited text); parsing happens once the import wizard confirms
+    /// the settings.
     fn process_file(
         file: File,
         set_progress: SendWrapper<WriteSignal<UploadProgress>>,
-        dashboard: DashboardContext,
+        set_pending: SendWrapper<WriteSignal<Option<PendingImport>>>,
     ) {
         let filename = file.name();
         let file_size = file.size() as u64;
@@ -131,10 +509,10 @@ impl CsvUploadManager {
         // Read file with progress (callback-based, non-blocking)
         let set_progress_clone = set_progress.clone();
         let set_progress_clone2 = set_progress.clone();
-        let dashboard_clone = dashboard;
 
         read_file_with_progress(
             file.clone(),
+            SourceFormat::from_filename(&filename).is_binary(),
             move |bytes_read, total| {
                 set_progress_clone.update(|p| {
                     p.state = UploadState::Uploading {
@@ -146,35 +524,21 @@ impl CsvUploadManager {
             },
             move |result| {
                 match result {
-                    Ok(csv_text) => {
-                        // File read successful, now parse
-                        set_progress_clone2.update(|p| {
-                            p.state = UploadState::Parsing {
-                                progress: 0.0,
-                                rows_processed: 0,
-                            };
-                        });
-
-                        // Parse CSV (this is fast, so we can do it synchronously)
-                        let filename = file.name();
-                        let file_size = file.size() as u64;
+                    Ok(content) => {
+                        // File read successful, detect the layout and hand over to the wizard
+                        match initial_options(&file.name(), &content) {
+                            Ok((options, sheets)) => {
+                                set_pending.set(Some(PendingImport {
+                                    filename: file.name(),
+                                    file_size: file.size() as u64,
+                                    content,
+                                    options,
+                                    sheets,
+                                }));
 
-                        match parse_csv_to_dataset(&csv_text, &filename, file_size) {
-                            Ok((dataset, _fields)) => {
-                                // Add to dashboard context
-                                let dataset_id = dataset.id.clone();
-                                dashboard_clone.add_dataset(dataset.clone());
-
-                                // Set as active using ID, not name
-                                dashboard_clone.set_active_dataset(Some(dataset_id));
-
-                                // Complete
                                 set_progress_clone2.update(|p| {
-                                    p.state = UploadState::Completed;
+                                    p.state = UploadState::AwaitingConfirmation;
                                 });
-
-                                // Log success
-                                log::info!("Successfully uploaded and parsed: {}", filename);
                             }
                             Err(e) => {
                                 set_progress_clone2.update(|p| {
@@ -0,0 +1,318 @@
+use crate::features::dashboard::{FieldFormat, NumberFormat};
+use crate::features::dashboard::csv_upload::value_converter;
+
+/// Currency symbols recognised before or after a number
+const CURRENCY_SYMBOLS: [char; 15] = [
+    '$', '€', '£', '¥', '₹', '₩', '₽', '₺', '₫', '₪', '฿', '₴', '₦', '₱', '¢',
+];
+
+/// A number read from a cell, with the decorations found around it
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParsedNumber {
+    /// Numeric value (percentages already divided by 100)
+    pub value: f64,
+    /// Currency symbol or ISO code found next to the digits
+    pub currency: Option<String>,
+    /// Whether the value was written with a `%` sign
+    pub percent: bool,
+}
+
+/// Parse a numeric cell written in the given format
+///
+/// Accepts an optional sign, accounting negatives in parentheses
+/// (`(450)`), a currency symbol or ISO code before or after the digits
+/// (`$1,200`, `€ 3 400`, `12 CHF`), a trailing `%` (scaled to a fraction)
+/// and an exponent (`1.5e3`). Digit groups must be well formed, so
+/// `1,5` is not read as `15` with a comma thousands separator.
+///
+/// # Arguments
+/// * `value` - Raw cell content
+/// * `format` - Decimal and thousands separators to use
+///
+/// # Returns
+/// The parsed number, or `None` if the cell isn't a number in this format
+pub fn parse_number(value: &str, format: &NumberFormat) -> Option<ParsedNumber> {
+    let mut s = value.trim();
+    let mut negative = false;
+    let mut signed = false;
+    let mut percent = false;
+    let mut currency: Option<String> = None;
+
+    // Peel decorations off both ends until only digits and separators remain
+    loop {
+        let before = s;
+
+        if let Some(inner) = s.strip_prefix('(').and_then(|rest| rest.strip_suffix(')')) {
+            if signed {
+                return None;
+            }
+            negative = true;
+            signed = true;
+            s = inner.trim();
+        } else if let Some(rest) = s.strip_prefix(['-', '+']) {
+            if signed {
+                return None;
+            }
+            negative = s.starts_with('-');
+            signed = true;
+            s = rest.trim_start();
+        } else if let Some(rest) = s.strip_suffix('%') {
+            if percent {
+                return None;
+            }
+            percent = true;
+            s = rest.trim_end();
+        } else if currency.is_none()
+            && let Some((symbol, rest)) = strip_currency(s)
+        {
+            currency = Some(symbol);
+            s = rest;
+        }
+
+        if s == before {
+            break;
+        }
+    }
+
+    let mut number = parse_digits(s, format)?;
+    if negative {
+        number = -number;
+    }
+    if percent {
+        number /= 100.0;
+    }
+
+    number.is_finite().then_some(ParsedNumber {
+        value: number,
+        currency,
+        percent,
+    })
+}
+
+/// Strip a currency symbol or 3-letter ISO code from either end
+///
+/// Returns the symbol and the remaining text.
+fn strip_currency(s: &str) -> Option<(String, &str)> {
+    let is_affix_char = |c: char| CURRENCY_SYMBOLS.contains(&c) || c.is_alphabetic();
+    let is_currency = |affix: &str| {
+        affix.chars().any(|c| CURRENCY_SYMBOLS.contains(&c))
+            || (affix.len() == 3 && affix.chars().all(|c| c.is_ascii_uppercase()))
+    };
+
+    // Prefix: "$12", "R$ 12", "EUR 12"
+    let prefix_len: usize = s
+        .chars()
+        .take_while(|c| is_affix_char(*c))
+        .map(char::len_utf8)
+        .sum();
+    if prefix_len > 0 && is_currency(&s[..prefix_len]) {
+        return Some((s[..prefix_len].to_string(), s[prefix_len..].trim_start()));
+    }
+
+    // Suffix: "12€", "12 CHF"
+    let suffix_len: usize = s
+        .chars()
+        .rev()
+        .take_while(|c| is_affix_char(*c))
+        .map(char::len_utf8)
+        .sum();
+    let split = s.len() - suffix_len;
+    if suffix_len > 0 && is_currency(&s[split..]) {
+        return Some((s[split..].to_string(), s[..split].trim_end()));
+    }
+
+    None
+}
+
+/// Parse the bare digits of a number (no sign, currency or percent)
+fn parse_digits(s: &str, format: &NumberFormat) -> Option<f64> {
+    let decimal = format.decimal_separator;
+    let thousands = format.thousands_separator.filter(|t| *t != decimal);
+
+    // Optional exponent, always written with `e`
+    let (mantissa, exponent) = match s.find(['e', 'E']) {
+        Some(pos) => (&s[..pos], Some(&s[pos + 1..])),
+        None => (s, None),
+    };
+
+    let (int_part, frac_part) = match mantissa.split_once(decimal) {
+        Some((int_part, frac_part)) => (int_part, Some(frac_part)),
+        None => (mantissa, None),
+    };
+
+    let is_digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+
+    // Integer part, validating digit groups when separators are present
+    let groups: Vec<&str> = match thousands {
+        Some(sep) => int_part.split(|c| is_group_separator(c, sep)).collect(),
+        None => vec![int_part],
+    };
+    if groups.len() > 1 {
+        let first_ok = (1..=3).contains(&groups[0].len());
+        let rest_ok = groups[1..].iter().all(|g| g.len() == 3);
+        if !first_ok || !rest_ok {
+            return None;
+        }
+    }
+    let int_digits: String = groups.concat();
+    if !is_digits(&int_digits) {
+        return None;
+    }
+
+    let frac_digits = frac_part.unwrap_or("");
+    if !is_digits(frac_digits) || (int_digits.is_empty() && frac_digits.is_empty()) {
+        return None;
+    }
+
+    let mut normalized = format!("{}.{}", if int_digits.is_empty() { "0" } else { &int_digits }, frac_digits);
+    if let Some(exponent) = exponent {
+        let digits = exponent.strip_prefix(['-', '+']).unwrap_or(exponent);
+        if digits.is_empty() || !is_digits(digits) {
+            return None;
+        }
+        normalized.push('e');
+        normalized.push_str(exponent);
+    }
+
+    normalized.parse::<f64>().ok()
+}
+
+/// Check a character against the thousands separator; a space separator
+/// also matches non-breaking and narrow non-breaking spaces
+fn is_group_separator(c: char, separator: char) -> bool {
+    c == separator || (separator == ' ' && matches!(c, '\u{a0}' | '\u{202f}'))
+}
+
+/// Pick the number format that reads the most cells of a sample
+///
+/// Cells that read the same way in every format (plain integers, text)
+/// don't change the outcome; ties favour the earlier preset, i.e. `1,234.56`.
+///
+/// # Arguments
+/// * `values` - Sample cells, from any column
+pub fn detect_number_format<'a>(values: impl IntoIterator<Item = &'a str>) -> NumberFormat {
+    let values: Vec<&str> = values
+        .into_iter()
+        .filter(|v| !value_converter::is_null_token(v))
+        .collect();
+
+    let mut best = (NumberFormat::default(), 0usize);
+    for format in NumberFormat::presets() {
+        let parsed = values
+            .iter()
+            .filter(|v| parse_number(v, &format).is_some())
+            .count();
+        if parsed > best.1 {
+            best = (format, parsed);
+        }
+    }
+
+    best.0
+}
+
+/// Work out the currency / percent metadata of a numeric column
+///
+/// A decoration is kept when at least half of the numeric cells carry it.
+///
+/// # Returns
+/// `None` when the column holds plain numbers
+pub fn detect_field_format(values: &[&str], format: &NumberFormat) -> Option<FieldFormat> {
+    let parsed: Vec<ParsedNumber> = values
+        .iter()
+        .filter_map(|v| parse_number(v, format))
+        .collect();
+    if parsed.is_empty() {
+        return None;
+    }
+
+    let percent = parsed.iter().filter(|n| n.percent).count() * 2 >= parsed.len();
+
+    let mut symbols: Vec<(&str, usize)> = Vec::new();
+    for symbol in parsed.iter().filter_map(|n| n.currency.as_deref()) {
+        match symbols.iter_mut().find(|(s, _)| *s == symbol) {
+            Some((_, n)) => *n += 1,
+            None => symbols.push((symbol, 1)),
+        }
+    }
+    let currency = symbols
+        .into_iter()
+        .max_by_key(|(_, n)| *n)
+        .filter(|(_, n)| n * 2 >= parsed.len())
+        .map(|(symbol, _)| symbol.to_string());
+
+    (percent || currency.is_some()).then_some(FieldFormat { currency, percent })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn european() -> NumberFormat {
+        NumberFormat { decimal_separator: ',', thousands_separator: Some('.') }
+    }
+
+    fn value(s: &str, format: &NumberFormat) -> Option<f64> {
+        parse_number(s, format).map(|n| n.value)
+    }
+
+    #[test]
+    fn test_parse_plain_and_grouped() {
+        let us = NumberFormat::default();
+        assert_eq!(value("123.45", &us), Some(123.45));
+        assert_eq!(value("-1,234,567.5", &us), Some(-1_234_567.5));
+        assert_eq!(value(".5", &us), Some(0.5));
+        assert_eq!(value("1.5e3", &us), Some(1500.0));
+        assert_eq!(value("1,5", &us), None);
+        assert_eq!(value("12a", &us), None);
+        assert_eq!(value("inf", &us), None);
+
+        assert_eq!(value("1.234,56", &european()), Some(1234.56));
+        assert_eq!(value("1,50", &european()), Some(1.5));
+    }
+
+    #[test]
+    fn test_parse_currency_percent_and_accounting() {
+        let us = NumberFormat::default();
+        let parsed = parse_number("$1,200", &us).unwrap();
+        assert_eq!(parsed.value, 1200.0);
+        assert_eq!(parsed.currency.as_deref(), Some("$"));
+
+        let parsed = parse_number("12%", &us).unwrap();
+        assert_eq!(parsed.value, 0.12);
+        assert!(parsed.percent);
+
+        assert_eq!(value("(450)", &us), Some(-450.0));
+        assert_eq!(value("($1,000.00)", &us), Some(-1000.0));
+        assert_eq!(value("-$5", &us), Some(-5.0));
+        assert_eq!(value("12 CHF", &us), Some(12.0));
+
+        let spaced = NumberFormat { decimal_separator: ',', thousands_separator: Some(' ') };
+        let parsed = parse_number("€ 3 400", &spaced).unwrap();
+        assert_eq!(parsed.value, 3400.0);
+        assert_eq!(parsed.currency.as_deref(), Some("€"));
+        assert_eq!(value("3\u{a0}400,5", &spaced), Some(3400.5));
+    }
+
+    #[test]
+    fn test_detect_number_format() {
+        assert_eq!(detect_number_format(["1,234.5", "12", "n/a"]), NumberFormat::default());
+        assert_eq!(detect_number_format(["1.234,56", "0,5", "7"]), european());
+        assert_eq!(
+            detect_number_format(["3 400,5", "12"]).thousands_separator,
+            Some(' ')
+        );
+    }
+
+    #[test]
+    fn test_detect_field_format() {
+        let us = NumberFormat::default();
+        let format = detect_field_format(&["$10", "$20.5", "7"], &us).unwrap();
+        assert_eq!(format.currency.as_deref(), Some("$"));
+        assert!(!format.percent);
+
+        let format = detect_field_format(&["12%", "3.5%"], &us).unwrap();
+        assert!(format.percent);
+
+        assert_eq!(detect_field_format(&["1", "2"], &us), None);
+    }
+}
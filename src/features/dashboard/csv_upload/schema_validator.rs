@@ -1,6 +1,12 @@
 use crate::features::dashboard::CsvError;
 use std::collections::HashSet;
 
+/// File extensions offered by the file picker
+pub const ACCEPTED_EXTENSIONS: [&str; 15] = [
+    ".csv", ".txt", ".tsv", ".json", ".ndjson", ".jsonl", ".xlsx", ".xlsm", ".xls", ".ods",
+    ".parquet", ".arrow", ".feather", ".ipc", ".arrows",
+];
+
 /// Validation configuration for CSV uploads
 #[derive(Clone, Debug)]
 pub struct ValidationConfig {
@@ -12,8 +18,7 @@ pub struct ValidationConfig {
     pub max_columns: usize,
     /// Whether headers are required
     pub require_headers: bool,
-    /// Allowed CSV delimiters
-    #[allow(dead_code)]
+    /// Allowed CSV delimiters (candidates for dialect sniffing)
     pub allowed_delimiters: Vec<char>,
 }
 
@@ -54,13 +59,11 @@ pub fn validate_file(
 
     // Check file extension (warning only, not an error)
     let lower_name = filename.to_lowercase();
-    if !lower_name.ends_with(".csv")
-        && !lower_name.ends_with(".txt")
-        && !lower_name.ends_with(".tsv")
-    {
+    if !ACCEPTED_EXTENSIONS.iter().any(|ext| lower_name.ends_with(ext)) {
         log::warn!(
-            "File '{}' doesn't have a standard CSV extension (.csv, .txt, .tsv)",
-            filename
+            "File '{}' doesn't have a supported extension ({})",
+            filename,
+            ACCEPTED_EXTENSIONS.join(", ")
         );
     }
 
@@ -0,0 +1,312 @@
+use crate::features::dashboard::{Dataset, FieldType, CsvError, NumberFormat};
+use crate::features::dashboard::csv_upload::{
+    csv_parser,
+    import_options::{ImportOptions, ImportPreview},
+    value_converter,
+};
+use calamine::{Data, Range, Reader, Sheets};
+use chrono::{NaiveDate, NaiveDateTime, Timelike};
+use std::io::Cursor;
+
+/// List the sheets of a workbook, in workbook order
+pub fn list_sheets(bytes: &[u8]) -> Result<Vec<String>, CsvError> {
+    Ok(open_workbook(bytes)?.sheet_names())
+}
+
+/// Parse the selected sheets of a workbook, one Dataset per sheet
+///
+/// Cell types are taken from the workbook (numbers, dates and booleans
+/// keep their native type instead of being re-detected from text), and
+/// title rows above the table are skipped.
+///
+/// # Arguments
+/// * `bytes` - The workbook file content
+/// * `filename` - Name of the workbook file
+/// * `file_size` - Size of the file in bytes
+/// * `options` - Selected sheets, header setting and per-column overrides
+///
+/// # Returns
+/// One Dataset per selected sheet, or a CsvError
+pub fn parse_workbook_with_options(
+    bytes: &[u8],
+    filename: &str,
+    file_size: u64,
+    options: &ImportOptions,
+) -> Result<Vec<Dataset>, CsvError> {
+    if options.sheets.is_empty() {
+        return Err(CsvError::InvalidCsvFormat("no sheet selected".to_string()));
+    }
+
+    let mut workbook = open_workbook(bytes)?;
+    options
+        .sheets
+        .iter()
+        .map(|sheet| {
+            let table = read_range(&worksheet(&mut workbook, sheet)?, options.dialect.has_header);
+            let name = format!("{} [{}]", filename, sheet);
+            let (dataset, _fields) = csv_parser::build_dataset(
+                table.headers,
+                table.rows,
+                &table.types,
+                &name,
+                file_size,
+                options,
+            )?;
+            Ok(dataset)
+        })
+        .collect()
+}
+
+/// Build the import wizard preview of the first selected sheet
+///
+/// # Arguments
+/// * `bytes` - The workbook file content
+/// * `options` - Current wizard settings
+/// * `limit` - Number of data rows to include in the preview
+pub fn preview_workbook(
+    bytes: &[u8],
+    options: &ImportOptions,
+    limit: usize,
+) -> Result<ImportPreview, CsvError> {
+    let Some(sheet) = options.sheets.first() else {
+        return Err(CsvError::InvalidCsvFormat("select at least one sheet".to_string()));
+    };
+
+    let mut workbook = open_workbook(bytes)?;
+    let table = read_range(&worksheet(&mut workbook, sheet)?, options.dialect.has_header);
+
+    let raw_rows = std::iter::once(table.headers.clone())
+        .chain(table.rows.iter().take(limit).cloned())
+        .collect();
+
+    let format = NumberFormat::default();
+    Ok(csv_parser::build_preview(raw_rows, table.headers, table.rows, table.types, &format, limit))
+}
+
+/// Open an xlsx / xlsm / xls / ods workbook from memory
+fn open_workbook(bytes: &[u8]) -> Result<Sheets<Cursor<&[u8]>>, CsvError> {
+    calamine::open_workbook_auto_from_rs(Cursor::new(bytes))
+        .map_err(|e| CsvError::InvalidCsvFormat(format!("unreadable workbook: {}", e)))
+}
+
+/// Read the used cell range of a sheet
+fn worksheet(workbook: &mut Sheets<Cursor<&[u8]>>, sheet: &str) -> Result<Range<Data>, CsvError> {
+    workbook
+        .worksheet_range(sheet)
+        .map_err(|e| CsvError::InvalidCsvFormat(format!("sheet '{}': {}", sheet, e)))
+}
+
+/// A worksheet as text cells plus the native type of each column
+struct SheetTable {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+    types: Vec<FieldType>,
+}
+
+/// A cell reduced to the types the dashboard knows about
+#[derive(Clone, Debug, PartialEq)]
+enum Cell {
+    Empty,
+    Text(String),
+    Number(f64),
+    Bool(bool),
+    DateTime(NaiveDateTime),
+}
+
+impl Cell {
+    fn from_data(data: &Data) -> Self {
+        match data {
+            Data::Empty | Data::Error(_) => Self::Empty,
+            Data::Int(i) => Self::Number(*i as f64),
+            Data::Float(f) => Self::Number(*f),
+            Data::Bool(b) => Self::Bool(*b),
+            Data::String(s) if value_converter::is_null_token(s) => Self::Empty,
+            Data::String(s) => Self::Text(s.clone()),
+            // Durations are kept as a number of days
+            Data::DateTime(dt) if dt.is_duration() => Self::Number(dt.as_f64()),
+            Data::DateTime(dt) => dt
+                .as_datetime()
+                .map(Self::DateTime)
+                .unwrap_or(Self::Number(dt.as_f64())),
+            Data::DateTimeIso(s) => parse_iso_datetime(s)
+                .map(Self::DateTime)
+                .unwrap_or_else(|| Self::Text(s.clone())),
+            Data::DurationIso(s) => Self::Text(s.clone()),
+        }
+    }
+
+    fn field_type(&self) -> Option<FieldType> {
+        match self {
+            Self::Empty => None,
+            Self::Text(_) => Some(FieldType::Text),
+            Self::Number(_) => Some(FieldType::Numeric),
+            Self::Bool(_) => Some(FieldType::Boolean),
+            Self::DateTime(_) => Some(FieldType::Date),
+        }
+    }
+
+    /// Render the cell as text the value converter reads back losslessly
+    fn to_text(&self, with_time: bool) -> String {
+        match self {
+            Self::Empty => String::new(),
+            Self::Text(s) => s.clone(),
+            Self::Number(n) => n.to_string(),
+            Self::Bool(b) => b.to_string(),
+            Self::DateTime(dt) if with_time => dt.format("%Y-%m-%dT%H:%M:%S").to_string(),
+            Self::DateTime(dt) => dt.format("%Y-%m-%d").to_string(),
+        }
+    }
+}
+
+/// Parse the ISO 8601 dates ODS files store
+fn parse_iso_datetime(s: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f")
+        .ok()
+        .or_else(|| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok().and_then(|d| d.and_hms_opt(0, 0, 0)))
+}
+
+/// Number of title rows above the table
+///
+/// A title (usually merged across the table) only fills one cell of its
+/// row, so leading rows with at most one value are skipped as long as the
+/// table itself is wider than one column.
+fn title_rows(rows: &[Vec<Cell>]) -> usize {
+    let filled = |row: &Vec<Cell>| row.iter().filter(|c| **c != Cell::Empty).count();
+    let width = rows.iter().map(filled).max().unwrap_or(0);
+    if width < 2 {
+        return 0;
+    }
+
+    rows.iter()
+        .take_while(|row| filled(row) <= 1)
+        .count()
+        .min(rows.len().saturating_sub(1))
+}
+
+/// Turn a sheet's cell range into header, text rows and column types
+///
+/// A column keeps its native type when every non-empty cell has it;
+/// mixed columns are imported as text.
+fn read_range(range: &Range<Data>, has_header: bool) -> SheetTable {
+    let mut rows: Vec<Vec<Cell>> = range
+        .rows()
+        .map(|row| row.iter().map(Cell::from_data).collect())
+        .collect();
+    rows.drain(..title_rows(&rows));
+
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let headers: Vec<String> = if has_header && !rows.is_empty() {
+        let header = rows.remove(0);
+        (0..width)
+            .map(|col| match header.get(col).map(|cell| cell.to_text(false)) {
+                Some(name) if !name.trim().is_empty() => name,
+                _ => format!("Column {}", col + 1),
+            })
+            .collect()
+    } else {
+        (1..=width).map(|i| format!("Column {}", i)).collect()
+    };
+
+    let mut types = Vec::with_capacity(width);
+    let mut columns: Vec<Vec<String>> = Vec::with_capacity(width);
+    for col in 0..width {
+        let cells: Vec<&Cell> = rows
+            .iter()
+            .map(|row| row.get(col).unwrap_or(&Cell::Empty))
+            .collect();
+
+        let mut native = cells.iter().filter_map(|cell| cell.field_type());
+        let field_type = match native.next() {
+            Some(first) if native.all(|t| t == first) => first,
+            _ => FieldType::Text,
+        };
+
+        // Dates only carry a time part when at least one cell has one
+        let with_time = cells.iter().any(|cell| {
+            matches!(cell, Cell::DateTime(dt) if dt.time().num_seconds_from_midnight() != 0)
+        });
+
+        types.push(field_type);
+        columns.push(cells.iter().map(|cell| cell.to_text(with_time)).collect());
+    }
+
+    let rows = (0..rows.len())
+        .map(|row| columns.iter().map(|column| column[row].clone()).collect())
+        .collect();
+
+    SheetTable { headers, rows, types }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use calamine::{ExcelDateTime, ExcelDateTimeType};
+
+    fn range(cells: Vec<Vec<Data>>) -> Range<Data> {
+        let height = cells.len() as u32;
+        let width = cells.iter().map(|row| row.len()).max().unwrap_or(0) as u32;
+        let mut range = Range::new((0, 0), (height - 1, width - 1));
+        for (r, row) in cells.into_iter().enumerate() {
+            for (c, value) in row.into_iter().enumerate() {
+                range.set_value((r as u32, c as u32), value);
+            }
+        }
+        range
+    }
+
+    fn text(s: &str) -> Data {
+        Data::String(s.to_string())
+    }
+
+    #[test]
+    fn test_read_range_keeps_native_types() {
+        // 45292 is 2024-01-01 in the 1900 date system
+        let date = |days: f64| Data::DateTime(ExcelDateTime::new(days, ExcelDateTimeType::DateTime, false));
+        let sheet = range(vec![
+            vec![text("zip"), text("amount"), text("day"), text("paid")],
+            vec![text("00123"), Data::Float(10.5), date(45292.0), Data::Bool(true)],
+            vec![text("04567"), Data::Int(3), date(45293.0), Data::Empty],
+        ]);
+
+        let table = read_range(&sheet, true);
+        assert_eq!(table.headers, vec!["zip", "amount", "day", "paid"]);
+        assert_eq!(
+            table.types,
+            vec![FieldType::Text, FieldType::Numeric, FieldType::Date, FieldType::Boolean]
+        );
+        assert_eq!(table.rows[0], vec!["00123", "10.5", "2024-01-01", "true"]);
+        assert_eq!(table.rows[1][3], "");
+    }
+
+    #[test]
+    fn test_read_range_skips_title_rows() {
+        let sheet = range(vec![
+            vec![text("Quarterly report"), Data::Empty],
+            vec![Data::Empty, Data::Empty],
+            vec![text("region"), text("revenue")],
+            vec![text("North"), Data::Float(1.0)],
+        ]);
+
+        let table = read_range(&sheet, true);
+        assert_eq!(table.headers, vec!["region", "revenue"]);
+        assert_eq!(table.rows.len(), 1);
+    }
+
+    #[test]
+    fn test_mixed_columns_become_text() {
+        let sheet = range(vec![
+            vec![text("code"), text("n")],
+            vec![Data::Int(1), Data::Int(1)],
+            vec![text("A2"), text("n/a")],
+        ]);
+
+        let table = read_range(&sheet, true);
+        assert_eq!(table.types, vec![FieldType::Text, FieldType::Numeric]);
+        assert_eq!(table.rows[1], vec!["A2", ""]);
+    }
+
+    #[test]
+    fn test_unreadable_workbook() {
+        assert!(list_sheets(b"not a workbook").is_err());
+    }
+}
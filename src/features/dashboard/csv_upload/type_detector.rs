@@ -1,4 +1,5 @@
-use crate::features::dashboard::FieldType;
+use crate::features::dashboard::{FieldType, NumberFormat};
+use crate::features::dashboard::csv_upload::{number_format, value_converter};
 
 /// Sample size for type detection (first N rows)
 const SAMPLE_SIZE: usize = 100;
@@ -11,10 +12,15 @@ const TYPE_THRESHOLD: f64 = 0.9;
 /// # Arguments
 /// * `column_name` - Name of the column being analyzed
 /// * `values` - Sample values from the column
+/// * `format` - Decimal / thousands separators of numeric cells
 ///
 /// # Returns
 /// Inferred field type
-pub fn detect_column_type(column_name: &str, values: &[String]) -> Result<FieldType, String> {
+pub fn detect_column_type(
+    column_name: &str,
+    values: &[String],
+    format: &NumberFormat,
+) -> Result<FieldType, String> {
     if values.is_empty() {
         return Err(format!("Column '{}' has no data", column_name));
     }
@@ -22,13 +28,7 @@ pub fn detect_column_type(column_name: &str, values: &[String]) -> Result<FieldT
     // Filter out null/empty values
     let non_null_values: Vec<&String> = values
         .iter()
-        .filter(|v| {
-            let trimmed = v.trim();
-            !trimmed.is_empty()
-                && trimmed.to_lowercase() != "null"
-                && trimmed.to_lowercase() != "n/a"
-                && trimmed.to_lowercase() != "na"
-        })
+        .filter(|v| !value_converter::is_null_token(v))
         .collect();
 
     // If all null/empty, default to Text
@@ -49,7 +49,7 @@ pub fn detect_column_type(column_name: &str, values: &[String]) -> Result<FieldT
     // Check for Numeric
     let numeric_count = non_null_values
         .iter()
-        .filter(|v| is_numeric(v))
+        .filter(|v| is_numeric(v, format))
         .count();
 
     if numeric_count as f64 / non_null_values.len() as f64 >= TYPE_THRESHOLD {
@@ -70,51 +70,32 @@ pub fn detect_column_type(column_name: &str, values: &[String]) -> Result<FieldT
     Ok(FieldType::Text)
 }
 
+/// Check whether a single value looks like the given field type
+///
+/// Text matches anything; used to tell header cells apart from data cells.
+pub fn value_matches_type(value: &str, field_type: FieldType, format: &NumberFormat) -> bool {
+    match field_type {
+        FieldType::Text => true,
+        FieldType::Numeric => is_numeric(value, format),
+        FieldType::Date => is_date(value),
+        FieldType::Boolean => is_boolean(value),
+    }
+}
+
 /// Check if a value represents a boolean
 fn is_boolean(value: &str) -> bool {
-    let lower = value.trim().to_lowercase();
-    matches!(
-        lower.as_str(),
-        "true" | "false" | "yes" | "no" | "1" | "0" | "y" | "n"
-    )
+    value_converter::parse_bool(value).is_some()
 }
 
-/// Check if a value represents a number
-fn is_numeric(value: &str) -> bool {
-    value.trim().parse::<f64>().is_ok()
+/// Check if a value represents a number (currency, percent and
+/// accounting notations included)
+fn is_numeric(value: &str, format: &NumberFormat) -> bool {
+    number_format::parse_number(value, format).is_some()
 }
 
 /// Check if a value represents a date
 fn is_date(value: &str) -> bool {
-    let trimmed = value.trim();
-
-    // Try ISO 8601 format
-    if chrono::DateTime::parse_from_rfc3339(trimmed).is_ok() {
-        return true;
-    }
-
-    // Try common date formats
-    let formats = [
-        "%Y-%m-%d",
-        "%m/%d/%Y",
-        "%d/%m/%Y",
-        "%Y-%m-%d %H:%M:%S",
-        "%m/%d/%Y %H:%M:%S",
-        "%d/%m/%Y %H:%M:%S",
-        "%Y/%m/%d",
-        "%m-%d-%Y",
-        "%d-%m-%Y",
-    ];
-
-    for format in formats {
-        if chrono::NaiveDateTime::parse_from_str(trimmed, format).is_ok()
-            || chrono::NaiveDate::parse_from_str(trimmed, format).is_ok()
-        {
-            return true;
-        }
-    }
-
-    false
+    value_converter::looks_like_date(value)
 }
 
 /// Detect types for all columns
@@ -122,12 +103,14 @@ fn is_date(value: &str) -> bool {
 /// # Arguments
 /// * `headers` - Column headers
 /// * `rows` - Sample rows from the CSV
+/// * `format` - Decimal / thousands separators of numeric cells
 ///
 /// # Returns
 /// Vector of (column_name, field_type) tuples
 pub fn detect_types(
     headers: Vec<String>,
     rows: Vec<Vec<String>>,
+    format: &NumberFormat,
 ) -> Result<Vec<(String, FieldType)>, String> {
     if headers.is_empty() {
         return Err("No headers found".to_string());
@@ -144,7 +127,7 @@ pub fn detect_types(
             .collect();
 
         // Detect type for this column
-        let field_type = detect_column_type(header, &column_values)?;
+        let field_type = detect_column_type(header, &column_values, format)?;
         results.push((header.clone(), field_type));
     }
 
@@ -168,12 +151,25 @@ mod tests {
 
     #[test]
     fn test_detect_numeric() {
-        assert!(is_numeric("123"));
-        assert!(is_numeric("123.45"));
-        assert!(is_numeric("-123"));
-        assert!(is_numeric("0.45"));
-        assert!(!is_numeric("abc"));
-        assert!(!is_numeric("12a"));
+        let format = NumberFormat::default();
+        assert!(is_numeric("123", &format));
+        assert!(is_numeric("123.45", &format));
+        assert!(is_numeric("-123", &format));
+        assert!(is_numeric("0.45", &format));
+        assert!(is_numeric("$1,200", &format));
+        assert!(is_numeric("12%", &format));
+        assert!(is_numeric("(450)", &format));
+        assert!(!is_numeric("abc", &format));
+        assert!(!is_numeric("12a", &format));
+    }
+
+    #[test]
+    fn test_detect_column_type_localized_numbers() {
+        let values = vec!["1.234,56".to_string(), "€ 3.400".to_string(), "0,5".to_string()];
+        let format = NumberFormat { decimal_separator: ',', thousands_separator: Some('.') };
+
+        let result = detect_column_type("amount", &values, &format).unwrap();
+        assert_eq!(result, FieldType::Numeric);
     }
 
     #[test]
@@ -191,7 +187,7 @@ mod tests {
             "0".to_string(),
         ];
 
-        let result = detect_column_type("test_col", &values).unwrap();
+        let result = detect_column_type("test_col", &values, &NumberFormat::default()).unwrap();
         assert_eq!(result, FieldType::Boolean);
     }
 
@@ -205,7 +201,7 @@ mod tests {
             "300.75".to_string(),
         ];
 
-        let result = detect_column_type("revenue", &values).unwrap();
+        let result = detect_column_type("revenue", &values, &NumberFormat::default()).unwrap();
         assert_eq!(result, FieldType::Numeric);
     }
 
@@ -219,7 +215,7 @@ mod tests {
             "Sports".to_string(),
         ];
 
-        let result = detect_column_type("category", &values).unwrap();
+        let result = detect_column_type("category", &values, &NumberFormat::default()).unwrap();
         assert_eq!(result, FieldType::Text);
     }
 }
@@ -14,6 +14,8 @@ pub enum UploadState {
         /// Total file size in bytes
         total_bytes: u64,
     },
+    /// File has been read and waits for the import wizard to be confirmed
+    AwaitingConfirmation,
     /// File is being parsed
     Parsing {
         /// Progress percentage (0-100)
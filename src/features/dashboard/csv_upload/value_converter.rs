@@ -0,0 +1,271 @@
+use crate::features::dashboard::{FieldType, NumberFormat};
+use crate::features::dashboard::csv_upload::number_format;
+use serde_json::Value;
+
+/// Cell contents treated as "no value" (compared case-insensitively)
+const NULL_TOKENS: [&str; 6] = ["", "null", "n/a", "na", "nan", "none"];
+
+/// Date formats recognised in date columns, tried in order
+const DATE_FORMATS: [&str; 6] = [
+    "%Y-%m-%d",
+    "%m/%d/%Y",
+    "%d/%m/%Y",
+    "%Y/%m/%d",
+    "%m-%d-%Y",
+    "%d-%m-%Y",
+];
+
+/// Date-time formats recognised in date columns, tried in order
+const DATETIME_FORMATS: [&str; 6] = [
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%m/%d/%Y %H:%M:%S",
+    "%d/%m/%Y %H:%M:%S",
+    "%Y/%m/%d %H:%M:%S",
+];
+
+/// Check whether a raw cell means "no value" (empty, `NA`, `null`, ...)
+pub fn is_null_token(value: &str) -> bool {
+    let trimmed = value.trim();
+    NULL_TOKENS.iter().any(|token| trimmed.eq_ignore_ascii_case(token))
+}
+
+/// Parse a boolean cell (true/false, yes/no, y/n, 1/0)
+pub fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "yes" | "y" | "1" | "t" => Some(true),
+        "false" | "no" | "n" | "0" | "f" => Some(false),
+        _ => None,
+    }
+}
+
+/// Check whether a single cell parses as a date in any supported layout
+pub fn looks_like_date(value: &str) -> bool {
+    let trimmed = value.trim();
+    all_date_layouts().any(|layout| normalize_date(trimmed, layout).is_some())
+}
+
+/// Every supported layout, most specific first
+fn all_date_layouts() -> impl Iterator<Item = DateLayout> {
+    std::iter::once(DateLayout::Rfc3339)
+        .chain(DATETIME_FORMATS.iter().map(|f| DateLayout::DateTime(f)))
+        .chain(DATE_FORMATS.iter().map(|f| DateLayout::Date(f)))
+}
+
+/// How the cells of a date column are written
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum DateLayout {
+    /// RFC 3339 / ISO 8601 with offset
+    Rfc3339,
+    /// Date-time without offset, in the given format
+    DateTime(&'static str),
+    /// Date only, in the given format
+    Date(&'static str),
+}
+
+/// Pick the single layout that parses every non-null value of a column
+///
+/// Choosing per column (rather than per cell) keeps `03/04/2024` from being
+/// read as March in one row and April in the next.
+fn detect_date_layout(values: &[&str]) -> Option<DateLayout> {
+    let non_null: Vec<&str> = values
+        .iter()
+        .map(|v| v.trim())
+        .filter(|v| !is_null_token(v))
+        .collect();
+
+    let mut best: Option<(DateLayout, usize)> = None;
+    for layout in all_date_layouts() {
+        let parsed = non_null
+            .iter()
+            .filter(|v| normalize_date(v, layout).is_some())
+            .count();
+        if parsed == non_null.len() {
+            return Some(layout);
+        }
+        if parsed > 0 && best.is_none_or(|(_, n)| parsed > n) {
+            best = Some((layout, parsed));
+        }
+    }
+
+    best.map(|(layout, _)| layout)
+}
+
+/// Normalize a date cell to ISO 8601
+///
+/// Dates become `YYYY-MM-DD`; date-times become `YYYY-MM-DDTHH:MM:SS`
+/// (converted to UTC with a `Z` suffix when an offset was given).
+fn normalize_date(value: &str, layout: DateLayout) -> Option<String> {
+    match layout {
+        DateLayout::Rfc3339 => chrono::DateTime::parse_from_rfc3339(value)
+            .ok()
+            .map(|dt| dt.with_timezone(&chrono::Utc).format("%Y-%m-%dT%H:%M:%SZ").to_string()),
+        DateLayout::DateTime(format) => chrono::NaiveDateTime::parse_from_str(value, format)
+            .ok()
+            .map(|dt| dt.format("%Y-%m-%dT%H:%M:%S").to_string()),
+        DateLayout::Date(format) => chrono::NaiveDate::parse_from_str(value, format)
+            .ok()
+            .map(|d| d.format("%Y-%m-%d").to_string()),
+    }
+}
+
+/// Convert a number to a JSON value (non-finite numbers become null)
+fn number_value(num: f64) -> Value {
+    serde_json::Number::from_f64(num)
+        .map(Value::Number)
+        .unwrap_or(Value::Null)
+}
+
+/// Converts the raw cells of one column according to its field type
+///
+/// Date columns pick a single layout up front (see `detect_date_layout`),
+/// so cells can be converted one at a time while a file is streamed.
+#[derive(Clone, Debug)]
+pub struct ColumnConverter {
+    field_type: FieldType,
+    number_format: NumberFormat,
+    date_layout: Option<DateLayout>,
+}
+
+impl ColumnConverter {
+    /// Create a converter for a column
+    ///
+    /// # Arguments
+    /// * `field_type` - Final type of the column
+    /// * `sample` - Cells used to pick the date layout of date columns
+    /// * `format` - Decimal / thousands separators of numeric cells
+    pub fn new(field_type: FieldType, sample: &[&str], format: &NumberFormat) -> Self {
+        let date_layout = match field_type {
+            FieldType::Date => detect_date_layout(sample),
+            _ => None,
+        };
+        Self { field_type, number_format: *format, date_layout }
+    }
+
+    /// Convert a single cell
+    ///
+    /// Null tokens become `Value::Null`. Cells that don't fit the column type
+    /// (e.g. `abc` in a numeric column) also become null rather than being
+    /// stored as a string of a different type.
+    pub fn convert(&self, raw: &str) -> Value {
+        let trimmed = raw.trim();
+        if is_null_token(trimmed) {
+            return Value::Null;
+        }
+
+        match self.field_type {
+            FieldType::Text => Value::String(raw.to_string()),
+            FieldType::Numeric => number_format::parse_number(trimmed, &self.number_format)
+                .map(|number| number_value(number.value))
+                .unwrap_or(Value::Null),
+            FieldType::Boolean => parse_bool(trimmed).map(Value::Bool).unwrap_or(Value::Null),
+            FieldType::Date => self
+                .date_layout
+                .and_then(|layout| normalize_date(trimmed, layout))
+                .map(Value::String)
+                .unwrap_or(Value::Null),
+        }
+    }
+}
+
+/// Convert one column of raw cells according to its field type
+///
+/// The date layout of date columns is picked from every cell of the column.
+///
+/// # Arguments
+/// * `values` - Raw cells of the column, in row order
+/// * `field_type` - Final type of the column
+/// * `format` - Decimal / thousands separators of numeric cells
+///
+/// # Returns
+/// One JSON value per input cell
+pub fn convert_column(values: &[&str], field_type: FieldType, format: &NumberFormat) -> Vec<Value> {
+    let converter = ColumnConverter::new(field_type, values, format);
+    values.iter().map(|raw| converter.convert(raw)).collect()
+}
+
+/// Count the cells of a column that would be dropped to null under a type
+///
+/// # Arguments
+/// * `values` - Raw cells of the column
+/// * `field_type` - Type the column would be imported as
+/// * `format` - Decimal / thousands separators of numeric cells
+pub fn count_rejected(values: &[&str], field_type: FieldType, format: &NumberFormat) -> usize {
+    convert_column(values, field_type, format)
+        .iter()
+        .zip(values)
+        .filter(|(value, raw)| value.is_null() && !is_null_token(raw))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_null_tokens() {
+        assert!(is_null_token(""));
+        assert!(is_null_token("  NA "));
+        assert!(is_null_token("null"));
+        assert!(is_null_token("N/A"));
+        assert!(!is_null_token("0"));
+    }
+
+    #[test]
+    fn test_convert_text_keeps_leading_zeros() {
+        let values = convert_column(&["00123", "", "abc"], FieldType::Text, &NumberFormat::default());
+        assert_eq!(values, vec![Value::from("00123"), Value::Null, Value::from("abc")]);
+    }
+
+    #[test]
+    fn test_convert_numeric_with_blanks() {
+        let values = convert_column(&["1.5", "", "NA", "x"], FieldType::Numeric, &NumberFormat::default());
+        assert_eq!(values[0], Value::from(1.5));
+        assert!(values[1..].iter().all(Value::is_null));
+    }
+
+    #[test]
+    fn test_convert_numeric_with_locale_format() {
+        let format = NumberFormat { decimal_separator: ',', thousands_separator: Some('.') };
+        let values = convert_column(&["1.234,5", "(2,5)", "10%"], FieldType::Numeric, &format);
+        assert_eq!(values, vec![Value::from(1234.5), Value::from(-2.5), Value::from(0.1)]);
+    }
+
+    #[test]
+    fn test_count_rejected_ignores_null_tokens() {
+        let values = ["1.5", "", "NA", "x", "2 kg"];
+        assert_eq!(count_rejected(&values, FieldType::Numeric, &NumberFormat::default()), 2);
+        assert_eq!(count_rejected(&values, FieldType::Text, &NumberFormat::default()), 0);
+    }
+
+    #[test]
+    fn test_convert_boolean_words() {
+        let values = convert_column(&["yes", "no", "Y", "0", "null"], FieldType::Boolean, &NumberFormat::default());
+        assert_eq!(
+            values,
+            vec![
+                Value::Bool(true),
+                Value::Bool(false),
+                Value::Bool(true),
+                Value::Bool(false),
+                Value::Null
+            ]
+        );
+    }
+
+    #[test]
+    fn test_convert_dates_normalized_per_column() {
+        // 13/04 only parses day-first, so the whole column is read day-first
+        let values = convert_column(&["03/04/2024", "13/04/2024", ""], FieldType::Date, &NumberFormat::default());
+        assert_eq!(values[0], Value::from("2024-04-03"));
+        assert_eq!(values[1], Value::from("2024-04-13"));
+        assert_eq!(values[2], Value::Null);
+
+        let values = convert_column(&["2024-01-05 10:30:00"], FieldType::Date, &NumberFormat::default());
+        assert_eq!(values[0], Value::from("2024-01-05T10:30:00"));
+
+        let values = convert_column(&["2024-01-05T10:30:00+02:00"], FieldType::Date, &NumberFormat::default());
+        assert_eq!(values[0], Value::from("2024-01-05T08:30:00Z"));
+    }
+}
@@ -1,54 +1,233 @@
-//! Data aggregation utilities
+//! Aggregation engine shared by charts, KPIs and tables
 
+use super::table::Column;
+use crate::features::dashboard::models::Dataset;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashSet;
 
 /// Aggregation function for measure fields
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+///
+/// The one set of functions offered everywhere a measure is summarized:
+/// chart series, KPI cards and table summary rows.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub enum AggregationFunction {
+    #[default]
     Sum,
+    #[serde(alias = "Average")]
     Avg,
+    /// Number of non-null values (of any type)
     Count,
+    /// Number of distinct non-null values
+    CountDistinct,
     Min,
     Max,
     Median,
+    /// Sample standard deviation
+    StdDev,
+    /// Sample variance
+    Variance,
+    /// Percentile between 0 and 100, interpolated between ranks
+    Percentile(f64),
+    /// Most frequent value (the smallest one on ties)
+    Mode,
+    /// Average weighted by another numeric field
+    WeightedAvg { weight: String },
+    /// First value, in row order
+    First,
+    /// Last value, in row order
+    Last,
     None, // No aggregation (use raw values)
 }
 
 impl AggregationFunction {
-    /// Get all aggregation functions
-    #[allow(dead_code)]
-    pub fn all() -> Vec<Self> {
+    /// Functions offered in pickers
+    ///
+    /// Weighted averages are left out: they need a weight field, so pickers
+    /// list one entry per candidate field (see [`Self::weighted_by`]).
+    pub fn common() -> Vec<Self> {
         vec![
             Self::Sum,
             Self::Avg,
             Self::Count,
+            Self::CountDistinct,
             Self::Min,
             Self::Max,
             Self::Median,
-            Self::None,
+            Self::StdDev,
+            Self::Variance,
+            Self::Percentile(25.0),
+            Self::Percentile(75.0),
+            Self::Percentile(90.0),
+            Self::Percentile(95.0),
+            Self::Percentile(99.0),
+            Self::Mode,
+            Self::First,
+            Self::Last,
         ]
     }
 
-    /// Get display name
-    #[allow(dead_code)]
-    pub fn display_name(&self) -> &'static str {
+    /// Weighted average by a field
+    pub fn weighted_by(weight: &str) -> Self {
+        Self::WeightedAvg { weight: weight.to_string() }
+    }
+
+    /// Get display name (e.g. `SUM`, `P90`, `WAVG(Quantity)`)
+    pub fn display_name(&self) -> String {
+        match self {
+            Self::Sum => "SUM".to_string(),
+            Self::Avg => "AVG".to_string(),
+            Self::Count => "COUNT".to_string(),
+            Self::CountDistinct => "COUNTD".to_string(),
+            Self::Min => "MIN".to_string(),
+            Self::Max => "MAX".to_string(),
+            Self::Median => "MEDIAN".to_string(),
+            Self::StdDev => "STDEV".to_string(),
+            Self::Variance => "VAR".to_string(),
+            Self::Percentile(p) => format!("P{}", p),
+            Self::Mode => "MODE".to_string(),
+            Self::WeightedAvg { weight } => format!("WAVG({})", weight),
+            Self::First => "FIRST".to_string(),
+            Self::Last => "LAST".to_string(),
+            Self::None => "NONE".to_string(),
+        }
+    }
+
+    /// Human-readable name, as shown on KPI cards
+    pub fn label(&self) -> String {
         match self {
-            Self::Sum => "SUM",
-            Self::Avg => "AVG",
-            Self::Count => "COUNT",
-            Self::Min => "MIN",
-            Self::Max => "MAX",
-            Self::Median => "MEDIAN",
-            Self::None => "NONE",
+            Self::Sum => "Total".to_string(),
+            Self::Avg => "Average".to_string(),
+            Self::Count => "Count".to_string(),
+            Self::CountDistinct => "Distinct count".to_string(),
+            Self::Min => "Minimum".to_string(),
+            Self::Max => "Maximum".to_string(),
+            Self::Median => "Median".to_string(),
+            Self::StdDev => "Std. deviation".to_string(),
+            Self::Variance => "Variance".to_string(),
+            Self::Percentile(p) => format!("{}th percentile", p),
+            Self::Mode => "Most frequent".to_string(),
+            Self::WeightedAvg { weight } => format!("Average weighted by {}", weight),
+            Self::First => "First".to_string(),
+            Self::Last => "Latest".to_string(),
+            Self::None => "Value".to_string(),
         }
     }
+
+    /// Whether the function counts values instead of combining them
+    ///
+    /// Counts are plain numbers: they don't carry the measure's currency
+    /// or percent format, and they are 0 (not missing) for empty groups.
+    pub fn is_count(&self) -> bool {
+        matches!(self, Self::Count | Self::CountDistinct)
+    }
 }
 
-impl Default for AggregationFunction {
-    fn default() -> Self {
-        Self::Sum
+/// Aggregate a sequence of JSON values
+///
+/// Nulls are handled explicitly rather than dropped or zeroed silently:
+/// - every function skips `Value::Null`;
+/// - `Count` counts the non-null values (of any type), `CountDistinct`
+///   the distinct ones;
+/// - numeric functions only look at numbers, and return `None` when a
+///   group has no numeric values, so callers can render a gap instead of
+///   a misleading 0.
+///
+/// Weighted averages need a weight column: use [`aggregate_field`].
+///
+/// # Arguments
+/// * `values` - Values of one field (e.g. one group of rows)
+/// * `agg_fn` - Aggregation function to apply
+///
+/// # Returns
+/// The aggregated value, or `None` if there was nothing to aggregate
+pub fn aggregate_values<'a>(
+    values: impl IntoIterator<Item = &'a Value>,
+    agg_fn: &AggregationFunction,
+) -> Option<f64> {
+    let non_null: Vec<&Value> = values.into_iter().filter(|v| !v.is_null()).collect();
+
+    match agg_fn {
+        AggregationFunction::Count => return Some(non_null.len() as f64),
+        AggregationFunction::CountDistinct => return Some(count_distinct(non_null.into_iter().cloned())),
+        _ => {}
     }
+
+    let numbers: Vec<f64> = non_null.iter().filter_map(|v| v.as_f64()).collect();
+    apply_aggregation(&numbers, agg_fn)
+}
+
+/// Aggregate some rows of a column
+///
+/// Same null handling as [`aggregate_values`]; weighted averages need a
+/// weight column, so they are `None` here (see [`aggregate_field`]).
+///
+/// # Arguments
+/// * `column` - Column of the measure field
+/// * `rows` - Indexes of the rows to aggregate (e.g. one group)
+/// * `agg_fn` - Aggregation function to apply
+pub fn aggregate_column(
+    column: &Column,
+    rows: impl IntoIterator<Item = usize>,
+    agg_fn: &AggregationFunction,
+) -> Option<f64> {
+    let rows = rows.into_iter();
+    match agg_fn {
+        AggregationFunction::Count => {
+            return Some(rows.filter(|&row| !column.is_null(row)).count() as f64);
+        }
+        AggregationFunction::CountDistinct => {
+            let values = rows.filter(|&row| !column.is_null(row)).map(|row| column.value(row));
+            return Some(count_distinct(values));
+        }
+        _ => {}
+    }
+
+    let numbers: Vec<f64> = rows.filter_map(|row| column.number(row)).collect();
+    apply_aggregation(&numbers, agg_fn)
+}
+
+/// Aggregate some rows of a dataset field
+///
+/// The entry point for anything built from a dataset. On top of
+/// [`aggregate_column`], resolves the weight field of a weighted average
+/// by name: rows missing either the value or the weight are skipped, and
+/// an unknown weight field or weights summing to 0 give `None`.
+///
+/// # Arguments
+/// * `dataset` - Dataset the field belongs to
+/// * `field_idx` - Index of the measure field
+/// * `rows` - Indexes of the rows to aggregate (e.g. one group)
+/// * `agg_fn` - Aggregation function to apply
+pub fn aggregate_field(
+    dataset: &Dataset,
+    field_idx: usize,
+    rows: impl IntoIterator<Item = usize>,
+    agg_fn: &AggregationFunction,
+) -> Option<f64> {
+    let column = dataset.data.column(field_idx);
+    let AggregationFunction::WeightedAvg { weight } = agg_fn else {
+        return aggregate_column(column, rows, agg_fn);
+    };
+
+    let weight_idx = dataset.fields.iter().position(|f| &f.name == weight)?;
+    let weights = dataset.data.column(weight_idx);
+    let (total, total_weight) = rows
+        .into_iter()
+        .filter_map(|row| Some((column.number(row)?, weights.number(row)?)))
+        .fold((0.0, 0.0), |(total, total_weight), (value, weight)| {
+            (total + value * weight, total_weight + weight)
+        });
+
+    (total_weight != 0.0).then(|| total / total_weight)
+}
+
+/// Convert an aggregation result to a JSON value (`None` becomes null)
+pub fn number_or_null(value: Option<f64>) -> Value {
+    value
+        .and_then(serde_json::Number::from_f64)
+        .map(Value::Number)
+        .unwrap_or(Value::Null)
 }
 
 /// Aggregate data for a set of measure fields
@@ -59,7 +238,7 @@ impl Default for AggregationFunction {
 /// * `agg_fn` - Aggregation function to apply
 ///
 /// # Returns
-/// Vector of aggregated values (one per field)
+/// Vector of aggregated values (one per field, `None` for all-null fields)
 ///
 /// # Example
 /// ```rust
@@ -68,72 +247,110 @@ impl Default for AggregationFunction {
 ///     vec![json!("B"), json!(15), json!(25)],
 /// ];
 /// let result = aggregate_data(&data, vec![1, 2], AggregationFunction::Sum);
-/// assert_eq!(result, vec![25.0, 45.0]);
+/// assert_eq!(result, vec![Some(25.0), Some(45.0)]);
 /// ```
 #[allow(dead_code)]
 pub fn aggregate_data(
     data: &[Vec<Value>],
     field_indexes: Vec<usize>,
     agg_fn: AggregationFunction,
-) -> Vec<f64> {
+) -> Vec<Option<f64>> {
     if field_indexes.is_empty() || data.is_empty() {
         return Vec::new();
     }
 
     field_indexes
         .iter()
-        .map(|&idx| {
-            let values: Vec<f64> = data
-                .iter()
-                .filter_map(|row| row.get(idx))
-                .filter_map(|v| v.as_f64())
-                .collect();
-
-            if values.is_empty() {
-                return 0.0;
-            }
-
-            apply_aggregation(&values, agg_fn)
-        })
+        .map(|&idx| aggregate_values(data.iter().filter_map(|row| row.get(idx)), &agg_fn))
         .collect()
 }
 
-/// Apply aggregation function to a set of values
-#[allow(dead_code)]
-fn apply_aggregation(values: &[f64], agg_fn: AggregationFunction) -> f64 {
-    match agg_fn {
+/// Number of distinct (non-null) values
+fn count_distinct(values: impl Iterator<Item = Value>) -> f64 {
+    // JSON text tells apart `1` and `"1"`, like the values themselves
+    values.map(|value| value.to_string()).collect::<HashSet<_>>().len() as f64
+}
+
+/// Apply aggregation function to a set of (non-null) numbers
+///
+/// Returns `None` for an empty set, except for counts which are 0, and for
+/// statistics the set is too small for (a variance needs two values).
+fn apply_aggregation(values: &[f64], agg_fn: &AggregationFunction) -> Option<f64> {
+    if values.is_empty() {
+        return agg_fn.is_count().then_some(0.0);
+    }
+
+    let result = match agg_fn {
         AggregationFunction::Sum => values.iter().sum(),
-        AggregationFunction::Avg => {
-            if values.is_empty() {
-                0.0
-            } else {
-                values.iter().sum::<f64>() / values.len() as f64
-            }
-        }
+        AggregationFunction::Avg => mean(values),
         AggregationFunction::Count => values.len() as f64,
+        AggregationFunction::CountDistinct => {
+            let mut distinct = values.to_vec();
+            distinct.sort_by(|a, b| a.total_cmp(b));
+            distinct.dedup();
+            distinct.len() as f64
+        }
         AggregationFunction::Min => {
             values.iter().fold(f64::INFINITY, |a, &b| a.min(b))
         }
         AggregationFunction::Max => {
             values.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b))
         }
-        AggregationFunction::Median => {
-            let mut sorted = values.to_vec();
-            sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
-            let len = sorted.len();
-            if len == 0 {
-                0.0
-            } else if len.is_multiple_of(2) {
-                (sorted[len / 2 - 1] + sorted[len / 2]) / 2.0
-            } else {
-                sorted[len / 2]
-            }
-        }
-        AggregationFunction::None => {
-            // Return first value or 0 if empty
-            values.first().copied().unwrap_or(0.0)
+        AggregationFunction::Median => percentile(values, 50.0),
+        AggregationFunction::StdDev => variance(values)?.sqrt(),
+        AggregationFunction::Variance => variance(values)?,
+        AggregationFunction::Percentile(p) => percentile(values, *p),
+        AggregationFunction::Mode => mode(values),
+        // Needs weights: see `aggregate_field`
+        AggregationFunction::WeightedAvg { .. } => return None,
+        AggregationFunction::First => values[0],
+        AggregationFunction::Last => values[values.len() - 1],
+        // No aggregation: first value
+        AggregationFunction::None => values[0],
+    };
+
+    Some(result)
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Sample variance (divided by n - 1), `None` for fewer than two values
+fn variance(values: &[f64]) -> Option<f64> {
+    if values.len() < 2 {
+        return None;
+    }
+    let mean = mean(values);
+    let squares: f64 = values.iter().map(|v| (v - mean).powi(2)).sum();
+    Some(squares / (values.len() - 1) as f64)
+}
+
+/// Percentile `p` (0-100), interpolating linearly between the closest ranks
+///
+/// Same definition as spreadsheets' `PERCENTILE.INC`: P50 is the median.
+pub(crate) fn percentile(values: &[f64], p: f64) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+
+    let rank = p.clamp(0.0, 100.0) / 100.0 * (sorted.len() - 1) as f64;
+    let below = rank.floor() as usize;
+    let above = rank.ceil() as usize;
+    sorted[below] + (sorted[above] - sorted[below]) * (rank - below as f64)
+}
+
+/// Most frequent value; the smallest one when several are as frequent
+fn mode(values: &[f64]) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+
+    let (mut best, mut best_count) = (sorted[0], 0);
+    for run in sorted.chunk_by(|a, b| a == b) {
+        if run.len() > best_count {
+            (best, best_count) = (run[0], run.len());
         }
     }
+    best
 }
 
 #[cfg(test)]
@@ -150,7 +367,7 @@ mod tests {
         ];
 
         let result = aggregate_data(&data, vec![1, 2], AggregationFunction::Sum);
-        assert_eq!(result, vec![45.0, 75.0]);
+        assert_eq!(result, vec![Some(45.0), Some(75.0)]);
     }
 
     #[test]
@@ -162,7 +379,7 @@ mod tests {
         ];
 
         let result = aggregate_data(&data, vec![0], AggregationFunction::Avg);
-        assert_eq!(result, vec![20.0]);
+        assert_eq!(result, vec![Some(20.0)]);
     }
 
     #[test]
@@ -174,7 +391,7 @@ mod tests {
         ];
 
         let result = aggregate_data(&data, vec![0], AggregationFunction::Count);
-        assert_eq!(result, vec![3.0]);
+        assert_eq!(result, vec![Some(3.0)]);
     }
 
     #[test]
@@ -188,8 +405,8 @@ mod tests {
         let min_result = aggregate_data(&data, vec![0], AggregationFunction::Min);
         let max_result = aggregate_data(&data, vec![0], AggregationFunction::Max);
 
-        assert_eq!(min_result, vec![5.0]);
-        assert_eq!(max_result, vec![20.0]);
+        assert_eq!(min_result, vec![Some(5.0)]);
+        assert_eq!(max_result, vec![Some(20.0)]);
     }
 
     #[test]
@@ -201,13 +418,107 @@ mod tests {
         ];
 
         let result = aggregate_data(&data, vec![0], AggregationFunction::Median);
-        assert_eq!(result, vec![20.0]);
+        assert_eq!(result, vec![Some(20.0)]);
     }
 
     #[test]
     fn test_empty_data() {
         let data: Vec<Vec<Value>> = vec![];
         let result = aggregate_data(&data, vec![0], AggregationFunction::Sum);
-        assert_eq!(result, Vec::<f64>::new());
+        assert_eq!(result, Vec::<Option<f64>>::new());
+    }
+
+    #[test]
+    fn test_nulls_are_explicit() {
+        let data = vec![
+            vec![json!(10), Value::Null],
+            vec![Value::Null, Value::Null],
+            vec![json!(30), Value::Null],
+        ];
+
+        // Nulls are skipped, not treated as 0
+        assert_eq!(aggregate_data(&data, vec![0], AggregationFunction::Avg), vec![Some(20.0)]);
+        assert_eq!(aggregate_data(&data, vec![0], AggregationFunction::Min), vec![Some(10.0)]);
+        // Count counts non-null values only
+        assert_eq!(aggregate_data(&data, vec![0], AggregationFunction::Count), vec![Some(2.0)]);
+        // An all-null field has no value (except for Count)
+        assert_eq!(aggregate_data(&data, vec![1], AggregationFunction::Sum), vec![None]);
+        assert_eq!(aggregate_data(&data, vec![1], AggregationFunction::Count), vec![Some(0.0)]);
+        assert_eq!(number_or_null(None), Value::Null);
+    }
+
+    #[test]
+    fn test_count_distinct_aggregation() {
+        let data = vec![
+            vec![json!("a"), json!(1)],
+            vec![json!("b"), json!(1)],
+            vec![json!("a"), Value::Null],
+            vec![Value::Null, json!("1")],
+        ];
+
+        assert_eq!(aggregate_data(&data, vec![0], AggregationFunction::CountDistinct), vec![Some(2.0)]);
+        // A number and a string with the same text are different values
+        assert_eq!(aggregate_data(&data, vec![1], AggregationFunction::CountDistinct), vec![Some(2.0)]);
+    }
+
+    #[test]
+    fn test_statistical_aggregations() {
+        let data: Vec<Vec<Value>> = [2, 4, 4, 4, 5, 5, 7, 9].iter().map(|v| vec![json!(v)]).collect();
+        let stat = |agg_fn| aggregate_data(&data, vec![0], agg_fn)[0].unwrap();
+
+        assert!((stat(AggregationFunction::Variance) - 32.0 / 7.0).abs() < 1e-9);
+        assert!((stat(AggregationFunction::StdDev) - (32.0f64 / 7.0).sqrt()).abs() < 1e-9);
+        assert_eq!(stat(AggregationFunction::Mode), 4.0);
+        assert_eq!(stat(AggregationFunction::Median), 4.5);
+        assert_eq!(stat(AggregationFunction::Percentile(25.0)), 4.0);
+        assert!((stat(AggregationFunction::Percentile(90.0)) - 7.6).abs() < 1e-9);
+        assert_eq!(stat(AggregationFunction::Percentile(100.0)), 9.0);
+        assert_eq!(stat(AggregationFunction::First), 2.0);
+        assert_eq!(stat(AggregationFunction::Last), 9.0);
+
+        // A single value has no sample variance
+        let single = vec![vec![json!(3)]];
+        assert_eq!(aggregate_data(&single, vec![0], AggregationFunction::StdDev), vec![None]);
+    }
+
+    #[test]
+    fn test_weighted_average() {
+        use crate::features::dashboard::data::DataTable;
+        use crate::features::dashboard::{Field, FieldType};
+
+        let fields = vec![
+            Field { name: "Price".to_string(), field_type: FieldType::Numeric, format: None },
+            Field { name: "Quantity".to_string(), field_type: FieldType::Numeric, format: None },
+        ];
+        let dataset = Dataset {
+            id: "orders".to_string(),
+            name: "orders.csv".to_string(),
+            size: "0 MB".to_string(),
+            uploaded_at: "Today".to_string(),
+            data: DataTable::from_rows(&fields, vec![
+                vec![json!(10), json!(1)],
+                vec![json!(20), json!(3)],
+                vec![json!(99), Value::Null],
+            ]),
+            fields,
+            active: true,
+            dialect: None,
+            calculated_fields: Vec::new(),
+            derived_from: None,
+        };
+
+        let weighted = AggregationFunction::weighted_by("Quantity");
+        assert_eq!(aggregate_field(&dataset, 0, 0..3, &weighted), Some(17.5));
+        assert_eq!(aggregate_field(&dataset, 0, 0..3, &AggregationFunction::weighted_by("Missing")), None);
+        // Without a weight column there is nothing to weight by
+        assert_eq!(aggregate_column(dataset.data.column(0), 0..3, &weighted), None);
+    }
+
+    #[test]
+    fn test_legacy_kpi_names_deserialize() {
+        let avg: AggregationFunction = serde_json::from_str("\"Average\"").unwrap();
+        assert_eq!(avg, AggregationFunction::Avg);
+        let last: AggregationFunction = serde_json::from_str("\"Last\"").unwrap();
+        assert_eq!(last, AggregationFunction::Last);
     }
 }
@@ -0,0 +1,391 @@
+//! Datasets computed from other datasets
+//!
+//! Reshaped and joined datasets remember how they were made, so they can
+//! be computed again whenever one of their sources changes.
+
+use super::join::{Join, JoinError};
+use super::replace::SchemaDiff;
+use super::reshape::{Reshape, ReshapeError};
+use super::table::DataTable;
+use crate::features::dashboard::models::{Dataset, DatasetId, Field};
+use serde::{Deserialize, Serialize};
+
+/// Error while computing a derived dataset
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeriveError {
+    /// A source dataset isn't loaded
+    UnknownDataset(DatasetId),
+    Reshape(ReshapeError),
+    Join(JoinError),
+}
+
+impl std::fmt::Display for DeriveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownDataset(id) => write!(f, "Dataset not found: {}", id),
+            Self::Reshape(e) => write!(f, "{}", e),
+            Self::Join(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for DeriveError {}
+
+impl From<ReshapeError> for DeriveError {
+    fn from(e: ReshapeError) -> Self {
+        Self::Reshape(e)
+    }
+}
+
+impl From<JoinError> for DeriveError {
+    fn from(e: JoinError) -> Self {
+        Self::Join(e)
+    }
+}
+
+/// How a derived dataset is computed
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum DerivedFrom {
+    /// Pivot or unpivot of one dataset
+    Reshape { source: DatasetId, reshape: Reshape },
+    /// Join of two datasets
+    Join(Join),
+}
+
+impl DerivedFrom {
+    /// Datasets the rows are computed from
+    pub fn sources(&self) -> Vec<&DatasetId> {
+        match self {
+            Self::Reshape { source, .. } => vec![source],
+            Self::Join(join) => vec![&join.left, &join.right],
+        }
+    }
+
+    /// Fields and rows of the derived dataset
+    pub fn compute(&self, datasets: &[Dataset]) -> Result<(Vec<Field>, DataTable), DeriveError> {
+        match self {
+            Self::Reshape { source, reshape } => Ok(reshape.apply(find(datasets, source)?)?),
+            Self::Join(join) => {
+                let (fields, data, _) = join.apply(find(datasets, &join.left)?, find(datasets, &join.right)?)?;
+                Ok((fields, data))
+            }
+        }
+    }
+
+    /// Follow a field of the dataset `source` renamed from `from` to `to`
+    pub fn rename_field(&mut self, source: &str, from: &str, to: &str) {
+        match self {
+            Self::Reshape { source: id, reshape } if id == source => reshape.rename_field(from, to),
+            Self::Reshape { .. } => {}
+            Self::Join(Join { left, right, keys, .. }) => {
+                for key in keys {
+                    if left == source && key.left == from {
+                        key.left = to.to_string();
+                    }
+                    if right == source && key.right == from {
+                        key.right = to.to_string();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Where the rows come from, e.g. `Left join of orders.csv and customers.csv`
+    pub fn describe(&self, datasets: &[Dataset]) -> String {
+        let name = |id| dataset_name(datasets, id);
+        match self {
+            Self::Reshape { source, .. } => format!("from {}", name(source)),
+            Self::Join(join) => format!("{} of {} and {}", join.kind.label(), name(&join.left), name(&join.right)),
+        }
+    }
+
+    /// Name of the derived dataset until one is chosen
+    pub fn default_name(&self, datasets: &[Dataset]) -> String {
+        let name = |id| dataset_name(datasets, id);
+        match self {
+            Self::Reshape { source, .. } => format!("{} (reshaped)", name(source)),
+            Self::Join(join) => format!("{} + {}", name(&join.left), name(&join.right)),
+        }
+    }
+}
+
+fn dataset_name(datasets: &[Dataset], id: &DatasetId) -> String {
+    find(datasets, id).map(|dataset| dataset.name.clone()).unwrap_or_else(|_| id.clone())
+}
+
+fn find<'a>(datasets: &'a [Dataset], id: &DatasetId) -> Result<&'a Dataset, DeriveError> {
+    datasets
+        .iter()
+        .find(|dataset| &dataset.id == id)
+        .ok_or_else(|| DeriveError::UnknownDataset(id.clone()))
+}
+
+/// Whether a dataset is computed directly from the dataset `id`
+fn derives_from(dataset: &Dataset, id: &str) -> bool {
+    dataset.derived_from.as_ref().is_some_and(|derived| derived.sources().iter().any(|source| *source == id))
+}
+
+/// Ids of the datasets derived, directly or not, from the dataset `source_id`
+pub fn derived_ids(datasets: &[Dataset], source_id: &str) -> Vec<DatasetId> {
+    let mut ids: Vec<DatasetId> = Vec::new();
+    let mut changed = vec![source_id.to_string()];
+    while let Some(changed_id) = changed.pop() {
+        for dataset in datasets {
+            if derives_from(dataset, &changed_id) && !ids.contains(&dataset.id) {
+                ids.push(dataset.id.clone());
+                changed.push(dataset.id.clone());
+            }
+        }
+    }
+    ids
+}
+
+/// Recompute the datasets derived (directly or not) from a changed one
+///
+/// Each is recomputed once, after those of its sources that are also
+/// stale, so a dataset reached along several paths (e.g. a join of two
+/// datasets derived from the changed one) sees all of them up to date.
+/// Derived datasets that can no longer be computed (e.g. a key field was
+/// removed from a source) keep their last rows, with a warning.
+pub fn refresh_derived(datasets: &mut [Dataset], source_id: &str) {
+    refresh_renamed(datasets, source_id, &SchemaDiff::default());
+}
+
+/// Recompute the datasets derived from one whose fields were renamed as
+/// `diff` tells (e.g. by a new import of its file)
+///
+/// How each is computed follows the renames of its sources, and its
+/// calculated fields the renames that reach it (see
+/// `SchemaDiff::renames_in`): a field of the same old name coming from
+/// another source is left alone.
+pub fn refresh_renamed(datasets: &mut [Dataset], source_id: &str, diff: &SchemaDiff) {
+    let mut stale = derived_ids(datasets, source_id);
+    let mut refreshed = vec![source_id.to_string()];
+
+    // Sources first: take a stale dataset none of whose sources is stale
+    while !stale.is_empty() {
+        let ready = stale.iter().position(|id| {
+            let dataset = datasets.iter().find(|dataset| &dataset.id == id);
+            !dataset.is_some_and(|dataset| stale.iter().any(|stale_id| derives_from(dataset, stale_id)))
+        });
+        let Some(ready) = ready else {
+            log::warn!("Derived datasets depend on each other, not refreshing: {:?}", stale);
+            return;
+        };
+        let id = stale.remove(ready);
+        let Some(idx) = datasets.iter().position(|dataset| dataset.id == id) else {
+            continue;
+        };
+        let mut dataset = datasets[idx].clone();
+        if let Some(derived_from) = &mut dataset.derived_from {
+            let sources: Vec<DatasetId> = derived_from.sources().into_iter().cloned().collect();
+            for source in sources.iter().filter(|source| refreshed.contains(source)) {
+                if let Some(fields) = datasets.iter().find(|dataset| &dataset.id == source).map(|d| &d.fields) {
+                    diff.renames_in(fields).repair_derived(derived_from, source);
+                }
+            }
+        }
+        match dataset.recompute(datasets, diff) {
+            Ok(()) => datasets[idx] = dataset,
+            Err(e) => log::warn!("Keeping the last rows of '{}': {}", dataset.name, e),
+        }
+        refreshed.push(id);
+    }
+}
+
+/// Recompute every derived dataset, sources first (e.g. after importing a
+/// template without rows)
+pub fn refresh_all_derived(datasets: &mut [Dataset]) {
+    let sources: Vec<DatasetId> = datasets
+        .iter()
+        .filter(|dataset| dataset.derived_from.is_none())
+        .map(|dataset| dataset.id.clone())
+        .collect();
+    for source_id in sources {
+        refresh_derived(datasets, &source_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::dashboard::data::join::{JoinKey, JoinKind};
+    use crate::features::dashboard::data::AggregationFunction;
+    use crate::features::dashboard::models::FieldType;
+    use serde_json::{json, Value};
+
+    fn dataset(id: &str, fields: &[(&str, FieldType)], rows: Vec<Vec<Value>>) -> Dataset {
+        let fields: Vec<Field> = fields
+            .iter()
+            .map(|(name, field_type)| Field { name: name.to_string(), field_type: *field_type, format: None })
+            .collect();
+        Dataset {
+            id: id.to_string(),
+            name: format!("{}.csv", id),
+            size: "0 MB".to_string(),
+            uploaded_at: "Today".to_string(),
+            data: DataTable::from_rows(&fields, rows),
+            fields,
+            active: true,
+            dialect: None,
+            calculated_fields: Vec::new(),
+            derived_from: None,
+        }
+    }
+
+    #[test]
+    fn test_derived_datasets_follow_their_sources() {
+        let sales = dataset(
+            "sales",
+            &[("Product", FieldType::Text), ("Jan", FieldType::Numeric)],
+            vec![vec![json!("Tea"), json!(3)]],
+        );
+        let targets = dataset(
+            "targets",
+            &[("Product", FieldType::Text), ("Target", FieldType::Numeric)],
+            vec![vec![json!("Tea"), json!(5)], vec![json!("Cake"), json!(6)]],
+        );
+        let mut datasets = vec![sales, targets];
+
+        let unpivot = DerivedFrom::Reshape {
+            source: "sales".to_string(),
+            reshape: Reshape::Unpivot {
+                columns: vec!["Jan".to_string()],
+                name_field: "Month".to_string(),
+                value_field: "Sales".to_string(),
+            },
+        };
+        let long = Dataset::derived(unpivot, "long".to_string(), "Long", &datasets).unwrap();
+        assert_eq!(long.uploaded_at, "from sales.csv");
+        datasets.push(long);
+
+        let pivot = DerivedFrom::Reshape {
+            source: "long".to_string(),
+            reshape: Reshape::Pivot {
+                row_fields: vec!["Product".to_string()],
+                column_field: "Month".to_string(),
+                value_field: "Sales".to_string(),
+                aggregation: AggregationFunction::Sum,
+            },
+        };
+        let join = DerivedFrom::Join(Join {
+            left: "targets".to_string(),
+            right: "wide".to_string(),
+            kind: JoinKind::Left,
+            keys: vec![JoinKey { left: "Product".to_string(), right: "Product".to_string() }],
+        });
+        let wide = Dataset::derived(pivot, "wide".to_string(), "Wide", &datasets).unwrap();
+        datasets.push(wide);
+        let joined = Dataset::derived(join, "joined".to_string(), "Joined", &datasets).unwrap();
+        assert_eq!(joined.uploaded_at, "Left join of targets.csv and Wide");
+        datasets.push(joined);
+
+        // A new sales row reaches the join, through the two reshapes
+        let fields = datasets[0].fields.clone();
+        let data = DataTable::from_rows(&fields, vec![vec![json!("Tea"), json!(3)], vec![json!("Cake"), json!(4)]]);
+        datasets[0].set_source_data(fields, data);
+        refresh_derived(&mut datasets, "sales");
+        assert_eq!(datasets[2].data.len(), 2);
+        assert_eq!(datasets[4].data.row(1), vec![json!("Cake"), json!(6.0), json!(4.0)]);
+
+        // Without the unpivoted column, derived datasets keep their rows
+        let fields = vec![datasets[0].fields[0].clone()];
+        datasets[0].set_source_data(fields.clone(), DataTable::from_rows(&fields, Vec::new()));
+        refresh_all_derived(&mut datasets);
+        assert_eq!(datasets[4].data.len(), 2);
+    }
+
+    #[test]
+    fn test_refresh_reaches_diamonds_in_order() {
+        // sales → long → wide, sales → units, joined = wide ⋈ units
+        let mut datasets = vec![dataset(
+            "sales",
+            &[("Product", FieldType::Text), ("Jan", FieldType::Numeric)],
+            vec![vec![json!("Tea"), json!(3)]],
+        )];
+        let unpivot = |source: &str, value_field: &str| DerivedFrom::Reshape {
+            source: source.to_string(),
+            reshape: Reshape::Unpivot {
+                columns: vec!["Jan".to_string()],
+                name_field: "Month".to_string(),
+                value_field: value_field.to_string(),
+            },
+        };
+        let pivot = DerivedFrom::Reshape {
+            source: "long".to_string(),
+            reshape: Reshape::Pivot {
+                row_fields: vec!["Product".to_string()],
+                column_field: "Month".to_string(),
+                value_field: "Sales".to_string(),
+                aggregation: AggregationFunction::Sum,
+            },
+        };
+        let join = DerivedFrom::Join(Join {
+            left: "wide".to_string(),
+            right: "units".to_string(),
+            kind: JoinKind::Inner,
+            keys: vec![JoinKey { left: "Product".to_string(), right: "Product".to_string() }],
+        });
+        // Listed so that the join is reached through "units" before "wide"
+        let chain = [(unpivot("sales", "Sales"), "long"), (unpivot("sales", "Units"), "units"), (pivot, "wide")];
+        for (derived, id) in chain {
+            let dataset = Dataset::derived(derived, id.to_string(), id, &datasets).unwrap();
+            datasets.push(dataset);
+        }
+        let joined = Dataset::derived(join, "joined".to_string(), "Joined", &datasets).unwrap();
+        datasets.push(joined);
+        assert_eq!(datasets[4].data.len(), 1);
+
+        let fields = datasets[0].fields.clone();
+        let data = DataTable::from_rows(&fields, vec![vec![json!("Tea"), json!(3)], vec![json!("Cake"), json!(4)]]);
+        datasets[0].set_source_data(fields, data);
+        refresh_derived(&mut datasets, "sales");
+        assert_eq!(datasets[3].data.len(), 2);
+        assert_eq!(datasets[4].data.len(), 2);
+    }
+
+    #[test]
+    fn test_refresh_follows_renames_that_reach_each_dataset() {
+        let price = [("Product", FieldType::Text), ("Unit Price", FieldType::Numeric)];
+        let mut datasets = vec![
+            dataset("sales", &price, vec![vec![json!("Tea"), json!(3)]]),
+            dataset("targets", &price, vec![vec![json!("Tea"), json!(5)]]),
+        ];
+        let unpivot = DerivedFrom::Reshape {
+            source: "sales".to_string(),
+            reshape: Reshape::Unpivot {
+                columns: vec!["Unit Price".to_string()],
+                name_field: "Measure".to_string(),
+                value_field: "Value".to_string(),
+            },
+        };
+        let join = DerivedFrom::Join(Join {
+            left: "targets".to_string(),
+            right: "sales".to_string(),
+            kind: JoinKind::Left,
+            keys: vec![JoinKey { left: "Product".to_string(), right: "Product".to_string() }],
+        });
+        let long = Dataset::derived(unpivot, "long".to_string(), "Long", &datasets).unwrap();
+        let mut joined = Dataset::derived(join, "joined".to_string(), "Joined", &datasets).unwrap();
+        joined.add_calculated_field("Target x2", "[Unit Price] * 2").unwrap();
+        datasets.extend([long, joined]);
+
+        let old_fields = datasets[0].fields.clone();
+        let fields = vec![old_fields[0].clone(), Field { name: "unit_price".to_string(), ..old_fields[1].clone() }];
+        let diff = SchemaDiff::between(&old_fields, &fields);
+        datasets[0].set_source_data(fields.clone(), DataTable::from_rows(&fields, vec![vec![json!("Tea"), json!(4)]]));
+        refresh_renamed(&mut datasets, "sales", &diff);
+
+        // The unpivot follows the rename
+        let Some(DerivedFrom::Reshape { reshape: Reshape::Unpivot { columns, .. }, .. }) = &datasets[2].derived_from
+        else {
+            unreachable!()
+        };
+        assert_eq!(columns, &vec!["unit_price".to_string()]);
+        assert_eq!(datasets[2].data.row(0), vec![json!("Tea"), json!("unit_price"), json!(4.0)]);
+
+        // "Unit Price" of the join is still the target's: the expression is left alone
+        assert_eq!(datasets[3].calculated_fields[0].expression, "[Unit Price] * 2");
+        assert_eq!(datasets[3].data.value(0, 3), json!(10.0));
+    }
+}
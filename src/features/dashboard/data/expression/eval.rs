@@ -0,0 +1,601 @@
+//! Type checking and evaluation of parsed expressions
+
+use super::parser::{BinaryOp, Expr, ExprKind, UnaryOp};
+use super::ExpressionError;
+use crate::features::dashboard::data::{Column, DataTable};
+use crate::features::dashboard::models::{Field, FieldType};
+use chrono::{DateTime, Datelike, NaiveDate};
+use serde_json::Value;
+
+const DAY_MILLIS: i64 = 86_400_000;
+
+/// Static type of an expression
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Type {
+    /// The `NULL` literal, compatible with every type
+    Null,
+    Number,
+    Text,
+    Bool,
+    Date,
+}
+
+impl Type {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Null => "Null",
+            Self::Number => "Number",
+            Self::Text => "Text",
+            Self::Bool => "Boolean",
+            Self::Date => "Date",
+        }
+    }
+
+    /// Field type of a column holding values of this type
+    pub fn field_type(&self) -> FieldType {
+        match self {
+            Self::Number => FieldType::Numeric,
+            Self::Text | Self::Null => FieldType::Text,
+            Self::Bool => FieldType::Boolean,
+            Self::Date => FieldType::Date,
+        }
+    }
+
+    fn of_field(field_type: FieldType) -> Self {
+        match field_type {
+            FieldType::Numeric => Self::Number,
+            FieldType::Text => Self::Text,
+            FieldType::Boolean => Self::Bool,
+            FieldType::Date => Self::Date,
+        }
+    }
+}
+
+/// Value of an expression for one row (dates are milliseconds since the epoch)
+#[derive(Clone, Debug, PartialEq)]
+pub enum Scalar {
+    Null,
+    Number(f64),
+    Text(String),
+    Bool(bool),
+    Date(i64),
+}
+
+impl Scalar {
+    fn from_json(value: Value) -> Self {
+        match value {
+            Value::Null => Self::Null,
+            Value::Bool(b) => Self::Bool(b),
+            Value::Number(n) => n.as_f64().map_or(Self::Null, Self::Number),
+            Value::String(s) => Self::Text(s),
+            other => Self::Text(other.to_string()),
+        }
+    }
+
+    /// Cell value as stored in a column (dates as ISO text)
+    ///
+    /// # Arguments
+    /// * `date_only` - Write dates without their (midnight) time
+    pub fn into_json(self, date_only: bool) -> Value {
+        match self {
+            Self::Null => Value::Null,
+            Self::Number(n) => serde_json::Number::from_f64(n).map_or(Value::Null, Value::Number),
+            Self::Text(s) => Value::String(s),
+            Self::Bool(b) => Value::Bool(b),
+            Self::Date(millis) => match DateTime::from_timestamp_millis(millis) {
+                Some(date) if date_only => Value::String(date.format("%Y-%m-%d").to_string()),
+                Some(date) => Value::String(date.format("%Y-%m-%dT%H:%M:%S").to_string()),
+                None => Value::Null,
+            },
+        }
+    }
+
+    fn number(&self) -> Option<f64> {
+        match self {
+            Self::Number(n) if n.is_finite() => Some(*n),
+            _ => None,
+        }
+    }
+
+    fn date(&self) -> Option<NaiveDate> {
+        match self {
+            Self::Date(millis) => DateTime::from_timestamp_millis(*millis).map(|date| date.date_naive()),
+            _ => None,
+        }
+    }
+
+    /// Text form, used by concatenation and `TEXT()` (null is empty)
+    fn to_text(&self) -> String {
+        match self {
+            Self::Null => String::new(),
+            Self::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => format!("{}", *n as i64),
+            Self::Number(n) => n.to_string(),
+            Self::Text(s) => s.clone(),
+            Self::Bool(b) => b.to_string(),
+            Self::Date(_) => match self.clone().into_json(false) {
+                Value::String(s) => s.trim_end_matches("T00:00:00").to_string(),
+                _ => String::new(),
+            },
+        }
+    }
+}
+
+/// Built-in function
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Function {
+    If,
+    IsNull,
+    IfNull,
+    Coalesce,
+    Abs,
+    Round,
+    Floor,
+    Ceil,
+    Sqrt,
+    Power,
+    Min,
+    Max,
+    Concat,
+    Upper,
+    Lower,
+    Trim,
+    Len,
+    Left,
+    Right,
+    Substr,
+    Replace,
+    Contains,
+    Text,
+    Number,
+    Year,
+    Quarter,
+    Month,
+    Day,
+    Weekday,
+    Date,
+    DateDiff,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "IF" => Self::If,
+            "ISNULL" => Self::IsNull,
+            "IFNULL" => Self::IfNull,
+            "COALESCE" => Self::Coalesce,
+            "ABS" => Self::Abs,
+            "ROUND" => Self::Round,
+            "FLOOR" => Self::Floor,
+            "CEIL" | "CEILING" => Self::Ceil,
+            "SQRT" => Self::Sqrt,
+            "POWER" | "POW" => Self::Power,
+            "MIN" => Self::Min,
+            "MAX" => Self::Max,
+            "CONCAT" => Self::Concat,
+            "UPPER" => Self::Upper,
+            "LOWER" => Self::Lower,
+            "TRIM" => Self::Trim,
+            "LEN" | "LENGTH" => Self::Len,
+            "LEFT" => Self::Left,
+            "RIGHT" => Self::Right,
+            "SUBSTR" | "MID" => Self::Substr,
+            "REPLACE" => Self::Replace,
+            "CONTAINS" => Self::Contains,
+            "TEXT" => Self::Text,
+            "NUMBER" | "VALUE" => Self::Number,
+            "YEAR" => Self::Year,
+            "QUARTER" => Self::Quarter,
+            "MONTH" => Self::Month,
+            "DAY" => Self::Day,
+            "WEEKDAY" => Self::Weekday,
+            "DATE" => Self::Date,
+            "DATEDIFF" => Self::DateDiff,
+            _ => return None,
+        })
+    }
+
+    /// Minimum and maximum number of arguments (`None`: any number)
+    fn arity(&self) -> (usize, Option<usize>) {
+        match self {
+            Self::If => (2, Some(3)),
+            Self::Round => (1, Some(2)),
+            Self::Substr => (2, Some(3)),
+            Self::Coalesce | Self::Min | Self::Max | Self::Concat => (1, None),
+            Self::IfNull | Self::Power | Self::Left | Self::Right | Self::Contains | Self::DateDiff => (2, Some(2)),
+            Self::Replace | Self::Date => (3, Some(3)),
+            _ => (1, Some(1)),
+        }
+    }
+}
+
+/// Type-checked expression, with fields resolved to columns
+#[derive(Clone, Debug, PartialEq)]
+pub enum Node {
+    Const(Scalar),
+    Column { index: usize, field_type: FieldType },
+    Unary(UnaryOp, Box<Node>),
+    Binary(BinaryOp, Box<Node>, Box<Node>),
+    Call(Function, Vec<Node>),
+}
+
+/// Resolve the fields of an expression and infer its type
+pub fn check(expr: &Expr, fields: &[Field]) -> Result<(Node, Type), ExpressionError> {
+    let position = expr.position;
+    match &expr.kind {
+        ExprKind::Number(n) => Ok((Node::Const(Scalar::Number(*n)), Type::Number)),
+        ExprKind::Text(s) => Ok((Node::Const(Scalar::Text(s.clone())), Type::Text)),
+        ExprKind::Bool(b) => Ok((Node::Const(Scalar::Bool(*b)), Type::Bool)),
+        ExprKind::Null => Ok((Node::Const(Scalar::Null), Type::Null)),
+        ExprKind::Field(name) => {
+            let index = fields
+                .iter()
+                .position(|field| &field.name == name)
+                .ok_or_else(|| ExpressionError::new(format!("unknown field '{}'", name), position))?;
+            let field_type = fields[index].field_type;
+            Ok((Node::Column { index, field_type }, Type::of_field(field_type)))
+        }
+        ExprKind::Unary(op, operand) => {
+            let (node, ty) = check(operand, fields)?;
+            let expected = match op {
+                UnaryOp::Neg => Type::Number,
+                UnaryOp::Not => Type::Bool,
+            };
+            expect(ty, expected, operand.position)?;
+            Ok((Node::Unary(*op, Box::new(node)), expected))
+        }
+        ExprKind::Binary(op, left, right) => {
+            let (left_node, left_ty) = check(left, fields)?;
+            let (right_node, right_ty) = check(right, fields)?;
+            let ty = binary_type(*op, (left_ty, left.position), (right_ty, right.position))?;
+            Ok((Node::Binary(*op, Box::new(left_node), Box::new(right_node)), ty))
+        }
+        ExprKind::Call(name, args) => {
+            let function = Function::from_name(name)
+                .ok_or_else(|| ExpressionError::new(format!("unknown function '{}'", name), position))?;
+            let (min, max) = function.arity();
+            if args.len() < min || max.is_some_and(|max| args.len() > max) {
+                let expected = match max {
+                    Some(max) if max == min => format!("{}", min),
+                    Some(max) => format!("{} to {}", min, max),
+                    None => format!("at least {}", min),
+                };
+                return Err(ExpressionError::new(
+                    format!("{} takes {} arguments, got {}", name, expected, args.len()),
+                    position,
+                ));
+            }
+
+            let mut nodes = Vec::with_capacity(args.len());
+            let mut types = Vec::with_capacity(args.len());
+            for arg in args {
+                let (node, ty) = check(arg, fields)?;
+                nodes.push(node);
+                types.push((ty, arg.position));
+            }
+            let ty = call_type(function, &types)?;
+            Ok((Node::Call(function, nodes), ty))
+        }
+    }
+}
+
+/// Check that a value has the expected type (null fits everywhere)
+fn expect(ty: Type, expected: Type, position: usize) -> Result<(), ExpressionError> {
+    if ty == expected || ty == Type::Null {
+        Ok(())
+    } else {
+        Err(ExpressionError::new(format!("expected {}, found {}", expected.name(), ty.name()), position))
+    }
+}
+
+/// Common type of values that may be returned in place of each other
+fn unify(types: &[(Type, usize)]) -> Result<Type, ExpressionError> {
+    let mut unified = Type::Null;
+    for &(ty, position) in types {
+        if unified == Type::Null {
+            unified = ty;
+        } else {
+            expect(ty, unified, position)?;
+        }
+    }
+    Ok(unified)
+}
+
+fn binary_type(op: BinaryOp, left: (Type, usize), right: (Type, usize)) -> Result<Type, ExpressionError> {
+    use Type::*;
+    let mismatch = || {
+        ExpressionError::new(
+            format!("cannot apply '{}' to {} and {}", op.symbol(), left.0.name(), right.0.name()),
+            right.1,
+        )
+    };
+    match op {
+        BinaryOp::Concat => Ok(Text),
+        BinaryOp::And | BinaryOp::Or => {
+            expect(left.0, Bool, left.1)?;
+            expect(right.0, Bool, right.1)?;
+            Ok(Bool)
+        }
+        BinaryOp::Eq | BinaryOp::Ne | BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => {
+            unify(&[left, right]).map_err(|_| mismatch())?;
+            Ok(Bool)
+        }
+        BinaryOp::Add => match (left.0, right.0) {
+            (Text, _) | (_, Text) => Ok(Text),
+            (Date, Number | Null) | (Number | Null, Date) => Ok(Date),
+            (Number | Null, Number | Null) => Ok(Number),
+            _ => Err(mismatch()),
+        },
+        BinaryOp::Sub => match (left.0, right.0) {
+            (Date, Date) => Ok(Number),
+            (Date, Number | Null) => Ok(Date),
+            (Number | Null, Number | Null) => Ok(Number),
+            _ => Err(mismatch()),
+        },
+        BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => match (left.0, right.0) {
+            (Number | Null, Number | Null) => Ok(Number),
+            _ => Err(mismatch()),
+        },
+    }
+}
+
+fn call_type(function: Function, args: &[(Type, usize)]) -> Result<Type, ExpressionError> {
+    use Type::*;
+    let expect_all = |expected: &[Type]| {
+        args.iter()
+            .zip(expected)
+            .try_for_each(|(&(ty, position), &expected)| expect(ty, expected, position))
+    };
+    match function {
+        Function::If => {
+            expect_all(&[Bool])?;
+            unify(&args[1..])
+        }
+        Function::IsNull => Ok(Bool),
+        Function::IfNull | Function::Coalesce => unify(args),
+        Function::Abs | Function::Round | Function::Floor | Function::Ceil | Function::Sqrt | Function::Power => {
+            expect_all(&[Number, Number])?;
+            Ok(Number)
+        }
+        Function::Min | Function::Max => unify(args),
+        Function::Concat | Function::Text => Ok(Text),
+        Function::Upper | Function::Lower | Function::Trim => {
+            expect_all(&[Text])?;
+            Ok(Text)
+        }
+        Function::Len => {
+            expect_all(&[Text])?;
+            Ok(Number)
+        }
+        Function::Left | Function::Right | Function::Substr => {
+            expect_all(&[Text, Number, Number])?;
+            Ok(Text)
+        }
+        Function::Replace => {
+            expect_all(&[Text, Text, Text])?;
+            Ok(Text)
+        }
+        Function::Contains => {
+            expect_all(&[Text, Text])?;
+            Ok(Bool)
+        }
+        Function::Number => Ok(Number),
+        Function::Year | Function::Quarter | Function::Month | Function::Day | Function::Weekday => {
+            expect_all(&[Date])?;
+            Ok(Number)
+        }
+        Function::Date => {
+            expect_all(&[Number, Number, Number])?;
+            Ok(Date)
+        }
+        Function::DateDiff => {
+            expect_all(&[Date, Date])?;
+            Ok(Number)
+        }
+    }
+}
+
+impl Node {
+    /// Evaluate for one row of a table
+    pub fn eval(&self, table: &DataTable, row: usize) -> Scalar {
+        match self {
+            Self::Const(value) => value.clone(),
+            Self::Column { index, field_type } => cell(table.column(*index), *field_type, row),
+            Self::Unary(op, operand) => match (op, operand.eval(table, row)) {
+                (UnaryOp::Neg, Scalar::Number(n)) => Scalar::Number(-n),
+                (UnaryOp::Not, Scalar::Bool(b)) => Scalar::Bool(!b),
+                _ => Scalar::Null,
+            },
+            Self::Binary(op @ (BinaryOp::And | BinaryOp::Or), left, right) => {
+                // Short-circuits; null only when the result depends on a null
+                let decisive = *op == BinaryOp::Or;
+                let left = truth(&left.eval(table, row));
+                if left == Some(decisive) {
+                    return Scalar::Bool(decisive);
+                }
+                match (left, truth(&right.eval(table, row))) {
+                    (_, Some(right)) if right == decisive => Scalar::Bool(decisive),
+                    (Some(_), Some(_)) => Scalar::Bool(!decisive),
+                    _ => Scalar::Null,
+                }
+            }
+            Self::Binary(op, left, right) => binary(*op, left.eval(table, row), right.eval(table, row)),
+            Self::Call(function, args) => call(*function, args, table, row),
+        }
+    }
+}
+
+/// Value of a cell, typed after its field
+fn cell(column: &Column, field_type: FieldType, row: usize) -> Scalar {
+    if column.is_null(row) {
+        return Scalar::Null;
+    }
+    match (field_type, column.timestamp(row)) {
+        (FieldType::Date, Some(millis)) => Scalar::Date(millis),
+        _ => Scalar::from_json(column.value(row)),
+    }
+}
+
+fn truth(value: &Scalar) -> Option<bool> {
+    match value {
+        Scalar::Bool(b) => Some(*b),
+        _ => None,
+    }
+}
+
+fn binary(op: BinaryOp, left: Scalar, right: Scalar) -> Scalar {
+    use Scalar::*;
+    if op == BinaryOp::Concat {
+        return Text(left.to_text() + &right.to_text());
+    }
+    if left == Null || right == Null {
+        return Null;
+    }
+
+    match op {
+        BinaryOp::Eq | BinaryOp::Ne | BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => {
+            let ordering = match (&left, &right) {
+                (Number(a), Number(b)) => a.partial_cmp(b),
+                (Text(a), Text(b)) => Some(a.cmp(b)),
+                (Bool(a), Bool(b)) => Some(a.cmp(b)),
+                (Date(a), Date(b)) => Some(a.cmp(b)),
+                _ => None,
+            };
+            let Some(ordering) = ordering else {
+                return Null;
+            };
+            Bool(match op {
+                BinaryOp::Eq => ordering.is_eq(),
+                BinaryOp::Ne => ordering.is_ne(),
+                BinaryOp::Lt => ordering.is_lt(),
+                BinaryOp::Le => ordering.is_le(),
+                BinaryOp::Gt => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            })
+        }
+        BinaryOp::Add => match (left, right) {
+            (Number(a), Number(b)) => Number(a + b),
+            (Date(date), Number(days)) | (Number(days), Date(date)) => Date(date + (days * DAY_MILLIS as f64) as i64),
+            (left, right) => Text(left.to_text() + &right.to_text()),
+        },
+        BinaryOp::Sub => match (left, right) {
+            (Number(a), Number(b)) => Number(a - b),
+            (Date(a), Date(b)) => Number((a - b) as f64 / DAY_MILLIS as f64),
+            (Date(date), Number(days)) => Date(date - (days * DAY_MILLIS as f64) as i64),
+            _ => Null,
+        },
+        BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => {
+            let (Number(a), Number(b)) = (left, right) else {
+                return Null;
+            };
+            match op {
+                BinaryOp::Mul => Number(a * b),
+                // Dividing by zero gives null rather than infinity
+                _ if b == 0.0 => Null,
+                BinaryOp::Div => Number(a / b),
+                _ => Number(a % b),
+            }
+        }
+        BinaryOp::Concat | BinaryOp::And | BinaryOp::Or => unreachable!("handled above"),
+    }
+}
+
+fn call(function: Function, args: &[Node], table: &DataTable, row: usize) -> Scalar {
+    // Branching functions only evaluate the arguments they need
+    match function {
+        Function::If => {
+            let branch = if truth(&args[0].eval(table, row)) == Some(true) { args.get(1) } else { args.get(2) };
+            return branch.map_or(Scalar::Null, |arg| arg.eval(table, row));
+        }
+        Function::IfNull | Function::Coalesce => {
+            return args
+                .iter()
+                .map(|arg| arg.eval(table, row))
+                .find(|value| *value != Scalar::Null)
+                .unwrap_or(Scalar::Null);
+        }
+        _ => {}
+    }
+
+    let values: Vec<Scalar> = args.iter().map(|arg| arg.eval(table, row)).collect();
+    match function {
+        Function::IsNull => return Scalar::Bool(values[0] == Scalar::Null),
+        Function::Concat => return Scalar::Text(values.iter().map(Scalar::to_text).collect()),
+        Function::Text if values[0] != Scalar::Null => return Scalar::Text(values[0].to_text()),
+        Function::Min | Function::Max => {
+            let present = values.into_iter().filter(|value| *value != Scalar::Null);
+            let pick = |a: Scalar, b: Scalar| {
+                let b_first = matches!(binary(BinaryOp::Lt, b.clone(), a.clone()), Scalar::Bool(true));
+                if b_first == (function == Function::Min) { b } else { a }
+            };
+            return present.reduce(pick).unwrap_or(Scalar::Null);
+        }
+        _ => {}
+    }
+
+    // Every other function is null when an argument is null
+    if values.contains(&Scalar::Null) {
+        return Scalar::Null;
+    }
+    let number = |i: usize| values[i].number().unwrap_or(f64::NAN);
+    let text = |i: usize| match &values[i] {
+        Scalar::Text(s) => s.as_str(),
+        _ => "",
+    };
+    let count = |i: usize| number(i).max(0.0) as usize;
+    let date_part = |part: fn(NaiveDate) -> u32| {
+        values[0].date().map_or(Scalar::Null, |date| Scalar::Number(part(date) as f64))
+    };
+
+    let result = match function {
+        Function::Abs => Scalar::Number(number(0).abs()),
+        Function::Round => {
+            let factor = 10f64.powi(values.get(1).map_or(0.0, |_| number(1)) as i32);
+            Scalar::Number((number(0) * factor).round() / factor)
+        }
+        Function::Floor => Scalar::Number(number(0).floor()),
+        Function::Ceil => Scalar::Number(number(0).ceil()),
+        Function::Sqrt => Scalar::Number(number(0).sqrt()),
+        Function::Power => Scalar::Number(number(0).powf(number(1))),
+        Function::Upper => Scalar::Text(text(0).to_uppercase()),
+        Function::Lower => Scalar::Text(text(0).to_lowercase()),
+        Function::Trim => Scalar::Text(text(0).trim().to_string()),
+        Function::Len => Scalar::Number(text(0).chars().count() as f64),
+        Function::Left => Scalar::Text(text(0).chars().take(count(1)).collect()),
+        Function::Right => {
+            let chars: Vec<char> = text(0).chars().collect();
+            Scalar::Text(chars[chars.len().saturating_sub(count(1))..].iter().collect())
+        }
+        Function::Substr => {
+            // 1-based start, like spreadsheets
+            let start = count(1).saturating_sub(1);
+            let len = values.get(2).map_or(usize::MAX, |_| count(2));
+            Scalar::Text(text(0).chars().skip(start).take(len).collect())
+        }
+        Function::Replace => Scalar::Text(text(0).replace(text(1), text(2))),
+        Function::Contains => Scalar::Bool(text(0).contains(text(1))),
+        Function::Number => match &values[0] {
+            Scalar::Number(n) => Scalar::Number(*n),
+            Scalar::Bool(b) => Scalar::Number(*b as u8 as f64),
+            Scalar::Text(s) => s.trim().parse().map_or(Scalar::Null, Scalar::Number),
+            _ => Scalar::Null,
+        },
+        Function::Year => values[0].date().map_or(Scalar::Null, |date| Scalar::Number(date.year() as f64)),
+        Function::Quarter => date_part(|date| date.month0() / 3 + 1),
+        Function::Month => date_part(|date| date.month()),
+        Function::Day => date_part(|date| date.day()),
+        Function::Weekday => date_part(|date| date.weekday().number_from_monday()),
+        Function::Date => NaiveDate::from_ymd_opt(number(0) as i32, number(1) as u32, number(2) as u32)
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .map_or(Scalar::Null, |date| Scalar::Date(date.and_utc().timestamp_millis())),
+        Function::DateDiff => match (&values[0], &values[1]) {
+            (Scalar::Date(from), Scalar::Date(to)) => Scalar::Number(((to - from) as f64 / DAY_MILLIS as f64).floor()),
+            _ => Scalar::Null,
+        },
+        _ => Scalar::Null,
+    };
+
+    match result {
+        Scalar::Number(n) if !n.is_finite() => Scalar::Null,
+        result => result,
+    }
+}
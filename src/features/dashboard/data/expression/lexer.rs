@@ -0,0 +1,198 @@
+//! Tokenizer of the expression language
+
+use super::ExpressionError;
+
+/// Kind of a token
+#[derive(Clone, Debug, PartialEq)]
+pub enum TokenKind {
+    Number(f64),
+    /// String literal, in double or single quotes
+    Text(String),
+    /// Bare name: a field, a function or a keyword
+    Ident(String),
+    /// Field name in square brackets, e.g. `[Order Date]`
+    Field(String),
+    LParen,
+    RParen,
+    Comma,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    /// `&`: string concatenation
+    Ampersand,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+    Not,
+    True,
+    False,
+    Null,
+}
+
+/// A token and the character position it starts at
+#[derive(Clone, Debug, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub position: usize,
+}
+
+/// Split an expression into tokens
+///
+/// Keywords (`AND`, `OR`, `NOT`, `TRUE`, `FALSE`, `NULL`) are
+/// case-insensitive; `&&`, `||` and `!` are accepted as well.
+pub fn tokenize(source: &str) -> Result<Vec<Token>, ExpressionError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let next = chars.get(i + 1).copied();
+        let (kind, len) = match (c, next) {
+            ('(', _) => (TokenKind::LParen, 1),
+            (')', _) => (TokenKind::RParen, 1),
+            (',', _) => (TokenKind::Comma, 1),
+            ('+', _) => (TokenKind::Plus, 1),
+            ('-', _) => (TokenKind::Minus, 1),
+            ('*', _) => (TokenKind::Star, 1),
+            ('/', _) => (TokenKind::Slash, 1),
+            ('%', _) => (TokenKind::Percent, 1),
+            ('&', Some('&')) => (TokenKind::And, 2),
+            ('&', _) => (TokenKind::Ampersand, 1),
+            ('|', Some('|')) => (TokenKind::Or, 2),
+            ('=', Some('=')) => (TokenKind::Eq, 2),
+            ('=', _) => (TokenKind::Eq, 1),
+            ('!', Some('=')) => (TokenKind::Ne, 2),
+            ('!', _) => (TokenKind::Not, 1),
+            ('<', Some('>')) => (TokenKind::Ne, 2),
+            ('<', Some('=')) => (TokenKind::Le, 2),
+            ('<', _) => (TokenKind::Lt, 1),
+            ('>', Some('=')) => (TokenKind::Ge, 2),
+            ('>', _) => (TokenKind::Gt, 1),
+            ('"' | '\'', _) => {
+                let (text, len) = read_string(&chars, i)?;
+                (TokenKind::Text(text), len)
+            }
+            ('[', _) => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|&c| c == ']')
+                    .ok_or_else(|| ExpressionError::new("unclosed field name, expected ']'", start))?;
+                let name: String = chars[i + 1..i + 1 + end].iter().collect();
+                (TokenKind::Field(name), end + 2)
+            }
+            (c, _) if c.is_ascii_digit() || (c == '.' && next.is_some_and(|n| n.is_ascii_digit())) => {
+                let len = chars[i..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_digit() || **c == '.')
+                    .count();
+                let text: String = chars[i..i + len].iter().collect();
+                let number = text
+                    .parse()
+                    .map_err(|_| ExpressionError::new(format!("invalid number '{}'", text), start))?;
+                (TokenKind::Number(number), len)
+            }
+            (c, _) if c.is_alphabetic() || c == '_' => {
+                let len = chars[i..]
+                    .iter()
+                    .take_while(|c| c.is_alphanumeric() || **c == '_')
+                    .count();
+                let word: String = chars[i..i + len].iter().collect();
+                (keyword(&word).unwrap_or(TokenKind::Ident(word)), len)
+            }
+            (c, _) => return Err(ExpressionError::new(format!("unexpected character '{}'", c), start)),
+        };
+
+        tokens.push(Token { kind, position: start });
+        i += len;
+    }
+
+    Ok(tokens)
+}
+
+pub(super) fn keyword(word: &str) -> Option<TokenKind> {
+    match word.to_ascii_uppercase().as_str() {
+        "AND" => Some(TokenKind::And),
+        "OR" => Some(TokenKind::Or),
+        "NOT" => Some(TokenKind::Not),
+        "TRUE" => Some(TokenKind::True),
+        "FALSE" => Some(TokenKind::False),
+        "NULL" => Some(TokenKind::Null),
+        _ => None,
+    }
+}
+
+/// Read a quoted string starting at `start`; a doubled quote is a literal quote
+///
+/// # Returns
+/// The string and the number of characters read (quotes included)
+fn read_string(chars: &[char], start: usize) -> Result<(String, usize), ExpressionError> {
+    let quote = chars[start];
+    let mut text = String::new();
+    let mut i = start + 1;
+    while i < chars.len() {
+        if chars[i] == quote {
+            if chars.get(i + 1) == Some(&quote) {
+                text.push(quote);
+                i += 2;
+                continue;
+            }
+            return Ok((text, i + 1 - start));
+        }
+        text.push(chars[i]);
+        i += 1;
+    }
+    Err(ExpressionError::new("unclosed string literal", start))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<TokenKind> {
+        tokenize(source).unwrap().into_iter().map(|token| token.kind).collect()
+    }
+
+    #[test]
+    fn test_tokens_and_positions() {
+        let tokens = tokenize("[Unit Price] * 1.5 >= 10").unwrap();
+        assert_eq!(tokens[0].kind, TokenKind::Field("Unit Price".to_string()));
+        assert_eq!(tokens[1], Token { kind: TokenKind::Star, position: 13 });
+        assert_eq!(tokens[2].kind, TokenKind::Number(1.5));
+        assert_eq!(tokens[3].kind, TokenKind::Ge);
+
+        assert_eq!(
+            kinds("Region = \"it's\" and not x <> 'a''b'"),
+            vec![
+                TokenKind::Ident("Region".to_string()),
+                TokenKind::Eq,
+                TokenKind::Text("it's".to_string()),
+                TokenKind::And,
+                TokenKind::Not,
+                TokenKind::Ident("x".to_string()),
+                TokenKind::Ne,
+                TokenKind::Text("a'b".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_errors() {
+        assert_eq!(tokenize("a + \"open").unwrap_err().position, 4);
+        assert_eq!(tokenize("[Open").unwrap_err().position, 0);
+        assert_eq!(tokenize("a # b").unwrap_err().position, 2);
+    }
+}
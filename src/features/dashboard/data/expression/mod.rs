@@ -0,0 +1,225 @@
+//! Expression language of calculated fields
+//!
+//! Expressions combine the fields of a dataset row by row, e.g.
+//! `Revenue - Cost`, `IF(Region = "EU", Sales * 1.1, Sales)`,
+//! `YEAR([Order Date])` or `City & ", " & Country`:
+//! - fields by name, in square brackets when the name has spaces;
+//! - numbers, `"text"` (or `'text'`), `TRUE`, `FALSE`, `NULL`;
+//! - `+ - * / %`, `&` (concatenation), `= <> < <= > >=`, `AND OR NOT`;
+//! - functions (case-insensitive):
+//!   - conditional: `IF`, `ISNULL`, `IFNULL`, `COALESCE`;
+//!   - math: `ABS`, `ROUND`, `FLOOR`, `CEIL`, `SQRT`, `POWER`, `MIN`, `MAX`;
+//!   - text: `CONCAT`, `UPPER`, `LOWER`, `TRIM`, `LEN`, `LEFT`, `RIGHT`,
+//!     `SUBSTR`, `REPLACE`, `CONTAINS`, `TEXT`, `NUMBER`;
+//!   - dates: `YEAR`, `QUARTER`, `MONTH`, `DAY`, `WEEKDAY`, `DATE`, `DATEDIFF`
+//!     (dates plus or minus a number shift by days).
+//!
+//! Nulls propagate: an operation on a null gives null (dividing by zero as
+//! well), except for the null-handling functions, `&` and `CONCAT` (null
+//! is empty text), and `AND`/`OR` when the other side decides the result.
+
+mod eval;
+mod lexer;
+mod parser;
+
+use super::table::Column;
+use super::DataTable;
+use crate::features::dashboard::models::{Field, FieldType};
+use eval::{check, Node, Scalar};
+use lexer::{tokenize, TokenKind};
+use std::fmt;
+
+/// Error in an expression, at a character position of its source
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExpressionError {
+    pub message: String,
+    /// Position of the offending character (0-based)
+    pub position: usize,
+}
+
+impl ExpressionError {
+    pub fn new(message: impl Into<String>, position: usize) -> Self {
+        Self { message: message.into(), position }
+    }
+}
+
+impl fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (at character {})", self.message, self.position + 1)
+    }
+}
+
+/// An expression checked against the fields of a dataset
+#[derive(Clone, Debug, PartialEq)]
+pub struct Expression {
+    node: Node,
+    field_type: FieldType,
+}
+
+impl Expression {
+    /// Parse an expression and check it against the fields it reads
+    ///
+    /// Fails on syntax errors, unknown fields or functions, wrong argument
+    /// counts and type mismatches (e.g. `Region * 2` on a text field).
+    pub fn compile(source: &str, fields: &[Field]) -> Result<Self, ExpressionError> {
+        let expr = parser::parse(source)?;
+        let (node, ty) = check(&expr, fields)?;
+        Ok(Self { node, field_type: ty.field_type() })
+    }
+
+    /// Type of the values the expression produces
+    pub fn field_type(&self) -> FieldType {
+        self.field_type
+    }
+
+    /// Evaluate the expression for every row of a table
+    ///
+    /// The table must have the fields the expression was compiled against.
+    pub fn evaluate(&self, table: &DataTable) -> Column {
+        let values: Vec<Scalar> = (0..table.len()).map(|row| self.node.eval(table, row)).collect();
+        // Dates at midnight are written as plain dates
+        let date_only = values.iter().all(|value| match value {
+            Scalar::Date(millis) => millis.rem_euclid(86_400_000) == 0,
+            _ => true,
+        });
+        Column::from_values(self.field_type, values.into_iter().map(|value| value.into_json(date_only)))
+    }
+}
+
+/// Rewrite the fields an expression reads, e.g. after they were renamed
+///
+/// `rename` gives the new name of a field (`None` to keep it). The rest of
+/// the source is kept as written; sources that don't tokenize are returned
+/// as they are.
+pub fn rename_fields<'a>(source: &str, rename: impl Fn(&str) -> Option<&'a str>) -> String {
+    let Ok(tokens) = tokenize(source) else {
+        return source.to_string();
+    };
+    let chars: Vec<char> = source.chars().collect();
+    let mut renamed = String::with_capacity(source.len());
+    let mut copied = 0;
+    for (idx, token) in tokens.iter().enumerate() {
+        let (name, len) = match &token.kind {
+            TokenKind::Field(name) => (name, name.chars().count() + 2),
+            // Names followed by a parenthesis are functions
+            TokenKind::Ident(name) if tokens.get(idx + 1).is_none_or(|next| next.kind != TokenKind::LParen) => {
+                (name, name.chars().count())
+            }
+            _ => continue,
+        };
+        let Some(new_name) = rename(name) else {
+            continue;
+        };
+        renamed.extend(&chars[copied..token.position]);
+        renamed.push_str(&field_reference(new_name));
+        copied = token.position + len;
+    }
+    renamed.extend(&chars[copied..]);
+    renamed
+}
+
+/// A field as written in an expression: bare when its name reads as one,
+/// in square brackets otherwise
+fn field_reference(name: &str) -> String {
+    let bare = name.chars().next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && lexer::keyword(name).is_none();
+    if bare { name.to_string() } else { format!("[{}]", name) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn field(name: &str, field_type: FieldType) -> Field {
+        Field { name: name.to_string(), field_type, format: None }
+    }
+
+    fn fields() -> Vec<Field> {
+        vec![
+            field("Region", FieldType::Text),
+            field("Revenue", FieldType::Numeric),
+            field("Cost", FieldType::Numeric),
+            field("Order Date", FieldType::Date),
+        ]
+    }
+
+    fn table() -> DataTable {
+        DataTable::from_rows(&fields(), vec![
+            vec![json!("EU"), json!(100), json!(60), json!("2024-03-15")],
+            vec![json!("US"), json!(200), json!(0), json!("2023-11-02")],
+            vec![Value::Null, Value::Null, json!(10), Value::Null],
+        ])
+    }
+
+    /// Values of an expression over the test table
+    fn values(source: &str) -> Vec<Value> {
+        let expression = Expression::compile(source, &fields()).unwrap();
+        let column = expression.evaluate(&table());
+        (0..column.len()).map(|row| column.value(row)).collect()
+    }
+
+    #[test]
+    fn test_arithmetic_and_conditionals() {
+        assert_eq!(values("Revenue - Cost"), vec![json!(40.0), json!(200.0), Value::Null]);
+        assert_eq!(values("Cost / Revenue"), vec![json!(0.6), json!(0.0), Value::Null]);
+        // Division by zero is null
+        assert_eq!(values("Revenue / Cost")[1], Value::Null);
+        assert_eq!(
+            values("IF(Region = \"EU\", Revenue * 1.1, Revenue)"),
+            vec![json!(110.00000000000001), json!(200.0), Value::Null]
+        );
+        assert_eq!(values("-Revenue + 2 * 3")[0], json!(-94.0));
+    }
+
+    #[test]
+    fn test_text_date_and_null_functions() {
+        assert_eq!(values("Region & \"-\" & UPPER(\"x\")"), vec![json!("EU-X"), json!("US-X"), json!("-X")]);
+        assert_eq!(values("YEAR([Order Date])"), vec![json!(2024.0), json!(2023.0), Value::Null]);
+        assert_eq!(values("QUARTER([Order Date])")[1], json!(4.0));
+        assert_eq!(values("[Order Date] + 20")[0], json!("2024-04-04"));
+        assert_eq!(values("IFNULL(Region, \"Unknown\")")[2], json!("Unknown"));
+        assert_eq!(values("ISNULL(Revenue) OR Revenue > 150"), vec![json!(false), json!(true), json!(true)]);
+        assert_eq!(values("LEFT(TEXT(Revenue), 2)")[1], json!("20"));
+    }
+
+    #[test]
+    fn test_type_inference() {
+        let type_of = |source| Expression::compile(source, &fields()).unwrap().field_type();
+        assert_eq!(type_of("Revenue * 2"), FieldType::Numeric);
+        assert_eq!(type_of("Region & Revenue"), FieldType::Text);
+        assert_eq!(type_of("Revenue > Cost"), FieldType::Boolean);
+        assert_eq!(type_of("DATE(2024, 1, 1)"), FieldType::Date);
+        assert_eq!(type_of("IF(Revenue > 0, NULL, Cost)"), FieldType::Numeric);
+    }
+
+    #[test]
+    fn test_errors_report_positions() {
+        let error = |source| Expression::compile(source, &fields()).unwrap_err();
+
+        assert_eq!(error("Revenue - Profit"), ExpressionError::new("unknown field 'Profit'", 10));
+        assert_eq!(error("Revenue * Region").position, 10);
+        assert_eq!(error("IF(Revenue, 1, 2)").position, 3);
+        assert_eq!(error("FOO(1)").message, "unknown function 'FOO'");
+        assert_eq!(error("ROUND()").message, "ROUND takes 1 to 2 arguments, got 0");
+        assert_eq!(error("Revenue +").to_string(), "unexpected end of expression (at character 10)");
+    }
+
+    #[test]
+    fn test_rename_fields() {
+        let rename = |source| {
+            rename_fields(source, |name| match name {
+                "Revenue" => Some("Net Revenue"),
+                "Order Date" => Some("OrderDate"),
+                "Max" => Some("Top"),
+                _ => None,
+            })
+        };
+        assert_eq!(rename("Revenue - Cost"), "[Net Revenue] - Cost");
+        assert_eq!(rename("YEAR([Order Date]) & \"Revenue\""), "YEAR(OrderDate) & \"Revenue\"");
+        assert_eq!(rename("MAX(Max, 1)"), "MAX(Top, 1)");
+        assert_eq!(rename("Revenue +"), "[Net Revenue] +");
+        assert_eq!(rename("[Unclosed"), "[Unclosed");
+    }
+}
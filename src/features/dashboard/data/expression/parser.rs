@@ -0,0 +1,270 @@
+//! Parser of the expression language
+//!
+//! Precedence, from loosest to tightest: `OR`, `AND`, `NOT`, comparisons,
+//! `+ - &`, `* / %`, unary minus.
+
+use super::lexer::{tokenize, Token, TokenKind};
+use super::ExpressionError;
+
+/// Binary operator
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Concat,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+impl BinaryOp {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::Rem => "%",
+            Self::Concat => "&",
+            Self::Eq => "=",
+            Self::Ne => "<>",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::And => "AND",
+            Self::Or => "OR",
+        }
+    }
+}
+
+/// Unary operator
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+}
+
+/// Node of a parsed expression
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExprKind {
+    Number(f64),
+    Text(String),
+    Bool(bool),
+    Null,
+    /// Reference to a field, by name
+    Field(String),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    /// Function call; the name is upper-cased
+    Call(String, Vec<Expr>),
+}
+
+/// Expression node and the character position it starts at
+#[derive(Clone, Debug, PartialEq)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub position: usize,
+}
+
+/// Parse an expression
+pub fn parse(source: &str) -> Result<Expr, ExpressionError> {
+    let mut parser = Parser { tokens: tokenize(source)?, index: 0, end: source.chars().count() };
+    let expr = parser.or()?;
+    match parser.peek() {
+        None => Ok(expr),
+        Some(token) => Err(ExpressionError::new("unexpected token", token.position)),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    index: usize,
+    /// Position reported for errors at the end of the input
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.index).cloned();
+        self.index += 1;
+        token
+    }
+
+    /// Consume the next token if it is `kind`
+    fn accept(&mut self, kind: &TokenKind) -> bool {
+        if self.peek().is_some_and(|token| &token.kind == kind) {
+            self.index += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind, what: &str) -> Result<(), ExpressionError> {
+        if self.accept(&kind) {
+            Ok(())
+        } else {
+            let position = self.peek().map_or(self.end, |token| token.position);
+            Err(ExpressionError::new(format!("expected {}", what), position))
+        }
+    }
+
+    /// Left-associative binary operators of one precedence level
+    fn binary(
+        &mut self,
+        operand: fn(&mut Self) -> Result<Expr, ExpressionError>,
+        operator: fn(&TokenKind) -> Option<BinaryOp>,
+    ) -> Result<Expr, ExpressionError> {
+        let mut left = operand(self)?;
+        while let Some(op) = self.peek().and_then(|token| operator(&token.kind)) {
+            self.index += 1;
+            let right = operand(self)?;
+            let position = left.position;
+            left = Expr { kind: ExprKind::Binary(op, Box::new(left), Box::new(right)), position };
+        }
+        Ok(left)
+    }
+
+    fn or(&mut self) -> Result<Expr, ExpressionError> {
+        self.binary(Self::and, |kind| (kind == &TokenKind::Or).then_some(BinaryOp::Or))
+    }
+
+    fn and(&mut self) -> Result<Expr, ExpressionError> {
+        self.binary(Self::not, |kind| (kind == &TokenKind::And).then_some(BinaryOp::And))
+    }
+
+    fn not(&mut self) -> Result<Expr, ExpressionError> {
+        if let Some(token) = self.peek()
+            && token.kind == TokenKind::Not
+        {
+            let position = token.position;
+            self.index += 1;
+            let operand = self.not()?;
+            return Ok(Expr { kind: ExprKind::Unary(UnaryOp::Not, Box::new(operand)), position });
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, ExpressionError> {
+        self.binary(Self::additive, |kind| match kind {
+            TokenKind::Eq => Some(BinaryOp::Eq),
+            TokenKind::Ne => Some(BinaryOp::Ne),
+            TokenKind::Lt => Some(BinaryOp::Lt),
+            TokenKind::Le => Some(BinaryOp::Le),
+            TokenKind::Gt => Some(BinaryOp::Gt),
+            TokenKind::Ge => Some(BinaryOp::Ge),
+            _ => None,
+        })
+    }
+
+    fn additive(&mut self) -> Result<Expr, ExpressionError> {
+        self.binary(Self::multiplicative, |kind| match kind {
+            TokenKind::Plus => Some(BinaryOp::Add),
+            TokenKind::Minus => Some(BinaryOp::Sub),
+            TokenKind::Ampersand => Some(BinaryOp::Concat),
+            _ => None,
+        })
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr, ExpressionError> {
+        self.binary(Self::unary, |kind| match kind {
+            TokenKind::Star => Some(BinaryOp::Mul),
+            TokenKind::Slash => Some(BinaryOp::Div),
+            TokenKind::Percent => Some(BinaryOp::Rem),
+            _ => None,
+        })
+    }
+
+    fn unary(&mut self) -> Result<Expr, ExpressionError> {
+        if let Some(token) = self.peek()
+            && token.kind == TokenKind::Minus
+        {
+            let position = token.position;
+            self.index += 1;
+            let operand = self.unary()?;
+            return Ok(Expr { kind: ExprKind::Unary(UnaryOp::Neg, Box::new(operand)), position });
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, ExpressionError> {
+        let Some(token) = self.next() else {
+            return Err(ExpressionError::new("unexpected end of expression", self.end));
+        };
+        let position = token.position;
+        let kind = match token.kind {
+            TokenKind::Number(number) => ExprKind::Number(number),
+            TokenKind::Text(text) => ExprKind::Text(text),
+            TokenKind::True => ExprKind::Bool(true),
+            TokenKind::False => ExprKind::Bool(false),
+            TokenKind::Null => ExprKind::Null,
+            TokenKind::Field(name) => ExprKind::Field(name),
+            TokenKind::Ident(name) if self.accept(&TokenKind::LParen) => {
+                ExprKind::Call(name.to_ascii_uppercase(), self.arguments()?)
+            }
+            TokenKind::Ident(name) => ExprKind::Field(name),
+            TokenKind::LParen => {
+                let inner = self.or()?;
+                self.expect(TokenKind::RParen, "')'")?;
+                return Ok(inner);
+            }
+            _ => return Err(ExpressionError::new("expected a value, field or function", position)),
+        };
+        Ok(Expr { kind, position })
+    }
+
+    /// Arguments of a call, after its opening parenthesis
+    fn arguments(&mut self) -> Result<Vec<Expr>, ExpressionError> {
+        let mut arguments = Vec::new();
+        if self.accept(&TokenKind::RParen) {
+            return Ok(arguments);
+        }
+        loop {
+            arguments.push(self.or()?);
+            if self.accept(&TokenKind::RParen) {
+                return Ok(arguments);
+            }
+            self.expect(TokenKind::Comma, "',' or ')'")?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_precedence() {
+        let expr = parse("a + b * 2 > 3 AND NOT c").unwrap();
+        let ExprKind::Binary(BinaryOp::And, left, right) = expr.kind else {
+            panic!("expected AND at the root: {:?}", expr);
+        };
+        let ExprKind::Binary(BinaryOp::Gt, sum, _) = left.kind else {
+            panic!("expected a comparison: {:?}", left);
+        };
+        assert!(matches!(sum.kind, ExprKind::Binary(BinaryOp::Add, _, _)));
+        assert!(matches!(right.kind, ExprKind::Unary(UnaryOp::Not, _)));
+        assert_eq!(right.position, 18);
+    }
+
+    #[test]
+    fn test_parse_errors_report_positions() {
+        assert_eq!(parse("IF(a, b").unwrap_err().position, 7);
+        assert_eq!(parse("a + * b").unwrap_err().position, 4);
+        assert_eq!(parse("(a + b) c").unwrap_err().position, 8);
+        assert_eq!(parse("").unwrap_err().position, 0);
+    }
+}
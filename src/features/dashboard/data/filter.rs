@@ -0,0 +1,476 @@
+//! Row filters of a widget
+//!
+//! A widget's filters form a tree of AND/OR groups whose leaves test one
+//! field each. They are evaluated against the dataset before anything else
+//! (chart transforms, KPI aggregation, table columns) sees its rows.
+
+use super::table::Column;
+use crate::features::dashboard::models::Dataset;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::cmp::Ordering;
+
+/// Test applied to the value of a field
+///
+/// Null cells only match `IsNull`: every other test, negated ones included,
+/// rejects them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FilterOperator {
+    #[default]
+    Equals,
+    NotEquals,
+    LessThan,
+    LessOrEqual,
+    GreaterThan,
+    GreaterOrEqual,
+    /// Equal to one of the values
+    In,
+    NotIn,
+    /// Contains the text, ignoring case
+    Contains,
+    NotContains,
+    /// Between two values, inclusive
+    Between,
+    IsNull,
+    IsNotNull,
+    /// Between two `YYYY-MM-DD` dates, inclusive; a null bound is open
+    DateRange,
+}
+
+/// Number of values an operator takes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FilterArity {
+    None,
+    One,
+    Two,
+    Many,
+}
+
+impl FilterOperator {
+    /// All operators, in the order shown in the filter editor
+    pub fn all() -> [Self; 14] {
+        [
+            Self::Equals,
+            Self::NotEquals,
+            Self::LessThan,
+            Self::LessOrEqual,
+            Self::GreaterThan,
+            Self::GreaterOrEqual,
+            Self::In,
+            Self::NotIn,
+            Self::Contains,
+            Self::NotContains,
+            Self::Between,
+            Self::IsNull,
+            Self::IsNotNull,
+            Self::DateRange,
+        ]
+    }
+
+    /// Get display name for UI
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Equals => "equals",
+            Self::NotEquals => "not equals",
+            Self::LessThan => "<",
+            Self::LessOrEqual => "<=",
+            Self::GreaterThan => ">",
+            Self::GreaterOrEqual => ">=",
+            Self::In => "in list",
+            Self::NotIn => "not in list",
+            Self::Contains => "contains",
+            Self::NotContains => "doesn't contain",
+            Self::Between => "between",
+            Self::IsNull => "is empty",
+            Self::IsNotNull => "is not empty",
+            Self::DateRange => "date range",
+        }
+    }
+
+    /// Number of values the operator compares against
+    pub fn arity(&self) -> FilterArity {
+        match self {
+            Self::IsNull | Self::IsNotNull => FilterArity::None,
+            Self::In | Self::NotIn => FilterArity::Many,
+            Self::Between | Self::DateRange => FilterArity::Two,
+            _ => FilterArity::One,
+        }
+    }
+}
+
+/// Condition on one field
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Filter {
+    pub field: String,
+    pub operator: FilterOperator,
+    /// Operands, as many as the operator's arity
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<Value>,
+}
+
+impl Filter {
+    /// Whether the cell at `row` passes the filter
+    fn matches(&self, column: &Column, row: usize) -> bool {
+        let cell = column.value(row);
+        if cell.is_null() {
+            return self.operator == FilterOperator::IsNull;
+        }
+        let operand = |idx: usize| self.values.get(idx).unwrap_or(&Value::Null);
+        let against = |idx: usize| compare(&cell, operand(idx));
+
+        match self.operator {
+            FilterOperator::Equals => against(0) == Some(Ordering::Equal),
+            FilterOperator::NotEquals => against(0) != Some(Ordering::Equal),
+            FilterOperator::LessThan => against(0) == Some(Ordering::Less),
+            FilterOperator::LessOrEqual => matches!(against(0), Some(Ordering::Less | Ordering::Equal)),
+            FilterOperator::GreaterThan => against(0) == Some(Ordering::Greater),
+            FilterOperator::GreaterOrEqual => matches!(against(0), Some(Ordering::Greater | Ordering::Equal)),
+            FilterOperator::In => self.values.iter().any(|value| compare(&cell, value) == Some(Ordering::Equal)),
+            FilterOperator::NotIn => self.values.iter().all(|value| compare(&cell, value) != Some(Ordering::Equal)),
+            FilterOperator::Contains => contains(&cell, operand(0)),
+            FilterOperator::NotContains => !contains(&cell, operand(0)),
+            FilterOperator::Between => {
+                matches!(against(0), Some(Ordering::Greater | Ordering::Equal))
+                    && matches!(against(1), Some(Ordering::Less | Ordering::Equal))
+            }
+            FilterOperator::IsNull => false,
+            FilterOperator::IsNotNull => true,
+            FilterOperator::DateRange => {
+                let Some(date) = cell.as_str() else {
+                    return false;
+                };
+                // Compare on the bound's length, so a `YYYY-MM-DD` bound
+                // includes every time of that day
+                let within = |bound: &Value, accepted: Ordering| match bound.as_str() {
+                    Some(bound) if !bound.is_empty() => {
+                        let prefix = date.get(..bound.len()).unwrap_or(date);
+                        let ordering = prefix.cmp(bound);
+                        ordering == Ordering::Equal || ordering == accepted
+                    }
+                    _ => true,
+                };
+                within(operand(0), Ordering::Greater) && within(operand(1), Ordering::Less)
+            }
+        }
+    }
+}
+
+/// Operand typed in the filter editor for `operator`
+///
+/// Text to search for is kept as typed; other operands are a number or
+/// boolean when they read as one.
+pub fn value_from_text(text: &str, operator: FilterOperator) -> Value {
+    if matches!(operator, FilterOperator::Contains | FilterOperator::NotContains) {
+        return Value::String(text.to_string());
+    }
+    let text = text.trim();
+    if let Ok(number) = text.parse::<i64>() {
+        return Value::from(number);
+    }
+    if let Ok(number) = text.parse::<f64>()
+        && let Some(number) = serde_json::Number::from_f64(number)
+    {
+        return Value::Number(number);
+    }
+    match text {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => Value::String(text.to_string()),
+    }
+}
+
+/// Order a cell against an operand (`None` when they can't be compared)
+///
+/// Operands typed as text are read as numbers or booleans against cells of
+/// those types; dates compare as their ISO text.
+fn compare(cell: &Value, operand: &Value) -> Option<Ordering> {
+    match (cell, operand) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+        (Value::Number(a), Value::String(b)) => a.as_f64()?.partial_cmp(&b.trim().parse::<f64>().ok()?),
+        (Value::String(a), Value::Number(b)) => a.trim().parse::<f64>().ok()?.partial_cmp(&b.as_f64()?),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        (Value::Bool(a), Value::String(b)) => Some(a.cmp(&b.trim().parse::<bool>().ok()?)),
+        _ => None,
+    }
+}
+
+/// Whether the text of a cell contains the operand, ignoring case
+///
+/// Numbers read as they are shown, whole ones without a trailing `.0`.
+fn contains(cell: &Value, operand: &Value) -> bool {
+    let text = |value: &Value| match value {
+        Value::String(text) => text.to_lowercase(),
+        Value::Number(number) => number.as_f64().map_or_else(|| number.to_string(), |number| number.to_string()),
+        other => other.to_string(),
+    };
+    text(cell).contains(&text(operand))
+}
+
+/// How the members of a group combine
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FilterCombinator {
+    /// Every member must match
+    #[default]
+    And,
+    /// At least one member must match
+    Or,
+}
+
+/// Member of a filter group
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum FilterNode {
+    Condition(Filter),
+    Group(FilterGroup),
+}
+
+/// Filters combined with AND or OR
+///
+/// Conditions on fields the dataset doesn't have are ignored, as are empty
+/// groups, so renaming a column never blanks a widget.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FilterGroup {
+    #[serde(default)]
+    pub combinator: FilterCombinator,
+    #[serde(default)]
+    pub filters: Vec<FilterNode>,
+}
+
+/// A filter tree with its fields resolved to columns
+enum Resolved<'a> {
+    Condition(&'a Filter, &'a Column),
+    Group(FilterCombinator, Vec<Resolved<'a>>),
+}
+
+impl Resolved<'_> {
+    fn matches(&self, row: usize) -> bool {
+        match self {
+            Self::Condition(filter, column) => filter.matches(column, row),
+            Self::Group(FilterCombinator::And, members) => members.iter().all(|member| member.matches(row)),
+            Self::Group(FilterCombinator::Or, members) => members.iter().any(|member| member.matches(row)),
+        }
+    }
+}
+
+impl FilterGroup {
+    /// Whether the group filters nothing
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// Fields the conditions are on, nested groups included
+    pub fn fields(&self) -> Vec<&str> {
+        self.filters
+            .iter()
+            .flat_map(|node| match node {
+                FilterNode::Condition(filter) => vec![filter.field.as_str()],
+                FilterNode::Group(group) => group.fields(),
+            })
+            .collect()
+    }
+
+    /// Point the conditions on a field to its new name
+    pub fn rename_field(&mut self, from: &str, to: &str) {
+        for node in &mut self.filters {
+            match node {
+                FilterNode::Condition(filter) if filter.field == from => filter.field = to.to_string(),
+                FilterNode::Condition(_) => {}
+                FilterNode::Group(group) => group.rename_field(from, to),
+            }
+        }
+    }
+
+    /// Resolve fields to columns, dropping what can't be evaluated
+    fn resolve<'a>(&'a self, dataset: &'a Dataset) -> Option<Resolved<'a>> {
+        let members: Vec<Resolved> = self
+            .filters
+            .iter()
+            .filter_map(|node| match node {
+                FilterNode::Condition(filter) => {
+                    let idx = dataset.fields.iter().position(|field| field.name == filter.field)?;
+                    Some(Resolved::Condition(filter, dataset.data.column(idx)))
+                }
+                FilterNode::Group(group) => group.resolve(dataset),
+            })
+            .collect();
+        (!members.is_empty()).then_some(Resolved::Group(self.combinator, members))
+    }
+
+    /// Indexes of the rows passing the filters
+    pub fn matching_rows(&self, dataset: &Dataset) -> Vec<usize> {
+        let rows = 0..dataset.data.len();
+        match self.resolve(dataset) {
+            Some(resolved) => rows.filter(|&row| resolved.matches(row)).collect(),
+            None => rows.collect(),
+        }
+    }
+
+    /// The dataset restricted to the rows passing the filters
+    ///
+    /// Borrowed when no row is filtered out.
+    pub fn apply<'a>(&self, dataset: &'a Dataset) -> Cow<'a, Dataset> {
+        if self.is_empty() {
+            return Cow::Borrowed(dataset);
+        }
+        let rows = self.matching_rows(dataset);
+        if rows.len() == dataset.data.len() {
+            return Cow::Borrowed(dataset);
+        }
+        let mut filtered = dataset.clone();
+        filtered.data = dataset.data.select_rows(&rows);
+        Cow::Owned(filtered)
+    }
+}
+
+/// Selections of the dashboard's filter controls, by control widget
+///
+/// Each control contributes one condition on its field. Together they
+/// filter every widget whose dataset has those fields, unless the widget
+/// opts out.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct GlobalFilters(BTreeMap<String, Filter>);
+
+impl GlobalFilters {
+    /// Whether no control filters anything
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Condition set by a control
+    pub fn get(&self, control_id: &str) -> Option<&Filter> {
+        self.0.get(control_id)
+    }
+
+    /// Set or clear the condition of a control
+    pub fn set(&mut self, control_id: &str, filter: Option<Filter>) {
+        match filter {
+            Some(filter) => self.0.insert(control_id.to_string(), filter),
+            None => self.0.remove(control_id),
+        };
+    }
+
+    /// Conditions of all controls, ANDed
+    pub fn group(&self) -> FilterGroup {
+        FilterGroup {
+            combinator: FilterCombinator::And,
+            filters: self.0.values().cloned().map(FilterNode::Condition).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::dashboard::data::DataTable;
+    use crate::features::dashboard::models::{Field, FieldType};
+    use serde_json::json;
+
+    fn dataset() -> Dataset {
+        let fields = vec![
+            Field { name: "Region".to_string(), field_type: FieldType::Text, format: None },
+            Field { name: "Sales".to_string(), field_type: FieldType::Numeric, format: None },
+            Field { name: "Day".to_string(), field_type: FieldType::Date, format: None },
+        ];
+        Dataset {
+            id: "sales".to_string(),
+            name: "sales.csv".to_string(),
+            size: "0 MB".to_string(),
+            uploaded_at: "Today".to_string(),
+            data: DataTable::from_rows(&fields, vec![
+                vec![json!("North"), json!(10), json!("2024-01-05")],
+                vec![json!("South"), json!(25), json!("2024-02-10")],
+                vec![json!("East"), Value::Null, json!("2024-03-15")],
+                vec![json!("North East"), json!(40), Value::Null],
+            ]),
+            fields,
+            active: true,
+            dialect: None,
+            calculated_fields: Vec::new(),
+            derived_from: None,
+        }
+    }
+
+    fn condition(field: &str, operator: FilterOperator, values: Vec<Value>) -> FilterNode {
+        FilterNode::Condition(Filter { field: field.to_string(), operator, values })
+    }
+
+    fn rows(filters: Vec<FilterNode>) -> Vec<usize> {
+        FilterGroup { combinator: FilterCombinator::And, filters }.matching_rows(&dataset())
+    }
+
+    #[test]
+    fn test_operators() {
+        use FilterOperator::*;
+        assert_eq!(rows(vec![condition("Region", Equals, vec![json!("North")])]), vec![0]);
+        assert_eq!(rows(vec![condition("Sales", GreaterThan, vec![json!("10")])]), vec![1, 3]);
+        assert_eq!(rows(vec![condition("Sales", NotEquals, vec![json!(10)])]), vec![1, 3]);
+        assert_eq!(rows(vec![condition("Region", In, vec![json!("East"), json!("South")])]), vec![1, 2]);
+        assert_eq!(rows(vec![condition("Region", Contains, vec![json!("north")])]), vec![0, 3]);
+        assert_eq!(rows(vec![condition("Sales", Between, vec![json!(10), json!(25)])]), vec![0, 1]);
+        assert_eq!(rows(vec![condition("Sales", IsNull, vec![])]), vec![2]);
+        assert_eq!(rows(vec![condition("Day", DateRange, vec![json!("2024-02-01"), Value::Null])]), vec![1, 2]);
+        assert_eq!(
+            rows(vec![condition("Day", DateRange, vec![json!("2024-01-05"), json!("2024-02-10")])]),
+            vec![0, 1]
+        );
+    }
+
+    #[test]
+    fn test_groups_and_unknown_fields() {
+        let south_or_big = FilterNode::Group(FilterGroup {
+            combinator: FilterCombinator::Or,
+            filters: vec![
+                condition("Region", FilterOperator::Equals, vec![json!("South")]),
+                condition("Sales", FilterOperator::GreaterOrEqual, vec![json!(40)]),
+            ],
+        });
+        assert_eq!(rows(vec![south_or_big.clone()]), vec![1, 3]);
+        assert_eq!(
+            rows(vec![south_or_big, condition("Day", FilterOperator::IsNotNull, vec![])]),
+            vec![1]
+        );
+
+        let unknown = vec![condition("Missing", FilterOperator::Equals, vec![json!(1)])];
+        assert_eq!(rows(unknown), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_apply_and_serde() {
+        let dataset = dataset();
+        assert!(matches!(FilterGroup::default().apply(&dataset), Cow::Borrowed(_)));
+
+        let group = FilterGroup {
+            combinator: FilterCombinator::And,
+            filters: vec![condition("Sales", FilterOperator::LessThan, vec![json!(30)])],
+        };
+        let filtered = group.apply(&dataset);
+        assert_eq!(filtered.data.len(), 2);
+        assert_eq!(filtered.data.value(1, 0), json!("South"));
+
+        let text = serde_json::to_string(&group).unwrap();
+        assert_eq!(serde_json::from_str::<FilterGroup>(&text).unwrap(), group);
+        assert_eq!(value_from_text(" 12.5 ", FilterOperator::Equals), json!(12.5));
+        assert_eq!(value_from_text("12", FilterOperator::Equals), json!(12));
+        assert_eq!(value_from_text("EU", FilterOperator::Equals), json!("EU"));
+    }
+
+    #[test]
+    fn test_contains_numbers() {
+        use FilterOperator::*;
+        let operand = value_from_text("12", Contains);
+        assert_eq!(operand, json!("12"));
+        let filter = Filter { field: "Name".into(), operator: Contains, values: vec![operand] };
+        let names = Column::from_values(FieldType::Text, vec![json!("Order 12"), json!("Order 7")]);
+        assert!(filter.matches(&names, 0));
+        assert!(!filter.matches(&names, 1));
+
+        // Numeric cells match as shown: 40, not 40.0
+        let digit = |text: &str| condition("Sales", Contains, vec![value_from_text(text, Contains)]);
+        assert_eq!(rows(vec![digit("0")]), vec![0, 3]);
+        assert_eq!(rows(vec![digit("0.0")]), Vec::<usize>::new());
+        assert_eq!(rows(vec![condition("Sales", Contains, vec![json!(25)])]), vec![1]);
+    }
+}
@@ -0,0 +1,355 @@
+//! Joins of two datasets on key fields
+//!
+//! Rows of the left and right dataset are paired when all their key
+//! fields match. Keys are compared across field types: numbers match
+//! numeric text (`7` and `"7.0"`), and dates match their ISO text. Null
+//! keys never match.
+
+use super::table::{Column, DataTable};
+use crate::features::dashboard::models::{Dataset, DatasetId, Field, FieldType};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Error while joining datasets
+#[derive(Debug, Clone, PartialEq)]
+pub enum JoinError {
+    /// Joining without any key pair
+    NoKeys,
+    /// A key field isn't in its dataset
+    UnknownField(String),
+    /// Two fields of the result would share a name
+    DuplicateField(String),
+}
+
+impl std::fmt::Display for JoinError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoKeys => write!(f, "Choose at least one pair of key fields"),
+            Self::UnknownField(name) => write!(f, "Field not found: {}", name),
+            Self::DuplicateField(name) => write!(f, "Two fields would be named '{}'", name),
+        }
+    }
+}
+
+impl std::error::Error for JoinError {}
+
+/// Rows kept by a join
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JoinKind {
+    /// Only matched rows
+    Inner,
+    /// All left rows, with the matching right rows if any
+    #[default]
+    Left,
+    /// All right rows, with the matching left rows if any
+    Right,
+    /// All rows of both sides
+    FullOuter,
+}
+
+impl JoinKind {
+    /// All kinds, in the order shown in pickers
+    pub fn all() -> [Self; 4] {
+        [Self::Inner, Self::Left, Self::Right, Self::FullOuter]
+    }
+
+    /// Name shown in pickers
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Inner => "Inner join",
+            Self::Left => "Left join",
+            Self::Right => "Right join",
+            Self::FullOuter => "Full outer join",
+        }
+    }
+
+    fn keeps_left(&self) -> bool {
+        matches!(self, Self::Left | Self::FullOuter)
+    }
+
+    fn keeps_right(&self) -> bool {
+        matches!(self, Self::Right | Self::FullOuter)
+    }
+}
+
+/// Pair of fields whose values must match
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JoinKey {
+    /// Field of the left dataset
+    pub left: String,
+    /// Field of the right dataset
+    pub right: String,
+}
+
+/// Join of two datasets
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Join {
+    pub left: DatasetId,
+    pub right: DatasetId,
+    pub kind: JoinKind,
+    /// Key pairs, all of which must match
+    pub keys: Vec<JoinKey>,
+}
+
+/// How many rows a join produced and left unmatched
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct JoinStats {
+    /// Rows of the result
+    pub rows: usize,
+    /// Left rows without a matching right row
+    pub unmatched_left: usize,
+    /// Right rows without a matching left row
+    pub unmatched_right: usize,
+}
+
+/// Pairs of rows making up a join's result (`None` for the missing side)
+struct Matches {
+    pairs: Vec<(Option<usize>, Option<usize>)>,
+    stats: JoinStats,
+}
+
+impl Join {
+    /// Fields and rows of the joined dataset
+    ///
+    /// Left fields come first; the right key fields are merged into the
+    /// left ones (taking the right value for unmatched right rows, and
+    /// becoming text when the two sides' types differ). Other right fields
+    /// sharing a left field's name get the right dataset's name as a suffix.
+    pub fn apply(
+        &self,
+        left: &Dataset,
+        right: &Dataset,
+    ) -> Result<(Vec<Field>, DataTable, JoinStats), JoinError> {
+        let keys = self.key_indexes(left, right)?;
+        let matches = self.match_rows(left, right, &keys);
+
+        let right_kept: Vec<usize> =
+            (0..right.fields.len()).filter(|idx| !keys.iter().any(|(_, r)| r == idx)).collect();
+        let mut fields = left.fields.clone();
+        let as_text: Vec<usize> = keys
+            .iter()
+            .filter(|(l, r)| left.fields[*l].field_type != right.fields[*r].field_type)
+            .map(|(l, _)| *l)
+            .collect();
+        for &idx in &as_text {
+            fields[idx] = Field { field_type: FieldType::Text, format: None, ..fields[idx].clone() };
+        }
+        for &idx in &right_kept {
+            let field = &right.fields[idx];
+            let name = if fields.iter().any(|f| f.name == field.name) {
+                format!("{} ({})", field.name, right.name)
+            } else {
+                field.name.clone()
+            };
+            if fields.iter().any(|f| f.name == name) {
+                return Err(JoinError::DuplicateField(name));
+            }
+            fields.push(Field { name, ..field.clone() });
+        }
+
+        let value = |dataset: &Dataset, row: Option<usize>, idx: usize| {
+            row.map(|row| dataset.data.value(row, idx)).unwrap_or(Value::Null)
+        };
+        let rows = matches.pairs.iter().map(|&(l, r)| {
+            let mut values: Vec<Value> = (0..left.fields.len()).map(|idx| value(left, l, idx)).collect();
+            // Unmatched right rows take their own key values
+            if l.is_none() {
+                for &(left_idx, right_idx) in &keys {
+                    values[left_idx] = value(right, r, right_idx);
+                }
+            }
+            for &idx in &as_text {
+                values[idx] = match std::mem::take(&mut values[idx]) {
+                    Value::Number(n) => n.as_f64().map(|n| Value::from(n.to_string())).unwrap_or(Value::Null),
+                    Value::Bool(b) => Value::from(b.to_string()),
+                    other => other,
+                };
+            }
+            values.extend(right_kept.iter().map(|&idx| value(right, r, idx)));
+            values
+        });
+        let data = DataTable::from_rows(&fields, rows);
+        Ok((fields, data, matches.stats))
+    }
+
+    /// Rows the join produces and leaves unmatched, without building it
+    pub fn stats(&self, left: &Dataset, right: &Dataset) -> Result<JoinStats, JoinError> {
+        let keys = self.key_indexes(left, right)?;
+        Ok(self.match_rows(left, right, &keys).stats)
+    }
+
+    fn key_indexes(&self, left: &Dataset, right: &Dataset) -> Result<Vec<(usize, usize)>, JoinError> {
+        if self.keys.is_empty() {
+            return Err(JoinError::NoKeys);
+        }
+        let index = |dataset: &Dataset, name: &str| {
+            dataset
+                .fields
+                .iter()
+                .position(|field| field.name == name)
+                .ok_or_else(|| JoinError::UnknownField(name.to_string()))
+        };
+        self.keys.iter().map(|key| Ok((index(left, &key.left)?, index(right, &key.right)?))).collect()
+    }
+
+    fn match_rows(&self, left: &Dataset, right: &Dataset, keys: &[(usize, usize)]) -> Matches {
+        let row_key = |dataset: &Dataset, row: usize, side: fn(&(usize, usize)) -> usize| {
+            keys.iter().map(|key| key_part(dataset.data.column(side(key)), row)).collect::<Option<Vec<_>>>()
+        };
+
+        let mut right_rows: HashMap<Vec<KeyPart>, Vec<usize>> = HashMap::new();
+        for row in 0..right.data.len() {
+            if let Some(key) = row_key(right, row, |key| key.1) {
+                right_rows.entry(key).or_default().push(row);
+            }
+        }
+
+        let mut pairs = Vec::new();
+        let mut stats = JoinStats::default();
+        let mut right_matched = vec![false; right.data.len()];
+        for row in 0..left.data.len() {
+            match row_key(left, row, |key| key.0).and_then(|key| right_rows.get(&key)) {
+                Some(matched) => {
+                    for &right_row in matched {
+                        right_matched[right_row] = true;
+                        pairs.push((Some(row), Some(right_row)));
+                    }
+                }
+                None => {
+                    stats.unmatched_left += 1;
+                    if self.kind.keeps_left() {
+                        pairs.push((Some(row), None));
+                    }
+                }
+            }
+        }
+        for (row, _) in right_matched.iter().enumerate().filter(|(_, matched)| !**matched) {
+            stats.unmatched_right += 1;
+            if self.kind.keeps_right() {
+                pairs.push((None, Some(row)));
+            }
+        }
+        stats.rows = pairs.len();
+        Matches { pairs, stats }
+    }
+}
+
+/// Key value of a cell, comparable across field types
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum KeyPart {
+    /// Bits of a number (numeric text included), `-0` as `0`
+    Number(u64),
+    Text(String),
+}
+
+fn key_part(column: &Column, row: usize) -> Option<KeyPart> {
+    let number = |n: f64| KeyPart::Number((n + 0.0).to_bits());
+    if let Some(n) = column.number(row) {
+        return Some(number(n));
+    }
+    let text = match column.value(row) {
+        Value::Null => return None,
+        Value::String(text) => text.trim().to_string(),
+        other => other.to_string(),
+    };
+    match text.parse::<f64>() {
+        Ok(n) if n.is_finite() => Some(number(n)),
+        _ if text.is_empty() => None,
+        _ => Some(KeyPart::Text(text)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn dataset(id: &str, fields: &[(&str, FieldType)], rows: Vec<Vec<Value>>) -> Dataset {
+        let fields: Vec<Field> = fields
+            .iter()
+            .map(|(name, field_type)| Field { name: name.to_string(), field_type: *field_type, format: None })
+            .collect();
+        Dataset {
+            id: id.to_string(),
+            name: format!("{}.csv", id),
+            size: "0 MB".to_string(),
+            uploaded_at: "Today".to_string(),
+            data: DataTable::from_rows(&fields, rows),
+            fields,
+            active: true,
+            dialect: None,
+            calculated_fields: Vec::new(),
+            derived_from: None,
+        }
+    }
+
+    fn orders_and_customers() -> (Dataset, Dataset) {
+        let orders = dataset(
+            "orders",
+            &[("Customer", FieldType::Numeric), ("Amount", FieldType::Numeric)],
+            vec![
+                vec![json!(1), json!(10)],
+                vec![json!(2), json!(20)],
+                vec![json!(1), json!(5)],
+                vec![Value::Null, json!(7)],
+            ],
+        );
+        let customers = dataset(
+            "customers",
+            &[("Id", FieldType::Text), ("Name", FieldType::Text), ("Amount", FieldType::Numeric)],
+            vec![
+                vec![json!("1"), json!("Ada"), json!(100)],
+                vec![json!(" 3 "), json!("Bob"), json!(300)],
+            ],
+        );
+        (orders, customers)
+    }
+
+    fn join(kind: JoinKind) -> Join {
+        Join {
+            left: "orders".to_string(),
+            right: "customers".to_string(),
+            kind,
+            keys: vec![JoinKey { left: "Customer".to_string(), right: "Id".to_string() }],
+        }
+    }
+
+    #[test]
+    fn test_join_kinds() {
+        let (orders, customers) = orders_and_customers();
+
+        let (fields, data, stats) = join(JoinKind::Inner).apply(&orders, &customers).unwrap();
+        let names: Vec<&str> = fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["Customer", "Amount", "Name", "Amount (customers.csv)"]);
+        assert_eq!(data.rows().collect::<Vec<_>>(), vec![
+            vec![json!("1"), json!(10.0), json!("Ada"), json!(100.0)],
+            vec![json!("1"), json!(5.0), json!("Ada"), json!(100.0)],
+        ]);
+        assert_eq!(stats, JoinStats { rows: 2, unmatched_left: 2, unmatched_right: 1 });
+
+        let (_, data, _) = join(JoinKind::Left).apply(&orders, &customers).unwrap();
+        assert_eq!(data.len(), 4);
+        assert_eq!(data.row(1), vec![json!("2"), json!(20.0), Value::Null, Value::Null]);
+
+        // Unmatched right rows keep their own key
+        let (_, data, _) = join(JoinKind::Right).apply(&orders, &customers).unwrap();
+        assert_eq!(data.len(), 3);
+        assert_eq!(data.row(2), vec![json!(" 3 "), Value::Null, json!("Bob"), json!(300.0)]);
+
+        assert_eq!(join(JoinKind::FullOuter).stats(&orders, &customers).unwrap().rows, 5);
+    }
+
+    #[test]
+    fn test_join_errors() {
+        let (orders, customers) = orders_and_customers();
+        let no_keys = Join { keys: Vec::new(), ..join(JoinKind::Inner) };
+        assert_eq!(no_keys.stats(&orders, &customers).unwrap_err(), JoinError::NoKeys);
+
+        let unknown = Join {
+            keys: vec![JoinKey { left: "Customer".to_string(), right: "Email".to_string() }],
+            ..join(JoinKind::Inner)
+        };
+        assert_eq!(unknown.stats(&orders, &customers).unwrap_err(), JoinError::UnknownField("Email".to_string()));
+    }
+}
@@ -4,10 +4,21 @@
 //! for converting CSV data into ECharts-compatible formats.
 
 pub mod aggregation;
+pub mod derived;
+pub mod expression;
+pub mod filter;
+pub mod join;
+pub mod replace;
+pub mod reshape;
+pub mod table;
+pub mod time;
 pub mod transform;
+pub mod window;
 
 pub use aggregation::AggregationFunction;
-pub use transform::dataset_to_echarts_format;
+pub use filter::FilterGroup;
+pub use table::{Column, DataTable};
+pub use transform::{series_data, SeriesData};
 // Unused exports kept for future use
 // pub use aggregation::aggregate_data;
 // pub use transform::TransformError;
@@ -0,0 +1,289 @@
+//! Replacing the data of a dataset in place
+//!
+//! A new export of the same source (e.g. next month's CSV) replaces the
+//! rows of a dataset, keeping its id so widgets keep showing it. The schema
+//! diff tells which fields were added, removed, renamed or retyped. Widget
+//! mappings, calculated fields and derived datasets follow the obvious
+//! renames; widgets left referencing missing fields, and calculated fields
+//! that no longer compute, are reported.
+
+use super::derived::DerivedFrom;
+use super::expression::rename_fields;
+use crate::features::dashboard::models::{DataMapping, Dataset, Field, FieldType};
+use std::fmt;
+
+/// A field found under a new name
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldRename {
+    pub from: String,
+    pub to: String,
+}
+
+/// A field whose type changed (named as in the new data)
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldRetype {
+    pub name: String,
+    pub from: FieldType,
+    pub to: FieldType,
+}
+
+/// How the fields of a dataset changed, in field order
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SchemaDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub renamed: Vec<FieldRename>,
+    pub retyped: Vec<FieldRetype>,
+}
+
+impl SchemaDiff {
+    /// Compare the fields of the old and new data
+    ///
+    /// A removed field is taken as renamed when an added one has the same
+    /// name up to case, spaces and punctuation (`Total Revenue` and
+    /// `total_revenue`), or when it is the only field removed and the only
+    /// one added, with the same type.
+    pub fn between(old: &[Field], new: &[Field]) -> Self {
+        let has = |fields: &[Field], name: &str| fields.iter().any(|field| field.name == name);
+        let mut removed: Vec<&Field> = old.iter().filter(|field| !has(new, &field.name)).collect();
+        let mut added: Vec<&Field> = new.iter().filter(|field| !has(old, &field.name)).collect();
+
+        let mut pairs: Vec<(&Field, &Field)> = Vec::new();
+        removed.retain(|from| {
+            let Some(idx) = added.iter().position(|to| normalized(&to.name) == normalized(&from.name)) else {
+                return true;
+            };
+            pairs.push((from, added.remove(idx)));
+            false
+        });
+        if let ([from], [to]) = (removed.as_slice(), added.as_slice())
+            && from.field_type == to.field_type
+        {
+            pairs.push((from, to));
+            removed.clear();
+            added.clear();
+        }
+
+        let kept = old.iter().filter_map(|from| Some((from, new.iter().find(|to| to.name == from.name)?)));
+        let retyped = kept
+            .chain(pairs.iter().copied())
+            .filter(|(from, to)| from.field_type != to.field_type)
+            .map(|(from, to)| FieldRetype { name: to.name.clone(), from: from.field_type, to: to.field_type })
+            .collect();
+        Self {
+            added: added.iter().map(|field| field.name.clone()).collect(),
+            removed: removed.iter().map(|field| field.name.clone()).collect(),
+            renamed: pairs
+                .iter()
+                .map(|(from, to)| FieldRename { from: from.name.clone(), to: to.name.clone() })
+                .collect(),
+            retyped,
+        }
+    }
+
+    /// Whether the fields are the same
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.renamed.is_empty() && self.retyped.is_empty()
+    }
+
+    /// New name of a renamed field
+    pub fn renamed_to(&self, name: &str) -> Option<&str> {
+        self.renamed.iter().find(|rename| rename.from == name).map(|rename| rename.to.as_str())
+    }
+
+    /// The renames that apply to a dataset of the given fields: those whose
+    /// new name it has and old name it doesn't (e.g. a dataset derived from
+    /// the one replaced, once computed again)
+    pub fn renames_in(&self, fields: &[Field]) -> SchemaDiff {
+        let has = |name: &str| fields.iter().any(|field| field.name == name);
+        SchemaDiff {
+            renamed: self.renamed.iter().filter(|rename| has(&rename.to) && !has(&rename.from)).cloned().collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Follow the renamed fields in a mapping; whether it changed
+    pub fn repair(&self, mapping: &mut DataMapping) -> bool {
+        let before = mapping.clone();
+        for rename in &self.renamed {
+            mapping.rename_field(&rename.from, &rename.to);
+        }
+        *mapping != before
+    }
+
+    /// Follow the renamed fields in the expression of a calculated field
+    pub fn repair_expression(&self, expression: &str) -> String {
+        rename_fields(expression, |name| self.renamed_to(name))
+    }
+
+    /// Follow the renamed fields of the dataset `source` in how a derived
+    /// dataset is computed; whether it changed
+    pub fn repair_derived(&self, derived: &mut DerivedFrom, source: &str) -> bool {
+        let before = derived.clone();
+        for rename in &self.renamed {
+            derived.rename_field(source, &rename.from, &rename.to);
+        }
+        *derived != before
+    }
+}
+
+/// Lowercase letters and digits of a field name
+fn normalized(name: &str) -> String {
+    name.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+/// Fields a mapping refers to that the dataset doesn't have
+pub fn missing_fields(mapping: &DataMapping, dataset: &Dataset) -> Vec<String> {
+    mapping
+        .fields()
+        .into_iter()
+        .filter(|name| !dataset.fields.iter().any(|field| field.name == *name))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Something a replacement left broken
+#[derive(Clone, Debug, PartialEq)]
+pub enum Breakage {
+    /// A widget referencing fields its dataset no longer has
+    Widget {
+        title: String,
+        /// Dataset of the widget, when derived from the one replaced
+        derived_dataset: Option<String>,
+        missing: Vec<String>,
+    },
+    /// Calculated fields dropped because their expression no longer computes
+    CalculatedFields { dataset_name: String, names: Vec<String> },
+}
+
+impl fmt::Display for Breakage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Widget { title, derived_dataset: None, missing } => {
+                write!(f, "{} is missing {}", title, missing.join(", "))
+            }
+            Self::Widget { title, derived_dataset: Some(dataset), missing } => {
+                write!(f, "{} (on {}) is missing {}", title, dataset, missing.join(", "))
+            }
+            Self::CalculatedFields { dataset_name, names } => {
+                write!(f, "Calculated fields dropped from {}: {}", dataset_name, names.join(", "))
+            }
+        }
+    }
+}
+
+/// Outcome of replacing the data of a dataset
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplaceReport {
+    /// Name of the dataset replaced
+    pub dataset_name: String,
+    pub diff: SchemaDiff,
+    /// Titles of the widgets whose mapping followed a rename
+    pub repaired: Vec<String>,
+    /// Widgets of the dataset, or of those derived from it, still
+    /// referencing missing fields, and calculated fields dropped
+    pub broken: Vec<Breakage>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::dashboard::data::filter::{Filter, FilterNode, FilterOperator};
+    use crate::features::dashboard::data::join::{Join, JoinKey, JoinKind};
+    use crate::features::dashboard::data::{AggregationFunction, DataTable};
+
+    fn fields(fields: &[(&str, FieldType)]) -> Vec<Field> {
+        fields
+            .iter()
+            .map(|(name, field_type)| Field { name: name.to_string(), field_type: *field_type, format: None })
+            .collect()
+    }
+
+    #[test]
+    fn test_schema_diff() {
+        let old = fields(&[
+            ("Date", FieldType::Date),
+            ("Total Revenue", FieldType::Numeric),
+            ("Region", FieldType::Text),
+            ("Units", FieldType::Numeric),
+            ("Notes", FieldType::Text),
+        ]);
+        let new = fields(&[
+            ("Date", FieldType::Text),
+            ("total_revenue", FieldType::Numeric),
+            ("Area", FieldType::Text),
+            ("Units", FieldType::Numeric),
+            ("Discount", FieldType::Numeric),
+        ]);
+        let diff = SchemaDiff::between(&old, &new);
+        let rename = |from: &str, to: &str| FieldRename { from: from.into(), to: to.into() };
+        assert_eq!(diff.renamed, vec![rename("Total Revenue", "total_revenue")]);
+        assert_eq!(diff.removed, vec!["Region", "Notes"]);
+        assert_eq!(diff.added, vec!["Area", "Discount"]);
+        assert_eq!(diff.retyped, vec![FieldRetype { name: "Date".into(), from: FieldType::Date, to: FieldType::Text }]);
+
+        // A single field removed and added with the same type is a rename
+        let diff = SchemaDiff::between(&old[..3], &new[..3]);
+        assert_eq!(diff.renamed[1], rename("Region", "Area"));
+        assert!(diff.removed.is_empty() && diff.added.is_empty());
+        assert!(SchemaDiff::between(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn test_repair_mapping() {
+        let old = fields(&[("Region", FieldType::Text), ("Sales", FieldType::Numeric), ("Cost", FieldType::Numeric)]);
+        let new = fields(&[("region", FieldType::Text), ("Sales", FieldType::Numeric)]);
+        let mut mapping = DataMapping {
+            x_axis: Some("Region".into()),
+            y_axis: vec!["Sales".into(), "Cost".into()],
+            ..Default::default()
+        };
+        mapping.set_aggregation("Sales", AggregationFunction::Avg);
+        mapping.filters.filters.push(FilterNode::Condition(Filter {
+            field: "Region".into(),
+            operator: FilterOperator::IsNotNull,
+            values: Vec::new(),
+        }));
+
+        let diff = SchemaDiff::between(&old, &new);
+        assert!(diff.repair(&mut mapping));
+        assert_eq!(mapping.x_axis.as_deref(), Some("region"));
+        assert_eq!(mapping.fields(), vec!["region", "Sales", "Cost"]);
+        assert_eq!(mapping.aggregation("Sales"), AggregationFunction::Avg);
+        assert!(!diff.repair(&mut mapping));
+
+        let dataset = Dataset {
+            id: "sales".into(),
+            name: "sales.csv".into(),
+            size: "0 MB".into(),
+            uploaded_at: "Today".into(),
+            data: DataTable::from_rows(&new, Vec::new()),
+            fields: new,
+            active: true,
+            dialect: None,
+            calculated_fields: Vec::new(),
+            derived_from: None,
+        };
+        assert_eq!(missing_fields(&mapping, &dataset), vec!["Cost"]);
+    }
+
+    #[test]
+    fn test_repair_derived() {
+        let old = fields(&[("Region", FieldType::Text), ("Unit Price", FieldType::Numeric)]);
+        let new = fields(&[("region", FieldType::Text), ("unit_price", FieldType::Numeric)]);
+        let diff = SchemaDiff::between(&old, &new);
+        assert_eq!(diff.repair_expression("[Unit Price] * 2 + LEN(Region)"), "unit_price * 2 + LEN(region)");
+
+        // Only the keys on the side of the replaced dataset follow
+        let mut derived = DerivedFrom::Join(Join {
+            left: "sales".into(),
+            right: "targets".into(),
+            kind: JoinKind::Left,
+            keys: vec![JoinKey { left: "Region".into(), right: "Region".into() }],
+        });
+        assert!(diff.repair_derived(&mut derived, "sales"));
+        let DerivedFrom::Join(join) = &derived else { unreachable!() };
+        assert_eq!(join.keys, vec![JoinKey { left: "region".into(), right: "Region".into() }]);
+        assert!(!diff.repair_derived(&mut derived, "sales"));
+    }
+}
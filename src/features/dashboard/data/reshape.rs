@@ -0,0 +1,305 @@
+//! Reshaping datasets between wide and long form
+//!
+//! Unpivoting turns columns into name/value pairs, one row per source row
+//! and column; pivoting spreads the values of a field into one measure
+//! column each. Reshaped datasets are derived (see `data::derived`): they
+//! are computed again when their source changes.
+
+use super::aggregation::{aggregate_field, number_or_null, AggregationFunction};
+use super::table::{Column, DataTable};
+use super::transform::value_label;
+use crate::features::dashboard::models::{Dataset, Field, FieldType};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Error while reshaping a dataset
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReshapeError {
+    /// A field named by the reshape isn't in the dataset
+    UnknownField(String),
+    /// Unpivoting without choosing any column
+    NoColumns,
+    /// Two fields of the result would share a name
+    DuplicateField(String),
+}
+
+impl std::fmt::Display for ReshapeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownField(name) => write!(f, "Field not found: {}", name),
+            Self::NoColumns => write!(f, "Choose at least one column to unpivot"),
+            Self::DuplicateField(name) => write!(f, "Two fields would be named '{}'", name),
+        }
+    }
+}
+
+impl std::error::Error for ReshapeError {}
+
+/// How a dataset is reshaped
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Reshape {
+    /// Wide to long: every source row gives one row per unpivoted column,
+    /// with the other fields repeated
+    Unpivot {
+        /// Columns turned into rows
+        columns: Vec<String>,
+        /// Field holding the name of the column each row comes from
+        name_field: String,
+        /// Field holding the column's value
+        value_field: String,
+    },
+    /// Long to wide: one row per combination of `row_fields`, one column
+    /// per value of `column_field`
+    Pivot {
+        /// Fields identifying the rows of the result (none: a single row)
+        row_fields: Vec<String>,
+        /// Field whose values become columns (null values are left out)
+        column_field: String,
+        /// Measure aggregated into the new columns
+        value_field: String,
+        aggregation: AggregationFunction,
+    },
+}
+
+impl Reshape {
+    /// Fields and rows of the reshaped dataset
+    pub fn apply(&self, source: &Dataset) -> Result<(Vec<Field>, DataTable), ReshapeError> {
+        let result = match self {
+            Self::Unpivot { columns, name_field, value_field } => {
+                unpivot(source, columns, name_field, value_field)?
+            }
+            Self::Pivot { row_fields, column_field, value_field, aggregation } => {
+                pivot(source, row_fields, column_field, value_field, aggregation)?
+            }
+        };
+
+        let (fields, _) = &result;
+        for (idx, field) in fields.iter().enumerate() {
+            if fields[..idx].iter().any(|earlier| earlier.name == field.name) {
+                return Err(ReshapeError::DuplicateField(field.name.clone()));
+            }
+        }
+        Ok(result)
+    }
+
+    /// Follow a field of the source renamed from `from` to `to`
+    pub fn rename_field(&mut self, from: &str, to: &str) {
+        let rename = |name: &mut String| {
+            if name == from {
+                *name = to.to_string();
+            }
+        };
+        match self {
+            Self::Unpivot { columns, .. } => columns.iter_mut().for_each(rename),
+            Self::Pivot { row_fields, column_field, value_field, aggregation } => {
+                row_fields.iter_mut().for_each(rename);
+                rename(column_field);
+                rename(value_field);
+                if let AggregationFunction::WeightedAvg { weight } = aggregation {
+                    rename(weight);
+                }
+            }
+        }
+    }
+}
+
+fn field_index(dataset: &Dataset, name: &str) -> Result<usize, ReshapeError> {
+    dataset
+        .fields
+        .iter()
+        .position(|field| field.name == name)
+        .ok_or_else(|| ReshapeError::UnknownField(name.to_string()))
+}
+
+fn unpivot(
+    source: &Dataset,
+    columns: &[String],
+    name_field: &str,
+    value_field: &str,
+) -> Result<(Vec<Field>, DataTable), ReshapeError> {
+    if columns.is_empty() {
+        return Err(ReshapeError::NoColumns);
+    }
+    let unpivoted = columns.iter().map(|name| field_index(source, name)).collect::<Result<Vec<_>, _>>()?;
+    let kept: Vec<usize> = (0..source.fields.len()).filter(|idx| !unpivoted.contains(idx)).collect();
+
+    // Values keep the type (and format) the unpivoted columns share
+    let first = &source.fields[unpivoted[0]];
+    let shared = |same: &dyn Fn(&Field) -> bool| unpivoted.iter().all(|&idx| same(&source.fields[idx]));
+    let same_type = shared(&|field| field.field_type == first.field_type);
+    let value = Field {
+        name: value_field.to_string(),
+        field_type: if same_type { first.field_type } else { FieldType::Text },
+        format: first.format.clone().filter(|_| shared(&|field| field.format == first.format)),
+    };
+    let mut fields: Vec<Field> = kept.iter().map(|&idx| source.fields[idx].clone()).collect();
+    fields.push(Field { name: name_field.to_string(), field_type: FieldType::Text, format: None });
+    fields.push(value);
+
+    let (kept, unpivoted) = (&kept, &unpivoted);
+    let rows = (0..source.data.len()).flat_map(move |row| {
+        unpivoted.iter().map(move |&column| {
+            let mut values: Vec<Value> = kept.iter().map(|&idx| source.data.value(row, idx)).collect();
+            values.push(Value::from(source.fields[column].name.as_str()));
+            values.push(source.data.value(row, column));
+            values
+        })
+    });
+    let data = DataTable::from_rows(&fields, rows);
+    Ok((fields, data))
+}
+
+fn pivot(
+    source: &Dataset,
+    row_fields: &[String],
+    column_field: &str,
+    value_field: &str,
+    aggregation: &AggregationFunction,
+) -> Result<(Vec<Field>, DataTable), ReshapeError> {
+    let row_idxs = row_fields.iter().map(|name| field_index(source, name)).collect::<Result<Vec<_>, _>>()?;
+    let column_idx = field_index(source, column_field)?;
+    let value_idx = field_index(source, value_field)?;
+
+    // Rows of each combination of row field values, in order of appearance
+    let row_codes: Vec<Vec<u32>> = row_idxs.iter().map(|&idx| source.data.column(idx).group_rows().codes).collect();
+    let mut row_index: HashMap<Vec<u32>, usize> = HashMap::new();
+    let mut first_rows = Vec::new();
+    let mut row_of = Vec::with_capacity(source.data.len());
+    for row in 0..source.data.len() {
+        let key: Vec<u32> = row_codes.iter().map(|codes| codes[row]).collect();
+        let next = first_rows.len();
+        let out_row = *row_index.entry(key).or_insert(next);
+        if out_row == next {
+            first_rows.push(row);
+        }
+        row_of.push(out_row);
+    }
+
+    // One column per non-null value of the column field, in order of appearance
+    let groups = source.data.column(column_idx).group_rows();
+    let mut cells: Vec<Vec<Vec<usize>>> = vec![vec![Vec::new(); groups.keys.len()]; first_rows.len()];
+    for (row, &code) in groups.codes.iter().enumerate() {
+        cells[row_of[row]][code as usize].push(row);
+    }
+    let spread: Vec<(usize, String)> = groups
+        .keys
+        .iter()
+        .enumerate()
+        .filter_map(|(code, key)| Some((code, value_label(key)?)))
+        .collect();
+
+    let format = source.fields[value_idx].format.clone().filter(|_| !aggregation.is_count());
+    let mut fields: Vec<Field> = row_idxs.iter().map(|&idx| source.fields[idx].clone()).collect();
+    fields.extend(spread.iter().map(|(_, label)| Field {
+        name: label.clone(),
+        field_type: FieldType::Numeric,
+        format: format.clone(),
+    }));
+
+    let row_columns = row_idxs.iter().map(|&idx| source.data.column(idx).take(&first_rows)).collect();
+    let mut data = DataTable::from_columns(row_columns);
+    for (code, _) in &spread {
+        // Combinations without rows are empty (or counted as 0)
+        let values = cells
+            .iter()
+            .map(|cell| number_or_null(aggregate_field(source, value_idx, cell[*code].iter().copied(), aggregation)));
+        data.push_column(Column::from_values(FieldType::Numeric, values));
+    }
+    Ok((fields, data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn dataset(fields: &[(&str, FieldType)], rows: Vec<Vec<Value>>) -> Dataset {
+        let fields: Vec<Field> = fields
+            .iter()
+            .map(|(name, field_type)| Field { name: name.to_string(), field_type: *field_type, format: None })
+            .collect();
+        Dataset {
+            id: "source".to_string(),
+            name: "source.csv".to_string(),
+            size: "0 MB".to_string(),
+            uploaded_at: "Today".to_string(),
+            data: DataTable::from_rows(&fields, rows),
+            fields,
+            active: true,
+            dialect: None,
+            calculated_fields: Vec::new(),
+            derived_from: None,
+        }
+    }
+
+    #[test]
+    fn test_unpivot() {
+        let wide = dataset(
+            &[("Product", FieldType::Text), ("Jan", FieldType::Numeric), ("Feb", FieldType::Numeric)],
+            vec![vec![json!("Tea"), json!(3), json!(4)], vec![json!("Cake"), json!(5), Value::Null]],
+        );
+        let reshape = Reshape::Unpivot {
+            columns: vec!["Jan".to_string(), "Feb".to_string()],
+            name_field: "Month".to_string(),
+            value_field: "Sales".to_string(),
+        };
+        let (fields, data) = reshape.apply(&wide).unwrap();
+
+        let types: Vec<(&str, FieldType)> = fields.iter().map(|f| (f.name.as_str(), f.field_type)).collect();
+        assert_eq!(
+            types,
+            vec![("Product", FieldType::Text), ("Month", FieldType::Text), ("Sales", FieldType::Numeric)]
+        );
+        assert_eq!(data.rows().collect::<Vec<_>>(), vec![
+            vec![json!("Tea"), json!("Jan"), json!(3.0)],
+            vec![json!("Tea"), json!("Feb"), json!(4.0)],
+            vec![json!("Cake"), json!("Jan"), json!(5.0)],
+            vec![json!("Cake"), json!("Feb"), Value::Null],
+        ]);
+
+        let clashing = Reshape::Unpivot {
+            columns: vec!["Jan".to_string()],
+            name_field: "Product".to_string(),
+            value_field: "Sales".to_string(),
+        };
+        assert_eq!(clashing.apply(&wide).unwrap_err(), ReshapeError::DuplicateField("Product".to_string()));
+    }
+
+    #[test]
+    fn test_pivot() {
+        let long = dataset(
+            &[("Product", FieldType::Text), ("Month", FieldType::Text), ("Sales", FieldType::Numeric)],
+            vec![
+                vec![json!("Tea"), json!("Jan"), json!(3)],
+                vec![json!("Tea"), json!("Jan"), json!(2)],
+                vec![json!("Cake"), json!("Feb"), json!(5)],
+                vec![json!("Tea"), json!("Feb"), json!(4)],
+                vec![json!("Tea"), Value::Null, json!(9)],
+            ],
+        );
+        let reshape = Reshape::Pivot {
+            row_fields: vec!["Product".to_string()],
+            column_field: "Month".to_string(),
+            value_field: "Sales".to_string(),
+            aggregation: AggregationFunction::Sum,
+        };
+        let (fields, data) = reshape.apply(&long).unwrap();
+
+        let names: Vec<&str> = fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["Product", "Jan", "Feb"]);
+        assert_eq!(fields[1].field_type, FieldType::Numeric);
+        assert_eq!(data.rows().collect::<Vec<_>>(), vec![
+            vec![json!("Tea"), json!(5.0), json!(4.0)],
+            vec![json!("Cake"), Value::Null, json!(5.0)],
+        ]);
+
+        let unknown = Reshape::Pivot {
+            row_fields: Vec::new(),
+            column_field: "Region".to_string(),
+            value_field: "Sales".to_string(),
+            aggregation: AggregationFunction::Sum,
+        };
+        assert_eq!(unknown.apply(&long).unwrap_err(), ReshapeError::UnknownField("Region".to_string()));
+    }
+}
@@ -0,0 +1,656 @@
+//! Columnar in-memory storage of dataset rows
+//!
+//! Each column is kept in a typed vector: numbers as `f64` with a validity
+//! bitmap, strings dictionary-encoded, booleans as bits and ISO dates as
+//! timestamps. Columns sit behind an `Arc`, so cloning a dataset doesn't
+//! copy its data. Values that don't fit the column's type (e.g. text in a
+//! numeric column of an imported template) are kept as-is in a mixed column.
+//!
+//! Tables serialize row-major, as `Vec<Vec<Value>>`, so saved dashboards and
+//! templates keep their format.
+
+use crate::features::dashboard::models::{Field, FieldType};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use serde::ser::{Serialize, SerializeSeq, Serializer};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Growable bit vector
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Bitmap {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl Bitmap {
+    /// Append a bit
+    pub fn push(&mut self, bit: bool) {
+        if self.len.is_multiple_of(64) {
+            self.words.push(0);
+        }
+        if bit {
+            self.words[self.len / 64] |= 1 << (self.len % 64);
+        }
+        self.len += 1;
+    }
+
+    /// Read a bit (`false` out of range)
+    pub fn get(&self, idx: usize) -> bool {
+        idx < self.len && self.words[idx / 64] & (1 << (idx % 64)) != 0
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+/// How the dates of a timestamp column are written
+///
+/// Matches the ISO 8601 forms dates are normalized to on import.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimestampKind {
+    /// `YYYY-MM-DD`
+    Date,
+    /// `YYYY-MM-DDTHH:MM:SS`
+    DateTime,
+    /// `YYYY-MM-DDTHH:MM:SSZ` (UTC)
+    DateTimeUtc,
+}
+
+impl TimestampKind {
+    /// Parse an ISO date string into milliseconds since the epoch
+    ///
+    /// Only strings that format back to exactly the same text are accepted.
+    fn parse(text: &str) -> Option<(Self, i64)> {
+        let (kind, millis) = if let Some(date_time) = text.strip_suffix('Z') {
+            let parsed = NaiveDateTime::parse_from_str(date_time, "%Y-%m-%dT%H:%M:%S").ok()?;
+            (Self::DateTimeUtc, parsed.and_utc().timestamp_millis())
+        } else if text.len() > 10 {
+            let parsed = NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S").ok()?;
+            (Self::DateTime, parsed.and_utc().timestamp_millis())
+        } else {
+            let parsed = NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()?;
+            (Self::Date, parsed.and_hms_opt(0, 0, 0)?.and_utc().timestamp_millis())
+        };
+        (kind.format(millis) == text).then_some((kind, millis))
+    }
+
+    /// Write a timestamp back as text
+    fn format(&self, millis: i64) -> String {
+        let Some(date_time) = DateTime::from_timestamp_millis(millis) else {
+            return String::new();
+        };
+        match self {
+            Self::Date => date_time.format("%Y-%m-%d").to_string(),
+            Self::DateTime => date_time.format("%Y-%m-%dT%H:%M:%S").to_string(),
+            Self::DateTimeUtc => date_time.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+        }
+    }
+}
+
+/// Storage of one column
+#[derive(Clone, Debug, PartialEq)]
+enum ColumnData {
+    Numeric { values: Vec<f64>, valid: Bitmap },
+    /// The dictionary is shared by the columns taken from this one
+    Text { dictionary: Arc<Vec<String>>, codes: Vec<u32>, valid: Bitmap },
+    Boolean { values: Bitmap, valid: Bitmap },
+    Timestamp { millis: Vec<i64>, valid: Bitmap, kind: Option<TimestampKind> },
+    Mixed(Vec<Value>),
+}
+
+impl ColumnData {
+    fn len(&self) -> usize {
+        match self {
+            Self::Numeric { values, .. } => values.len(),
+            Self::Text { codes, .. } => codes.len(),
+            Self::Boolean { values, .. } => values.len(),
+            Self::Timestamp { millis, .. } => millis.len(),
+            Self::Mixed(values) => values.len(),
+        }
+    }
+}
+
+/// Hashable identity of a cell, for grouping
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum CellKey {
+    Null,
+    Number(u64),
+    Text(u32),
+    Bool(bool),
+    Timestamp(i64),
+    Json(String),
+}
+
+/// A typed column of a dataset
+#[derive(Clone, Debug, PartialEq)]
+pub struct Column(ColumnData);
+
+/// Empty column returned for out-of-range indexes
+static EMPTY_COLUMN: Column = Column(ColumnData::Mixed(Vec::new()));
+
+/// Distinct values of a column, and the group each row belongs to
+#[derive(Clone, Debug, PartialEq)]
+pub struct Groups {
+    /// Distinct values (including null), in order of first appearance
+    pub keys: Vec<Value>,
+    /// Index into `keys` of every row
+    pub codes: Vec<u32>,
+}
+
+impl Groups {
+    /// Row indexes of every group, in `keys` order
+    pub fn members(&self) -> Vec<Vec<usize>> {
+        let mut members = vec![Vec::new(); self.keys.len()];
+        for (row, &code) in self.codes.iter().enumerate() {
+            members[code as usize].push(row);
+        }
+        members
+    }
+}
+
+impl Column {
+    /// Build a column from converted values
+    pub fn from_values(field_type: FieldType, values: impl IntoIterator<Item = Value>) -> Self {
+        let mut builder = ColumnBuilder::new(field_type);
+        for value in values {
+            builder.push(value);
+        }
+        builder.finish()
+    }
+
+    /// Number of rows
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether the column has no rows
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether a cell is null (or out of range)
+    pub fn is_null(&self, row: usize) -> bool {
+        match &self.0 {
+            ColumnData::Numeric { valid, .. }
+            | ColumnData::Text { valid, .. }
+            | ColumnData::Boolean { valid, .. }
+            | ColumnData::Timestamp { valid, .. } => !valid.get(row),
+            ColumnData::Mixed(values) => values.get(row).is_none_or(Value::is_null),
+        }
+    }
+
+    /// Value of a cell (null out of range)
+    pub fn value(&self, row: usize) -> Value {
+        if self.is_null(row) {
+            return Value::Null;
+        }
+        match &self.0 {
+            ColumnData::Numeric { values, .. } => serde_json::Number::from_f64(values[row])
+                .map(Value::Number)
+                .unwrap_or(Value::Null),
+            ColumnData::Text { dictionary, codes, .. } => {
+                Value::String(dictionary[codes[row] as usize].clone())
+            }
+            ColumnData::Boolean { values, .. } => Value::Bool(values.get(row)),
+            ColumnData::Timestamp { millis, kind, .. } => {
+                Value::String(kind.unwrap_or(TimestampKind::Date).format(millis[row]))
+            }
+            ColumnData::Mixed(values) => values[row].clone(),
+        }
+    }
+
+    /// Number in a cell, `None` for nulls and non-numeric cells
+    pub fn number(&self, row: usize) -> Option<f64> {
+        if self.is_null(row) {
+            return None;
+        }
+        match &self.0 {
+            ColumnData::Numeric { values, .. } => Some(values[row]),
+            ColumnData::Mixed(values) => values[row].as_f64(),
+            _ => None,
+        }
+    }
+
+    /// Text of a string cell, `None` for nulls and other types
+    pub fn text(&self, row: usize) -> Option<&str> {
+        if self.is_null(row) {
+            return None;
+        }
+        match &self.0 {
+            ColumnData::Text { dictionary, codes, .. } => Some(&dictionary[codes[row] as usize]),
+            ColumnData::Mixed(values) => values[row].as_str(),
+            _ => None,
+        }
+    }
+
+    /// Milliseconds since the epoch of a date cell
+    pub fn timestamp(&self, row: usize) -> Option<i64> {
+        if self.is_null(row) {
+            return None;
+        }
+        match &self.0 {
+            ColumnData::Timestamp { millis, .. } => Some(millis[row]),
+            ColumnData::Mixed(values) => values[row].as_str().and_then(TimestampKind::parse).map(|(_, ms)| ms),
+            _ => None,
+        }
+    }
+
+    /// A column of the given rows, in order
+    pub fn take(&self, rows: &[usize]) -> Column {
+        let pick = |valid: &Bitmap| {
+            let mut picked = Bitmap::default();
+            rows.iter().for_each(|&row| picked.push(valid.get(row)));
+            picked
+        };
+        Column(match &self.0 {
+            ColumnData::Numeric { values, valid } => ColumnData::Numeric {
+                values: rows.iter().map(|&row| values.get(row).copied().unwrap_or_default()).collect(),
+                valid: pick(valid),
+            },
+            ColumnData::Text { dictionary, codes, valid } => ColumnData::Text {
+                dictionary: Arc::clone(dictionary),
+                codes: rows.iter().map(|&row| codes.get(row).copied().unwrap_or_default()).collect(),
+                valid: pick(valid),
+            },
+            ColumnData::Boolean { values, valid } => ColumnData::Boolean { values: pick(values), valid: pick(valid) },
+            ColumnData::Timestamp { millis, valid, kind } => ColumnData::Timestamp {
+                millis: rows.iter().map(|&row| millis.get(row).copied().unwrap_or_default()).collect(),
+                valid: pick(valid),
+                kind: *kind,
+            },
+            ColumnData::Mixed(values) => {
+                ColumnData::Mixed(rows.iter().map(|&row| values.get(row).cloned().unwrap_or(Value::Null)).collect())
+            }
+        })
+    }
+
+    /// Number of non-null cells
+    pub fn count_valid(&self) -> usize {
+        (0..self.len()).filter(|&row| !self.is_null(row)).count()
+    }
+
+    /// Group the rows by value (nulls form their own group)
+    pub fn group_rows(&self) -> Groups {
+        let mut index: HashMap<CellKey, u32> = HashMap::new();
+        let mut keys = Vec::new();
+        let mut codes = Vec::with_capacity(self.len());
+
+        for row in 0..self.len() {
+            let code = *index.entry(self.key(row)).or_insert_with(|| {
+                keys.push(self.value(row));
+                (keys.len() - 1) as u32
+            });
+            codes.push(code);
+        }
+
+        Groups { keys, codes }
+    }
+
+    fn key(&self, row: usize) -> CellKey {
+        if self.is_null(row) {
+            return CellKey::Null;
+        }
+        match &self.0 {
+            ColumnData::Numeric { values, .. } => CellKey::Number(values[row].to_bits()),
+            ColumnData::Text { codes, .. } => CellKey::Text(codes[row]),
+            ColumnData::Boolean { values, .. } => CellKey::Bool(values.get(row)),
+            ColumnData::Timestamp { millis, .. } => CellKey::Timestamp(millis[row]),
+            ColumnData::Mixed(values) => CellKey::Json(values[row].to_string()),
+        }
+    }
+}
+
+/// Builds a column value by value, in the storage of its field type
+///
+/// Falls back to a mixed column as soon as a value doesn't fit.
+pub struct ColumnBuilder {
+    data: ColumnData,
+    dictionary_index: HashMap<String, u32>,
+}
+
+impl ColumnBuilder {
+    /// Start an empty column for a field type
+    pub fn new(field_type: FieldType) -> Self {
+        let data = match field_type {
+            FieldType::Numeric => ColumnData::Numeric { values: Vec::new(), valid: Bitmap::default() },
+            FieldType::Text => ColumnData::Text {
+                dictionary: Arc::default(),
+                codes: Vec::new(),
+                valid: Bitmap::default(),
+            },
+            FieldType::Boolean => ColumnData::Boolean { values: Bitmap::default(), valid: Bitmap::default() },
+            FieldType::Date => ColumnData::Timestamp { millis: Vec::new(), valid: Bitmap::default(), kind: None },
+        };
+        Self { data, dictionary_index: HashMap::new() }
+    }
+
+    /// Append a value
+    pub fn push(&mut self, value: Value) {
+        if !self.try_push(&value) {
+            self.make_mixed();
+            if let ColumnData::Mixed(values) = &mut self.data {
+                values.push(value);
+            }
+        }
+    }
+
+    /// Append a value in typed storage; `false` if it doesn't fit
+    fn try_push(&mut self, value: &Value) -> bool {
+        let null = value.is_null();
+        match (&mut self.data, value) {
+            (ColumnData::Mixed(_), _) => false,
+            (ColumnData::Numeric { values, valid }, _) if null => {
+                values.push(0.0);
+                valid.push(false);
+                true
+            }
+            (ColumnData::Numeric { values, valid }, Value::Number(number)) => {
+                values.push(number.as_f64().unwrap_or(0.0));
+                valid.push(true);
+                true
+            }
+            (ColumnData::Text { codes, valid, .. }, _) if null => {
+                codes.push(0);
+                valid.push(false);
+                true
+            }
+            (ColumnData::Text { dictionary, codes, valid }, Value::String(text)) => {
+                let code = match self.dictionary_index.get(text) {
+                    Some(&code) => code,
+                    None => {
+                        let code = dictionary.len() as u32;
+                        Arc::make_mut(dictionary).push(text.clone());
+                        self.dictionary_index.insert(text.clone(), code);
+                        code
+                    }
+                };
+                codes.push(code);
+                valid.push(true);
+                true
+            }
+            (ColumnData::Boolean { values, valid }, _) if null => {
+                values.push(false);
+                valid.push(false);
+                true
+            }
+            (ColumnData::Boolean { values, valid }, Value::Bool(b)) => {
+                values.push(*b);
+                valid.push(true);
+                true
+            }
+            (ColumnData::Timestamp { millis, valid, .. }, _) if null => {
+                millis.push(0);
+                valid.push(false);
+                true
+            }
+            (ColumnData::Timestamp { millis, valid, kind }, Value::String(text)) => {
+                match TimestampKind::parse(text) {
+                    Some((parsed, ms)) if kind.is_none_or(|kind| kind == parsed) => {
+                        *kind = Some(parsed);
+                        millis.push(ms);
+                        valid.push(true);
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Move the values pushed so far to mixed storage
+    fn make_mixed(&mut self) {
+        if matches!(self.data, ColumnData::Mixed(_)) {
+            return;
+        }
+        let column = Column(std::mem::replace(&mut self.data, ColumnData::Mixed(Vec::new())));
+        self.data = ColumnData::Mixed((0..column.len()).map(|row| column.value(row)).collect());
+        self.dictionary_index.clear();
+    }
+
+    /// Finish the column
+    pub fn finish(self) -> Column {
+        Column(self.data)
+    }
+}
+
+/// Source of table revisions
+static NEXT_REVISION: AtomicU64 = AtomicU64::new(1);
+
+fn next_revision() -> u64 {
+    NEXT_REVISION.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Rows of a dataset, stored column by column
+#[derive(Clone, Debug, Default)]
+pub struct DataTable {
+    columns: Vec<Arc<Column>>,
+    rows: usize,
+    /// Changes whenever the table does; clones share it
+    revision: u64,
+}
+
+impl PartialEq for DataTable {
+    fn eq(&self, other: &Self) -> bool {
+        self.rows == other.rows && self.columns == other.columns
+    }
+}
+
+impl DataTable {
+    /// Build a table from finished columns (all of the same length)
+    pub fn from_columns(columns: Vec<Column>) -> Self {
+        let rows = columns.iter().map(Column::len).max().unwrap_or(0);
+        Self {
+            columns: columns.into_iter().map(Arc::new).collect(),
+            rows,
+            revision: next_revision(),
+        }
+    }
+
+    /// Build a table from row-major values, one column per field
+    ///
+    /// Missing cells become null; cells beyond the fields are dropped.
+    pub fn from_rows(fields: &[Field], rows: impl IntoIterator<Item = Vec<Value>>) -> Self {
+        let mut builders: Vec<ColumnBuilder> =
+            fields.iter().map(|field| ColumnBuilder::new(field.field_type)).collect();
+        for row in rows {
+            let mut cells = row.into_iter();
+            for builder in &mut builders {
+                builder.push(cells.next().unwrap_or(Value::Null));
+            }
+        }
+        Self::from_columns(builders.into_iter().map(ColumnBuilder::finish).collect())
+    }
+
+    /// Tells tables apart without comparing their rows: tables built or
+    /// changed separately never share a revision
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Number of rows
+    pub fn len(&self) -> usize {
+        self.rows
+    }
+
+    /// Whether the table has no rows
+    pub fn is_empty(&self) -> bool {
+        self.rows == 0
+    }
+
+    /// Number of columns
+    pub fn column_count(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// Column at a field index (an empty column out of range)
+    pub fn column(&self, idx: usize) -> &Column {
+        self.columns.get(idx).map(Arc::as_ref).unwrap_or(&EMPTY_COLUMN)
+    }
+
+    /// Append a column (of the table's length)
+    pub fn push_column(&mut self, column: Column) {
+        self.rows = self.rows.max(column.len());
+        self.columns.push(Arc::new(column));
+        self.revision = next_revision();
+    }
+
+    /// Keep only the first `count` columns
+    pub fn truncate_columns(&mut self, count: usize) {
+        self.columns.truncate(count);
+        self.revision = next_revision();
+    }
+
+    /// A table of the given rows, in order
+    pub fn select_rows(&self, rows: &[usize]) -> Self {
+        Self {
+            columns: self.columns.iter().map(|column| Arc::new(column.take(rows))).collect(),
+            rows: rows.len(),
+            revision: next_revision(),
+        }
+    }
+
+    /// Value of a cell (null out of range)
+    pub fn value(&self, row: usize, column: usize) -> Value {
+        self.column(column).value(row)
+    }
+
+    /// Values of one row
+    pub fn row(&self, row: usize) -> Vec<Value> {
+        self.columns.iter().map(|column| column.value(row)).collect()
+    }
+
+    /// Iterate over the rows as values
+    pub fn rows(&self) -> impl Iterator<Item = Vec<Value>> + '_ {
+        (0..self.rows).map(|row| self.row(row))
+    }
+}
+
+/// One row, serialized as an array without collecting it first
+struct RowRef<'a> {
+    table: &'a DataTable,
+    row: usize,
+}
+
+impl Serialize for RowRef<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.table.columns.len()))?;
+        for column in &self.table.columns {
+            seq.serialize_element(&column.value(self.row))?;
+        }
+        seq.end()
+    }
+}
+
+impl Serialize for DataTable {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.rows))?;
+        for row in 0..self.rows {
+            seq.serialize_element(&RowRef { table: self, row })?;
+        }
+        seq.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn field(name: &str, field_type: FieldType) -> Field {
+        Field { name: name.to_string(), field_type, format: None }
+    }
+
+    #[test]
+    fn test_round_trips_typed_columns() {
+        let fields = vec![
+            field("region", FieldType::Text),
+            field("sales", FieldType::Numeric),
+            field("day", FieldType::Date),
+            field("paid", FieldType::Boolean),
+        ];
+        let rows = vec![
+            vec![json!("North"), json!(10.5), json!("2024-01-01"), json!(true)],
+            vec![json!("South"), Value::Null, json!("2024-01-02"), Value::Null],
+            vec![json!("North"), json!(-3.0), Value::Null, json!(false)],
+        ];
+
+        let table = DataTable::from_rows(&fields, rows.clone());
+        assert_eq!(table.len(), 3);
+        assert_eq!(table.rows().collect::<Vec<_>>(), rows);
+        assert_eq!(serde_json::to_value(&table).unwrap(), json!(rows));
+
+        assert_eq!(table.column(1).number(0), Some(10.5));
+        assert_eq!(table.column(1).number(1), None);
+        assert_eq!(table.column(0).text(2), Some("North"));
+        assert_eq!(table.column(2).timestamp(0), Some(1_704_067_200_000));
+        assert_eq!(table.column(1).count_valid(), 2);
+    }
+
+    #[test]
+    fn test_values_that_dont_fit_become_mixed() {
+        let fields = vec![field("amount", FieldType::Numeric), field("when", FieldType::Date)];
+        let rows = vec![
+            vec![json!(1.0), json!("2024-01-01T10:00:00")],
+            vec![json!("n/a"), json!("2024-01-01")],
+        ];
+
+        let table = DataTable::from_rows(&fields, rows.clone());
+        assert_eq!(table.rows().collect::<Vec<_>>(), rows);
+        assert_eq!(table.column(0).number(0), Some(1.0));
+        assert_eq!(table.column(0).text(1), Some("n/a"));
+    }
+
+    #[test]
+    fn test_select_rows_keeps_types() {
+        let fields = vec![
+            field("region", FieldType::Text),
+            field("sales", FieldType::Numeric),
+            field("day", FieldType::Date),
+            field("paid", FieldType::Boolean),
+        ];
+        let rows = vec![
+            vec![json!("North"), json!(10.5), json!("2024-01-01"), json!(true)],
+            vec![json!("South"), Value::Null, json!("2024-01-02"), Value::Null],
+            vec![json!("East"), json!(-3.0), Value::Null, json!(false)],
+        ];
+
+        let full = DataTable::from_rows(&fields, rows.clone());
+        let table = full.select_rows(&[2, 0]);
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.rows().collect::<Vec<_>>(), vec![rows[2].clone(), rows[0].clone()]);
+        assert_eq!(table.column(2).timestamp(1), Some(1_704_067_200_000));
+
+        // Text columns share their dictionary rather than copy it
+        let (ColumnData::Text { dictionary, .. }, ColumnData::Text { dictionary: taken, .. }) =
+            (&full.column(0).0, &table.column(0).0)
+        else {
+            unreachable!()
+        };
+        assert!(Arc::ptr_eq(dictionary, taken));
+    }
+
+    #[test]
+    fn test_group_rows() {
+        let column = Column::from_values(
+            FieldType::Text,
+            vec![json!("b"), json!("a"), Value::Null, json!("b")],
+        );
+        let groups = column.group_rows();
+
+        assert_eq!(groups.keys, vec![json!("b"), json!("a"), Value::Null]);
+        assert_eq!(groups.codes, vec![0, 1, 2, 0]);
+        assert_eq!(groups.members()[0], vec![0, 3]);
+    }
+
+    #[test]
+    fn test_out_of_range_reads_are_null() {
+        let table = DataTable::from_rows(&[field("x", FieldType::Numeric)], vec![vec![json!(1.0)]]);
+        assert_eq!(table.value(5, 0), Value::Null);
+        assert_eq!(table.value(0, 3), Value::Null);
+        assert!(table.column(3).is_empty());
+    }
+}
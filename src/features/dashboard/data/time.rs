@@ -0,0 +1,242 @@
+//! Time bucketing of date dimensions
+//!
+//! Dates on the x-axis are grouped into periods (hours to fiscal years)
+//! before measures are aggregated. Periods are identified by their start,
+//! written as ISO text so ECharts time axes read them directly.
+
+use chrono::{DateTime, Datelike, Days, NaiveDate, NaiveDateTime, TimeDelta, Timelike};
+use serde::{Deserialize, Serialize};
+
+/// Most periods gap filling adds, so a stray far-off date can't produce
+/// millions of empty periods
+pub const MAX_FILLED_PERIODS: usize = 10_000;
+
+/// Length of the periods dates are grouped into
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeGranularity {
+    Hour,
+    #[default]
+    Day,
+    /// Weeks starting on Monday
+    Week,
+    Month,
+    /// Quarters of the fiscal year
+    Quarter,
+    /// Fiscal years
+    Year,
+}
+
+impl TimeGranularity {
+    /// All granularities, finest first
+    pub fn all() -> [Self; 6] {
+        [Self::Hour, Self::Day, Self::Week, Self::Month, Self::Quarter, Self::Year]
+    }
+
+    /// Name shown in the data panel
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Hour => "Hour",
+            Self::Day => "Day",
+            Self::Week => "Week",
+            Self::Month => "Month",
+            Self::Quarter => "Quarter",
+            Self::Year => "Year",
+        }
+    }
+
+    /// Whether periods depend on the fiscal year's first month
+    pub fn is_fiscal(&self) -> bool {
+        matches!(self, Self::Quarter | Self::Year)
+    }
+}
+
+fn january() -> u32 {
+    1
+}
+
+/// How the dates of the x-axis are grouped into periods
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeBucketing {
+    pub granularity: TimeGranularity,
+    /// First month of the fiscal year (1 = January), which quarters and
+    /// years start from
+    #[serde(default = "january")]
+    pub fiscal_year_start: u32,
+    /// Whether periods without rows between the first and last one are
+    /// shown (as gaps or zeros, like other missing values)
+    #[serde(default)]
+    pub fill_gaps: bool,
+}
+
+impl Default for TimeBucketing {
+    fn default() -> Self {
+        Self { granularity: TimeGranularity::default(), fiscal_year_start: january(), fill_gaps: false }
+    }
+}
+
+impl TimeBucketing {
+    /// Start of the period a timestamp (milliseconds since the epoch) falls in
+    pub fn period_start(&self, millis: i64) -> Option<NaiveDateTime> {
+        let date_time = DateTime::from_timestamp_millis(millis)?.naive_utc();
+        let date = date_time.date();
+        let start = match self.granularity {
+            TimeGranularity::Hour => return date.and_hms_opt(date_time.hour(), 0, 0),
+            TimeGranularity::Day => date,
+            TimeGranularity::Week => date.checked_sub_days(Days::new(date.weekday().num_days_from_monday().into()))?,
+            TimeGranularity::Month => month_start(date.year(), date.month0() as i32)?,
+            TimeGranularity::Quarter | TimeGranularity::Year => {
+                let months_into_year = (date.month0() as i32 - self.fiscal_month0()).rem_euclid(12);
+                let months_into_period = match self.granularity {
+                    TimeGranularity::Quarter => months_into_year % 3,
+                    _ => months_into_year,
+                };
+                month_start(date.year(), date.month0() as i32 - months_into_period)?
+            }
+        };
+        start.and_hms_opt(0, 0, 0)
+    }
+
+    /// Start of the period after the one starting at `start`
+    pub fn next_period(&self, start: NaiveDateTime) -> Option<NaiveDateTime> {
+        let months = match self.granularity {
+            TimeGranularity::Hour => return start.checked_add_signed(TimeDelta::hours(1)),
+            TimeGranularity::Day => return start.checked_add_days(Days::new(1)),
+            TimeGranularity::Week => return start.checked_add_days(Days::new(7)),
+            TimeGranularity::Month => 1,
+            TimeGranularity::Quarter => 3,
+            TimeGranularity::Year => 12,
+        };
+        month_start(start.year(), start.month0() as i32 + months)?.and_hms_opt(0, 0, 0)
+    }
+
+    /// Label of a period: its start as ISO text (with the hour for hourly periods)
+    pub fn period_label(&self, start: NaiveDateTime) -> String {
+        match self.granularity {
+            TimeGranularity::Hour => start.format("%Y-%m-%dT%H:%M:%S").to_string(),
+            _ => start.format("%Y-%m-%d").to_string(),
+        }
+    }
+
+    /// First and last date of the period a label names, for a date range
+    /// filter
+    ///
+    /// Bounds are as coarse as the period allows (`YYYY-MM` for months,
+    /// `YYYY-MM-DDTHH` for hours), since date ranges compare on the
+    /// bound's length.
+    pub fn period_bounds(&self, label: &str) -> Option<(String, String)> {
+        let start = NaiveDateTime::parse_from_str(label, "%Y-%m-%dT%H:%M:%S")
+            .or_else(|_| NaiveDate::parse_from_str(label, "%Y-%m-%d").map(|date| date.and_time(Default::default())))
+            .ok()?;
+        let bounds = match self.granularity {
+            TimeGranularity::Hour => {
+                let hour = start.format("%Y-%m-%dT%H").to_string();
+                (hour.clone(), hour)
+            }
+            TimeGranularity::Day => {
+                let day = start.format("%Y-%m-%d").to_string();
+                (day.clone(), day)
+            }
+            TimeGranularity::Week => (
+                start.format("%Y-%m-%d").to_string(),
+                start.checked_add_days(Days::new(6))?.format("%Y-%m-%d").to_string(),
+            ),
+            TimeGranularity::Month | TimeGranularity::Quarter | TimeGranularity::Year => {
+                let last_month = self.next_period(start)?.date().checked_sub_days(Days::new(1))?;
+                (start.format("%Y-%m").to_string(), last_month.format("%Y-%m").to_string())
+            }
+        };
+        Some(bounds)
+    }
+
+    /// Zero-based first month of the fiscal year
+    fn fiscal_month0(&self) -> i32 {
+        self.fiscal_year_start.clamp(1, 12) as i32 - 1
+    }
+}
+
+/// Label of the period a year before the one labelled `label`
+///
+/// Weeks go back 52 weeks so they still start on Monday; other periods
+/// (and exact dates) keep their day and month, so 29 February has none.
+///
+/// # Returns
+/// `None` for labels that aren't ISO dates
+pub fn same_period_last_year(label: &str, granularity: Option<TimeGranularity>) -> Option<String> {
+    let (start, format) = match NaiveDateTime::parse_from_str(label, "%Y-%m-%dT%H:%M:%S") {
+        Ok(start) => (start, "%Y-%m-%dT%H:%M:%S"),
+        Err(_) => (NaiveDate::parse_from_str(label, "%Y-%m-%d").ok()?.and_time(Default::default()), "%Y-%m-%d"),
+    };
+    let previous = match granularity {
+        Some(TimeGranularity::Week) => start.checked_sub_days(Days::new(52 * 7))?,
+        _ => start.with_year(start.year() - 1)?,
+    };
+    Some(previous.format(format).to_string())
+}
+
+/// First day of a month, `month0` counting from January of `year` (it may
+/// run past December or before January)
+fn month_start(year: i32, month0: i32) -> Option<NaiveDate> {
+    let months = year * 12 + month0;
+    NaiveDate::from_ymd_opt(months.div_euclid(12), months.rem_euclid(12) as u32 + 1, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn millis(text: &str) -> i64 {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S").unwrap().and_utc().timestamp_millis()
+    }
+
+    fn bucketing(granularity: TimeGranularity, fiscal_year_start: u32) -> TimeBucketing {
+        TimeBucketing { granularity, fiscal_year_start, fill_gaps: false }
+    }
+
+    #[test]
+    fn test_period_starts() {
+        let at = millis("2024-02-15T13:45:00");
+        let start = |granularity, fiscal| {
+            let bucketing = bucketing(granularity, fiscal);
+            bucketing.period_label(bucketing.period_start(at).unwrap())
+        };
+        assert_eq!(start(TimeGranularity::Hour, 1), "2024-02-15T13:00:00");
+        assert_eq!(start(TimeGranularity::Day, 1), "2024-02-15");
+        assert_eq!(start(TimeGranularity::Week, 1), "2024-02-12");
+        assert_eq!(start(TimeGranularity::Month, 1), "2024-02-01");
+        assert_eq!(start(TimeGranularity::Quarter, 1), "2024-01-01");
+        assert_eq!(start(TimeGranularity::Year, 1), "2024-01-01");
+
+        // Fiscal years starting in April
+        assert_eq!(start(TimeGranularity::Quarter, 4), "2024-01-01");
+        assert_eq!(start(TimeGranularity::Year, 4), "2023-04-01");
+        assert_eq!(start(TimeGranularity::Year, 2), "2024-02-01");
+    }
+
+    #[test]
+    fn test_next_period_and_bounds() {
+        let quarter = bucketing(TimeGranularity::Quarter, 11);
+        let start = quarter.period_start(millis("2024-01-10T00:00:00")).unwrap();
+        assert_eq!(quarter.period_label(start), "2023-11-01");
+        assert_eq!(quarter.period_label(quarter.next_period(start).unwrap()), "2024-02-01");
+        assert_eq!(quarter.period_bounds("2023-11-01"), Some(("2023-11".to_string(), "2024-01".to_string())));
+
+        let week = bucketing(TimeGranularity::Week, 1);
+        assert_eq!(week.period_bounds("2024-02-26"), Some(("2024-02-26".to_string(), "2024-03-03".to_string())));
+
+        let hour = bucketing(TimeGranularity::Hour, 1);
+        assert_eq!(
+            hour.period_bounds("2024-02-15T13:00:00"),
+            Some(("2024-02-15T13".to_string(), "2024-02-15T13".to_string()))
+        );
+        assert_eq!(hour.period_bounds("Feb"), None);
+    }
+
+    #[test]
+    fn test_same_period_last_year() {
+        assert_eq!(same_period_last_year("2024-03-01", Some(TimeGranularity::Month)).as_deref(), Some("2023-03-01"));
+        assert_eq!(same_period_last_year("2024-02-26", Some(TimeGranularity::Week)).as_deref(), Some("2023-02-27"));
+        assert_eq!(same_period_last_year("2024-02-29", None), None);
+        assert_eq!(same_period_last_year("2024-02-15T13:00:00", None).as_deref(), Some("2023-02-15T13:00:00"));
+        assert_eq!(same_period_last_year("Feb", None), None);
+    }
+}
@@ -1,9 +1,11 @@
 //! Data transformation utilities
 
-use super::aggregation::AggregationFunction;
-use crate::features::dashboard::models::{DataMapping, Dataset, Field, FieldType};
+use super::aggregation::{aggregate_field, number_or_null};
+use super::time::MAX_FILLED_PERIODS;
+use crate::features::dashboard::models::{DataMapping, Dataset, Field, FieldFormat, FieldType};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 /// Error types for data transformation
 #[derive(Clone, Debug, PartialEq)]
@@ -77,15 +79,117 @@ pub fn find_field_indexes(
         .collect()
 }
 
-/// Transform dataset data to ECharts 2D array format
+/// Most series the category field splits measures into, unless the
+/// mapping sets its own limit
+pub const DEFAULT_SERIES_LIMIT: usize = 10;
+
+/// Label of the series merging the categories beyond the series limit, and
+/// of the item merging those beyond a top-N limit
+pub const OTHER_SERIES: &str = "Other";
+
+/// Order of the items of a categorical chart or table
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum SortOrder {
+    /// By dimension value (chronological for dates)
+    #[default]
+    Dimension,
+    /// By the first measure, smallest first
+    MeasureAscending,
+    /// By the first measure, largest first
+    MeasureDescending,
+    /// In the order of the listed dimension labels; unlisted items follow
+    /// in dimension order
+    Manual(Vec<String>),
+}
+
+/// Items of a chart or table, ordered and limited for display
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RankedItems {
+    /// Items shown, in display order
+    pub shown: Vec<usize>,
+    /// Items beyond the top-N limit, in dimension order, to be merged into
+    /// one "Other" item after the shown ones
+    pub others: Vec<usize>,
+}
+
+/// Order items and keep the `top_n` with the largest measure
+///
+/// # Arguments
+/// * `labels` - Dimension label of each item, the items being in dimension order
+/// * `values` - First measure of each item (`None` ranks below any number)
+/// * `sort` - Order of the items shown
+/// * `top_n` - Most items shown, if limited
+pub fn rank_items(
+    labels: &[Option<String>],
+    values: &[Option<f64>],
+    sort: &SortOrder,
+    top_n: Option<usize>,
+) -> RankedItems {
+    let by_measure = |descending: bool| {
+        move |a: &usize, b: &usize| match (values[*a], values[*b]) {
+            (Some(a), Some(b)) if descending => b.total_cmp(&a),
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            (a, b) => b.is_some().cmp(&a.is_some()),
+        }
+    };
+
+    // Sorts are stable, so ties keep dimension order
+    let mut shown: Vec<usize> = (0..labels.len()).collect();
+    let mut others = Vec::new();
+    if let Some(limit) = top_n.map(|limit| limit.max(1))
+        && shown.len() > limit
+    {
+        shown.sort_by(by_measure(true));
+        others = shown.split_off(limit);
+        others.sort_unstable();
+        shown.sort_unstable();
+    }
+
+    match sort {
+        SortOrder::Dimension => {}
+        SortOrder::MeasureAscending => shown.sort_by(by_measure(false)),
+        SortOrder::MeasureDescending => shown.sort_by(by_measure(true)),
+        SortOrder::Manual(order) => shown.sort_by_key(|&idx| {
+            labels[idx]
+                .as_ref()
+                .and_then(|label| order.iter().position(|listed| listed == label))
+                .unwrap_or(order.len())
+        }),
+    }
+    RankedItems { shown, others }
+}
+
+/// What series split by category show at x values the category has no
+/// rows for
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MissingValues {
+    /// A gap
+    #[default]
+    Null,
+    /// Zero, so stacks and lines stay continuous
+    Zero,
+}
+
+/// Transform dataset data to an ECharts 2D array, with the measure of each
+/// of its series
+///
+/// Rows are grouped by the x-axis field, with one column per measure; date
+/// fields are ordered chronologically and, with a time bucketing, grouped
+/// into periods. Groups are then put in the mapping's sort order, those
+/// beyond its top-N limit merged into an "Other" row. With a category
+/// field, each measure is split into one column per category value (rows
+/// with a null category are left out); beyond the mapping's series limit
+/// the smallest categories, by their first measure, are merged into an
+/// "Other" column.
 ///
 /// # Arguments
 /// * `dataset` - The dataset with fields and raw data
-/// * `mapping` - Field mapping configuration
-/// * `agg_fn` - Aggregation function to apply
+/// * `mapping` - Field mapping configuration, including the aggregation
+///   function of each measure
 ///
 /// # Returns
-/// ECharts-compatible 2D array (header row + data rows)
+/// ECharts-compatible 2D array (header row + data rows), and the measure
+/// of each column after the x values
 ///
 /// # Example
 /// For Line chart with x_axis="Month" and y_axis="Sales":
@@ -97,11 +201,12 @@ pub fn find_field_indexes(
 ///   ...
 /// ]
 /// ```
-pub fn dataset_to_echarts_format(
+/// With category="Region" the header becomes `["Month", "North", "South"]`
+/// (`"North · Sales"` and so on when there are several measures).
+fn series_table(
     dataset: &Dataset,
     mapping: &DataMapping,
-    agg_fn: AggregationFunction,
-) -> Result<Vec<Vec<Value>>, TransformError> {
+) -> Result<(Vec<Vec<Value>>, Vec<String>), TransformError> {
     // Find field indexes
     let x_idx = find_field_index(&dataset.fields, &mapping.x_axis)?;
     let y_idxs = find_field_indexes(&dataset.fields, &mapping.y_axis)?;
@@ -112,109 +217,353 @@ pub fn dataset_to_echarts_format(
         ));
     }
 
+    let split = match &mapping.category {
+        Some(_) => Some(CategorySplit::new(
+            dataset,
+            find_field_index(&dataset.fields, &mapping.category)?,
+            y_idxs[0],
+            mapping,
+        )),
+        None => None,
+    };
+
     // Build header row
     let mut header = vec![Value::String(dataset.fields[x_idx].name.clone())];
-    header.extend(
-        y_idxs
-            .iter()
-            .map(|&idx| Value::String(dataset.fields[idx].name.clone())),
-    );
+    let mut measures = Vec::new();
+    for name in &mapping.y_axis {
+        match &split {
+            Some(split) => {
+                for label in &split.labels {
+                    header.push(Value::String(if y_idxs.len() == 1 {
+                        label.clone()
+                    } else {
+                        format!("{} · {}", label, name)
+                    }));
+                    measures.push(name.clone());
+                }
+            }
+            None => {
+                header.push(Value::String(name.clone()));
+                measures.push(name.clone());
+            }
+        }
+    }
 
     // Transform data rows
     let mut echarts_data = vec![header];
 
-    // Group by x_axis value and aggregate y_axis values
-    let mut grouped_data: HashMap<String, Vec<Vec<Value>>> = HashMap::new();
+    // Group rows by x_axis value and aggregate y_axis values per group
+    let groups = rank_groups(dataset, x_groups(dataset, x_idx, mapping), y_idxs[0], mapping);
+    for (x_val, rows) in groups {
+        let mut data_row = vec![x_val];
+        let buckets = match &split {
+            Some(split) => split.partition(&rows),
+            None => vec![rows],
+        };
+
+        // Aggregate each y field (all-null groups stay null so charts show a gap);
+        // percent fields are scaled to display units to match the axis labels
+        for (&y_idx, name) in y_idxs.iter().zip(&mapping.y_axis) {
+            let agg_fn = mapping.aggregation(name);
+            for bucket in &buckets {
+                // No rows: a category absent at this x value, or a filled gap
+                if bucket.is_empty() {
+                    data_row.push(match mapping.missing_values {
+                        MissingValues::Null => Value::Null,
+                        MissingValues::Zero => Value::from(0),
+                    });
+                    continue;
+                }
+                let aggregated = aggregate_field(dataset, y_idx, bucket.iter().copied(), &agg_fn);
+                let display = match &dataset.fields[y_idx].format {
+                    Some(format) if !agg_fn.is_count() => aggregated.map(|value| format.display_value(value)),
+                    _ => aggregated,
+                };
+                data_row.push(number_or_null(display));
+            }
+        }
+
+        echarts_data.push(data_row);
+    }
+
+    apply_calculations(&mut echarts_data[1..], &measures, mapping);
+
+    Ok((echarts_data, measures))
+}
+
+/// Groups in the mapping's sort order, those beyond its top-N limit
+/// (by the first measure) merged into one "Other" group at the end
+fn rank_groups(
+    dataset: &Dataset,
+    groups: Vec<(Value, Vec<usize>)>,
+    first_measure: usize,
+    mapping: &DataMapping,
+) -> Vec<(Value, Vec<usize>)> {
+    if mapping.sort == SortOrder::Dimension && mapping.top_n.is_none() {
+        return groups;
+    }
+    let labels: Vec<Option<String>> = groups.iter().map(|(x, _)| value_label(x)).collect();
+    let agg_fn = mapping.aggregation(&dataset.fields[first_measure].name);
+    let values: Vec<Option<f64>> = groups
+        .iter()
+        .map(|(_, rows)| aggregate_field(dataset, first_measure, rows.iter().copied(), &agg_fn))
+        .collect();
+    let ranked = rank_items(&labels, &values, &mapping.sort, mapping.top_n);
+
+    let mut groups: Vec<Option<(Value, Vec<usize>)>> = groups.into_iter().map(Some).collect();
+    let mut ranked_groups: Vec<(Value, Vec<usize>)> =
+        ranked.shown.iter().filter_map(|&idx| groups[idx].take()).collect();
+    if !ranked.others.is_empty() {
+        let rows = ranked.others.iter().filter_map(|&idx| groups[idx].take()).flat_map(|(_, rows)| rows).collect();
+        ranked_groups.push((Value::from(OTHER_SERIES), rows));
+    }
+    ranked_groups
+}
 
-    for row in &dataset.data {
-        if let Some(x_val) = row.get(x_idx) {
-            let x_key = serde_json::to_string(x_val).unwrap_or_default();
+/// Replace the aggregated values of measures with a window calculation by
+/// its results, down each column in display order
+///
+/// Rows without an x value are off the axis, so their calculated values
+/// are left empty.
+fn apply_calculations(rows: &mut [Vec<Value>], measures: &[String], mapping: &DataMapping) {
+    let on_axis: Vec<usize> = (0..rows.len()).filter(|&row| !rows[row][0].is_null()).collect();
+    let periods: Vec<Value> = on_axis.iter().map(|&row| rows[row][0].clone()).collect();
+    let granularity = mapping.time_bucketing.map(|bucketing| bucketing.granularity);
 
-            grouped_data.entry(x_key).or_default().push(row.clone());
+    for (idx, measure) in measures.iter().enumerate() {
+        let Some(calculation) = mapping.calculation(measure) else {
+            continue;
+        };
+        let column = idx + 1;
+        let values: Vec<Option<f64>> = on_axis.iter().map(|&row| rows[row][column].as_f64()).collect();
+        let results = calculation.apply(&periods, &values, granularity);
+        for row in rows.iter_mut() {
+            row[column] = Value::Null;
+        }
+        for (&row, result) in on_axis.iter().zip(results) {
+            rows[row][column] = number_or_null(result);
         }
     }
+}
 
-    // Aggregate and build final data
-    for (x_key, rows) in grouped_data {
-        let x_val: Value = serde_json::from_str(&x_key).unwrap_or(Value::Null);
+/// Rows of each x value, in axis order (null x values last)
+///
+/// Dates are ordered by timestamp; with a time bucketing they are grouped
+/// into periods, labelled by their start, and gaps between the first and
+/// last period are filled if asked (up to `MAX_FILLED_PERIODS`).
+fn x_groups(dataset: &Dataset, x_idx: usize, mapping: &DataMapping) -> Vec<(Value, Vec<usize>)> {
+    let column = dataset.data.column(x_idx);
+    let temporal = dataset.fields[x_idx].field_type == FieldType::Date;
 
-        let mut data_row = vec![x_val];
+    if temporal && let Some(bucketing) = &mapping.time_bucketing {
+        let mut periods = BTreeMap::new();
+        let mut undated = Vec::new();
+        for row in 0..column.len() {
+            match column.timestamp(row).and_then(|millis| bucketing.period_start(millis)) {
+                Some(start) => periods.entry(start).or_insert_with(Vec::new).push(row),
+                None => undated.push(row),
+            }
+        }
+
+        if bucketing.fill_gaps
+            && let (Some(&first), Some(&last)) = (periods.keys().next(), periods.keys().next_back())
+        {
+            let mut period = Some(first);
+            for _ in 0..MAX_FILLED_PERIODS {
+                let Some(start) = period.filter(|start| *start < last) else {
+                    break;
+                };
+                periods.entry(start).or_default();
+                period = bucketing.next_period(start);
+            }
+        }
+
+        let mut groups: Vec<(Value, Vec<usize>)> = periods
+            .into_iter()
+            .map(|(start, rows)| (Value::String(bucketing.period_label(start)), rows))
+            .collect();
+        if !undated.is_empty() {
+            groups.push((Value::Null, undated));
+        }
+        return groups;
+    }
+
+    let groups = column.group_rows();
+    let members = groups.members();
+    let mut groups: Vec<(Value, Vec<usize>)> = groups.keys.into_iter().zip(members).collect();
+    if temporal {
+        // Chronological whatever the text, undated values last
+        groups.sort_by_key(|(_, rows)| {
+            let millis = column.timestamp(rows[0]);
+            (millis.is_none(), millis)
+        });
+    } else {
+        groups.sort_by(|a, b| compare_values(&a.0, &b.0));
+    }
+    groups
+}
 
-        // Aggregate each y field
-        for &y_idx in &y_idxs {
-            let values: Vec<f64> = rows
+/// Series the category field splits a measure into
+struct CategorySplit {
+    /// Label of each series, in column order
+    labels: Vec<String>,
+    /// Series of each row, `None` for rows with a null category
+    row_series: Vec<Option<usize>>,
+}
+
+impl CategorySplit {
+    fn new(dataset: &Dataset, category_idx: usize, first_measure: usize, mapping: &DataMapping) -> Self {
+        let groups = dataset.data.column(category_idx).group_rows();
+        let mut categories: Vec<usize> = (0..groups.keys.len()).filter(|&code| !groups.keys[code].is_null()).collect();
+
+        // Over the limit, keep the largest categories and merge the rest
+        let limit = mapping.series_limit.unwrap_or(DEFAULT_SERIES_LIMIT).max(1);
+        let mut merged = Vec::new();
+        if categories.len() > limit {
+            let members = groups.members();
+            let agg_fn = mapping.aggregation(&dataset.fields[first_measure].name);
+            let sizes: Vec<f64> = members
                 .iter()
-                .filter_map(|row| row.get(y_idx))
-                .filter_map(|v| v.as_f64())
+                .map(|rows| {
+                    aggregate_field(dataset, first_measure, rows.iter().copied(), &agg_fn).unwrap_or(f64::NEG_INFINITY)
+                })
                 .collect();
+            categories.sort_by(|&a, &b| sizes[b].total_cmp(&sizes[a]));
+            merged = categories.split_off(limit - 1);
+        }
+        categories.sort_by(|&a, &b| compare_values(&groups.keys[a], &groups.keys[b]));
 
-            let aggregated = if values.is_empty() {
-                0.0
-            } else {
-                apply_aggregation(&values, agg_fn)
-            };
-
-            data_row.push(Value::Number(
-                serde_json::Number::from_f64(aggregated).unwrap_or(serde_json::Number::from(0)),
-            ));
+        let mut code_series = vec![None; groups.keys.len()];
+        let mut labels = Vec::with_capacity(categories.len() + 1);
+        for (series, &code) in categories.iter().enumerate() {
+            code_series[code] = Some(series);
+            labels.push(value_label(&groups.keys[code]).unwrap_or_default());
+        }
+        if !merged.is_empty() {
+            for &code in &merged {
+                code_series[code] = Some(labels.len());
+            }
+            labels.push(OTHER_SERIES.to_string());
         }
 
-        echarts_data.push(data_row);
+        let row_series = groups.codes.iter().map(|&code| code_series[code as usize]).collect();
+        Self { labels, row_series }
     }
 
-    // Sort by x-axis value (for proper ordering)
-    echarts_data.sort_by(|a, b| {
-        if a.len() > 1 && b.len() > 1 {
-            // Compare x-axis values (index 0)
-            match (&a[0], &b[0]) {
-                (Value::String(s1), Value::String(s2)) => s1.cmp(s2),
-                (Value::Number(n1), Value::Number(n2)) => n1
-                    .as_f64()
-                    .partial_cmp(&n2.as_f64())
-                    .unwrap_or(std::cmp::Ordering::Equal),
-                _ => std::cmp::Ordering::Equal,
+    /// Rows of each series among `rows`
+    fn partition(&self, rows: &[usize]) -> Vec<Vec<usize>> {
+        let mut buckets = vec![Vec::new(); self.labels.len()];
+        for &row in rows {
+            if let Some(series) = self.row_series[row] {
+                buckets[series].push(row);
             }
+        }
+        buckets
+    }
+}
+
+/// Aggregated table of the series widgets (line, bar, area, pie)
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SeriesData {
+    /// ECharts dataset source: header row, then one row per x value
+    pub source: Vec<Vec<Value>>,
+    /// Currency / percent notation shared by the measures
+    pub format: Option<FieldFormat>,
+    /// Whether the x values are dates in date order, drawn on a time axis
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub temporal: bool,
+    /// Measure of each series, when the category field splits them (series
+    /// of the same measure stack together)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub split_measures: Vec<String>,
+}
+
+impl SeriesData {
+    /// Number of series: the columns after the x values
+    pub fn series_count(&self) -> usize {
+        self.source.first().map_or(0, |header| header.len().saturating_sub(1))
+    }
+
+    /// Stack a series belongs to when the chart is stacked
+    pub fn stack(&self, series: usize) -> String {
+        self.split_measures.get(series).cloned().unwrap_or_else(|| "total".to_string())
+    }
+}
+
+/// Aggregate a dataset into the table and measure format the series
+/// widgets are built from
+pub fn series_data(dataset: &Dataset, mapping: &DataMapping) -> Result<SeriesData, TransformError> {
+    let (source, measures) = series_table(dataset, mapping)?;
+    // Dates put in a measure's order, or cut with an "Other" item, are
+    // categories rather than points in time
+    let ranked = mapping.sort != SortOrder::Dimension || mapping.top_n.is_some();
+    let temporal = !ranked
+        && find_field_index(&dataset.fields, &mapping.x_axis)
+            .is_ok_and(|idx| dataset.fields[idx].field_type == FieldType::Date);
+    Ok(SeriesData {
+        source,
+        format: measure_format(dataset, mapping),
+        temporal,
+        split_measures: if mapping.category.is_some() { measures } else { Vec::new() },
+    })
+}
+
+/// Display format shared by all measures of a mapping
+///
+/// Returns `None` when the y-axis fields are plain numbers (counts and
+/// ranks included) or use different notations (e.g. a currency and a
+/// percentage).
+pub fn measure_format(dataset: &Dataset, mapping: &DataMapping) -> Option<FieldFormat> {
+    let mut formats = mapping.y_axis.iter().map(|name| {
+        let format = if mapping.aggregation(name).is_count() {
+            None
         } else {
-            std::cmp::Ordering::Equal
+            dataset.fields.iter().find(|f| &f.name == name).and_then(|f| f.format.as_ref())
+        };
+        match mapping.calculation(name) {
+            Some(calculation) => calculation.output_format(format),
+            None => format.cloned(),
         }
     });
 
-    Ok(echarts_data)
+    let first = formats.next()??;
+    formats.all(|format| format.as_ref() == Some(&first)).then_some(first)
 }
 
-/// Apply aggregation function to values (internal helper)
-fn apply_aggregation(values: &[f64], agg_fn: AggregationFunction) -> f64 {
-    match agg_fn {
-        AggregationFunction::Sum => values.iter().sum(),
-        AggregationFunction::Avg => {
-            if values.is_empty() {
-                0.0
-            } else {
-                values.iter().sum::<f64>() / values.len() as f64
-            }
-        }
-        AggregationFunction::Count => values.len() as f64,
-        AggregationFunction::Min => values.iter().fold(f64::INFINITY, |a, &b| a.min(b)),
-        AggregationFunction::Max => values.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b)),
-        AggregationFunction::Median => {
-            let mut sorted = values.to_vec();
-            sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
-            let len = sorted.len();
-            if len == 0 {
-                0.0
-            } else if len.is_multiple_of(2) {
-                (sorted[len / 2 - 1] + sorted[len / 2]) / 2.0
-            } else {
-                sorted[len / 2]
-            }
-        }
-        AggregationFunction::None => values.first().copied().unwrap_or(0.0),
+/// Order two dimension values: numbers numerically, strings lexically,
+/// nulls last
+pub fn compare_values(a: &Value, b: &Value) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+
+    match (a, b) {
+        (Value::String(s1), Value::String(s2)) => s1.cmp(s2),
+        (Value::Number(n1), Value::Number(n2)) => n1
+            .as_f64()
+            .partial_cmp(&n2.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => Ordering::Greater,
+        (_, Value::Null) => Ordering::Less,
+        _ => Ordering::Equal,
+    }
+}
+
+/// Text label for a dimension value, `None` for nulls
+///
+/// Used where values become category names (heatmap axes, treemap nodes),
+/// so numeric and boolean categories are not lost.
+pub fn value_label(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::features::dashboard::data::{AggregationFunction, DataTable};
     use crate::features::dashboard::models::Field;
 
     #[test]
@@ -223,10 +572,12 @@ mod tests {
             Field {
                 name: "Name".to_string(),
                 field_type: FieldType::Text,
+                format: None,
             },
             Field {
                 name: "Value".to_string(),
                 field_type: FieldType::Numeric,
+                format: None,
             },
         ];
 
@@ -238,29 +589,370 @@ mod tests {
     }
 
     #[test]
-    fn test_dataset_to_echarts_format() {
+    fn test_series_source() {
         let fields = vec![
             Field {
                 name: "Month".to_string(),
                 field_type: FieldType::Text,
+                format: None,
             },
             Field {
                 name: "Sales".to_string(),
                 field_type: FieldType::Numeric,
+                format: None,
             },
         ];
 
-        let _dataset = Dataset {
+        let dataset = Dataset {
+            id: "test".to_string(),
+            name: "Test".to_string(),
+            size: "0 MB".to_string(),
+            uploaded_at: "Today".to_string(),
+            data: DataTable::from_rows(&fields, vec![
+                vec![Value::from("Jan"), Value::from(10)],
+                vec![Value::from("Feb"), Value::from(20)],
+                vec![Value::from("Jan"), Value::from(5)],
+            ]),
+            fields,
+            active: true,
+            dialect: None,
+            calculated_fields: Vec::new(),
+            derived_from: None,
+        };
+        let mapping = DataMapping {
+            x_axis: Some("Month".to_string()),
+            y_axis: vec!["Sales".to_string()],
+            ..Default::default()
+        };
+
+        // One row per month, in label order, summing its sales
+        let (source, measures) = series_table(&dataset, &mapping).unwrap();
+        assert_eq!(source, vec![
+            vec![Value::from("Month"), Value::from("Sales")],
+            vec![Value::from("Feb"), Value::from(20.0)],
+            vec![Value::from("Jan"), Value::from(15.0)],
+        ]);
+        assert_eq!(measures, vec!["Sales"]);
+    }
+
+    #[test]
+    fn test_series_source_keeps_nulls_explicit() {
+        use serde_json::json;
+
+        let fields = vec![
+            Field { name: "Month".to_string(), field_type: FieldType::Text, format: None },
+            Field { name: "Sales".to_string(), field_type: FieldType::Numeric, format: None },
+        ];
+        let dataset = Dataset {
+            id: "test".to_string(),
+            name: "Test".to_string(),
+            size: "0 MB".to_string(),
+            uploaded_at: "Today".to_string(),
+            data: DataTable::from_rows(&fields, vec![
+                vec![json!("Feb"), json!(5)],
+                vec![json!("Jan"), json!(10)],
+                vec![json!("Jan"), Value::Null],
+                vec![json!("Mar"), Value::Null],
+                vec![Value::Null, json!(1)],
+            ]),
+            fields,
+            active: true,
+            dialect: None,
+            calculated_fields: Vec::new(),
+            derived_from: None,
+        };
+        let mut mapping = DataMapping {
+            x_axis: Some("Month".to_string()),
+            y_axis: vec!["Sales".to_string()],
+            ..Default::default()
+        };
+        mapping.set_aggregation("Sales", AggregationFunction::Avg);
+
+        let result = series_data(&dataset, &mapping).unwrap().source;
+        assert_eq!(result[0], vec![json!("Month"), json!("Sales")]);
+        assert_eq!(result[1], vec![json!("Feb"), json!(5.0)]);
+        // The null in January is skipped, not averaged in as 0
+        assert_eq!(result[2], vec![json!("Jan"), json!(10.0)]);
+        // An all-null group stays null so the chart shows a gap
+        assert_eq!(result[3], vec![json!("Mar"), Value::Null]);
+        // Rows without a dimension value are grouped last
+        assert_eq!(result[4], vec![Value::Null, json!(1.0)]);
+    }
+
+    #[test]
+    fn test_percent_measures_use_display_units() {
+        use serde_json::json;
+
+        let percent = FieldFormat { currency: None, percent: true };
+        let fields = vec![
+            Field { name: "Month".to_string(), field_type: FieldType::Text, format: None },
+            Field { name: "Share".to_string(), field_type: FieldType::Numeric, format: Some(percent.clone()) },
+        ];
+        let dataset = Dataset {
             id: "test".to_string(),
             name: "Test".to_string(),
             size: "0 MB".to_string(),
             uploaded_at: "Today".to_string(),
-            fields: fields.clone(),
+            data: DataTable::from_rows(&fields, vec![vec![json!("Jan"), json!(0.25)]]),
+            fields,
             active: true,
-            data: Vec::new(),
+            dialect: None,
+            calculated_fields: Vec::new(),
+            derived_from: None,
+        };
+        let mut mapping = DataMapping {
+            x_axis: Some("Month".to_string()),
+            y_axis: vec!["Share".to_string()],
+            ..Default::default()
         };
 
-        // Note: This test assumes dataset.data exists, but the struct doesn't have it yet
-        // We'll need to add the `data` field to the Dataset struct
+        let result = series_data(&dataset, &mapping).unwrap().source;
+        assert_eq!(result[1], vec![json!("Jan"), json!(25.0)]);
+        assert_eq!(measure_format(&dataset, &mapping), Some(percent.clone()));
+        assert_eq!(percent.axis_label_template(), "{value}%");
+
+        // A count of percent values is a plain number
+        mapping.set_aggregation("Share", AggregationFunction::Count);
+        let result = series_data(&dataset, &mapping).unwrap().source;
+        assert_eq!(result[1], vec![json!("Jan"), json!(1.0)]);
+        assert_eq!(measure_format(&dataset, &mapping), None);
+    }
+
+    #[test]
+    fn test_each_measure_uses_its_aggregation() {
+        use serde_json::json;
+
+        let fields = vec![
+            Field { name: "Region".to_string(), field_type: FieldType::Text, format: None },
+            Field { name: "Sales".to_string(), field_type: FieldType::Numeric, format: None },
+            Field { name: "Price".to_string(), field_type: FieldType::Numeric, format: None },
+        ];
+        let dataset = Dataset {
+            id: "test".to_string(),
+            name: "Test".to_string(),
+            size: "0 MB".to_string(),
+            uploaded_at: "Today".to_string(),
+            data: DataTable::from_rows(&fields, vec![
+                vec![json!("North"), json!(10), json!(4)],
+                vec![json!("North"), json!(30), json!(8)],
+            ]),
+            fields,
+            active: true,
+            dialect: None,
+            calculated_fields: Vec::new(),
+            derived_from: None,
+        };
+        let mut mapping = DataMapping {
+            x_axis: Some("Region".to_string()),
+            y_axis: vec!["Sales".to_string(), "Price".to_string()],
+            ..Default::default()
+        };
+        mapping.set_aggregation("Price", AggregationFunction::Avg);
+
+        let result = series_data(&dataset, &mapping).unwrap().source;
+        // Sales has no explicit aggregation and is summed
+        assert_eq!(result[1], vec![json!("North"), json!(40.0), json!(6.0)]);
+
+        // Aggregations of measures dropped from the mapping are not kept
+        let narrowed = DataMapping { y_axis: vec!["Sales".to_string()], ..mapping.clone() };
+        assert!(narrowed.clone().with_settings_from(&mapping).aggregations.is_empty());
+        let same = DataMapping { aggregations: Default::default(), ..mapping.clone() };
+        assert_eq!(same.with_settings_from(&mapping), mapping);
+    }
+
+    #[test]
+    fn test_category_splits_series() {
+        use serde_json::json;
+
+        let fields = vec![
+            Field { name: "Month".to_string(), field_type: FieldType::Text, format: None },
+            Field { name: "Region".to_string(), field_type: FieldType::Text, format: None },
+            Field { name: "Sales".to_string(), field_type: FieldType::Numeric, format: None },
+        ];
+        let dataset = Dataset {
+            id: "test".to_string(),
+            name: "Test".to_string(),
+            size: "0 MB".to_string(),
+            uploaded_at: "Today".to_string(),
+            data: DataTable::from_rows(&fields, vec![
+                vec![json!("Jan"), json!("South"), json!(5)],
+                vec![json!("Jan"), json!("North"), json!(10)],
+                vec![json!("Feb"), json!("North"), json!(7)],
+                vec![json!("Feb"), json!("East"), json!(1)],
+                vec![json!("Feb"), Value::Null, json!(100)],
+            ]),
+            fields,
+            active: true,
+            dialect: None,
+            calculated_fields: Vec::new(),
+            derived_from: None,
+        };
+        let mut mapping = DataMapping {
+            x_axis: Some("Month".to_string()),
+            y_axis: vec!["Sales".to_string()],
+            category: Some("Region".to_string()),
+            ..Default::default()
+        };
+
+        // One column per region; rows without a region are left out
+        let result = series_data(&dataset, &mapping).unwrap();
+        assert_eq!(result.source, vec![
+            vec![json!("Month"), json!("East"), json!("North"), json!("South")],
+            vec![json!("Feb"), json!(1.0), json!(7.0), Value::Null],
+            vec![json!("Jan"), Value::Null, json!(10.0), json!(5.0)],
+        ]);
+        assert_eq!(result.series_count(), 3);
+        assert_eq!(result.stack(2), "Sales");
+
+        // Missing combinations as zero; the smallest region merged into "Other"
+        mapping.missing_values = MissingValues::Zero;
+        mapping.series_limit = Some(2);
+        let result = series_data(&dataset, &mapping).unwrap().source;
+        assert_eq!(result, vec![
+            vec![json!("Month"), json!("North"), json!(OTHER_SERIES)],
+            vec![json!("Feb"), json!(7.0), json!(1.0)],
+            vec![json!("Jan"), json!(10.0), json!(5.0)],
+        ]);
+
+        // Without a category, all measures share one stack
+        mapping.category = None;
+        assert_eq!(series_data(&dataset, &mapping).unwrap().stack(0), "total");
+    }
+
+    #[test]
+    fn test_dates_bucketed_and_calculated_by_period() {
+        use crate::features::dashboard::data::time::{TimeBucketing, TimeGranularity};
+        use crate::features::dashboard::data::window::WindowCalculation;
+        use serde_json::json;
+
+        let fields = vec![
+            Field { name: "Day".to_string(), field_type: FieldType::Date, format: None },
+            Field { name: "Sales".to_string(), field_type: FieldType::Numeric, format: None },
+        ];
+        let dataset = Dataset {
+            id: "test".to_string(),
+            name: "Test".to_string(),
+            size: "0 MB".to_string(),
+            uploaded_at: "Today".to_string(),
+            data: DataTable::from_rows(&fields, vec![
+                vec![json!("2024-03-02"), json!(4)],
+                vec![Value::Null, json!(1)],
+                vec![json!("2024-01-20"), json!(2)],
+                vec![json!("2024-01-15"), json!(3)],
+            ]),
+            fields,
+            active: true,
+            dialect: None,
+            calculated_fields: Vec::new(),
+            derived_from: None,
+        };
+        let mut mapping = DataMapping {
+            x_axis: Some("Day".to_string()),
+            y_axis: vec!["Sales".to_string()],
+            ..Default::default()
+        };
+
+        // Exact dates, in date order with undated rows last
+        let result = series_data(&dataset, &mapping).unwrap();
+        assert!(result.temporal);
+        let days: Vec<&Value> = result.source[1..].iter().map(|row| &row[0]).collect();
+        assert_eq!(days, vec![&json!("2024-01-15"), &json!("2024-01-20"), &json!("2024-03-02"), &Value::Null]);
+
+        // Months, labelled by their first day
+        mapping.time_bucketing = Some(TimeBucketing { granularity: TimeGranularity::Month, ..Default::default() });
+        assert_eq!(series_data(&dataset, &mapping).unwrap().source, vec![
+            vec![json!("Day"), json!("Sales")],
+            vec![json!("2024-01-01"), json!(5.0)],
+            vec![json!("2024-03-01"), json!(4.0)],
+            vec![Value::Null, json!(1.0)],
+        ]);
+
+        // Filled gaps follow the missing values setting
+        mapping.time_bucketing = mapping.time_bucketing.map(|bucketing| TimeBucketing { fill_gaps: true, ..bucketing });
+        mapping.missing_values = MissingValues::Zero;
+        let months: Vec<Vec<Value>> = series_data(&dataset, &mapping).unwrap().source[1..3].to_vec();
+        assert_eq!(months, vec![vec![json!("2024-01-01"), json!(5.0)], vec![json!("2024-02-01"), json!(0)]]);
+
+        // Window calculations run down the axis; undated rows are off it
+        mapping.set_calculation("Sales", Some(WindowCalculation::RunningSum));
+        let source = series_data(&dataset, &mapping).unwrap().source;
+        let totals: Vec<Value> = source[1..].iter().map(|row| row[1].clone()).collect();
+        assert_eq!(totals, vec![json!(5.0), json!(5.0), json!(9.0), Value::Null]);
+
+        mapping.set_calculation("Sales", Some(WindowCalculation::PercentOfTotal));
+        assert!(series_data(&dataset, &mapping).unwrap().format.is_some_and(|format| format.percent));
+
+        // Ranked periods are categories: the "Other" item isn't a date
+        mapping.set_calculation("Sales", None);
+        mapping.top_n = Some(1);
+        let result = series_data(&dataset, &mapping).unwrap();
+        assert!(!result.temporal);
+        assert_eq!(result.source.last().unwrap()[0], json!(OTHER_SERIES));
+    }
+
+    #[test]
+    fn test_rank_items() {
+        let labels: Vec<Option<String>> = ["A", "B", "C", "D"].iter().map(|l| Some(l.to_string())).collect();
+        let values = [Some(3.0), None, Some(5.0), Some(1.0)];
+        let rank = |sort: &SortOrder, top_n| rank_items(&labels, &values, sort, top_n);
+
+        assert_eq!(rank(&SortOrder::Dimension, None).shown, vec![0, 1, 2, 3]);
+        assert_eq!(rank(&SortOrder::MeasureDescending, None).shown, vec![2, 0, 3, 1]);
+        assert_eq!(rank(&SortOrder::MeasureAscending, None).shown, vec![3, 0, 2, 1]);
+
+        // The largest two are kept, in dimension order; the rest merge
+        assert_eq!(rank(&SortOrder::Dimension, Some(2)), RankedItems { shown: vec![0, 2], others: vec![1, 3] });
+
+        // Listed labels first, the others after them in dimension order
+        let manual = SortOrder::Manual(vec!["D".to_string(), "B".to_string(), "Z".to_string()]);
+        assert_eq!(rank(&manual, None).shown, vec![3, 1, 0, 2]);
+        assert_eq!(rank(&SortOrder::Dimension, Some(0)).shown, vec![2]);
+    }
+
+    #[test]
+    fn test_top_n_merges_other_groups() {
+        use serde_json::json;
+
+        let fields = vec![
+            Field { name: "Region".to_string(), field_type: FieldType::Text, format: None },
+            Field { name: "Sales".to_string(), field_type: FieldType::Numeric, format: None },
+        ];
+        let dataset = Dataset {
+            id: "test".to_string(),
+            name: "Test".to_string(),
+            size: "0 MB".to_string(),
+            uploaded_at: "Today".to_string(),
+            data: DataTable::from_rows(&fields, vec![
+                vec![json!("East"), json!(4)],
+                vec![json!("North"), json!(10)],
+                vec![json!("South"), json!(6)],
+                vec![json!("West"), json!(2)],
+                vec![json!("North"), json!(5)],
+            ]),
+            fields,
+            active: true,
+            dialect: None,
+            calculated_fields: Vec::new(),
+            derived_from: None,
+        };
+        let mut mapping = DataMapping {
+            x_axis: Some("Region".to_string()),
+            y_axis: vec!["Sales".to_string()],
+            sort: SortOrder::MeasureAscending,
+            top_n: Some(2),
+            ..Default::default()
+        };
+
+        // Other rows are aggregated again from their rows, not from the groups
+        assert_eq!(series_data(&dataset, &mapping).unwrap().source, vec![
+            vec![json!("Region"), json!("Sales")],
+            vec![json!("South"), json!(6.0)],
+            vec![json!("North"), json!(15.0)],
+            vec![json!(OTHER_SERIES), json!(6.0)],
+        ]);
+
+        mapping.set_aggregation("Sales", AggregationFunction::Avg);
+        let result = series_data(&dataset, &mapping).unwrap().source;
+        assert_eq!(result[3], vec![json!(OTHER_SERIES), json!(3.0)]);
     }
 }
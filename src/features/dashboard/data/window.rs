@@ -0,0 +1,230 @@
+//! Window calculations over aggregated series
+//!
+//! Applied after rows are grouped by x and aggregated: each value is
+//! computed from the values around it in axis order (running totals,
+//! moving averages, change from the previous period...).
+
+use super::aggregation::percentile;
+use super::time::{same_period_last_year, TimeGranularity};
+use crate::features::dashboard::models::FieldFormat;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Window of moving calculations until one is chosen
+pub const DEFAULT_WINDOW: usize = 7;
+
+/// Calculation applied to a measure's aggregated values
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WindowCalculation {
+    /// Sum of the values so far
+    RunningSum,
+    /// Average of the last `window` values (the current one included)
+    MovingAverage { window: usize },
+    /// Median of the last `window` values (the current one included)
+    MovingMedian { window: usize },
+    /// Change from the previous value
+    Difference,
+    /// Change from the previous value, in percent of it
+    PercentDifference,
+    /// Share of the total of all values, in percent
+    PercentOfTotal,
+    /// Position from the largest value (1), ties sharing a rank
+    Rank,
+    /// Value of the same period a year earlier (date dimensions only)
+    SamePeriodLastYear,
+}
+
+impl WindowCalculation {
+    /// All calculations, moving ones with the default window
+    pub fn all() -> [Self; 8] {
+        [
+            Self::RunningSum,
+            Self::MovingAverage { window: DEFAULT_WINDOW },
+            Self::MovingMedian { window: DEFAULT_WINDOW },
+            Self::Difference,
+            Self::PercentDifference,
+            Self::PercentOfTotal,
+            Self::Rank,
+            Self::SamePeriodLastYear,
+        ]
+    }
+
+    /// Name shown in pickers
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::RunningSum => "Running sum",
+            Self::MovingAverage { .. } => "Moving average",
+            Self::MovingMedian { .. } => "Moving median",
+            Self::Difference => "Difference from previous",
+            Self::PercentDifference => "% difference from previous",
+            Self::PercentOfTotal => "% of total",
+            Self::Rank => "Rank",
+            Self::SamePeriodLastYear => "Same period last year",
+        }
+    }
+
+    /// Number of values moving calculations cover
+    pub fn window(&self) -> Option<usize> {
+        match self {
+            Self::MovingAverage { window } | Self::MovingMedian { window } => Some(*window),
+            _ => None,
+        }
+    }
+
+    /// The same calculation over another window (unchanged for calculations
+    /// without one)
+    pub fn with_window(self, window: usize) -> Self {
+        match self {
+            Self::MovingAverage { .. } => Self::MovingAverage { window },
+            Self::MovingMedian { .. } => Self::MovingMedian { window },
+            other => other,
+        }
+    }
+
+    /// Display format of the results, from the format of the measure
+    ///
+    /// Percentages are in display units (`12.5` is 12.5%) and ranks are
+    /// plain numbers; other results keep the measure's notation.
+    pub fn output_format(&self, measure_format: Option<&FieldFormat>) -> Option<FieldFormat> {
+        match self {
+            Self::PercentDifference | Self::PercentOfTotal => Some(FieldFormat { currency: None, percent: true }),
+            Self::Rank => None,
+            _ => measure_format.cloned(),
+        }
+    }
+
+    /// Apply the calculation to a column of values
+    ///
+    /// # Arguments
+    /// * `periods` - Dimension value of each row, in axis order (used to
+    ///   find last year's periods)
+    /// * `values` - Aggregated value of each row (`None` for gaps)
+    /// * `granularity` - Periods the dimension is grouped into, if any
+    ///
+    /// # Returns
+    /// One result per row; gaps stay gaps except in running sums, which
+    /// carry the total so far
+    pub fn apply(
+        &self,
+        periods: &[Value],
+        values: &[Option<f64>],
+        granularity: Option<TimeGranularity>,
+    ) -> Vec<Option<f64>> {
+        let previous = |idx: usize| idx.checked_sub(1).and_then(|idx| values[idx]);
+        match self {
+            Self::RunningSum => {
+                let mut total = None;
+                values
+                    .iter()
+                    .map(|value| {
+                        if let Some(value) = value {
+                            total = Some(total.unwrap_or(0.0) + value);
+                        }
+                        total
+                    })
+                    .collect()
+            }
+            Self::MovingAverage { window } | Self::MovingMedian { window } => (0..values.len())
+                .map(|idx| {
+                    let start = (idx + 1).saturating_sub((*window).max(1));
+                    let covered: Vec<f64> = values[start..=idx].iter().flatten().copied().collect();
+                    if covered.is_empty() {
+                        return None;
+                    }
+                    Some(match self {
+                        Self::MovingAverage { .. } => covered.iter().sum::<f64>() / covered.len() as f64,
+                        _ => percentile(&covered, 50.0),
+                    })
+                })
+                .collect(),
+            Self::Difference => {
+                (0..values.len()).map(|idx| Some(values[idx]? - previous(idx)?)).collect()
+            }
+            Self::PercentDifference => (0..values.len())
+                .map(|idx| {
+                    let previous = previous(idx).filter(|previous| *previous != 0.0)?;
+                    Some((values[idx]? - previous) / previous.abs() * 100.0)
+                })
+                .collect(),
+            Self::PercentOfTotal => {
+                let total: f64 = values.iter().flatten().sum();
+                values
+                    .iter()
+                    .map(|value| value.filter(|_| total != 0.0).map(|value| value / total * 100.0))
+                    .collect()
+            }
+            Self::Rank => values
+                .iter()
+                .map(|value| {
+                    let value = (*value)?;
+                    Some(1.0 + values.iter().flatten().filter(|other| **other > value).count() as f64)
+                })
+                .collect(),
+            Self::SamePeriodLastYear => {
+                let by_period: HashMap<&str, Option<f64>> = periods
+                    .iter()
+                    .zip(values)
+                    .filter_map(|(period, value)| Some((period.as_str()?, *value)))
+                    .collect();
+                periods
+                    .iter()
+                    .map(|period| {
+                        let last_year = same_period_last_year(period.as_str()?, granularity)?;
+                        by_period.get(last_year.as_str()).copied().flatten()
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn apply(calculation: WindowCalculation, values: &[Option<f64>]) -> Vec<Option<f64>> {
+        let periods: Vec<Value> = (1..=values.len()).map(|month| json!(format!("2024-{:02}-01", month))).collect();
+        calculation.apply(&periods, values, Some(TimeGranularity::Month))
+    }
+
+    #[test]
+    fn test_running_and_moving_calculations() {
+        let values = [Some(1.0), None, Some(3.0), Some(8.0)];
+        assert_eq!(apply(WindowCalculation::RunningSum, &values), vec![Some(1.0), Some(1.0), Some(4.0), Some(12.0)]);
+        assert_eq!(
+            apply(WindowCalculation::MovingAverage { window: 2 }, &values),
+            vec![Some(1.0), Some(1.0), Some(3.0), Some(5.5)]
+        );
+        assert_eq!(
+            apply(WindowCalculation::MovingMedian { window: 3 }, &values),
+            vec![Some(1.0), Some(1.0), Some(2.0), Some(5.5)]
+        );
+    }
+
+    #[test]
+    fn test_change_share_and_rank() {
+        let values = [Some(10.0), Some(15.0), None, Some(15.0), Some(-5.0)];
+        assert_eq!(apply(WindowCalculation::Difference, &values), vec![None, Some(5.0), None, None, Some(-20.0)]);
+        assert_eq!(
+            apply(WindowCalculation::PercentDifference, &values),
+            vec![None, Some(50.0), None, None, Some(-(20.0 / 15.0) * 100.0)]
+        );
+        assert_eq!(
+            apply(WindowCalculation::PercentOfTotal, &values),
+            vec![Some(28.57142857142857), Some(42.857142857142854), None, Some(42.857142857142854), Some(-14.285714285714285)]
+        );
+        assert_eq!(apply(WindowCalculation::Rank, &values), vec![Some(3.0), Some(1.0), None, Some(1.0), Some(4.0)]);
+    }
+
+    #[test]
+    fn test_same_period_last_year() {
+        let periods = [json!("2023-01-01"), json!("2023-02-01"), json!("2024-01-01"), json!("2024-03-01")];
+        let values = [Some(1.0), Some(2.0), Some(3.0), Some(4.0)];
+        assert_eq!(
+            WindowCalculation::SamePeriodLastYear.apply(&periods, &values, Some(TimeGranularity::Month)),
+            vec![None, None, Some(1.0), None]
+        );
+    }
+}
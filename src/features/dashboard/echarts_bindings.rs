@@ -60,4 +60,18 @@ extern "C" {
     /// Hide loading animation
     #[wasm_bindgen(method, js_name = hideLoading)]
     pub fn hide_loading(this: &ECharts);
+
+    /// Register an event handler
+    ///
+    /// # Arguments
+    /// * `event` - Event name, e.g. `"click"` or `"brushSelected"`
+    /// * `handler` - Called with the event's parameters object
+    ///
+    /// Handlers are removed when the chart is disposed.
+    #[wasm_bindgen(method)]
+    pub fn on(this: &ECharts, event: &str, handler: &js_sys::Function);
+
+    /// Trigger a chart action, e.g. `{ type: "select", name: [...] }`
+    #[wasm_bindgen(method, js_name = dispatchAction)]
+    pub fn dispatch_action(this: &ECharts, action: &JsValue);
 }
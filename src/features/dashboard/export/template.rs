@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 use super::super::models::*;
+use super::super::data::filter::GlobalFilters;
+use super::super::data::derived::DerivedFrom;
 use super::super::context::DashboardContext;
 use leptos::prelude::GetUntracked;
 
@@ -21,6 +23,10 @@ pub struct DashboardTemplate {
 
     /// Layers (widget visibility/lock state)
     pub layers: Vec<Layer>,
+
+    /// Selections of the filter controls
+    #[serde(default, skip_serializing_if = "GlobalFilters::is_empty")]
+    pub global_filters: GlobalFilters,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -56,6 +62,24 @@ pub struct DatasetExport {
     /// Relative path in .zip bundle: "./data/{filename}"
     #[serde(skip_serializing_if = "Option::is_none")]
     pub csv_path: Option<String>,
+
+    /// CSV dialect the dataset was imported with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dialect: Option<CsvDialect>,
+
+    /// Calculated fields, recomputed on import. `fields` and `data` only
+    /// hold the source fields.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub calculated_fields: Vec<CalculatedField>,
+
+    /// How a derived (reshaped or joined) dataset is computed, so it is
+    /// computed again on import
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub derived_from: Option<DerivedFrom>,
+
+    /// Whether the dataset was the active one
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub active: bool,
 }
 
 /// Current schema version (SchemaVer format: MODEL-REVISION-ADDITION)
@@ -72,17 +96,27 @@ impl DashboardTemplate {
         let widgets = ctx.widgets.get_untracked();
         let layers = ctx.layers.get_untracked();
         let title = ctx.title.get_untracked();
+        let global_filters = ctx.global_filters.get_untracked();
+
+        let datasets_export = datasets.into_iter().map(|mut ds| {
+            // Calculated columns are recomputed on import, not stored
+            let source_fields = ds.source_field_count();
+            ds.fields.truncate(source_fields);
+            ds.data.truncate_columns(source_fields);
 
-        let datasets_export = datasets.into_iter().map(|ds| {
             DatasetExport {
                 id: ds.id,
                 name: ds.name.clone(),
                 fields: ds.fields,
                 data: match template_type {
                     TemplateType::Generic => None,  // No data for generic templates
-                    TemplateType::Complete => Some(ds.data),
+                    TemplateType::Complete => Some(ds.data.rows().collect()),
                 },
                 csv_path: None,
+                dialect: ds.dialect,
+                calculated_fields: ds.calculated_fields,
+                derived_from: ds.derived_from,
+                active: ds.active,
             }
         }).collect();
 
@@ -99,6 +133,7 @@ impl DashboardTemplate {
             widgets,
             datasets: datasets_export,
             layers,
+            global_filters,
         }
     }
 
@@ -121,6 +156,34 @@ impl DashboardTemplate {
         Ok(template)
     }
 
+    /// Bind widgets saved before they named their dataset
+    ///
+    /// Saves of that time don't record which dataset was active either, so
+    /// each widget goes to the first dataset having every field its mapping
+    /// refers to, the one recorded as active first. Widgets no dataset fits
+    /// go to the active dataset, or else the first one.
+    pub fn bind_unbound_widgets(&mut self) {
+        let mut candidates: Vec<&DatasetExport> = self.datasets.iter().collect();
+        candidates.sort_by_key(|ds| !ds.active);
+        let Some(fallback) = candidates.first().map(|ds| ds.id.clone()) else {
+            return;
+        };
+        let has_field = |ds: &DatasetExport, name: &str| {
+            ds.fields.iter().any(|field| field.name == name)
+                || ds.calculated_fields.iter().any(|calc| calc.name == name)
+        };
+
+        for widget in &mut self.widgets {
+            let config = &mut widget.chart_config;
+            if config.dataset_id.is_some() {
+                continue;
+            }
+            let fields = config.data_mapping.fields();
+            let fitting = candidates.iter().find(|ds| fields.iter().all(|name| has_field(ds, name)));
+            config.dataset_id = Some(fitting.map_or_else(|| fallback.clone(), |ds| ds.id.clone()));
+        }
+    }
+
     /// Check if this template uses a legacy version format
     pub fn is_legacy_version(&self) -> bool {
         self.version == "1.0"
@@ -131,3 +194,146 @@ impl DashboardTemplate {
         &self.version
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::dashboard::data::AggregationFunction;
+
+    #[test]
+    fn test_aggregations_round_trip() {
+        let mut mapping = DataMapping {
+            x_axis: Some("Month".into()),
+            y_axis: vec!["Sales".into(), "Price".into()],
+            ..Default::default()
+        };
+        mapping.set_aggregation("Price", AggregationFunction::Avg);
+
+        let template = DashboardTemplate {
+            version: SCHEMA_VERSION.into(),
+            metadata: DashboardMetadata {
+                title: "Sales".into(),
+                created_at: "2024-01-01T00:00:00Z".into(),
+                exported_at: "2024-01-01T00:00:00Z".into(),
+                template_type: TemplateType::Generic,
+            },
+            widgets: vec![Widget {
+                id: "w1".into(),
+                title: "Sales".into(),
+                subtitle: None,
+                widget_type: WidgetType::Bar,
+                chart_config: ChartConfig {
+                    chart_type: Some(WidgetType::Bar),
+                    dataset_id: None,
+                    data_mapping: mapping.clone(),
+                    style_options: "{}".into(),
+                },
+                grid_position: GridPosition::default(),
+                editing: false,
+            }],
+            datasets: Vec::new(),
+            layers: Vec::new(),
+            global_filters: Default::default(),
+        };
+
+        let imported = DashboardTemplate::from_json(&template.to_json().unwrap()).unwrap();
+        let imported_mapping = &imported.widgets[0].chart_config.data_mapping;
+        assert_eq!(imported_mapping, &mapping);
+        assert_eq!(imported_mapping.aggregation("Price"), AggregationFunction::Avg);
+        assert_eq!(imported_mapping.aggregation("Sales"), AggregationFunction::Sum);
+
+        // Mappings saved before aggregations existed still load
+        let legacy: DataMapping = serde_json::from_str(r#"{"x_axis":"Month","y_axis":["Sales"]}"#).unwrap();
+        assert!(legacy.aggregations.is_empty());
+    }
+
+    #[test]
+    fn test_global_filters_round_trip() {
+        use crate::features::dashboard::data::filter::{Filter, FilterOperator};
+
+        let mut global_filters = GlobalFilters::default();
+        global_filters.set("region_control", Some(Filter {
+            field: "Region".into(),
+            operator: FilterOperator::In,
+            values: vec![serde_json::json!("North")],
+        }));
+        let template = DashboardTemplate {
+            version: SCHEMA_VERSION.into(),
+            metadata: DashboardMetadata {
+                title: "Sales".into(),
+                created_at: "2024-01-01T00:00:00Z".into(),
+                exported_at: "2024-01-01T00:00:00Z".into(),
+                template_type: TemplateType::Generic,
+            },
+            widgets: Vec::new(),
+            datasets: Vec::new(),
+            layers: Vec::new(),
+            global_filters: global_filters.clone(),
+        };
+
+        let imported = DashboardTemplate::from_json(&template.to_json().unwrap()).unwrap();
+        assert_eq!(imported.global_filters, global_filters);
+
+        // Templates saved before filter controls existed still load
+        let legacy = template.to_json().unwrap().replace("\"global_filters\"", "\"unused\"");
+        assert!(DashboardTemplate::from_json(&legacy).unwrap().global_filters.is_empty());
+    }
+
+    #[test]
+    fn test_widgets_bound_to_datasets() {
+        let dataset = |id: &str, active: bool| DatasetExport {
+            id: id.into(),
+            name: format!("{}.csv", id),
+            fields: vec![Field { name: "Sales".into(), field_type: FieldType::Numeric, format: None }],
+            data: None,
+            csv_path: None,
+            dialect: None,
+            calculated_fields: Vec::new(),
+            derived_from: None,
+            active,
+        };
+        let widget = |id: &str, dataset_id: Option<&str>| Widget {
+            id: id.into(),
+            title: id.into(),
+            subtitle: None,
+            widget_type: WidgetType::Kpi,
+            chart_config: ChartConfig { dataset_id: dataset_id.map(Into::into), ..Default::default() },
+            grid_position: GridPosition::default(),
+            editing: false,
+        };
+        let mut template = DashboardTemplate {
+            version: SCHEMA_VERSION.into(),
+            metadata: DashboardMetadata {
+                title: "Sales".into(),
+                created_at: "2024-01-01T00:00:00Z".into(),
+                exported_at: "2024-01-01T00:00:00Z".into(),
+                template_type: TemplateType::Generic,
+            },
+            widgets: vec![widget("w1", None), widget("w2", Some("targets"))],
+            datasets: vec![dataset("sales", false), dataset("targets", true)],
+            layers: Vec::new(),
+            global_filters: Default::default(),
+        };
+
+        // Widgets saved before the binding show the dataset active at the time
+        let mut imported = DashboardTemplate::from_json(&template.to_json().unwrap()).unwrap();
+        imported.bind_unbound_widgets();
+        let bound: Vec<_> = imported.widgets.iter().map(|w| w.chart_config.dataset_id.as_deref()).collect();
+        assert_eq!(bound, vec![Some("targets"), Some("targets")]);
+
+        // ... or, without one recorded, the first dataset having their fields
+        template.datasets[1].active = false;
+        template.datasets[1].fields[0].name = "Target".into();
+        template.widgets[0].chart_config.data_mapping.kpi_field = Some("Target".into());
+        template.widgets.push(widget("w3", None));
+        template.bind_unbound_widgets();
+        let bound: Vec<_> = template.widgets.iter().map(|w| w.chart_config.dataset_id.as_deref()).collect();
+        assert_eq!(bound, vec![Some("targets"), Some("targets"), Some("sales")]);
+        assert!(super::super::validation::validate_template(&template).is_ok());
+
+        // A widget bound to a dataset that isn't in the template is an error
+        template.datasets.remove(1);
+        let errors = super::super::validation::validate_template(&template).unwrap_err();
+        assert_eq!(errors[0].field_path, "widgets[0].chart_config.dataset_id");
+    }
+}
@@ -1,6 +1,7 @@
 use super::template::{DashboardTemplate, DatasetExport};
 use super::error::ValidationError;
-use crate::features::dashboard::models::{Widget, WidgetType, FieldType, Field};
+use crate::features::dashboard::data::DataTable;
+use crate::features::dashboard::models::{Dataset, Widget, WidgetType, FieldType, Field};
 use leptos::logging::*;
 
 /// Supported template schema versions (includes legacy versions for backward compatibility)
@@ -211,14 +212,19 @@ fn validate_widget_data_mapping(
             }
         }
 
-        WidgetType::Table | WidgetType::Kpi => {
-            // Less strict requirements
-            if mapping.columns.is_empty() {
-                warnings.push(ValidationError::warning(
-                    format!("{}.data_mapping.columns", path),
-                    "No columns configured",
-                ));
-            }
+        // Less strict requirements
+        WidgetType::Table | WidgetType::Kpi if mapping.columns.is_empty() => {
+            warnings.push(ValidationError::warning(
+                format!("{}.data_mapping.columns", path),
+                "No columns configured",
+            ));
+        }
+
+        widget_type if widget_type.is_filter_control() && mapping.filter_field.is_none() => {
+            warnings.push(ValidationError::warning(
+                format!("{}.data_mapping.filter_field", path),
+                "No field configured for filter control",
+            ));
         }
 
         _ => {
@@ -276,6 +282,8 @@ fn validate_datasets(
         if let Some(data) = &dataset.data {
             validate_dataset_data(data, &dataset.fields, &path, errors, warnings);
         }
+
+        validate_calculated_fields(dataset, &path, warnings);
     }
 
     // Check for duplicate dataset IDs
@@ -320,6 +328,38 @@ fn validate_fields(
     }
 }
 
+/// Check the expressions of calculated fields
+///
+/// Invalid ones are dropped on import, so they are only warnings.
+fn validate_calculated_fields(
+    dataset: &DatasetExport,
+    base_path: &str,
+    warnings: &mut Vec<ValidationError>,
+) {
+    // Recompute the fields on an empty table: checking needs the types only
+    let mut schema = Dataset {
+        id: dataset.id.clone(),
+        name: dataset.name.clone(),
+        size: String::new(),
+        uploaded_at: String::new(),
+        data: DataTable::from_rows(&dataset.fields, Vec::new()),
+        fields: dataset.fields.clone(),
+        active: false,
+        dialect: None,
+        calculated_fields: Vec::new(),
+        derived_from: None,
+    };
+
+    for (idx, calculated) in dataset.calculated_fields.iter().enumerate() {
+        if let Err(e) = schema.add_calculated_field(&calculated.name, &calculated.expression) {
+            warnings.push(ValidationError::warning(
+                format!("{}.calculated_fields[{}].expression", base_path, idx),
+                format!("Calculated field '{}' will be dropped: {}", calculated.name, e),
+            ));
+        }
+    }
+}
+
 /// Validate dataset data rows
 fn validate_dataset_data(
     data: &[Vec<serde_json::Value>],
@@ -407,7 +447,7 @@ fn validate_field_value(
 fn validate_cross_references(
     template: &DashboardTemplate,
     errors: &mut Vec<ValidationError>,
-    _warnings: &mut Vec<ValidationError>,
+    warnings: &mut Vec<ValidationError>,
 ) {
     debug_log!("Validating cross-references");
 
@@ -424,6 +464,44 @@ fn validate_cross_references(
             ));
         }
     }
+
+    // Build dataset ID set
+    let dataset_ids: std::collections::HashSet<_> =
+        template.datasets.iter().map(|ds| &ds.id).collect();
+
+    // Check widget dataset references
+    for (idx, widget) in template.widgets.iter().enumerate() {
+        if let Some(dataset_id) = &widget.chart_config.dataset_id
+            && !dataset_ids.contains(dataset_id)
+        {
+            errors.push(ValidationError::error(
+                format!("widgets[{}].chart_config.dataset_id", idx),
+                format!("References non-existent dataset: '{}'", dataset_id),
+            ));
+        }
+    }
+
+    // Check the sources of derived datasets
+    for (idx, dataset) in template.datasets.iter().enumerate() {
+        let Some(derived_from) = &dataset.derived_from else {
+            continue;
+        };
+        for source in derived_from.sources() {
+            if !dataset_ids.contains(source) {
+                errors.push(ValidationError::error(
+                    format!("datasets[{}].derived_from", idx),
+                    format!("Derived from non-existent dataset: '{}'", source),
+                ));
+            }
+        }
+    }
+
+    if template.datasets.iter().filter(|ds| ds.active).count() > 1 {
+        warnings.push(ValidationError::warning(
+            "datasets",
+            "More than one dataset marked active",
+        ));
+    }
 }
 
 /// Auto-fix common issues (non-destructive)
@@ -2,7 +2,8 @@
 //!
 //! Implements a Command Pattern-based undo/redo system for all widget operations.
 
-use super::models::{DataMapping, GridPosition, Layer, Widget};
+use super::data::filter::GlobalFilters;
+use super::models::{DataMapping, Dataset, GridPosition, Layer, Widget};
 use crate::features::dashboard::WidgetId;
 
 /// Command represents any undoable operation on the dashboard
@@ -41,8 +42,21 @@ pub enum Command {
         new_mapping: Box<DataMapping>,
     },
 
-    /// Batch operations (for future use with drag-drop or bulk edits)
-    #[allow(dead_code)]
+    /// Replace the data of a dataset (same id)
+    ReplaceDataset {
+        previous: Box<Dataset>,
+        new: Box<Dataset>,
+    },
+
+    /// Change the conditions set on the dashboard's filter controls (e.g.
+    /// to follow renamed fields)
+    SetGlobalFilters {
+        previous: Box<GlobalFilters>,
+        new: Box<GlobalFilters>,
+    },
+
+    /// Batch operations (e.g. a dataset replacement with the widget
+    /// mappings it repaired)
     Batch {
         commands: Vec<Command>,
         description: String,
@@ -91,8 +105,8 @@ impl HistoryManager {
     /// Returns the command if successful, None if no command to undo
     pub fn undo(&mut self) -> Option<&Command> {
         let command = self.undo_stack.pop()?;
-        self.redo_stack.push(command.clone());
-        self.undo_stack.last()
+        self.redo_stack.push(command);
+        self.redo_stack.last()
     }
 
     /// Redo the most recently undone command
@@ -100,7 +114,7 @@ impl HistoryManager {
     /// Returns the command if successful, None if no command to redo
     pub fn redo(&mut self) -> Option<&Command> {
         let command = self.redo_stack.pop()?;
-        self.undo_stack.push(command.clone());
+        self.undo_stack.push(command);
         self.undo_stack.last()
     }
 
@@ -146,6 +160,7 @@ mod tests {
             widget_type: crate::features::dashboard::models::WidgetType::Line,
             chart_config: crate::features::dashboard::models::ChartConfig {
                 chart_type: Some(crate::features::dashboard::models::WidgetType::Line),
+                dataset_id: None,
                 data_mapping: DataMapping::default(),
                 style_options: "{}".to_string(),
             },
@@ -49,82 +49,8 @@ pub fn download_json(template: &DashboardTemplate, filename: &str) -> Result<(),
     Ok(())
 }
 
-/// LocalStorage functions (WASM-compatible)
-///
-/// These functions provide dashboard persistence to browser localStorage.
-pub mod storage {
-    use super::*;
-
-    const STORAGE_PREFIX: &str = "dashboard_template_";
-
-    /// Save template to localStorage
-    pub fn save_template(name: &str, template: &DashboardTemplate) -> Result<(), JsValue> {
-        let json = template.to_json()
-            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))?;
-
-        let storage = window()
-            .unwrap()
-            .local_storage()?
-            .ok_or_else(|| JsValue::from_str("localStorage not available"))?;
-
-        let key = format!("{}{}", STORAGE_PREFIX, name);
-        storage.set_item(&key, &json)?;
-
-        Ok(())
-    }
-
-    /// Load template from localStorage
-    pub fn load_template(name: &str) -> Result<DashboardTemplate, String> {
-        let storage = window()
-            .unwrap()
-            .local_storage()
-            .map_err(|e| format!("Storage error: {:?}", e))?
-            .ok_or_else(|| "localStorage not available".to_string())?;
-
-        let key = format!("{}{}", STORAGE_PREFIX, name);
-        let json = storage
-            .get_item(&key)
-            .map_err(|e| format!("Read error: {:?}", e))?
-            .ok_or_else(|| format!("Template '{}' not found", name))?;
-
-        DashboardTemplate::from_json(&json)
-            .map_err(|e| format!("Parse error: {}", e))
-    }
-
-    /// List all saved template names
-    pub fn list_templates() -> Result<Vec<String>, JsValue> {
-        let storage = window()
-            .unwrap()
-            .local_storage()?
-            .ok_or_else(|| JsValue::from_str("localStorage not available"))?;
-
-        let len = storage.length()?;
-        let mut names = Vec::new();
-
-        for i in 0..len {
-            if let Some(key) = storage.key(i)?
-                && let Some(name) = key.strip_prefix(STORAGE_PREFIX) {
-                    names.push(name.to_string());
-                }
-        }
-
-        Ok(names)
-    }
-
-    /// Delete template from localStorage
-    #[allow(dead_code)]
-    pub fn delete_template(name: &str) -> Result<(), JsValue> {
-        let storage = window()
-            .unwrap()
-            .local_storage()?
-            .ok_or_else(|| JsValue::from_str("localStorage not available"))?;
-
-        let key = format!("{}{}", STORAGE_PREFIX, name);
-        storage.remove_item(&key)?;
-
-        Ok(())
-    }
-}
+/// Dashboard persistence (IndexedDB, localStorage)
+pub mod storage;
 
 /// ZIP Bundle functions (requires zip dependency)
 ///
@@ -0,0 +1,209 @@
+//! IndexedDB backend, for templates and their data
+
+use super::local::write_error;
+use super::{StorageBackend, StorageError, StorageEstimate};
+use futures::channel::oneshot;
+use js_sys::Function;
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{window, IdbDatabase, IdbObjectStore, IdbRequest, IdbTransaction, IdbTransactionMode};
+
+/// Schema version of the database; bump it to add object stores
+const DATABASE_VERSION: u32 = 1;
+
+/// Entries stored in an IndexedDB object store, keyed by string
+///
+/// IndexedDB quotas are a share of the disk rather than localStorage's few
+/// megabytes, so whole datasets fit. The database is opened by the first
+/// operation and the connection kept for the next ones; it is dropped (and
+/// reopened when next needed) after a failed transaction, or closed when
+/// another tab upgrades the schema.
+pub struct IndexedDbBackend {
+    database: &'static str,
+    store: &'static str,
+    connection: RefCell<Option<IdbDatabase>>,
+}
+
+impl IndexedDbBackend {
+    pub fn new(database: &'static str, store: &'static str) -> Self {
+        Self { database, store, connection: RefCell::new(None) }
+    }
+
+    /// The open connection, opening the database if there is none
+    async fn connection(&self) -> Result<IdbDatabase, StorageError> {
+        if let Some(database) = self.connection.borrow().clone() {
+            return Ok(database);
+        }
+        let database = self.open().await?;
+
+        // Don't block a newer version opened in another tab
+        let closing = database.clone();
+        database.set_onversionchange(Some(Closure::once_into_js(move || closing.close()).unchecked_ref()));
+
+        *self.connection.borrow_mut() = Some(database.clone());
+        Ok(database)
+    }
+
+    /// Open the database, creating the object store on first use
+    async fn open(&self) -> Result<IdbDatabase, StorageError> {
+        let factory = window()
+            .ok_or_else(|| StorageError::Unavailable("no window".to_string()))?
+            .indexed_db()
+            .map_err(|e| StorageError::Unavailable(format!("{:?}", e)))?
+            .ok_or_else(|| StorageError::Unavailable("IndexedDB not available".to_string()))?;
+        let request = factory
+            .open_with_u32(self.database, DATABASE_VERSION)
+            .map_err(|e| StorageError::Unavailable(format!("{:?}", e)))?;
+
+        let store = self.store;
+        let upgrading = request.clone();
+        let on_upgrade = Closure::<dyn FnMut()>::new(move || {
+            let Ok(database) = upgrading.result().map(IdbDatabase::unchecked_from_js) else {
+                return;
+            };
+            if !database.object_store_names().contains(store)
+                && let Err(e) = database.create_object_store(store)
+            {
+                log::error!("Failed to create object store '{}': {:?}", store, e);
+            }
+        });
+        request.set_onupgradeneeded(Some(on_upgrade.as_ref().unchecked_ref()));
+
+        let opened = settle(&request).await;
+        request.set_onupgradeneeded(None);
+        opened
+            .and_then(|_| request.result())
+            .map(IdbDatabase::unchecked_from_js)
+            .map_err(|e| StorageError::Unavailable(format!("{:?}", e)))
+    }
+
+    /// Run one request in its own transaction and wait for the transaction
+    ///
+    /// Waiting for completion rather than for the request matters for
+    /// writes: a full disk is only reported when the transaction aborts.
+    async fn run(
+        &self,
+        mode: IdbTransactionMode,
+        operation: impl FnOnce(&IdbObjectStore) -> Result<IdbRequest, JsValue>,
+        map_error: impl FnOnce(JsValue) -> StorageError,
+    ) -> Result<JsValue, StorageError> {
+        let database = self.connection().await?;
+        let result = async {
+            let transaction = database.transaction_with_str_and_mode(self.store, mode)?;
+            let request = operation(&transaction.object_store(self.store)?)?;
+            complete(&transaction).await?;
+            request.result()
+        }
+        .await;
+        if result.is_err()
+            && let Some(database) = self.connection.take()
+        {
+            database.close();
+        }
+        result.map_err(map_error)
+    }
+}
+
+impl StorageBackend for IndexedDbBackend {
+    async fn get(&self, key: &str) -> Result<Option<String>, StorageError> {
+        let value = self
+            .run(IdbTransactionMode::Readonly, |store| store.get(&JsValue::from_str(key)), backend_error)
+            .await?;
+        Ok(value.as_string())
+    }
+
+    async fn set(&self, key: &str, value: &str) -> Result<(), StorageError> {
+        self.run(
+            IdbTransactionMode::Readwrite,
+            |store| store.put_with_key(&JsValue::from_str(value), &JsValue::from_str(key)),
+            |e| write_error(key, value.len(), e),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), StorageError> {
+        self.run(IdbTransactionMode::Readwrite, |store| store.delete(&JsValue::from_str(key)), backend_error)
+            .await
+            .map(|_| ())
+    }
+
+    async fn keys(&self) -> Result<Vec<String>, StorageError> {
+        let keys = self
+            .run(IdbTransactionMode::Readonly, |store| store.get_all_keys(), backend_error)
+            .await?;
+        Ok(js_sys::Array::from(&keys).iter().filter_map(|key| key.as_string()).collect())
+    }
+
+    async fn estimate(&self) -> Option<StorageEstimate> {
+        let promise = window()?.navigator().storage().estimate().ok()?;
+        let estimate = JsFuture::from(promise).await.ok()?;
+        let bytes = |name: &str| {
+            js_sys::Reflect::get(&estimate, &JsValue::from_str(name))
+                .ok()?
+                .as_f64()
+                .map(|bytes| bytes as u64)
+        };
+        Some(StorageEstimate { usage: bytes("usage")?, quota: bytes("quota")? })
+    }
+}
+
+fn backend_error(error: JsValue) -> StorageError {
+    StorageError::Backend(format!("{:?}", error))
+}
+
+/// Wait for a request to succeed or fail
+async fn settle(request: &IdbRequest) -> Result<(), JsValue> {
+    let succeeded = wait_for(|on_success, on_failure| {
+        request.set_onsuccess(Some(on_success));
+        request.set_onerror(Some(on_failure));
+    })
+    .await;
+    request.set_onsuccess(None);
+    request.set_onerror(None);
+
+    if succeeded {
+        Ok(())
+    } else {
+        Err(request.error().ok().flatten().map(JsValue::from).unwrap_or(JsValue::NULL))
+    }
+}
+
+/// Wait for a transaction to commit or abort
+async fn complete(transaction: &IdbTransaction) -> Result<(), JsValue> {
+    let committed = wait_for(|on_complete, on_abort| {
+        transaction.set_oncomplete(Some(on_complete));
+        transaction.set_onabort(Some(on_abort));
+    })
+    .await;
+    transaction.set_oncomplete(None);
+    transaction.set_onabort(None);
+
+    if committed {
+        Ok(())
+    } else {
+        Err(transaction.error().map(JsValue::from).unwrap_or(JsValue::NULL))
+    }
+}
+
+/// Register a success and a failure handler, and wait for the first to fire
+async fn wait_for(register: impl FnOnce(&Function, &Function)) -> bool {
+    let (sender, receiver) = oneshot::channel();
+    let sender = Rc::new(RefCell::new(Some(sender)));
+    let handler = |outcome: bool| {
+        let sender = sender.clone();
+        Closure::<dyn FnMut()>::new(move || {
+            if let Some(sender) = sender.borrow_mut().take() {
+                let _ = sender.send(outcome);
+            }
+        })
+    };
+
+    let on_success = handler(true);
+    let on_failure = handler(false);
+    register(on_success.as_ref().unchecked_ref(), on_failure.as_ref().unchecked_ref());
+    receiver.await.unwrap_or(false)
+}
@@ -0,0 +1,82 @@
+//! localStorage backend, for small entries
+
+use super::{StorageBackend, StorageError};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{window, DomException, Storage};
+
+/// Entries stored in localStorage under a key prefix
+///
+/// localStorage is synchronous and limited to a few megabytes per origin:
+/// only suitable for metadata.
+pub struct LocalStorageBackend {
+    prefix: &'static str,
+}
+
+impl LocalStorageBackend {
+    pub fn new(prefix: &'static str) -> Self {
+        Self { prefix }
+    }
+
+    fn storage(&self) -> Result<Storage, StorageError> {
+        window()
+            .ok_or_else(|| StorageError::Unavailable("no window".to_string()))?
+            .local_storage()
+            .map_err(|e| StorageError::Unavailable(format!("{:?}", e)))?
+            .ok_or_else(|| StorageError::Unavailable("localStorage not available".to_string()))
+    }
+
+    fn item_key(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, key)
+    }
+}
+
+impl StorageBackend for LocalStorageBackend {
+    async fn get(&self, key: &str) -> Result<Option<String>, StorageError> {
+        self.storage()?
+            .get_item(&self.item_key(key))
+            .map_err(|e| StorageError::Backend(format!("{:?}", e)))
+    }
+
+    async fn set(&self, key: &str, value: &str) -> Result<(), StorageError> {
+        self.storage()?
+            .set_item(&self.item_key(key), value)
+            .map_err(|e| write_error(key, value.len(), e))
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), StorageError> {
+        self.storage()?
+            .remove_item(&self.item_key(key))
+            .map_err(|e| StorageError::Backend(format!("{:?}", e)))
+    }
+
+    async fn keys(&self) -> Result<Vec<String>, StorageError> {
+        let storage = self.storage()?;
+        let len = storage.length().map_err(|e| StorageError::Backend(format!("{:?}", e)))?;
+
+        let mut keys = Vec::new();
+        for i in 0..len {
+            if let Ok(Some(key)) = storage.key(i)
+                && let Some(key) = key.strip_prefix(self.prefix)
+            {
+                keys.push(key.to_string());
+            }
+        }
+        Ok(keys)
+    }
+}
+
+/// Map a failed write to `QuotaExceeded` when that is what the browser reported
+pub(super) fn write_error(key: &str, bytes: usize, error: JsValue) -> StorageError {
+    match error.dyn_ref::<DomException>() {
+        Some(exception) if is_quota_error(exception) => {
+            StorageError::QuotaExceeded { key: key.to_string(), bytes: bytes as u64 }
+        }
+        _ => StorageError::Backend(format!("{:?}", error)),
+    }
+}
+
+/// Whether a DOMException reports a full storage (names differ across browsers)
+pub(super) fn is_quota_error(exception: &DomException) -> bool {
+    matches!(exception.name().as_str(), "QuotaExceededError" | "NS_ERROR_DOM_QUOTA_REACHED")
+}
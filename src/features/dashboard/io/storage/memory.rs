@@ -0,0 +1,61 @@
+//! In-memory storage backend, for tests
+
+use super::{StorageBackend, StorageError, StorageEstimate};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+/// Entries kept in a map, with an optional quota on their total size
+#[derive(Default)]
+pub struct MemoryBackend {
+    entries: RefCell<BTreeMap<String, String>>,
+    quota: Option<u64>,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A backend refusing writes beyond `quota` bytes
+    pub fn with_quota(quota: u64) -> Self {
+        Self { quota: Some(quota), ..Self::default() }
+    }
+
+    fn usage(&self) -> u64 {
+        self.entries
+            .borrow()
+            .iter()
+            .map(|(key, value)| (key.len() + value.len()) as u64)
+            .sum()
+    }
+}
+
+impl StorageBackend for MemoryBackend {
+    async fn get(&self, key: &str) -> Result<Option<String>, StorageError> {
+        Ok(self.entries.borrow().get(key).cloned())
+    }
+
+    async fn set(&self, key: &str, value: &str) -> Result<(), StorageError> {
+        let bytes = (key.len() + value.len()) as u64;
+        if let Some(quota) = self.quota
+            && self.usage() + bytes > quota
+        {
+            return Err(StorageError::QuotaExceeded { key: key.to_string(), bytes });
+        }
+        self.entries.borrow_mut().insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), StorageError> {
+        self.entries.borrow_mut().remove(key);
+        Ok(())
+    }
+
+    async fn keys(&self) -> Result<Vec<String>, StorageError> {
+        Ok(self.entries.borrow().keys().cloned().collect())
+    }
+
+    async fn estimate(&self) -> Option<StorageEstimate> {
+        self.quota.map(|quota| StorageEstimate { usage: self.usage(), quota })
+    }
+}
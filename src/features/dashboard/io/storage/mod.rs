@@ -0,0 +1,393 @@
+//! Dashboard persistence
+//!
+//! A saved dashboard is stored in two parts: a small summary (title, dates,
+//! first widget) that the Projects page can list cheaply, and the full
+//! template, datasets included. In the browser the summaries live in
+//! localStorage and the templates in IndexedDB, whose quota is large enough
+//! for real datasets.
+//!
+//! Dashboards saved by older versions, as whole templates in localStorage
+//! under the `dashboard_template_` prefix, are moved over the first time the
+//! store is opened in a session.
+
+mod indexed_db;
+mod local;
+#[cfg(test)]
+mod memory;
+
+pub use indexed_db::IndexedDbBackend;
+pub use local::LocalStorageBackend;
+#[cfg(test)]
+pub use memory::MemoryBackend;
+
+use super::super::export::{DashboardTemplate, TemplateType};
+use super::super::models::WidgetType;
+use serde::{Deserialize, Serialize};
+use std::cell::OnceCell;
+use std::fmt;
+use std::future::Future;
+use std::rc::Rc;
+
+/// localStorage prefix of dashboards saved before the storage split
+pub const LEGACY_PREFIX: &str = "dashboard_template_";
+
+/// localStorage prefix of dashboard summaries
+const SUMMARY_PREFIX: &str = "dashboard_summary_";
+
+/// IndexedDB database and object store holding the templates
+const DATABASE_NAME: &str = "dashboard-studio";
+const TEMPLATE_STORE: &str = "templates";
+
+/// Errors raised by a storage backend
+#[derive(Debug, Clone, PartialEq)]
+pub enum StorageError {
+    /// The storage API is missing or blocked (private browsing, old browser)
+    Unavailable(String),
+    /// Writing would exceed the storage quota
+    QuotaExceeded { key: String, bytes: u64 },
+    /// No entry under this key
+    NotFound(String),
+    /// A stored entry couldn't be encoded or decoded
+    Serialization(String),
+    /// Any other failure reported by the backend
+    Backend(String),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unavailable(reason) => write!(f, "Storage unavailable: {}", reason),
+            Self::QuotaExceeded { key, bytes } => {
+                write!(f, "Storage quota exceeded saving '{}' ({} bytes)", key, bytes)
+            }
+            Self::NotFound(key) => write!(f, "Dashboard '{}' not found", key),
+            Self::Serialization(reason) => write!(f, "Invalid stored dashboard: {}", reason),
+            Self::Backend(reason) => write!(f, "Storage error: {}", reason),
+        }
+    }
+}
+
+/// Bytes used and available, as estimated by the browser
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StorageEstimate {
+    pub usage: u64,
+    pub quota: u64,
+}
+
+impl StorageEstimate {
+    /// Whether `bytes` more can be written without exceeding the quota
+    pub fn fits(&self, bytes: u64) -> bool {
+        self.usage.saturating_add(bytes) <= self.quota
+    }
+}
+
+/// Key-value store of strings
+///
+/// Browser storage is asynchronous (IndexedDB) or may be, so every
+/// operation returns a future. Futures aren't `Send`: they run on the
+/// browser's event loop.
+pub trait StorageBackend {
+    /// Read an entry, `None` when there is none
+    fn get(&self, key: &str) -> impl Future<Output = Result<Option<String>, StorageError>>;
+
+    /// Create or replace an entry
+    fn set(&self, key: &str, value: &str) -> impl Future<Output = Result<(), StorageError>>;
+
+    /// Delete an entry (deleting a missing entry is not an error)
+    fn remove(&self, key: &str) -> impl Future<Output = Result<(), StorageError>>;
+
+    /// Keys of all entries
+    fn keys(&self) -> impl Future<Output = Result<Vec<String>, StorageError>>;
+
+    /// Usage and quota, for backends that can tell
+    fn estimate(&self) -> impl Future<Output = Option<StorageEstimate>> {
+        async { None }
+    }
+}
+
+/// First widget of a saved dashboard, shown on its project card
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WidgetSummary {
+    pub title: String,
+    pub subtitle: Option<String>,
+    pub widget_type: WidgetType,
+}
+
+/// What the Projects page shows of a saved dashboard
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DashboardSummary {
+    pub key: String,
+    pub title: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub template_type: TemplateType,
+    pub widget_count: usize,
+    pub first_widget: Option<WidgetSummary>,
+    /// Size of the stored template, in bytes
+    pub size: u64,
+}
+
+impl DashboardSummary {
+    fn of(key: &str, template: &DashboardTemplate, size: u64) -> Self {
+        Self {
+            key: key.to_string(),
+            title: template.metadata.title.clone(),
+            created_at: template.metadata.created_at.clone(),
+            updated_at: template.metadata.exported_at.clone(),
+            template_type: template.metadata.template_type,
+            widget_count: template.widgets.len(),
+            first_widget: template.widgets.first().map(|widget| WidgetSummary {
+                title: widget.title.clone(),
+                subtitle: widget.subtitle.clone(),
+                widget_type: widget.widget_type,
+            }),
+            size,
+        }
+    }
+}
+
+/// Saved dashboards: summaries in one backend, templates in another
+pub struct DashboardStore<S, T> {
+    summaries: S,
+    templates: T,
+}
+
+impl<S: StorageBackend, T: StorageBackend> DashboardStore<S, T> {
+    pub fn new(summaries: S, templates: T) -> Self {
+        Self { summaries, templates }
+    }
+
+    /// Save a dashboard under `key`, replacing any previous version
+    ///
+    /// The quota is checked first when the template backend can estimate it,
+    /// so a save that cannot fit fails with `QuotaExceeded` instead of
+    /// leaving a partial write behind.
+    pub async fn save(&self, key: &str, template: &DashboardTemplate) -> Result<DashboardSummary, StorageError> {
+        let json = serde_json::to_string(template)
+            .map_err(|e| StorageError::Serialization(e.to_string()))?;
+        let bytes = json.len() as u64;
+
+        if let Some(estimate) = self.templates.estimate().await
+            && !estimate.fits(bytes)
+        {
+            return Err(StorageError::QuotaExceeded { key: key.to_string(), bytes });
+        }
+
+        self.templates.set(key, &json).await?;
+
+        let summary = DashboardSummary::of(key, template, bytes);
+        let summary_json = serde_json::to_string(&summary)
+            .map_err(|e| StorageError::Serialization(e.to_string()))?;
+        self.summaries.set(key, &summary_json).await?;
+        Ok(summary)
+    }
+
+    /// Load the dashboard saved under `key`
+    pub async fn load(&self, key: &str) -> Result<DashboardTemplate, StorageError> {
+        let json = self
+            .templates
+            .get(key)
+            .await?
+            .ok_or_else(|| StorageError::NotFound(key.to_string()))?;
+        DashboardTemplate::from_json(&json).map_err(|e| StorageError::Serialization(e.to_string()))
+    }
+
+    /// Summaries of all saved dashboards, oldest key first
+    ///
+    /// Summaries that can't be read are skipped (and logged).
+    pub async fn list(&self) -> Result<Vec<DashboardSummary>, StorageError> {
+        let mut keys = self.summaries.keys().await?;
+        keys.sort();
+
+        let mut summaries = Vec::with_capacity(keys.len());
+        for key in keys {
+            let Some(json) = self.summaries.get(&key).await? else {
+                continue;
+            };
+            match serde_json::from_str::<DashboardSummary>(&json) {
+                Ok(summary) => summaries.push(summary),
+                Err(e) => log::warn!("Skipping unreadable dashboard summary '{}': {}", key, e),
+            }
+        }
+        Ok(summaries)
+    }
+
+    /// Delete a saved dashboard
+    pub async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        self.templates.remove(key).await?;
+        self.summaries.remove(key).await
+    }
+
+    /// Move dashboards saved by older versions into this store
+    ///
+    /// Each legacy entry is removed once saved here, so migrating twice is
+    /// harmless. Entries that can't be parsed are left in place.
+    ///
+    /// # Returns
+    /// The number of dashboards moved
+    pub async fn migrate_from(&self, legacy: &impl StorageBackend) -> Result<usize, StorageError> {
+        let mut migrated = 0;
+        for key in legacy.keys().await? {
+            let Some(json) = legacy.get(&key).await? else {
+                continue;
+            };
+            let template = match DashboardTemplate::from_json(&json) {
+                Ok(template) => template,
+                Err(e) => {
+                    log::warn!("Not migrating unreadable dashboard '{}': {}", key, e);
+                    continue;
+                }
+            };
+            self.save(&key, &template).await?;
+            legacy.remove(&key).await?;
+            migrated += 1;
+        }
+        Ok(migrated)
+    }
+}
+
+/// The browser's dashboard store
+pub type BrowserStore = DashboardStore<LocalStorageBackend, IndexedDbBackend>;
+
+thread_local! {
+    /// The browser's store, shared by every operation of the session
+    static BROWSER_STORE: OnceCell<Rc<BrowserStore>> = const { OnceCell::new() };
+}
+
+/// The browser's dashboard store
+///
+/// Created by the first call of the session, which also migrates legacy
+/// dashboards into it; later calls share it and its open database.
+pub async fn open() -> Rc<BrowserStore> {
+    let mut created = false;
+    let store = BROWSER_STORE.with(|cell| {
+        cell.get_or_init(|| {
+            created = true;
+            Rc::new(DashboardStore::new(
+                LocalStorageBackend::new(SUMMARY_PREFIX),
+                IndexedDbBackend::new(DATABASE_NAME, TEMPLATE_STORE),
+            ))
+        })
+        .clone()
+    });
+    if created {
+        match store.migrate_from(&LocalStorageBackend::new(LEGACY_PREFIX)).await {
+            Ok(0) => {}
+            Ok(count) => log::info!("Moved {} saved dashboard(s) from localStorage to IndexedDB", count),
+            Err(e) => log::warn!("Failed to migrate saved dashboards: {}", e),
+        }
+    }
+    store
+}
+
+/// Save a dashboard with its data, or without it if the data doesn't fit
+///
+/// # Returns
+/// The type of template that was saved
+pub async fn save_template(
+    key: &str,
+    complete: &DashboardTemplate,
+    generic: &DashboardTemplate,
+) -> Result<TemplateType, StorageError> {
+    let store = open().await;
+    match store.save(key, complete).await {
+        Ok(summary) => Ok(summary.template_type),
+        Err(StorageError::QuotaExceeded { bytes, .. }) => {
+            log::warn!(
+                "Dashboard data ({} bytes) exceeds the storage quota, saving the layout only",
+                bytes
+            );
+            let summary = store.save(key, generic).await?;
+            Ok(summary.template_type)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Load a saved dashboard
+pub async fn load_template(key: &str) -> Result<DashboardTemplate, StorageError> {
+    open().await.load(key).await
+}
+
+/// Summaries of all saved dashboards, oldest key first
+pub async fn list_dashboards() -> Result<Vec<DashboardSummary>, StorageError> {
+    open().await.list().await
+}
+
+/// Delete a saved dashboard
+#[allow(dead_code)]
+pub async fn delete_template(key: &str) -> Result<(), StorageError> {
+    open().await.delete(key).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::dashboard::export::template::{DashboardMetadata, SCHEMA_VERSION};
+    use futures::executor::block_on;
+
+    fn template(title: &str, template_type: TemplateType) -> DashboardTemplate {
+        DashboardTemplate {
+            version: SCHEMA_VERSION.into(),
+            metadata: DashboardMetadata {
+                title: title.into(),
+                created_at: "2024-01-01T00:00:00Z".into(),
+                exported_at: "2024-01-02T00:00:00Z".into(),
+                template_type,
+            },
+            widgets: Vec::new(),
+            datasets: Vec::new(),
+            layers: Vec::new(),
+            global_filters: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_save_load_list_delete() {
+        let store = DashboardStore::new(MemoryBackend::new(), MemoryBackend::new());
+        block_on(async {
+            store.save("2_sales", &template("Sales", TemplateType::Complete)).await.unwrap();
+            store.save("1_costs", &template("Costs", TemplateType::Generic)).await.unwrap();
+
+            let loaded = store.load("2_sales").await.unwrap();
+            assert_eq!(loaded.metadata.title, "Sales");
+
+            let summaries = store.list().await.unwrap();
+            let keys: Vec<_> = summaries.iter().map(|s| s.key.as_str()).collect();
+            assert_eq!(keys, ["1_costs", "2_sales"]);
+            assert_eq!(summaries[1].template_type, TemplateType::Complete);
+            assert!(summaries[1].size > 0);
+
+            store.delete("2_sales").await.unwrap();
+            assert_eq!(store.load("2_sales").await.unwrap_err(), StorageError::NotFound("2_sales".into()));
+            assert_eq!(store.list().await.unwrap().len(), 1);
+        });
+    }
+
+    #[test]
+    fn test_save_checks_quota_before_writing() {
+        let store = DashboardStore::new(MemoryBackend::new(), MemoryBackend::with_quota(64));
+        block_on(async {
+            let result = store.save("big", &template("Big", TemplateType::Complete)).await;
+            assert!(matches!(result, Err(StorageError::QuotaExceeded { .. })));
+            assert!(store.list().await.unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn test_migrates_legacy_dashboards() {
+        let store = DashboardStore::new(MemoryBackend::new(), MemoryBackend::new());
+        let legacy = MemoryBackend::new();
+        block_on(async {
+            let json = template("Old", TemplateType::Generic).to_json().unwrap();
+            legacy.set("1700000000000_old", &json).await.unwrap();
+            legacy.set("broken", "{not json").await.unwrap();
+
+            assert_eq!(store.migrate_from(&legacy).await.unwrap(), 1);
+            assert_eq!(store.load("1700000000000_old").await.unwrap().metadata.title, "Old");
+            // Unreadable entries stay where they are; migrated ones are gone
+            assert_eq!(legacy.keys().await.unwrap(), ["broken"]);
+            assert_eq!(store.migrate_from(&legacy).await.unwrap(), 0);
+        });
+    }
+}
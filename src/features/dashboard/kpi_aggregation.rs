@@ -1,78 +1,77 @@
 //! KPI aggregation functions
 //!
-//! Provides functions to calculate aggregations (SUM, AVG, COUNT, etc.)
-//! from dataset fields for KPI widgets.
+//! Calculates KPI values from dataset fields with the shared aggregation
+//! engine, and suggests KPIs for a dataset.
 
-use crate::features::dashboard::models::{Dataset, Field, FieldType, KpiAggregation};
+use crate::features::dashboard::data::aggregation::{aggregate_field, AggregationFunction};
+use crate::features::dashboard::data::series_data;
+use crate::features::dashboard::data::window::WindowCalculation;
+use crate::features::dashboard::models::{DataMapping, Dataset, Field, FieldType};
 
 /// Result of a KPI aggregation
 #[derive(Clone, Debug, PartialEq)]
 pub struct KpiValue {
     pub value: f64,
     pub formatted: String,
-    pub aggregation: KpiAggregation,
+    pub aggregation: AggregationFunction,
 }
 
 /// Calculate KPI value from a dataset field
 pub fn calculate_kpi(
     dataset: &Dataset,
     field_name: &str,
-    aggregation: KpiAggregation,
+    aggregation: AggregationFunction,
 ) -> Option<KpiValue> {
     // Find field index
     let field_index = dataset.fields.iter().position(|f| f.name == field_name)?;
     let field = &dataset.fields[field_index];
 
-    // Extract values from data rows
-    let values: Vec<f64> = dataset
-        .data
-        .iter()
-        .filter_map(|row| {
-            row.get(field_index)
-                .and_then(|val| val.as_f64())
-                .or_else(|| {
-                    // Try parsing as string first
-                    row.get(field_index)
-                        .and_then(|val| val.as_str())
-                        .and_then(|s| s.parse::<f64>().ok())
-                })
-        })
-        .collect();
+    // Aggregate the whole column; nulls are skipped explicitly by the
+    // engine (and counted out of Count), never treated as 0
+    let value = aggregate_field(dataset, field_index, 0..dataset.data.len(), &aggregation)?;
+    let formatted = if aggregation.is_count() {
+        format_with_commas(value)
+    } else {
+        format_number(value, field)
+    };
 
-    if values.is_empty() {
-        return None;
-    }
+    Some(KpiValue {
+        value,
+        formatted,
+        aggregation,
+    })
+}
 
-    // Calculate aggregation
-    let (value, formatted) = match aggregation {
-        KpiAggregation::Sum => {
-            let sum: f64 = values.iter().sum();
-            (sum, format_number(sum, field))
-        }
-        KpiAggregation::Average => {
-            let avg: f64 = values.iter().sum::<f64>() / values.len() as f64;
-            (avg, format_number(avg, field))
-        }
-        KpiAggregation::Count => {
-            let count = values.len() as f64;
-            (count, format!("{:.0}", count))
-        }
-        KpiAggregation::Min => {
-            let min = values.iter().fold(f64::INFINITY, |a, &b| a.min(b));
-            (min, format_number(min, field))
-        }
-        KpiAggregation::Max => {
-            let max = values.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
-            (max, format_number(max, field))
-        }
-        KpiAggregation::Last => {
-            let last = *values.last()?;
-            (last, format_number(last, field))
-        }
-        KpiAggregation::First => {
-            let first = *values.first()?;
-            (first, format_number(first, field))
-        }
+/// Calculate a KPI value through a window calculation
+///
+/// The field is aggregated per value of the mapping's x-axis (bucketed
+/// like a chart's), the calculation applied down the axis, and the last
+/// period's result shown: e.g. month-over-month change with dates grouped
+/// by month and a `PercentDifference`.
+pub fn calculate_window_kpi(
+    dataset: &Dataset,
+    mapping: &DataMapping,
+    field_name: &str,
+    aggregation: AggregationFunction,
+    calculation: WindowCalculation,
+) -> Option<KpiValue> {
+    let mut series_mapping = DataMapping {
+        x_axis: mapping.x_axis.clone(),
+        time_bucketing: mapping.time_bucketing,
+        y_axis: vec![field_name.to_string()],
+        ..Default::default()
+    };
+    series_mapping.set_aggregation(field_name, aggregation.clone());
+    series_mapping.set_calculation(field_name, Some(calculation));
+    let data = series_data(dataset, &series_mapping).ok()?;
+
+    // Rows without an x value come last and are off the axis
+    let last_period = data.source.iter().skip(1).rfind(|row| !row[0].is_null())?;
+    let value = last_period[1].as_f64()?;
+    let formatted = match &data.format {
+        Some(format) if format.percent => format.decorate(&format!("{:.1}", value)),
+        Some(format) => format.decorate(&format_plain_number(value)),
+        None => format_plain_number(value),
     };
 
     Some(KpiValue {
@@ -83,43 +82,52 @@ pub fn calculate_kpi(
 }
 
 /// Format a number based on field type
+///
+/// Numeric fields imported with a currency or percent notation are shown
+/// the same way (e.g. `$1,200`, `12.5%`).
 fn format_number(value: f64, field: &Field) -> String {
     match field.field_type {
-        FieldType::Numeric => {
-            // Format with commas for thousands
-            if value.fract() == 0.0 && value.abs() < 1_000_000.0 {
-                // Integer under 1M
-                format_with_commas(value)
-            } else if value.abs() >= 1_000_000.0 {
-                // Large numbers: use M/K suffix
-                format_large_number(value)
-            } else {
-                // Decimal
-                format!("{:.2}", value)
+        FieldType::Numeric => match &field.format {
+            Some(format) if format.percent => {
+                format.decorate(&format!("{:.1}", format.display_value(value)))
             }
-        }
+            Some(format) => format.decorate(&format_plain_number(value)),
+            None => format_plain_number(value),
+        },
         FieldType::Date => format_date(value),
         _ => format!("{:.0}", value),
     }
 }
 
+/// Format a plain number for display
+fn format_plain_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1_000_000.0 {
+        // Integer under 1M: format with commas for thousands
+        format_with_commas(value)
+    } else if value.abs() >= 1_000_000.0 {
+        // Large numbers: use M/K suffix
+        format_large_number(value)
+    } else {
+        // Decimal
+        format!("{:.2}", value)
+    }
+}
+
 /// Format number with thousand separators
 fn format_with_commas(value: f64) -> String {
-    let formatted = format!("{:.0}", value);
-    let chars: Vec<char> = formatted.chars().collect();
+    let formatted = format!("{:.0}", value.abs());
     let mut result = String::new();
-    let mut count = 0;
-
-    for c in chars.iter() {
-        if c == &'-' {
-            result.push(*c);
-        } else if c.is_ascii_digit() {
-            if count > 0 && count % 3 == 0 {
-                result.push(',');
-            }
-            result.push(*c);
-            count += 1;
+
+    // Group digits in threes, counting from the right
+    for (i, c) in formatted.chars().enumerate() {
+        if i > 0 && (formatted.len() - i) % 3 == 0 {
+            result.push(',');
         }
+        result.push(c);
+    }
+
+    if value < 0.0 && formatted != "0" {
+        result.insert(0, '-');
     }
 
     result
@@ -147,23 +155,25 @@ fn format_date(_value: f64) -> String {
 }
 
 /// Suggest KPI aggregations for a numeric field
-pub fn suggest_aggregations(field: &Field) -> Vec<(KpiAggregation, String)> {
+pub fn suggest_aggregations(field: &Field) -> Vec<(AggregationFunction, String)> {
     match field.field_type {
         FieldType::Numeric => vec![
-            (KpiAggregation::Sum, format!("Total {}", field.name)),
-            (KpiAggregation::Average, format!("Average {}", field.name)),
-            (KpiAggregation::Min, format!("Min {}", field.name)),
-            (KpiAggregation::Max, format!("Max {}", field.name)),
+            (AggregationFunction::Sum, format!("Total {}", field.name)),
+            (AggregationFunction::Avg, format!("Average {}", field.name)),
+            (AggregationFunction::Median, format!("Median {}", field.name)),
+            (AggregationFunction::Min, format!("Min {}", field.name)),
+            (AggregationFunction::Max, format!("Max {}", field.name)),
         ],
         FieldType::Text => vec![
-            (KpiAggregation::Count, format!("Count of {}", field.name)),
+            (AggregationFunction::Count, format!("Count of {}", field.name)),
+            (AggregationFunction::CountDistinct, format!("Distinct {}", field.name)),
         ],
         _ => vec![],
     }
 }
 
 /// Analyze dataset and suggest KPIs for all numeric fields
-pub fn analyze_dataset_for_kpis(dataset: &Dataset) -> Vec<(String, Vec<(KpiAggregation, String)>)> {
+pub fn analyze_dataset_for_kpis(dataset: &Dataset) -> Vec<(String, Vec<(AggregationFunction, String)>)> {
     dataset
         .fields
         .iter()
@@ -178,11 +188,14 @@ pub fn analyze_dataset_for_kpis(dataset: &Dataset) -> Vec<(String, Vec<(KpiAggre
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::features::dashboard::data::DataTable;
+    use crate::features::dashboard::models::FieldFormat;
+    use serde_json::Value;
 
     #[test]
     fn test_calculate_sum() {
         let dataset = create_test_dataset();
-        let result = calculate_kpi(&dataset, "revenue", KpiAggregation::Sum);
+        let result = calculate_kpi(&dataset, "revenue", AggregationFunction::Sum);
 
         assert!(result.is_some());
         assert_eq!(result.unwrap().value, 600.0); // 100 + 200 + 300
@@ -191,30 +204,101 @@ mod tests {
     #[test]
     fn test_calculate_avg() {
         let dataset = create_test_dataset();
-        let result = calculate_kpi(&dataset, "revenue", KpiAggregation::Average);
+        let result = calculate_kpi(&dataset, "revenue", AggregationFunction::Avg);
 
         assert!(result.is_some());
         assert_eq!(result.unwrap().value, 200.0); // (100 + 200 + 300) / 3
     }
 
+    #[test]
+    fn test_nulls_are_skipped() {
+        let mut dataset = create_test_dataset();
+        let rows = dataset.data.rows().chain(std::iter::once(vec![Value::Null]));
+        dataset.data = DataTable::from_rows(&dataset.fields, rows.collect::<Vec<_>>());
+
+        let avg = calculate_kpi(&dataset, "revenue", AggregationFunction::Avg).unwrap();
+        assert_eq!(avg.value, 200.0);
+
+        let count = calculate_kpi(&dataset, "revenue", AggregationFunction::Count).unwrap();
+        assert_eq!(count.value, 3.0);
+
+        dataset.data = DataTable::from_rows(&dataset.fields, vec![vec![Value::Null]]);
+        assert!(calculate_kpi(&dataset, "revenue", AggregationFunction::Sum).is_none());
+        assert_eq!(calculate_kpi(&dataset, "revenue", AggregationFunction::Count).unwrap().value, 0.0);
+    }
+
+    #[test]
+    fn test_format_uses_field_format() {
+        let mut dataset = create_test_dataset();
+        dataset.fields[0].format = Some(FieldFormat {
+            currency: Some("$".into()),
+            percent: false,
+        });
+        let sum = calculate_kpi(&dataset, "revenue", AggregationFunction::Sum).unwrap();
+        assert_eq!(sum.formatted, "$600");
+
+        dataset.fields[0].format = Some(FieldFormat {
+            currency: None,
+            percent: true,
+        });
+        dataset.data = DataTable::from_rows(&dataset.fields, vec![vec![Value::from(0.125)]]);
+        let avg = calculate_kpi(&dataset, "revenue", AggregationFunction::Avg).unwrap();
+        assert_eq!(avg.formatted, "12.5%");
+
+        assert_eq!(format_with_commas(1234.0), "1,234");
+        assert_eq!(format_with_commas(-123456.0), "-123,456");
+    }
+
+    #[test]
+    fn test_window_kpi_shows_last_period() {
+        let fields = vec![
+            Field { name: "month".into(), field_type: FieldType::Text, format: None },
+            Field { name: "revenue".into(), field_type: FieldType::Numeric, format: None },
+        ];
+        let mut dataset = create_test_dataset();
+        dataset.data = DataTable::from_rows(&fields, vec![
+            vec![Value::from("2024-01"), Value::from(100)],
+            vec![Value::from("2024-02"), Value::from(150)],
+            vec![Value::from("2024-02"), Value::from(50)],
+        ]);
+        dataset.fields = fields;
+        let mapping = DataMapping { x_axis: Some("month".into()), ..Default::default() };
+
+        let change = calculate_window_kpi(
+            &dataset,
+            &mapping,
+            "revenue",
+            AggregationFunction::Sum,
+            WindowCalculation::PercentDifference,
+        )
+        .unwrap();
+        assert_eq!(change.value, 100.0);
+        assert_eq!(change.formatted, "100.0%");
+    }
+
     fn create_test_dataset() -> Dataset {
+        let fields = vec![
+            Field {
+                name: "revenue".into(),
+                field_type: FieldType::Numeric,
+                format: None,
+            },
+        ];
         Dataset {
             id: "test".into(),
             name: "Test".into(),
             size: "1KB".into(),
             uploaded_at: "2024-01-01".into(),
-            fields: vec![
-                Field {
-                    name: "revenue".into(),
-                    field_type: FieldType::Numeric,
-                },
-            ],
-            active: true,
-            data: vec![
+            data: DataTable::from_rows(&fields, vec![
                 vec![Value::Number(serde_json::Number::from(100))],
                 vec![Value::Number(serde_json::Number::from(200))],
                 vec![Value::Number(serde_json::Number::from(300))],
-            ],
+            ]),
+            fields,
+            active: true,
+            dialect: None,
+            calculated_fields: Vec::new(),
+            derived_from: None,
         }
     }
 }
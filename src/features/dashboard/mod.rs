@@ -6,6 +6,10 @@ pub mod drag_drop;
 pub mod csv_upload;
 pub mod history;
 pub mod kpi_aggregation;
+pub mod cross_filter;
+
+// Off-main-thread parsing and data transforms
+pub mod compute;
 
 // New configuration and data processing modules
 pub mod config;
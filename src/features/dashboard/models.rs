@@ -1,4 +1,13 @@
+use crate::features::dashboard::data::expression::{Expression, ExpressionError};
+use crate::features::dashboard::data::filter::FilterNode;
+use crate::features::dashboard::data::derived::{DeriveError, DerivedFrom};
+use crate::features::dashboard::data::replace::SchemaDiff;
+use crate::features::dashboard::data::time::TimeBucketing;
+use crate::features::dashboard::data::transform::{MissingValues, SortOrder};
+use crate::features::dashboard::data::window::WindowCalculation;
+use crate::features::dashboard::data::{AggregationFunction, DataTable, FilterGroup};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// Unique identifier for widgets
 pub type WidgetId = String;
@@ -19,6 +28,21 @@ pub enum FieldType {
 }
 
 impl FieldType {
+    /// All field types, in the order shown in selectors
+    pub fn all() -> [FieldType; 4] {
+        [Self::Text, Self::Numeric, Self::Date, Self::Boolean]
+    }
+
+    /// Get display name for UI
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::Text => "Text",
+            Self::Numeric => "Numeric",
+            Self::Date => "Date",
+            Self::Boolean => "Boolean",
+        }
+    }
+
     /// Get CSS color class for field type
     pub fn icon_color(&self) -> &'static str {
         match self {
@@ -35,15 +59,70 @@ impl FieldType {
 pub struct Field {
     pub name: String,
     pub field_type: FieldType,
+    /// Currency / percent detected on import, reused when formatting values
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<FieldFormat>,
+}
+
+/// Field computed from the other fields of a dataset by an expression
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CalculatedField {
+    pub name: String,
+    /// Source of the expression, e.g. `Revenue - Cost` (see `data::expression`)
+    pub expression: String,
+}
+
+/// Display metadata of a numeric field
+///
+/// Values are always stored as plain numbers (`12%` is stored as `0.12`);
+/// this records how they were written so KPIs and axes can show them the
+/// same way.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FieldFormat {
+    /// Currency symbol or code found next to the values (e.g. `$`, `€`, `CHF`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    /// Whether values were written as percentages
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub percent: bool,
+}
+
+impl FieldFormat {
+    /// Convert a stored value to the unit shown to the user
+    /// (percent fields are stored as fractions)
+    pub fn display_value(&self, value: f64) -> f64 {
+        if self.percent { value * 100.0 } else { value }
+    }
+
+    /// Add the currency symbol or percent sign to a formatted number
+    pub fn decorate(&self, number: &str) -> String {
+        match (&self.currency, self.percent) {
+            (_, true) => format!("{}%", number),
+            (Some(symbol), false) if symbol.chars().all(|c| c.is_ascii_alphabetic()) => {
+                format!("{} {}", number, symbol)
+            }
+            (Some(symbol), false) => match number.strip_prefix('-') {
+                Some(abs) => format!("-{}{}", symbol, abs),
+                None => format!("{}{}", symbol, number),
+            },
+            (None, false) => number.to_string(),
+        }
+    }
+
+    /// ECharts axis label template (`{value}` is replaced by ECharts)
+    pub fn axis_label_template(&self) -> String {
+        self.decorate("{value}")
+    }
 }
 
 /// Widget type enumeration (unified system)
 ///
 /// Replaces the old ChartType enum. Supports all 10 widget types with
 /// contextual configurations in Data/Style/AI tabs.
-#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub enum WidgetType {
     // Basic Widget Families (5 families, 15+ variants)
+    #[default]
     Line,      // basic, smooth, step, stacked, area
     Bar,       // basic, stacked, grouped, race, waterfall
     Pie,       // basic, doughnut, rose
@@ -59,6 +138,12 @@ pub enum WidgetType {
     // Non-ECharts Widget Types (2 types)
     Kpi,
     Table,
+
+    // Filter controls: set dashboard-wide filters instead of showing data
+    FilterDropdown,
+    FilterDateRange,
+    FilterRange,
+    FilterSearch,
 }
 
 impl WidgetType {
@@ -76,12 +161,24 @@ impl WidgetType {
             Self::Treemap => "Treemap",
             Self::Kpi => "KPI",
             Self::Table => "Table",
+            Self::FilterDropdown => "Dropdown Filter",
+            Self::FilterDateRange => "Date Range Filter",
+            Self::FilterRange => "Range Slider",
+            Self::FilterSearch => "Search Box",
         }
     }
 
     /// Check if this is an ECharts-based widget
     pub fn is_echarts(&self) -> bool {
-        !matches!(self, Self::Kpi | Self::Table)
+        !matches!(self, Self::Kpi | Self::Table) && !self.is_filter_control()
+    }
+
+    /// Check if this is a dashboard filter control
+    pub fn is_filter_control(&self) -> bool {
+        matches!(
+            self,
+            Self::FilterDropdown | Self::FilterDateRange | Self::FilterRange | Self::FilterSearch
+        )
     }
 
     /// Get icon name for layer display
@@ -98,12 +195,19 @@ impl WidgetType {
             Self::Treemap => "account-tree".into(),
             Self::Kpi => "monitoring".into(),
             Self::Table => "table-chart".into(),
+            Self::FilterDropdown => "filter-list".into(),
+            Self::FilterDateRange => "date-range".into(),
+            Self::FilterRange => "tune".into(),
+            Self::FilterSearch => "search".into(),
         }
     }
 }
 
 /// Dataset representing uploaded data
+///
+/// Cloning is cheap: the rows are shared, column by column.
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "StoredDataset")]
 pub struct Dataset {
     pub id: DatasetId,
     pub name: String,           // e.g., "Q3_Sales_Data.csv"
@@ -111,10 +215,287 @@ pub struct Dataset {
     pub uploaded_at: String,    // e.g., "Today"
     pub fields: Vec<Field>,
     pub active: bool,
-    /// Raw data rows (each row is a Vec of JSON values)
-    /// This is the parsed CSV data
+    /// Parsed rows, stored column by column (one column per field).
+    /// Serialized as a list of rows.
+    pub data: DataTable,
+    /// CSV dialect detected (or chosen) when the file was imported.
+    /// Re-imports of the same source reuse it instead of sniffing again.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dialect: Option<CsvDialect>,
+    /// Calculated fields, in definition order. Their columns come after
+    /// the source ones in `fields` and `data`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub calculated_fields: Vec<CalculatedField>,
+    /// Source and reshape of a dataset computed from another one (none
+    /// for imported datasets)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub derived_from: Option<DerivedFrom>,
+}
+
+/// Serialized form of a Dataset, with its rows as JSON arrays
+///
+/// The rows can only be stored in typed columns once the fields are known,
+/// so deserialization goes through this struct.
+#[derive(Deserialize)]
+struct StoredDataset {
+    id: DatasetId,
+    name: String,
+    size: String,
+    uploaded_at: String,
+    fields: Vec<Field>,
+    active: bool,
+    #[serde(default)]
+    data: Vec<Vec<serde_json::Value>>,
+    #[serde(default)]
+    dialect: Option<CsvDialect>,
+    #[serde(default)]
+    calculated_fields: Vec<CalculatedField>,
+    #[serde(default)]
+    derived_from: Option<DerivedFrom>,
+}
+
+impl From<StoredDataset> for Dataset {
+    fn from(stored: StoredDataset) -> Self {
+        let data = DataTable::from_rows(&stored.fields, stored.data);
+        let mut dataset = Self {
+            id: stored.id,
+            name: stored.name,
+            size: stored.size,
+            uploaded_at: stored.uploaded_at,
+            fields: stored.fields,
+            active: stored.active,
+            data,
+            dialect: stored.dialect,
+            calculated_fields: stored.calculated_fields,
+            derived_from: stored.derived_from,
+        };
+        dataset.recompute_calculated_fields();
+        dataset
+    }
+}
+
+impl Dataset {
+    /// Number of fields read from the source (the others are calculated)
+    pub fn source_field_count(&self) -> usize {
+        self.fields.len().saturating_sub(self.calculated_fields.len())
+    }
+
+    /// Whether a field is calculated
+    pub fn is_calculated(&self, name: &str) -> bool {
+        self.calculated_fields.iter().any(|field| field.name == name)
+    }
+
+    /// Add a calculated field, evaluated for every row
+    ///
+    /// The expression may read source fields and earlier calculated fields;
+    /// the field's type is inferred from it.
+    pub fn add_calculated_field(&mut self, name: &str, expression: &str) -> Result<(), ExpressionError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(ExpressionError::new("the field needs a name", 0));
+        }
+        if self.fields.iter().any(|field| field.name == name) {
+            return Err(ExpressionError::new(format!("a field named '{}' already exists", name), 0));
+        }
+
+        let compiled = Expression::compile(expression, &self.fields)?;
+        self.data.push_column(compiled.evaluate(&self.data));
+        self.fields.push(Field { name: name.to_string(), field_type: compiled.field_type(), format: None });
+        self.calculated_fields.push(CalculatedField { name: name.to_string(), expression: expression.to_string() });
+        Ok(())
+    }
+
+    /// Add calculated fields, dropping (with a warning) those whose
+    /// expression doesn't check against the dataset
+    pub fn with_calculated_fields(mut self, definitions: Vec<CalculatedField>) -> Self {
+        self.add_calculated_fields(definitions);
+        self
+    }
+
+    /// Remove a calculated field, and the calculated fields reading it
+    pub fn remove_calculated_field(&mut self, name: &str) {
+        let mut definitions = self.take_calculated_fields();
+        definitions.retain(|field| field.name != name);
+        self.add_calculated_fields(definitions);
+    }
+
+    /// Evaluate the calculated fields again, e.g. after the rows changed
+    pub fn recompute_calculated_fields(&mut self) {
+        let definitions = self.take_calculated_fields();
+        self.add_calculated_fields(definitions);
+    }
+
+    /// New dataset computed from others (reshaped or joined), which
+    /// remembers how so it can be computed again
+    ///
+    /// The result is inactive, without calculated fields of its own.
+    pub fn derived(
+        derived_from: DerivedFrom,
+        id: DatasetId,
+        name: &str,
+        datasets: &[Dataset],
+    ) -> Result<Dataset, DeriveError> {
+        let (fields, data) = derived_from.compute(datasets)?;
+        Ok(Dataset {
+            id,
+            name: name.to_string(),
+            size: format!("{} rows", data.len()),
+            uploaded_at: derived_from.describe(datasets),
+            fields,
+            active: false,
+            data,
+            dialect: None,
+            calculated_fields: Vec::new(),
+            derived_from: Some(derived_from),
+        })
+    }
+
+    /// Compute a derived dataset again from its (changed) sources, keeping
+    /// its calculated fields
+    ///
+    /// Calculated fields follow the renames of `renamed` that reach the
+    /// dataset. Nothing changes for datasets that aren't derived, or when
+    /// they can no longer be computed (e.g. a source field was removed).
+    pub fn recompute(&mut self, datasets: &[Dataset], renamed: &SchemaDiff) -> Result<(), DeriveError> {
+        let Some(derived_from) = &self.derived_from else {
+            return Ok(());
+        };
+        let (fields, data) = derived_from.compute(datasets)?;
+        let renamed = renamed.renames_in(&fields);
+        for calculated in &mut self.calculated_fields {
+            calculated.expression = renamed.repair_expression(&calculated.expression);
+        }
+        self.size = format!("{} rows", data.len());
+        self.uploaded_at = derived_from.describe(datasets);
+        self.set_source_data(fields, data);
+        Ok(())
+    }
+
+    /// Replace the source fields and rows, keeping the calculated fields
+    pub fn set_source_data(&mut self, fields: Vec<Field>, data: DataTable) {
+        let definitions = self.take_calculated_fields();
+        self.fields = fields;
+        self.data = data;
+        self.add_calculated_fields(definitions);
+    }
+
+    /// Replace the rows with those of a new import of the same source,
+    /// keeping the id, name and calculated fields
+    ///
+    /// Calculated fields follow the renamed fields; those that no longer
+    /// compute (e.g. reading a removed field) are dropped.
+    ///
+    /// # Returns
+    /// How the source fields changed
+    pub fn replace_data(&mut self, replacement: Dataset) -> SchemaDiff {
+        let diff = SchemaDiff::between(&self.fields[..self.source_field_count()], &replacement.fields);
+        for calculated in &mut self.calculated_fields {
+            calculated.expression = diff.repair_expression(&calculated.expression);
+        }
+        self.size = replacement.size;
+        self.uploaded_at = replacement.uploaded_at;
+        self.dialect = replacement.dialect;
+        self.set_source_data(replacement.fields, replacement.data);
+        diff
+    }
+
+    /// Remove the calculated fields and their columns, returning their definitions
+    fn take_calculated_fields(&mut self) -> Vec<CalculatedField> {
+        let source_fields = self.source_field_count();
+        self.fields.truncate(source_fields);
+        self.data.truncate_columns(source_fields);
+        std::mem::take(&mut self.calculated_fields)
+    }
+
+    fn add_calculated_fields(&mut self, definitions: Vec<CalculatedField>) {
+        for definition in definitions {
+            if let Err(e) = self.add_calculated_field(&definition.name, &definition.expression) {
+                log::warn!("Dropping calculated field '{}' of '{}': {}", definition.name, self.name, e);
+            }
+        }
+    }
+}
+
+/// CSV dialect: how a delimited text file is laid out
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CsvDialect {
+    /// Field separator (`,`, tab, `;` or `|`)
+    pub delimiter: char,
+    /// Quote character wrapping fields that contain delimiters or newlines
+    pub quote: char,
+    /// Escape character for quotes inside quoted fields (e.g. `\`).
+    /// `None` means quotes are escaped by doubling them (`""`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub escape: Option<char>,
+    /// Whether the first row holds column names
+    pub has_header: bool,
+    /// Decimal and thousands separators used by numeric cells
     #[serde(default)]
-    pub data: Vec<Vec<serde_json::Value>>,
+    pub number_format: NumberFormat,
+}
+
+/// How numbers are written in a source file (e.g. `1,234.56` or `1.234,56`)
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NumberFormat {
+    /// Separator between the integer and fractional part
+    pub decimal_separator: char,
+    /// Digit group separator, if any. A space also accepts the
+    /// non-breaking spaces used by some locales.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thousands_separator: Option<char>,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self {
+            decimal_separator: '.',
+            thousands_separator: Some(','),
+        }
+    }
+}
+
+impl NumberFormat {
+    /// Formats offered in the import wizard, most common first
+    pub fn presets() -> [NumberFormat; 5] {
+        [
+            Self { decimal_separator: '.', thousands_separator: Some(',') },
+            Self { decimal_separator: ',', thousands_separator: Some('.') },
+            Self { decimal_separator: ',', thousands_separator: Some(' ') },
+            Self { decimal_separator: '.', thousands_separator: Some(' ') },
+            Self { decimal_separator: '.', thousands_separator: Some('\'') },
+        ]
+    }
+
+    /// Example rendering for UI display, e.g. `1,234.56`
+    pub fn example(&self) -> String {
+        let thousands = self.thousands_separator.map(String::from).unwrap_or_default();
+        format!("1{}234{}56", thousands, self.decimal_separator)
+    }
+}
+
+impl Default for CsvDialect {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            quote: '"',
+            escape: None,
+            has_header: true,
+            number_format: NumberFormat::default(),
+        }
+    }
+}
+
+impl CsvDialect {
+    /// Human-readable name of a delimiter for UI display
+    pub fn delimiter_label(delimiter: char) -> &'static str {
+        match delimiter {
+            ',' => "Comma",
+            '\t' => "Tab",
+            ';' => "Semicolon",
+            '|' => "Pipe",
+            _ => "Custom",
+        }
+    }
 }
 
 /// Chart type enumeration
@@ -144,12 +525,44 @@ pub struct DataMapping {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub x_axis: Option<String>,   // Field name for X-axis (dimension)
 
+    /// Periods a date x-axis is grouped into (none: one point per date)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_bucketing: Option<TimeBucketing>,
+
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub y_axis: Vec<String>,      // Field names for Y-axis (measures, can be multiple)
 
+    /// Aggregation of each measure, by field name; measures without an
+    /// entry are summed
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub aggregations: BTreeMap<String, AggregationFunction>,
+
+    /// Window calculation of each measure (chart measure, table column or
+    /// KPI field), applied after aggregation, by field name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub calculations: BTreeMap<String, WindowCalculation>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub category: Option<String>, // Field for grouping/categorization (stacking)
 
+    /// Most series the category splits each measure into; smaller
+    /// categories are merged into "Other" (10 when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub series_limit: Option<usize>,
+
+    /// What category series show where a category has no rows
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub missing_values: MissingValues,
+
+    /// Order of the x values, slices, nodes or table rows
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub sort: SortOrder,
+
+    /// Most x values, slices, nodes or table rows shown; the rest are
+    /// merged into "Other"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_n: Option<usize>,
+
     // Advanced fields for specialized widgets
     #[serde(skip_serializing_if = "Option::is_none")]
     pub size: Option<String>,         // For Scatter: bubble size measure
@@ -183,44 +596,140 @@ pub struct DataMapping {
     pub kpi_field: Option<String>,           // For KPI: field to aggregate
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub kpi_aggregation: Option<KpiAggregation>, // For KPI: how to aggregate
-}
+    pub kpi_aggregation: Option<AggregationFunction>, // For KPI: how to aggregate
 
-/// KPI aggregation type
-#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
-pub enum KpiAggregation {
-    Sum,        // Sum of all values
-    Average,    // Average (mean)
-    Count,      // Count of rows
-    Min,        // Minimum value
-    Max,        // Maximum value
-    Last,       // Last (most recent) value
-    First,      // First value
+    /// Row filters, applied before the data is aggregated or displayed
+    #[serde(default, skip_serializing_if = "FilterGroup::is_empty")]
+    pub filters: FilterGroup,
+
+    /// Whether the dashboard's filter controls leave this widget alone
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub ignore_global_filters: bool,
+
+    // Filter control fields
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter_field: Option<String>,        // For filter controls: field the control filters
 }
 
-impl KpiAggregation {
-    pub fn display_name(&self) -> &'static str {
-        match self {
-            Self::Sum => "Total",
-            Self::Average => "Average",
-            Self::Count => "Count",
-            Self::Min => "Minimum",
-            Self::Max => "Maximum",
-            Self::Last => "Latest",
-            Self::First => "First",
+impl DataMapping {
+    /// Aggregation function of a measure (`Sum` unless chosen otherwise)
+    pub fn aggregation(&self, measure: &str) -> AggregationFunction {
+        self.aggregations.get(measure).cloned().unwrap_or_default()
+    }
+
+    /// Choose the aggregation function of a measure
+    pub fn set_aggregation(&mut self, measure: &str, aggregation: AggregationFunction) {
+        self.aggregations.insert(measure.to_string(), aggregation);
+    }
+
+    /// Window calculation of a measure, if any
+    pub fn calculation(&self, measure: &str) -> Option<WindowCalculation> {
+        self.calculations.get(measure).copied()
+    }
+
+    /// Choose the window calculation of a measure (`None` shows the
+    /// aggregated values as they are)
+    pub fn set_calculation(&mut self, measure: &str, calculation: Option<WindowCalculation>) {
+        match calculation {
+            Some(calculation) => self.calculations.insert(measure.to_string(), calculation),
+            None => self.calculations.remove(measure),
+        };
+    }
+
+    /// Fields the mapping refers to, filters included, each once
+    pub fn fields(&self) -> Vec<&str> {
+        let singles = [
+            &self.x_axis, &self.category, &self.size, &self.color, &self.open,
+            &self.close, &self.high, &self.low, &self.kpi_field, &self.filter_field,
+        ];
+        let mut fields: Vec<&str> = Vec::new();
+        let names = singles
+            .into_iter()
+            .flatten()
+            .chain(&self.y_axis)
+            .chain(&self.hierarchy)
+            .chain(&self.columns)
+            .map(String::as_str)
+            .chain(self.filters.fields());
+        for name in names {
+            if !fields.contains(&name) {
+                fields.push(name);
+            }
         }
+        fields
     }
 
-    pub fn icon(&self) -> &'static str {
-        match self {
-            Self::Sum => "sum", // Σ
-            Self::Average => "average",
-            Self::Count => "counter",
-            Self::Min => "arrow-downward",
-            Self::Max => "arrow-upward",
-            Self::Last => "schedule",
-            Self::First => "history",
+    /// Follow the rename of a field, settings of the measure included
+    pub fn rename_field(&mut self, from: &str, to: &str) {
+        let singles = [
+            &mut self.x_axis, &mut self.category, &mut self.size, &mut self.color, &mut self.open,
+            &mut self.close, &mut self.high, &mut self.low, &mut self.kpi_field, &mut self.filter_field,
+        ];
+        let names = singles
+            .into_iter()
+            .flatten()
+            .chain(&mut self.y_axis)
+            .chain(&mut self.hierarchy)
+            .chain(&mut self.columns);
+        for name in names {
+            if name == from {
+                *name = to.to_string();
+            }
+        }
+        if let Some(aggregation) = self.aggregations.remove(from) {
+            self.aggregations.insert(to.to_string(), aggregation);
+        }
+        if let Some(calculation) = self.calculations.remove(from) {
+            self.calculations.insert(to.to_string(), calculation);
         }
+        self.filters.rename_field(from, to);
+    }
+
+    /// Keep the settings of `previous` that outlive a change of fields: the
+    /// aggregations and window calculations of the measures this mapping
+    /// still uses, the time bucketing, the category series settings, the
+    /// sort order and top-N limit, the filters and the opt-out from
+    /// dashboard filters
+    pub fn with_settings_from(mut self, previous: &DataMapping) -> Self {
+        self.time_bucketing = previous.time_bucketing;
+        self.sort = previous.sort.clone();
+        self.top_n = previous.top_n;
+        self.series_limit = previous.series_limit;
+        self.missing_values = previous.missing_values;
+        self.filters = previous.filters.clone();
+        self.ignore_global_filters = previous.ignore_global_filters;
+        self.aggregations = previous
+            .aggregations
+            .iter()
+            .filter(|(measure, _)| self.y_axis.contains(measure))
+            .map(|(measure, aggregation)| (measure.clone(), aggregation.clone()))
+            .collect();
+        self.calculations = previous
+            .calculations
+            .iter()
+            .filter(|(measure, _)| {
+                self.y_axis.contains(measure)
+                    || self.columns.contains(measure)
+                    || self.kpi_field.as_ref() == Some(measure)
+            })
+            .map(|(measure, calculation)| (measure.clone(), *calculation))
+            .collect();
+        self
+    }
+
+    /// The mapping a widget's data is computed with: its own filters ANDed
+    /// with the dashboard's (filter controls and chart selections), unless
+    /// it ignores them
+    pub fn with_global_filters(&self, global: &FilterGroup) -> DataMapping {
+        let mut mapping = self.clone();
+        if self.ignore_global_filters || global.is_empty() {
+            return mapping;
+        }
+        mapping.filters = global.clone();
+        if !self.filters.is_empty() {
+            mapping.filters.filters.push(FilterNode::Group(self.filters.clone()));
+        }
+        mapping
     }
 }
 
@@ -229,6 +738,10 @@ impl KpiAggregation {
 pub struct ChartConfig {
     #[serde(default)]
     pub chart_type: Option<WidgetType>,
+    /// Dataset the widget shows. Unbound widgets (templates) use the
+    /// active dataset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dataset_id: Option<DatasetId>,
     pub data_mapping: DataMapping,
     pub style_options: String,    // JSON string of ECharts options
 }
@@ -237,10 +750,20 @@ impl ChartConfig {
     pub fn default_with_type(widget_type: WidgetType) -> Self {
         Self {
             chart_type: Some(widget_type),
+            dataset_id: None,
             data_mapping: DataMapping::default(),
             style_options: "{}".into(),
         }
     }
+
+    /// The dataset the widget shows: the bound one (none if it was
+    /// removed), or the active one when unbound
+    pub fn dataset<'a>(&self, datasets: &'a [Dataset]) -> Option<&'a Dataset> {
+        match &self.dataset_id {
+            Some(id) => datasets.iter().find(|ds| &ds.id == id),
+            None => datasets.iter().find(|ds| ds.active),
+        }
+    }
 }
 
 /// Grid position and size
@@ -258,12 +781,6 @@ impl Default for GridPosition {
     }
 }
 
-impl Default for WidgetType {
-    fn default() -> Self {
-        Self::Line
-    }
-}
-
 /// Widget on the dashboard canvas
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Widget {
@@ -301,6 +818,7 @@ pub struct Layer {
 fn default_true() -> bool { true }
 fn is_true(v: &bool) -> bool { *v }
 fn is_false(v: &bool) -> bool { !*v }
+fn is_default<T: Default + PartialEq>(v: &T) -> bool { *v == T::default() }
 
 /// Template category for organizing template gallery
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
@@ -332,7 +850,7 @@ pub struct Template {
 }
 
 /// CSV upload and parsing errors
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum CsvError {
     /// File size exceeds limit (max MB)
     FileTooLarge {
@@ -361,6 +879,10 @@ pub enum CsvError {
     },
     /// Type inference failed
     TypeInferenceFailed(String),
+    /// Malformed JSON, or JSON that isn't a list of objects
+    InvalidJson(String),
+    /// Parsing was stopped by the user
+    Cancelled,
 }
 
 impl std::fmt::Display for CsvError {
@@ -386,8 +908,119 @@ impl std::fmt::Display for CsvError {
                 write!(f, "Parse error at row {}: {}", row, message)
             }
             Self::TypeInferenceFailed(msg) => write!(f, "Type inference failed: {}", msg),
+            Self::InvalidJson(msg) => write!(f, "Invalid JSON: {}", msg),
+            Self::Cancelled => write!(f, "Import cancelled"),
         }
     }
 }
 
 impl std::error::Error for CsvError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn dataset() -> Dataset {
+        let fields = vec![
+            Field { name: "Revenue".to_string(), field_type: FieldType::Numeric, format: None },
+            Field { name: "Cost".to_string(), field_type: FieldType::Numeric, format: None },
+        ];
+        Dataset {
+            id: "sales".to_string(),
+            name: "sales.csv".to_string(),
+            size: "0 MB".to_string(),
+            uploaded_at: "Today".to_string(),
+            data: DataTable::from_rows(&fields, vec![vec![json!(100), json!(60)], vec![json!(50), json!(70)]]),
+            fields,
+            active: true,
+            dialect: None,
+            calculated_fields: Vec::new(),
+            derived_from: None,
+        }
+    }
+
+    fn column_values(dataset: &Dataset, name: &str) -> Vec<Value> {
+        let idx = dataset.fields.iter().position(|f| f.name == name).unwrap();
+        (0..dataset.data.len()).map(|row| dataset.data.value(row, idx)).collect()
+    }
+
+    #[test]
+    fn test_calculated_fields() {
+        let mut dataset = dataset();
+        dataset.add_calculated_field("Profit", "Revenue - Cost").unwrap();
+        dataset.add_calculated_field("Loss", "Profit < 0").unwrap();
+
+        assert_eq!(dataset.fields[3].field_type, FieldType::Boolean);
+        assert_eq!(column_values(&dataset, "Profit"), vec![json!(40.0), json!(-20.0)]);
+        assert_eq!(dataset.source_field_count(), 2);
+        assert!(dataset.add_calculated_field("Profit", "1").is_err());
+        assert_eq!(dataset.add_calculated_field("Margin", "Profit / Price").unwrap_err().position, 9);
+
+        // Removing a field drops the fields computed from it
+        dataset.remove_calculated_field("Profit");
+        assert!(dataset.calculated_fields.is_empty());
+        assert_eq!(dataset.data.column_count(), 2);
+    }
+
+    #[test]
+    fn test_calculated_fields_follow_source_data() {
+        let mut dataset = dataset();
+        dataset.add_calculated_field("Profit", "Revenue - Cost").unwrap();
+
+        // Saved and loaded again
+        let loaded: Dataset = serde_json::from_str(&serde_json::to_string(&dataset).unwrap()).unwrap();
+        assert_eq!(loaded, dataset);
+
+        let fields = dataset.fields[..2].to_vec();
+        let data = DataTable::from_rows(&fields, vec![vec![json!(10), json!(1)]]);
+        dataset.set_source_data(fields, data);
+        assert_eq!(column_values(&dataset, "Profit"), vec![json!(9.0)]);
+    }
+
+    #[test]
+    fn test_chart_config_dataset() {
+        let mut targets = dataset();
+        targets.id = "targets".to_string();
+        targets.active = false;
+        let datasets = vec![dataset(), targets];
+
+        let mut config = ChartConfig::default();
+        assert_eq!(config.dataset(&datasets).map(|ds| ds.id.as_str()), Some("sales"));
+        config.dataset_id = Some("targets".to_string());
+        assert_eq!(config.dataset(&datasets).map(|ds| ds.id.as_str()), Some("targets"));
+
+        // A removed dataset isn't replaced by the active one
+        assert!(config.dataset(&datasets[..1]).is_none());
+
+        // Saved before the binding existed
+        let saved = serde_json::to_value(ChartConfig::default()).unwrap();
+        assert!(saved.get("dataset_id").is_none());
+        assert_eq!(serde_json::from_value::<ChartConfig>(saved).unwrap().dataset_id, None);
+    }
+
+    #[test]
+    fn test_global_filters_join_widget_filters() {
+        use crate::features::dashboard::data::filter::{Filter, FilterOperator, GlobalFilters};
+
+        let condition = |field: &str, value: i64| Filter {
+            field: field.to_string(),
+            operator: FilterOperator::GreaterThan,
+            values: vec![json!(value)],
+        };
+        let mut global = GlobalFilters::default();
+        global.set("control_1", Some(condition("Revenue", 60)));
+
+        let mut mapping = DataMapping::default();
+        mapping.filters.filters.push(FilterNode::Condition(condition("Cost", 65)));
+        let effective = mapping.with_global_filters(&global.group());
+        assert!(effective.filters.matching_rows(&dataset()).is_empty());
+        assert_eq!(effective.filters.filters.len(), 2);
+
+        // Opted-out widgets keep their own filters only
+        mapping.ignore_global_filters = true;
+        assert_eq!(mapping.with_global_filters(&global.group()), mapping);
+        global.set("control_1", None);
+        assert!(global.is_empty());
+    }
+}
@@ -9,6 +9,9 @@ mod features;  // Dashboard feature modules
 mod config;     // Application configuration
 pub mod ui; // UI component library
 
+// Entry point of the compute Web Worker (see src/bin/compute_worker.rs)
+pub use crate::features::dashboard::compute::run_compute_worker;
+
 // Top-Level pages
 use crate::pages::{home::Home, dashboard::Dashboard, projects::Projects, settings::Settings};
 use crate::config::BASE_PATH;
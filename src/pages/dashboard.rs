@@ -1,4 +1,4 @@
-use crate::features::dashboard::{csv_upload::CsvUploadManager, DashboardContext, components::LeftSidebar};
+use crate::features::dashboard::{compute::ComputeClient, csv_upload::CsvUploadManager, DashboardContext, components::LeftSidebar};
 use crate::ui::atoms::ToastContainer;
 use crate::ui::organisms::data::*;
 use crate::ui::organisms::*;
@@ -29,6 +29,9 @@ pub fn Dashboard() -> impl IntoView {
     // Initialize toast context for notifications
     let _toast = ToastContext::provide();
 
+    // Start the compute worker (widget data transforms off the main thread)
+    let _compute = ComputeClient::provide(dashboard);
+
     // Initialize with empty state (users upload their own CSV files)
     dashboard.init_empty();
 
@@ -49,6 +52,10 @@ pub fn Dashboard() -> impl IntoView {
         let layers_signal = dashboard.layers;
         let _ = layers_signal.get();
 
+        // Datasets and filter selections are saved with the dashboard
+        dashboard.datasets.track();
+        dashboard.global_filters.track();
+
         // Clear existing timer
         let timer = debounce_timer.clone();
         if let Some(id) = *timer.borrow() {
@@ -157,6 +164,16 @@ pub fn Dashboard() -> impl IntoView {
             // Toast notifications container
             <ToastContainer />
 
+            // Import wizard (opens while an uploaded file awaits confirmation)
+            <ImportWizard upload_manager=upload_manager />
+
+            // Blocks editing until the saved dashboard has loaded
+            <Show when=move || dashboard.restoring.get()>
+                <div class="fixed inset-0 z-50 flex items-center justify-center bg-base-100/70">
+                    <span class="loading loading-spinner loading-lg text-primary"></span>
+                </div>
+            </Show>
+
             // Header
             <Header user_avatar_url=avatar_url />
 
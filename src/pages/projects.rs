@@ -1,17 +1,15 @@
+use crate::features::dashboard::io::storage::{self, DashboardSummary};
 use crate::ui::atoms::{Button, ButtonVariant, Icon, IconName};
 use crate::ui::molecules::{project_card::ProjectData, ProjectsGrid, SearchBar};
 use crate::ui::organisms::Header;
 use leptos::prelude::*;
 
-/// Convert DashboardTemplate to ProjectData
-fn template_to_project(
-    template: &crate::features::dashboard::export::DashboardTemplate,
-    storage_key: &str,
-) -> ProjectData {
+/// Convert a saved dashboard's summary to ProjectData
+fn summary_to_project(summary: &DashboardSummary) -> ProjectData {
     // Parse timestamps
-    let created = chrono::DateTime::parse_from_rfc3339(&template.metadata.created_at)
+    let created = chrono::DateTime::parse_from_rfc3339(&summary.created_at)
         .unwrap_or_else(|_| chrono::Utc::now().into());
-    let updated = chrono::DateTime::parse_from_rfc3339(&template.metadata.exported_at)
+    let updated = chrono::DateTime::parse_from_rfc3339(&summary.updated_at)
         .unwrap_or_else(|_| chrono::Utc::now().into());
 
     let created_at = created.format("%Y-%m-%d").to_string();
@@ -26,14 +24,14 @@ fn template_to_project(
     };
 
     // Description from first widget
-    let description = template.widgets.first()
+    let description = summary.first_widget.as_ref()
         .map(|w| w.subtitle.as_ref()
             .map(|s| format!("{}: {}", w.title, s))
             .unwrap_or_else(|| w.title.clone()))
         .unwrap_or_else(|| "Custom dashboard".into());
 
     // Icon from first widget
-    let icon = template.widgets.first()
+    let icon = summary.first_widget.as_ref()
         .map(|w| match w.widget_type {
             crate::features::dashboard::WidgetType::Bar => "icon-[lucide--bar-chart-2]",
             crate::features::dashboard::WidgetType::Line => "icon-[lucide--trending-up]",
@@ -44,9 +42,9 @@ fn template_to_project(
         .to_string();
 
     ProjectData {
-        id: storage_key.into(),
+        id: summary.key.clone(),
         icon,
-        title: template.metadata.title.clone(),
+        title: summary.title.clone(),
         description,
         views: 0,
         edits: 0,
@@ -74,23 +72,16 @@ pub fn Projects() -> impl IntoView {
     let avatar_url =
         "https://ui-avatars.com/api/?name=Data+Viz&background=1C4E80&color=fff".to_string();
 
-    // Load dashboards from localStorage
-    let projects_resource = Resource::new(
-        || (),
-        |_| async {
-            crate::features::dashboard::io::storage::list_templates()
-                .map(|keys| {
-                    keys.into_iter()
-                        .filter_map(|key| {
-                            crate::features::dashboard::io::storage::load_template(&key)
-                                .ok()
-                                .map(|tpl| template_to_project(&tpl, &key))
-                        })
-                        .collect::<Vec<_>>()
-                })
-                .unwrap_or_default()
+    // Load saved dashboards (summaries only, not their data)
+    let projects_resource = LocalResource::new(|| async {
+        match storage::list_dashboards().await {
+            Ok(summaries) => summaries.iter().map(summary_to_project).collect::<Vec<_>>(),
+            Err(e) => {
+                log::error!("Failed to list saved dashboards: {}", e);
+                Vec::new()
+            }
         }
-    );
+    });
 
     view! {
         <div class="min-h-screen bg-base-200 text-base-content font-display">
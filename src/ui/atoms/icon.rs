@@ -80,6 +80,8 @@ pub enum IconName {
     Hash,
     Settings,
     Filter,
+    ListFilter,
+    Sliders,
     Sort,
     Schedule,
     History,
@@ -160,6 +162,8 @@ impl IconName {
             Self::Hash => "icon-[lucide--hash]",
             Self::Settings => "icon-[lucide--settings]",
             Self::Filter => "icon-[lucide--filter]",
+            Self::ListFilter => "icon-[lucide--list-filter]",
+            Self::Sliders => "icon-[lucide--sliders-horizontal]",
             Self::Sort => "icon-[lucide--arrow-up-down]",
             Self::Schedule => "icon-[lucide--clock]",
             Self::History => "icon-[lucide--history]",
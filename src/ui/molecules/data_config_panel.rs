@@ -5,13 +5,16 @@
 //! Supports bidirectional binding with selected widget.
 
 use crate::features::dashboard::{
+    data::time::{TimeBucketing, TimeGranularity},
+    data::transform::{MissingValues, SortOrder, DEFAULT_SERIES_LIMIT},
+    data::window::{WindowCalculation, DEFAULT_WINDOW},
+    data::AggregationFunction,
     models::{DataMapping, FieldType, WidgetType},
     DashboardContext,
 };
-use crate::ui::molecules::field_selector::{
-    AggregationFunction, AggregationSelector, FieldSelector,
-};
-use crate::ui::molecules::ColumnSelector;
+use crate::ui::molecules::field_selector::{AggregationSelector, FieldSelector};
+use crate::ui::atoms::Toggle;
+use crate::ui::molecules::{ColumnSelector, FilterEditor};
 use leptos::prelude::*;
 
 /// Data configuration panel component
@@ -29,13 +32,21 @@ pub fn DataConfigPanel(
 ) -> impl IntoView {
     let dashboard = DashboardContext::use_context();
 
-    // Get fields from active dataset
+    // Get selected widget (if any)
+    let selected_widget = Memo::new(move |_| {
+        dashboard
+            .selected_widget_id
+            .get()
+            .and_then(|id| dashboard.get_widgets().into_iter().find(|w| w.id == id))
+    });
+
+    // Get fields from the selected widget's dataset (the active one for a
+    // new widget)
     let fields = Memo::new(move |_| {
+        let config = selected_widget.get().map(|w| w.chart_config).unwrap_or_default();
         dashboard
-            .get_datasets()
-            .into_iter()
-            .find(|ds| ds.active)
-            .map(|ds| ds.fields)
+            .datasets
+            .with(|datasets| config.dataset(datasets).map(|ds| ds.fields.clone()))
             .unwrap_or_default()
     });
 
@@ -61,14 +72,26 @@ pub fn DataConfigPanel(
             .collect::<Vec<_>>()
     });
 
-    // Get selected widget (if any)
-    let selected_widget = Memo::new(move |_| {
-        dashboard
-            .selected_widget_id
+    // Fields a filter control can be bound to
+    let control_fields = Memo::new(move |_| {
+        fields
             .get()
-            .and_then(|id| dashboard.get_widgets().into_iter().find(|w| w.id == id))
+            .into_iter()
+            .filter(|f| match widget_type {
+                WidgetType::FilterDateRange => f.field_type == FieldType::Date,
+                WidgetType::FilterRange => f.field_type == FieldType::Numeric,
+                WidgetType::FilterSearch => f.field_type == FieldType::Text,
+                _ => true,
+            })
+            .collect::<Vec<_>>()
+    });
+
+    // Weights offered for weighted averages
+    let weight_fields = Signal::derive(move || {
+        numeric_fields.get().into_iter().map(|f| f.name).collect::<Vec<_>>()
     });
 
+
     // Initialize signals with selected widget's data mapping or defaults
     // Use get_untracked() for initialization to avoid reactive tracking warnings
     let (x_axis, set_x_axis) = signal(
@@ -86,7 +109,20 @@ pub fn DataConfigPanel(
             .get_untracked()
             .and_then(|w| w.chart_config.data_mapping.category),
     );
-    let (aggregation, set_aggregation) = signal(AggregationFunction::Sum);
+    // Aggregation of the selected measure
+    let measure_aggregation = |mapping: &DataMapping| {
+        mapping
+            .y_axis
+            .first()
+            .map(|measure| mapping.aggregation(measure))
+            .unwrap_or_default()
+    };
+    let (aggregation, set_aggregation) = signal(
+        selected_widget
+            .get_untracked()
+            .map(|w| measure_aggregation(&w.chart_config.data_mapping))
+            .unwrap_or_default(),
+    );
 
     // Convert to Signal type for FieldSelector using derive
     let x_axis_sig = Signal::derive(move || x_axis.get());
@@ -102,6 +138,7 @@ pub fn DataConfigPanel(
         ) {
             // Update signals without triggering effects if possible, though here they are primarily for UI binding
             untrack(move || {
+                set_aggregation.set(measure_aggregation(&widget.chart_config.data_mapping));
                 set_x_axis.set(widget.chart_config.data_mapping.x_axis);
                 set_y_axis.set(widget.chart_config.data_mapping.y_axis.first().cloned());
                 set_category.set(widget.chart_config.data_mapping.category);
@@ -109,6 +146,85 @@ pub fn DataConfigPanel(
         }
     });
 
+    // Whether the selected dimension is a date
+    let x_is_date = Signal::derive(move || {
+        let x_axis = x_axis.get();
+        fields.with(|fields| {
+            fields
+                .iter()
+                .any(|f| Some(&f.name) == x_axis.as_ref() && f.field_type == FieldType::Date)
+        })
+    });
+
+    // Time bucketing of a date dimension (line and bar)
+    let time_settings = move || {
+        view! {
+            <TimeBucketingSettings
+                mapping=Signal::derive(move || {
+                    selected_widget.get().filter(|_| x_is_date.get()).map(|w| w.chart_config.data_mapping)
+                })
+                on_change=Callback::new(move |mapping: DataMapping| {
+                    if let Some(widget) = selected_widget.get_untracked() {
+                        dashboard.update_widget_mapping(&widget.id, mapping);
+                    }
+                })
+            />
+        }
+    };
+
+    // Choose the window calculation of a measure of the selected widget
+    let set_calculation = move |measure: &str, calculation: Option<WindowCalculation>| {
+        if let Some(widget) = selected_widget.get_untracked() {
+            let mut new_mapping = widget.chart_config.data_mapping.clone();
+            new_mapping.set_calculation(measure, calculation);
+            dashboard.update_widget_mapping(&widget.id, new_mapping);
+        }
+    };
+
+    // Window calculation of the selected measure (line and bar)
+    let calculation_settings = move || {
+        move || {
+            let measure = y_axis.get()?;
+            let selected = selected_widget.get()?.chart_config.data_mapping.calculation(&measure);
+            Some(view! {
+                <CalculationSelect
+                    label="Calculation"
+                    selected=selected
+                    on_change=Callback::new(move |calculation| set_calculation(&measure, calculation))
+                />
+            })
+        }
+    };
+
+    // Order and top-N limit of the items (pie, bar, radar, treemap, table)
+    let sort_settings = move || {
+        view! {
+            <SortSettings
+                mapping=Signal::derive(move || selected_widget.get().map(|w| w.chart_config.data_mapping))
+                on_change=Callback::new(move |mapping: DataMapping| {
+                    if let Some(widget) = selected_widget.get_untracked() {
+                        dashboard.update_widget_mapping(&widget.id, mapping);
+                    }
+                })
+            />
+        }
+    };
+
+    // Series settings of the category (line and bar)
+    let category_settings = move || {
+        view! {
+            <CategorySeriesSettings
+                mapping=Signal::derive(move || selected_widget.get().map(|w| w.chart_config.data_mapping))
+                on_change=Callback::new(move |mapping: DataMapping| {
+                    set_category.set(mapping.category.clone());
+                    if let Some(widget) = selected_widget.get_untracked() {
+                        dashboard.update_widget_mapping(&widget.id, mapping);
+                    }
+                })
+            />
+        }
+    };
+
     view! {
         <div class="flex flex-col gap-4 p-4">
             // Panel header
@@ -138,6 +254,45 @@ pub fn DataConfigPanel(
                 </div>
             </div>
 
+            // Dataset the selected widget shows
+            {move || {
+                selected_widget.get().map(|widget| {
+                    let bound = widget.chart_config.dataset_id.clone();
+                    let widget_id = widget.id.clone();
+                    let datasets = dashboard.get_datasets();
+                    // Unbound widgets show the active dataset; a bound one may have been removed
+                    let placeholder = match &bound {
+                        None => Some("Active dataset"),
+                        Some(id) if !datasets.iter().any(|ds| &ds.id == id) => Some("Dataset not found"),
+                        Some(_) => None,
+                    };
+                    view! {
+                        <div class="flex items-center gap-2">
+                            <span class="text-xs text-base-content/70 flex-1">"Dataset"</span>
+                            <select
+                                class="select select-bordered select-xs max-w-48"
+                                on:change=move |ev| dashboard.set_widget_dataset(&widget_id, event_target_value(&ev))
+                            >
+                                {placeholder.map(|label| view! {
+                                    <option value="" selected=true disabled=true>{label}</option>
+                                })}
+                                {datasets
+                                    .into_iter()
+                                    .map(|dataset| {
+                                        let is_selected = bound.as_ref() == Some(&dataset.id);
+                                        view! {
+                                            <option value=dataset.id selected=is_selected>
+                                                {dataset.name}
+                                            </option>
+                                        }
+                                    })
+                                    .collect::<Vec<_>>()}
+                            </select>
+                        </div>
+                    }
+                })
+            }}
+
             // No dataset message
             {move || {
                 let has_fields = !fields.get().is_empty();
@@ -188,11 +343,15 @@ pub fn DataConfigPanel(
                                                             category: category.get_untracked(),
                                                             ..Default::default()
                                                         };
-                                                        dashboard.update_widget_mapping(&widget.id, new_mapping);
+                                                        dashboard.update_widget_mapping(
+                                                            &widget.id,
+                                                            new_mapping.with_settings_from(&widget.chart_config.data_mapping),
+                                                        );
                                                     }
                                                 }),
                                             )
                                         />
+                                        {time_settings()}
 
                                         // Y-Axis (Measures)
                                         <div class="flex flex-col gap-2">
@@ -211,13 +370,17 @@ pub fn DataConfigPanel(
                                                     Callback::new(move |name: String| {
                                                         set_y_axis.set(Some(name.clone()));
                                                         if let Some(widget) = selected_widget.get_untracked() {
+                                                            set_aggregation.set(widget.chart_config.data_mapping.aggregation(&name));
                                                             let new_mapping = DataMapping {
                                                                 x_axis: x_axis.get_untracked(),
                                                                 y_axis: vec![name],
                                                                 category: category.get_untracked(),
                                                                 ..Default::default()
                                                             };
-                                                            dashboard.update_widget_mapping(&widget.id, new_mapping);
+                                                            dashboard.update_widget_mapping(
+                                                                &widget.id,
+                                                                new_mapping.with_settings_from(&widget.chart_config.data_mapping),
+                                                            );
                                                         }
                                                     }),
                                                 )
@@ -240,19 +403,32 @@ pub fn DataConfigPanel(
                                                             category: Some(name),
                                                             ..Default::default()
                                                         };
-                                                        dashboard.update_widget_mapping(&widget.id, new_mapping);
+                                                        dashboard.update_widget_mapping(
+                                                            &widget.id,
+                                                            new_mapping.with_settings_from(&widget.chart_config.data_mapping),
+                                                        );
                                                     }
                                                 }),
                                             )
                                         />
+                                        {category_settings()}
 
                                         // Aggregation
                                         <AggregationSelector
                                             selected=Signal::derive(move || aggregation.get())
+                                            weight_fields=weight_fields
                                             on_change=Callback::new(move |agg: AggregationFunction| {
-                                                set_aggregation.set(agg);
+                                                set_aggregation.set(agg.clone());
+                                                if let Some(widget) = selected_widget.get_untracked()
+                                                    && let Some(measure) = y_axis.get_untracked()
+                                                {
+                                                    let mut new_mapping = widget.chart_config.data_mapping.clone();
+                                                    new_mapping.set_aggregation(&measure, agg.clone());
+                                                    dashboard.update_widget_mapping(&widget.id, new_mapping);
+                                                }
                                             })
                                         />
+                                        {calculation_settings()}
                                     </div>
                                 }
                                     .into_any()
@@ -276,11 +452,15 @@ pub fn DataConfigPanel(
                                                             category: category.get_untracked(),
                                                             ..Default::default()
                                                         };
-                                                        dashboard.update_widget_mapping(&widget.id, new_mapping);
+                                                        dashboard.update_widget_mapping(
+                                                            &widget.id,
+                                                            new_mapping.with_settings_from(&widget.chart_config.data_mapping),
+                                                        );
                                                     }
                                                 }),
                                             )
                                         />
+                                        {time_settings()}
 
                                         <div class="flex flex-col gap-2">
                                             <label class="text-xs font-semibold text-base-content/70">
@@ -298,13 +478,17 @@ pub fn DataConfigPanel(
                                                     Callback::new(move |name: String| {
                                                         set_y_axis.set(Some(name.clone()));
                                                         if let Some(widget) = selected_widget.get_untracked() {
+                                                            set_aggregation.set(widget.chart_config.data_mapping.aggregation(&name));
                                                             let new_mapping = DataMapping {
                                                                 x_axis: x_axis.get_untracked(),
                                                                 y_axis: vec![name],
                                                                 category: category.get_untracked(),
                                                                 ..Default::default()
                                                             };
-                                                            dashboard.update_widget_mapping(&widget.id, new_mapping);
+                                                            dashboard.update_widget_mapping(
+                                                                &widget.id,
+                                                                new_mapping.with_settings_from(&widget.chart_config.data_mapping),
+                                                            );
                                                         }
                                                     }),
                                                 )
@@ -326,18 +510,32 @@ pub fn DataConfigPanel(
                                                             category: Some(name),
                                                             ..Default::default()
                                                         };
-                                                        dashboard.update_widget_mapping(&widget.id, new_mapping);
+                                                        dashboard.update_widget_mapping(
+                                                            &widget.id,
+                                                            new_mapping.with_settings_from(&widget.chart_config.data_mapping),
+                                                        );
                                                     }
                                                 }),
                                             )
                                         />
+                                        {category_settings()}
 
                                         <AggregationSelector
                                             selected=Signal::derive(move || aggregation.get())
+                                            weight_fields=weight_fields
                                             on_change=Callback::new(move |agg: AggregationFunction| {
-                                                set_aggregation.set(agg);
+                                                set_aggregation.set(agg.clone());
+                                                if let Some(widget) = selected_widget.get_untracked()
+                                                    && let Some(measure) = y_axis.get_untracked()
+                                                {
+                                                    let mut new_mapping = widget.chart_config.data_mapping.clone();
+                                                    new_mapping.set_aggregation(&measure, agg.clone());
+                                                    dashboard.update_widget_mapping(&widget.id, new_mapping);
+                                                }
                                             })
                                         />
+                                        {calculation_settings()}
+                                        {sort_settings()}
                                     </div>
                                 }
                                     .into_any()
@@ -361,7 +559,10 @@ pub fn DataConfigPanel(
                                                             category: category.get_untracked(),
                                                             ..Default::default()
                                                         };
-                                                        dashboard.update_widget_mapping(&widget.id, new_mapping);
+                                                        dashboard.update_widget_mapping(
+                                                            &widget.id,
+                                                            new_mapping.with_settings_from(&widget.chart_config.data_mapping),
+                                                        );
                                                     }
                                                 }),
                                             )
@@ -383,18 +584,23 @@ pub fn DataConfigPanel(
                                                     Callback::new(move |name: String| {
                                                         set_y_axis.set(Some(name.clone()));
                                                         if let Some(widget) = selected_widget.get_untracked() {
+                                                            set_aggregation.set(widget.chart_config.data_mapping.aggregation(&name));
                                                             let new_mapping = DataMapping {
                                                                 x_axis: x_axis.get_untracked(),
                                                                 y_axis: vec![name],
                                                                 category: category.get_untracked(),
                                                                 ..Default::default()
                                                             };
-                                                            dashboard.update_widget_mapping(&widget.id, new_mapping);
+                                                            dashboard.update_widget_mapping(
+                                                                &widget.id,
+                                                                new_mapping.with_settings_from(&widget.chart_config.data_mapping),
+                                                            );
                                                         }
                                                     }),
                                                 )
                                             />
                                         </div>
+                                        {sort_settings()}
                                     </div>
                                 }
                                     .into_any()
@@ -462,7 +668,10 @@ pub fn DataConfigPanel(
                                                             high: high_field.get_untracked(),
                                                             ..Default::default()
                                                         };
-                                                        dashboard.update_widget_mapping(&widget.id, new_mapping);
+                                                        dashboard.update_widget_mapping(
+                                                            &widget.id,
+                                                            new_mapping.with_settings_from(&widget.chart_config.data_mapping),
+                                                        );
                                                     }
                                                 }),
                                             )
@@ -490,7 +699,10 @@ pub fn DataConfigPanel(
                                                                 high: high_field.get_untracked(),
                                                                 ..Default::default()
                                                             };
-                                                            dashboard.update_widget_mapping(&widget.id, new_mapping);
+                                                            dashboard.update_widget_mapping(
+                                                                &widget.id,
+                                                                new_mapping.with_settings_from(&widget.chart_config.data_mapping),
+                                                            );
                                                         }
                                                     }),
                                                 )
@@ -519,7 +731,10 @@ pub fn DataConfigPanel(
                                                                 high: Some(name),
                                                                 ..Default::default()
                                                             };
-                                                            dashboard.update_widget_mapping(&widget.id, new_mapping);
+                                                            dashboard.update_widget_mapping(
+                                                                &widget.id,
+                                                                new_mapping.with_settings_from(&widget.chart_config.data_mapping),
+                                                            );
                                                         }
                                                     }),
                                                 )
@@ -548,7 +763,10 @@ pub fn DataConfigPanel(
                                                                 high: high_field.get_untracked(),
                                                                 ..Default::default()
                                                             };
-                                                            dashboard.update_widget_mapping(&widget.id, new_mapping);
+                                                            dashboard.update_widget_mapping(
+                                                                &widget.id,
+                                                                new_mapping.with_settings_from(&widget.chart_config.data_mapping),
+                                                            );
                                                         }
                                                     }),
                                                 )
@@ -577,7 +795,10 @@ pub fn DataConfigPanel(
                                                                 high: high_field.get_untracked(),
                                                                 ..Default::default()
                                                             };
-                                                            dashboard.update_widget_mapping(&widget.id, new_mapping);
+                                                            dashboard.update_widget_mapping(
+                                                                &widget.id,
+                                                                new_mapping.with_settings_from(&widget.chart_config.data_mapping),
+                                                            );
                                                         }
                                                     }),
                                                 )
@@ -628,10 +849,133 @@ pub fn DataConfigPanel(
                                                         columns: cols,
                                                         ..Default::default()
                                                     };
-                                                    dashboard.update_widget_mapping(&widget.id, new_mapping);
+                                                    dashboard.update_widget_mapping(
+                                                        &widget.id,
+                                                        new_mapping.with_settings_from(&widget.chart_config.data_mapping),
+                                                    );
                                                 }
                                             })
                                         />
+
+                                        // Window calculations of the numeric columns
+                                        {move || {
+                                            let mapping = selected_widget.get()?.chart_config.data_mapping;
+                                            let numeric: Vec<String> = selected_columns
+                                                .get()
+                                                .into_iter()
+                                                .filter(|column| numeric_fields.with(|fields| fields.iter().any(|f| &f.name == column)))
+                                                .collect();
+                                            (!numeric.is_empty())
+                                                .then(|| {
+                                                    view! {
+                                                        <div class="flex flex-col gap-2 p-2 bg-base-200 rounded-lg">
+                                                            <span class="text-xs font-semibold text-base-content/70">
+                                                                "Column calculations"
+                                                            </span>
+                                                            {numeric
+                                                                .into_iter()
+                                                                .map(|column| {
+                                                                    let selected = mapping.calculation(&column);
+                                                                    view! {
+                                                                        <CalculationSelect
+                                                                            label=column.clone()
+                                                                            selected=selected
+                                                                            on_change=Callback::new(move |calculation| {
+                                                                                set_calculation(&column, calculation)
+                                                                            })
+                                                                        />
+                                                                    }
+                                                                })
+                                                                .collect::<Vec<_>>()}
+                                                        </div>
+                                                    }
+                                                })
+                                        }}
+                                        {sort_settings()}
+                                    </div>
+                                }
+                                    .into_any()
+                            }
+                            WidgetType::Kpi => {
+                                view! {
+                                    <div class="flex flex-col gap-4">
+                                        <div class="p-3 bg-info/10 border border-info/20 rounded-lg">
+                                            <p class="text-xs text-info">
+                                                "Choose a period field to show a calculation of the KPI's measure, such as its change from the previous month."
+                                            </p>
+                                        </div>
+
+                                        // Period of window calculations
+                                        <FieldSelector
+                                            label="Period (Optional - for calculations)"
+                                            fields=text_fields
+                                            selected=x_axis_sig
+                                            placeholder="No period"
+                                            on_change=Some(
+                                                Callback::new(move |name: String| {
+                                                    set_x_axis.set(Some(name.clone()));
+                                                    if let Some(widget) = selected_widget.get_untracked() {
+                                                        let mut new_mapping = widget.chart_config.data_mapping.clone();
+                                                        new_mapping.x_axis = Some(name);
+                                                        dashboard.update_widget_mapping(&widget.id, new_mapping);
+                                                    }
+                                                }),
+                                            )
+                                        />
+                                        {time_settings()}
+                                        {move || {
+                                            let mapping = selected_widget.get()?.chart_config.data_mapping;
+                                            mapping.x_axis.as_ref()?;
+                                            let field = mapping.kpi_field.clone()?;
+                                            Some(view! {
+                                                <CalculationSelect
+                                                    label="Calculation"
+                                                    selected=mapping.calculation(&field)
+                                                    on_change=Callback::new(move |calculation| set_calculation(&field, calculation))
+                                                />
+                                            })
+                                        }}
+                                    </div>
+                                }
+                                    .into_any()
+                            }
+                            WidgetType::FilterDropdown
+                            | WidgetType::FilterDateRange
+                            | WidgetType::FilterRange
+                            | WidgetType::FilterSearch => {
+                                let filter_field = Signal::derive(move || {
+                                    selected_widget
+                                        .get()
+                                        .and_then(|w| w.chart_config.data_mapping.filter_field)
+                                });
+
+                                view! {
+                                    <div class="flex flex-col gap-4">
+                                        <div class="p-3 bg-info/10 border border-info/20 rounded-lg">
+                                            <p class="text-xs text-info">
+                                                "The control filters every widget whose dataset has this field."
+                                            </p>
+                                        </div>
+
+                                        <FieldSelector
+                                            label="Filter Field"
+                                            fields=control_fields
+                                            selected=filter_field
+                                            placeholder="Select field to filter..."
+                                            on_change=Some(
+                                                Callback::new(move |name: String| {
+                                                    if let Some(widget) = selected_widget.get_untracked() {
+                                                        let new_mapping = DataMapping {
+                                                            filter_field: Some(name),
+                                                            ..Default::default()
+                                                        };
+                                                        // The selection made on the old field no longer applies
+                                                        dashboard.set_global_filter(&widget.id, None);
+                                                        dashboard.update_widget_mapping(&widget.id, new_mapping);
+                                                    }
+                                                }),
+                                            )
+                                        />
                                     </div>
                                 }
                                     .into_any()
@@ -782,6 +1126,7 @@ pub fn DataConfigPanel(
                                                 "Field selectors for this widget type will be implemented in Phase 4."
                                             </span>
                                         </div>
+                                        {matches!(widget_type, WidgetType::Radar | WidgetType::Treemap).then(sort_settings)}
                                     </div>
                                 }
                                     .into_any()
@@ -790,6 +1135,434 @@ pub fn DataConfigPanel(
                     )
                 }
             }}
+
+            // Row filters of the selected widget (filter controls set
+            // dashboard filters instead)
+            {move || {
+                let has_filters = !widget_type.is_filter_control()
+                    && selected_widget.with(Option::is_some)
+                    && !fields.with(Vec::is_empty);
+                has_filters.then(|| {
+                    view! {
+                        <div class="divider my-0"></div>
+                        <Toggle
+                            label="Ignore dashboard filters"
+                            description="Show all rows whatever the filter controls select"
+                            checked=Signal::derive(move || {
+                                selected_widget
+                                    .get()
+                                    .is_some_and(|w| w.chart_config.data_mapping.ignore_global_filters)
+                            })
+                            on_change=Callback::new(move |ignore| {
+                                if let Some(widget) = selected_widget.get_untracked() {
+                                    let new_mapping = DataMapping {
+                                        ignore_global_filters: ignore,
+                                        ..widget.chart_config.data_mapping.clone()
+                                    };
+                                    dashboard.update_widget_mapping(&widget.id, new_mapping);
+                                }
+                            })
+                        />
+                        <FilterEditor
+                            fields=Signal::derive(move || fields.get())
+                            filters=Signal::derive(move || {
+                                selected_widget
+                                    .get()
+                                    .map(|w| w.chart_config.data_mapping.filters)
+                                    .unwrap_or_default()
+                            })
+                            on_change=Callback::new(move |filters| {
+                                if let Some(widget) = selected_widget.get_untracked() {
+                                    let new_mapping = DataMapping {
+                                        filters,
+                                        ..widget.chart_config.data_mapping.clone()
+                                    };
+                                    dashboard.update_widget_mapping(&widget.id, new_mapping);
+                                }
+                            })
+                        />
+                    }
+                })
+            }}
         </div>
     }
 }
+
+/// Settings of the series a category field splits the measures into
+///
+/// Hidden while the mapping has no category.
+#[component]
+fn CategorySeriesSettings(
+    /// Mapping of the selected widget
+    #[prop(into)]
+    mapping: Signal<Option<DataMapping>>,
+    /// Callback with the changed mapping
+    on_change: Callback<DataMapping>,
+) -> impl IntoView {
+    let edit = move |change: &dyn Fn(&mut DataMapping)| {
+        if let Some(mut mapping) = mapping.get_untracked() {
+            change(&mut mapping);
+            on_change.run(mapping);
+        }
+    };
+
+    move || {
+        let mapping = mapping.get().filter(|mapping| mapping.category.is_some())?;
+        let limit = mapping.series_limit.unwrap_or(DEFAULT_SERIES_LIMIT);
+        Some(view! {
+            <div class="flex flex-col gap-2 p-2 bg-base-200 rounded-lg">
+                <MissingValuesSelect
+                    selected=mapping.missing_values
+                    on_change=Callback::new(move |missing| edit(&|mapping| mapping.missing_values = missing))
+                />
+                <div class="flex items-center gap-2">
+                    <span class="text-xs text-base-content/70 flex-1">"Max series (rest in \"Other\")"</span>
+                    <input
+                        type="number"
+                        class="input input-bordered input-xs w-16"
+                        min="1"
+                        value=limit
+                        on:change=move |ev| {
+                            if let Ok(limit) = event_target_value(&ev).parse::<usize>()
+                                && limit > 0
+                            {
+                                edit(&|mapping| mapping.series_limit = Some(limit));
+                            }
+                        }
+                    />
+                </div>
+                <button
+                    class="btn btn-xs btn-ghost self-start"
+                    on:click=move |_| edit(&|mapping| mapping.category = None)
+                >
+                    "Remove category"
+                </button>
+            </div>
+        })
+    }
+}
+
+/// Settings of the periods a date dimension is grouped into
+///
+/// Hidden while there is no mapping (no widget, or a dimension that isn't
+/// a date).
+#[component]
+fn TimeBucketingSettings(
+    /// Mapping of the selected widget
+    #[prop(into)]
+    mapping: Signal<Option<DataMapping>>,
+    /// Callback with the changed mapping
+    on_change: Callback<DataMapping>,
+) -> impl IntoView {
+    let edit = move |change: &dyn Fn(&mut DataMapping)| {
+        if let Some(mut mapping) = mapping.get_untracked() {
+            change(&mut mapping);
+            on_change.run(mapping);
+        }
+    };
+    let edit_bucketing = move |change: &dyn Fn(&mut TimeBucketing)| {
+        edit(&|mapping| {
+            if let Some(bucketing) = &mut mapping.time_bucketing {
+                change(bucketing);
+            }
+        })
+    };
+    let months = [
+        "January", "February", "March", "April", "May", "June", "July", "August", "September", "October",
+        "November", "December",
+    ];
+
+    move || {
+        let mapping = mapping.get()?;
+        let bucketing = mapping.time_bucketing;
+        Some(view! {
+            <div class="flex flex-col gap-2 p-2 bg-base-200 rounded-lg">
+                <div class="flex items-center gap-2">
+                    <span class="text-xs text-base-content/70 flex-1">"Group dates by"</span>
+                    <select
+                        class="select select-bordered select-xs"
+                        on:change=move |ev| {
+                            let label = event_target_value(&ev);
+                            let granularity = TimeGranularity::all()
+                                .into_iter()
+                                .find(|granularity| granularity.label() == label);
+                            edit(&|mapping| {
+                                mapping.time_bucketing = granularity.map(|granularity| TimeBucketing {
+                                    granularity,
+                                    ..mapping.time_bucketing.unwrap_or_default()
+                                });
+                            });
+                        }
+                    >
+                        <option value="" selected=bucketing.is_none()>"Exact date"</option>
+                        {TimeGranularity::all()
+                            .into_iter()
+                            .map(|granularity| {
+                                view! {
+                                    <option
+                                        value=granularity.label()
+                                        selected=bucketing.is_some_and(|b| b.granularity == granularity)
+                                    >
+                                        {granularity.label()}
+                                    </option>
+                                }
+                            })
+                            .collect::<Vec<_>>()}
+                    </select>
+                </div>
+                {bucketing
+                    .filter(|bucketing| bucketing.granularity.is_fiscal())
+                    .map(|bucketing| {
+                        view! {
+                            <div class="flex items-center gap-2">
+                                <span class="text-xs text-base-content/70 flex-1">"Fiscal year starts in"</span>
+                                <select
+                                    class="select select-bordered select-xs"
+                                    on:change=move |ev| {
+                                        if let Ok(month) = event_target_value(&ev).parse::<u32>() {
+                                            edit_bucketing(&|bucketing| bucketing.fiscal_year_start = month);
+                                        }
+                                    }
+                                >
+                                    {(1..=12u32)
+                                        .zip(months)
+                                        .map(|(month, name)| {
+                                            view! {
+                                                <option
+                                                    value=month.to_string()
+                                                    selected=bucketing.fiscal_year_start == month
+                                                >
+                                                    {name}
+                                                </option>
+                                            }
+                                        })
+                                        .collect::<Vec<_>>()}
+                                </select>
+                            </div>
+                        }
+                    })}
+                {bucketing
+                    .map(|bucketing| {
+                        view! {
+                            <Toggle
+                                label="Fill gaps"
+                                description="Show periods without rows"
+                                checked=Signal::derive(move || bucketing.fill_gaps)
+                                on_change=Callback::new(move |fill_gaps| {
+                                    edit_bucketing(&|bucketing| bucketing.fill_gaps = fill_gaps)
+                                })
+                            />
+                        }
+                    })}
+                // Categories have their own missing values setting
+                {bucketing
+                    .filter(|bucketing| bucketing.fill_gaps && mapping.category.is_none())
+                    .map(|_| {
+                        view! {
+                            <MissingValuesSelect
+                                selected=mapping.missing_values
+                                on_change=Callback::new(move |missing| {
+                                    edit(&|mapping| mapping.missing_values = missing)
+                                })
+                            />
+                        }
+                    })}
+            </div>
+        })
+    }
+}
+
+/// How values missing from a series are drawn: as gaps or zeros
+#[component]
+fn MissingValuesSelect(
+    /// Current setting
+    selected: MissingValues,
+    /// Callback with the chosen setting
+    on_change: Callback<MissingValues>,
+) -> impl IntoView {
+    view! {
+        <div class="flex items-center gap-2">
+            <span class="text-xs text-base-content/70 flex-1">"Missing values"</span>
+            <select
+                class="select select-bordered select-xs"
+                on:change=move |ev| {
+                    let missing = match event_target_value(&ev).as_str() {
+                        "Zero" => MissingValues::Zero,
+                        _ => MissingValues::Null,
+                    };
+                    on_change.run(missing);
+                }
+            >
+                <option value="Null" selected=selected == MissingValues::Null>
+                    "Leave gaps"
+                </option>
+                <option value="Zero" selected=selected == MissingValues::Zero>
+                    "Show as zero"
+                </option>
+            </select>
+        </div>
+    }
+}
+
+/// Window calculation of a measure, with the window of moving ones
+#[component]
+fn CalculationSelect(
+    /// Label shown before the picker
+    #[prop(into)]
+    label: String,
+    /// Current calculation
+    selected: Option<WindowCalculation>,
+    /// Callback with the chosen calculation (`None` for plain values)
+    on_change: Callback<Option<WindowCalculation>>,
+) -> impl IntoView {
+    let window = selected.and_then(|calculation| calculation.window());
+
+    view! {
+        <div class="flex flex-col gap-2">
+            <div class="flex items-center gap-2">
+                <span class="text-xs text-base-content/70 flex-1">{label}</span>
+                <select
+                    class="select select-bordered select-xs"
+                    on:change=move |ev| {
+                        let label = event_target_value(&ev);
+                        let calculation = WindowCalculation::all()
+                            .into_iter()
+                            .find(|calculation| calculation.label() == label)
+                            .map(|calculation| calculation.with_window(window.unwrap_or(DEFAULT_WINDOW)));
+                        on_change.run(calculation);
+                    }
+                >
+                    <option value="" selected=selected.is_none()>"None"</option>
+                    {WindowCalculation::all()
+                        .into_iter()
+                        .map(|calculation| {
+                            view! {
+                                <option
+                                    value=calculation.label()
+                                    selected=selected.is_some_and(|selected| selected.label() == calculation.label())
+                                >
+                                    {calculation.label()}
+                                </option>
+                            }
+                        })
+                        .collect::<Vec<_>>()}
+                </select>
+            </div>
+            {selected
+                .zip(window)
+                .map(|(calculation, window)| {
+                    view! {
+                        <div class="flex items-center gap-2">
+                            <span class="text-xs text-base-content/70 flex-1">"Window (periods)"</span>
+                            <input
+                                type="number"
+                                class="input input-bordered input-xs w-16"
+                                min="1"
+                                value=window
+                                on:change=move |ev| {
+                                    if let Ok(window) = event_target_value(&ev).parse::<usize>()
+                                        && window > 0
+                                    {
+                                        on_change.run(Some(calculation.with_window(window)));
+                                    }
+                                }
+                            />
+                        </div>
+                    }
+                })}
+        </div>
+    }
+}
+
+/// Order of the items and the top-N limit merging the rest into "Other"
+///
+/// Hidden while no widget is selected.
+#[component]
+fn SortSettings(
+    /// Mapping of the selected widget
+    #[prop(into)]
+    mapping: Signal<Option<DataMapping>>,
+    /// Callback with the changed mapping
+    on_change: Callback<DataMapping>,
+) -> impl IntoView {
+    let edit = move |change: &dyn Fn(&mut DataMapping)| {
+        if let Some(mut mapping) = mapping.get_untracked() {
+            change(&mut mapping);
+            on_change.run(mapping);
+        }
+    };
+
+    move || {
+        let mapping = mapping.get()?;
+        let manual_order = match &mapping.sort {
+            SortOrder::Manual(order) => Some(order.join("\n")),
+            _ => None,
+        };
+        Some(view! {
+            <div class="flex flex-col gap-2 p-2 bg-base-200 rounded-lg">
+                <div class="flex items-center gap-2">
+                    <span class="text-xs text-base-content/70 flex-1">"Sort by"</span>
+                    <select
+                        class="select select-bordered select-xs"
+                        on:change=move |ev| {
+                            let sort = match event_target_value(&ev).as_str() {
+                                "MeasureAscending" => SortOrder::MeasureAscending,
+                                "MeasureDescending" => SortOrder::MeasureDescending,
+                                "Manual" => SortOrder::Manual(Vec::new()),
+                                _ => SortOrder::Dimension,
+                            };
+                            edit(&|mapping| mapping.sort = sort.clone());
+                        }
+                    >
+                        <option value="Dimension" selected=mapping.sort == SortOrder::Dimension>
+                            "Dimension"
+                        </option>
+                        <option value="MeasureAscending" selected=mapping.sort == SortOrder::MeasureAscending>
+                            "Measure, ascending"
+                        </option>
+                        <option value="MeasureDescending" selected=mapping.sort == SortOrder::MeasureDescending>
+                            "Measure, descending"
+                        </option>
+                        <option value="Manual" selected=manual_order.is_some()>
+                            "Manual order"
+                        </option>
+                    </select>
+                </div>
+                {manual_order
+                    .map(|order| {
+                        view! {
+                            <textarea
+                                class="textarea textarea-bordered textarea-xs w-full"
+                                rows="4"
+                                placeholder="One value per line, in order"
+                                prop:value=order
+                                on:change=move |ev| {
+                                    let order: Vec<String> = event_target_value(&ev)
+                                        .lines()
+                                        .map(str::trim)
+                                        .filter(|label| !label.is_empty())
+                                        .map(str::to_string)
+                                        .collect();
+                                    edit(&|mapping| mapping.sort = SortOrder::Manual(order.clone()));
+                                }
+                            ></textarea>
+                        }
+                    })}
+                <div class="flex items-center gap-2">
+                    <span class="text-xs text-base-content/70 flex-1">"Show top (rest in \"Other\")"</span>
+                    <input
+                        type="number"
+                        class="input input-bordered input-xs w-16"
+                        min="1"
+                        placeholder="All"
+                        value=mapping.top_n.map(|top_n| top_n.to_string()).unwrap_or_default()
+                        on:change=move |ev| {
+                            let top_n = event_target_value(&ev).parse::<usize>().ok().filter(|top_n| *top_n > 0);
+                            edit(&|mapping| mapping.top_n = top_n);
+                        }
+                    />
+                </div>
+            </div>
+        })
+    }
+}
@@ -4,8 +4,8 @@ use leptos::prelude::*;
 
 /// Dataset item component for sidebar
 ///
-/// Displays a dataset row with icon, name, metadata, and optional delete button.
-/// Shows different styling for active state.
+/// Displays a dataset row with icon, name, metadata, and optional replace
+/// and delete buttons. Shows different styling for active state.
 ///
 /// # Example
 /// ```rust
@@ -40,6 +40,9 @@ pub fn DatasetItem(
     /// Optional delete handler
     #[prop(optional)]
     on_delete: Option<Callback<MouseEvent>>,
+    /// Optional handler replacing the dataset's data (button shown on hover)
+    #[prop(optional_no_strip)]
+    on_replace: Option<Callback<MouseEvent>>,
     /// Optional click handler (for dataset activation)
     #[prop(optional)]
     on_click: Option<Callback<MouseEvent>>,
@@ -51,6 +54,13 @@ pub fn DatasetItem(
         }
     };
 
+    let replace_handler = move |ev: MouseEvent| {
+        ev.stop_propagation();
+        if let Some(cb) = &on_replace {
+            cb.run(ev);
+        }
+    };
+
     let click_handler = move |ev: MouseEvent| {
         if let Some(cb) = &on_click {
             cb.run(ev);
@@ -102,6 +112,21 @@ pub fn DatasetItem(
                 <p class="text-base-content/50 text-[10px] mt-0.5">{metadata}</p>
             </div>
 
+            {if on_replace.is_some() {
+                view! {
+                    <button
+                        class="text-slate-400 hover:text-primary opacity-0 group-hover:opacity-100 transition-all"
+                        title="Replace data"
+                        on:click=replace_handler
+                    >
+                        <Icon name=IconName::Upload class="w-4 h-4" />
+                    </button>
+                }
+                    .into_any()
+            } else {
+                ().into_any()
+            }}
+
             {if show_delete {
                 view! {
                     <button
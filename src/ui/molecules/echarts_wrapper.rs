@@ -1,8 +1,9 @@
+use crate::features::dashboard::cross_filter::ChartEvent;
 use crate::features::dashboard::echarts_bindings::*;
 use leptos::html::Div;
 use leptos::prelude::*;
 use send_wrapper::SendWrapper;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 use wasm_bindgen::prelude::*;
 
@@ -14,7 +15,13 @@ use wasm_bindgen::prelude::*;
 /// # Critical Pattern
 /// - **Effect 1**: Initialize chart on mount
 /// - **Effect 2**: Update chart when options change (via setOption)
-/// - **Effect 3**: Cleanup - dispose chart on unmount (prevents memory leaks!)
+/// - **Effect 3**: Show the loading animation while `loading` is set
+/// - **Effect 4**: Cleanup - dispose chart on unmount (prevents memory leaks!)
+/// - **Effect 5**: Show the `selected` elements as selected
+///
+/// # Event Bridge
+/// With `on_event`, ECharts `click` and `brushSelected` events are decoded
+/// into [`ChartEvent`]s, resolving axis indexes against the options drawn.
 ///
 /// # Example
 /// ```rust
@@ -46,13 +53,28 @@ pub fn EChartsWrapper(
     /// Chart height (CSS value, e.g., "400px" or "100%")
     #[prop(optional, into)]
     height: Option<String>,
+
+    /// Whether the chart data is being computed (shows the loading animation)
+    #[prop(optional, into)]
+    loading: Option<Signal<bool>>,
+
+    /// Called with the chart's click and brush events
+    #[prop(optional, into)]
+    on_event: Option<Callback<ChartEvent>>,
+
+    /// Names of the data items to show as selected
+    #[prop(optional, into)]
+    selected: Option<Signal<Vec<String>>>,
 ) -> impl IntoView {
     let container_ref = NodeRef::<Div>::new();
     let chart_instance = SendWrapper::new(Rc::new(RefCell::new(None::<ECharts>)));
+    // Event handlers, kept alive until the chart is disposed
+    let handlers = SendWrapper::new(Rc::new(RefCell::new(Vec::<Closure<dyn FnMut(JsValue)>>::new())));
 
     // Effect 1: Initialize chart on mount
     {
         let chart_instance = chart_instance.clone();
+        let handlers = handlers.clone();
         Effect::new(move |_| {
             if let Some(container) = container_ref.get() {
                 // Convert leptos Div to web_sys HtmlElement
@@ -60,6 +82,9 @@ pub fn EChartsWrapper(
 
                 // Initialize ECharts
                 let chart = init(&element);
+                if let Some(on_event) = on_event {
+                    handlers.borrow_mut().extend(register_events(&chart, options, on_event));
+                }
                 *chart_instance.borrow_mut() = Some(chart);
 
                 log::info!("ECharts instance initialized");
@@ -95,6 +120,10 @@ pub fn EChartsWrapper(
         let chart_instance = chart_instance.clone();
         Effect::new(move |_| {
             let options_json = options.get();
+            if options_json.is_empty() {
+                // Nothing computed yet
+                return;
+            }
 
             if let Some(chart) = chart_instance.borrow().as_ref() {
                 // Parse JSON string to JsValue
@@ -111,7 +140,23 @@ pub fn EChartsWrapper(
         });
     }
 
-    // Effect 3: Cleanup on component unmount
+    // Effect 3: Loading animation while the data is computed
+    if let Some(loading) = loading {
+        let chart_instance = chart_instance.clone();
+        Effect::new(move |_| {
+            let loading = loading.get();
+
+            if let Some(chart) = chart_instance.borrow().as_ref() {
+                if loading {
+                    chart.show_loading();
+                } else {
+                    chart.hide_loading();
+                }
+            }
+        });
+    }
+
+    // Effect 4: Cleanup on component unmount
     {
         let chart_instance = chart_instance.clone();
         on_cleanup(move || {
@@ -119,6 +164,31 @@ pub fn EChartsWrapper(
                 chart.dispose();
                 log::info!("ECharts instance disposed");
             }
+            handlers.borrow_mut().clear();
+        });
+    }
+
+    // Effect 5: Selected elements, selected again after every setOption
+    if let Some(selected) = selected {
+        let chart_instance = chart_instance.clone();
+        let shown = SendWrapper::new(Rc::new(RefCell::new(Vec::<String>::new())));
+        Effect::new(move |_| {
+            options.track();
+            let names = selected.get();
+
+            if let Some(chart) = chart_instance.borrow().as_ref() {
+                let mut shown = shown.borrow_mut();
+                for (action, names) in [("unselect", &*shown), ("select", &names)] {
+                    if names.is_empty() {
+                        continue;
+                    }
+                    let payload = serde_json::json!({ "type": action, "name": names }).to_string();
+                    if let Ok(action) = js_sys::JSON::parse(&payload) {
+                        chart.dispatch_action(&action);
+                    }
+                }
+                *shown = names;
+            }
         });
     }
 
@@ -139,3 +209,53 @@ pub fn EChartsWrapper(
         ></div>
     }
 }
+
+/// Bridge the chart's click and brush events to `on_event`
+///
+/// A brush cleared after selecting something is reported as an empty
+/// `ChartEvent::Brush`.
+///
+/// # Returns
+/// The handlers, which must outlive the chart
+fn register_events(
+    chart: &ECharts,
+    options: Signal<String>,
+    on_event: Callback<ChartEvent>,
+) -> [Closure<dyn FnMut(JsValue)>; 2] {
+    let drawn_options = move || serde_json::from_str(&options.get_untracked()).unwrap_or_default();
+
+    let on_click = Closure::<dyn FnMut(JsValue)>::new(move |params: JsValue| {
+        let params = plain_fields(&params, &["seriesName", "name", "value", "treePathInfo"]);
+        on_event.run(ChartEvent::from_click(&params, &drawn_options()));
+    });
+
+    let brushing = Rc::new(Cell::new(false));
+    let on_brush = Closure::<dyn FnMut(JsValue)>::new(move |params: JsValue| {
+        let params = plain_fields(&params, &["batch"]);
+        match ChartEvent::from_brush(&params, &drawn_options()) {
+            Some(event) => {
+                brushing.set(true);
+                on_event.run(event);
+            }
+            None if brushing.replace(false) => on_event.run(ChartEvent::Brush(Vec::new())),
+            None => {}
+        }
+    });
+
+    chart.on("click", on_click.as_ref().unchecked_ref());
+    chart.on("brushSelected", on_brush.as_ref().unchecked_ref());
+    [on_click, on_brush]
+}
+
+/// Copy fields of an event's parameters into JSON
+///
+/// Only plain-data fields can be copied: the parameters also hold the DOM
+/// event, which doesn't serialize.
+fn plain_fields(params: &JsValue, keys: &[&str]) -> serde_json::Value {
+    let fields = keys.iter().filter_map(|key| {
+        let value = js_sys::Reflect::get(params, &JsValue::from_str(key)).ok()?;
+        let json = js_sys::JSON::stringify(&value).ok()?.as_string()?;
+        Some((key.to_string(), serde_json::from_str(&json).ok()?))
+    });
+    serde_json::Value::Object(fields.collect())
+}
@@ -1,3 +1,4 @@
+use crate::features::dashboard::data::AggregationFunction;
 use crate::features::dashboard::models::FieldType;
 use crate::ui::atoms::{Icon, IconName};
 use leptos::ev;
@@ -259,31 +260,6 @@ pub fn FieldSelector(
     }
 }
 
-/// Aggregation function options
-#[derive(Clone, Copy, PartialEq, Eq, Default)]
-pub enum AggregationFunction {
-    #[default]
-    Sum,
-    Avg,
-    Count,
-    Min,
-    Max,
-    CountDistinct,
-}
-
-impl AggregationFunction {
-    pub fn label(&self) -> &'static str {
-        match self {
-            Self::Sum => "SUM",
-            Self::Avg => "AVG",
-            Self::Count => "COUNT",
-            Self::Min => "MIN",
-            Self::Max => "MAX",
-            Self::CountDistinct => "COUNTD",
-        }
-    }
-}
-
 /// Aggregation selector dropdown component
 ///
 /// Allows users to select an aggregation function (SUM, AVG, COUNT, etc.)
@@ -305,6 +281,9 @@ pub fn AggregationSelector(
     selected: Signal<AggregationFunction>,
     /// Callback when selection changes
     on_change: Callback<AggregationFunction>,
+    /// Numeric fields offered as weights of a weighted average
+    #[prop(optional)]
+    weight_fields: Signal<Vec<String>>,
 ) -> impl IntoView {
     let (is_open, set_is_open) = signal(false);
 
@@ -363,14 +342,12 @@ pub fn AggregationSelector(
     });
     on_cleanup(move || handle_click_outside.remove());
 
-    let options = vec![
-        AggregationFunction::Sum,
-        AggregationFunction::Avg,
-        AggregationFunction::Count,
-        AggregationFunction::Min,
-        AggregationFunction::Max,
-        AggregationFunction::CountDistinct,
-    ];
+    // Shared functions, then one weighted average per weight field
+    let options = move || {
+        let mut options = AggregationFunction::common();
+        options.extend(weight_fields.get().iter().map(|field| AggregationFunction::weighted_by(field)));
+        options
+    };
 
     // Toggle dropdown
     let toggle_dropdown = move |_| {
@@ -401,35 +378,39 @@ pub fn AggregationSelector(
                 transition-all text-sm"
                 on:click=toggle_dropdown
             >
-                <span class="font-bold text-base-content">{move || selected.get().label()}</span>
+                <span class="font-bold text-base-content">{move || selected.get().display_name()}</span>
                 <Icon name=IconName::ChevronDown class="w-3 h-3 text-base-content/40" />
             </button>
 
             // Dropdown menu
             {move || {
                 if is_open.get() {
-                    let options_clone = options.clone();
+                    let options_clone = options();
                     view! {
                         <div
                             class="absolute z-50 mt-1 bg-base-100
                             border border-base-300
-                            rounded-lg shadow-lg overflow-hidden"
+                            rounded-lg shadow-lg max-h-64 overflow-y-auto"
                             style="left: 0; right: 0;"
                         >
                             {options_clone
                                 .into_iter()
                                 .map(|agg| {
-                                    let is_selected = move || agg == selected.get();
+                                    let label = agg.display_name();
+                                    let is_selected = {
+                                        let agg = agg.clone();
+                                        move || agg == selected.get()
+                                    };
                                     view! {
                                         <button
                                             class="w-full flex items-center gap-2 px-3 py-1.5
                                             text-left hover:bg-base-200
                                             transition-colors text-sm"
                                             class:bg-base-200=move || is_selected()
-                                            on:click=move |_| select_aggregation(agg)
+                                            on:click=move |_| select_aggregation(agg.clone())
                                         >
                                             <span class="font-bold text-base-content">
-                                                {agg.label()}
+                                                {label}
                                             </span>
                                         </button>
                                     }
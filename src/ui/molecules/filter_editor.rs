@@ -0,0 +1,322 @@
+//! Filter editor molecule
+//!
+//! Edits the row filters of a widget: conditions on one field each,
+//! combined in AND/OR groups that can nest.
+
+use crate::features::dashboard::data::filter::{
+    value_from_text, Filter, FilterArity, FilterCombinator, FilterGroup, FilterNode, FilterOperator,
+};
+use crate::features::dashboard::models::{Field, FieldType};
+use crate::ui::atoms::{Icon, IconName};
+use leptos::prelude::*;
+use serde_json::Value;
+
+/// Filter editor component
+///
+/// Every edit produces a whole new filter group, handed to `on_change`.
+/// Operands are committed on change (blur or Enter) rather than on every
+/// keystroke, since each commit recomputes the widget.
+///
+/// # Example
+/// ```rust
+/// view! {
+///     <FilterEditor
+///         fields=fields
+///         filters=Signal::derive(move || mapping.get().filters)
+///         on_change=Callback::new(|filters: FilterGroup| {
+///             // Save the filters in the widget's data mapping
+///         })
+///     />
+/// }
+/// ```
+#[component]
+pub fn FilterEditor(
+    /// Fields conditions can test
+    #[prop(into)]
+    fields: Signal<Vec<Field>>,
+    /// Current filters
+    #[prop(into)]
+    filters: Signal<FilterGroup>,
+    /// Callback when the filters change
+    on_change: Callback<FilterGroup>,
+) -> impl IntoView {
+    view! {
+        <div class="flex flex-col gap-2">
+            <div class="flex items-center gap-2">
+                <Icon name=IconName::Filter class="w-4 h-4 text-base-content/50" />
+                <label class="text-xs font-semibold text-base-content/70">"Filters"</label>
+            </div>
+            {move || group_editor(fields.get(), filters.get(), on_change, None)}
+        </div>
+    }
+}
+
+/// Editor of one group (`on_remove` is set for nested groups)
+fn group_editor(
+    fields: Vec<Field>,
+    group: FilterGroup,
+    on_change: Callback<FilterGroup>,
+    on_remove: Option<Callback<()>>,
+) -> AnyView {
+    let edit = {
+        let group = group.clone();
+        move |change: &dyn Fn(&mut FilterGroup)| {
+            let mut group = group.clone();
+            change(&mut group);
+            on_change.run(group);
+        }
+    };
+
+    let members = group
+        .filters
+        .iter()
+        .enumerate()
+        .map(|(idx, node)| {
+            let update = {
+                let edit = edit.clone();
+                Callback::new(move |node: FilterNode| edit(&|group| group.filters[idx] = node.clone()))
+            };
+            let remove = {
+                let edit = edit.clone();
+                Callback::new(move |()| {
+                    edit(&|group| {
+                        group.filters.remove(idx);
+                    })
+                })
+            };
+            match node {
+                FilterNode::Condition(filter) => condition_editor(
+                    &fields,
+                    filter.clone(),
+                    Callback::new(move |filter| update.run(FilterNode::Condition(filter))),
+                    remove,
+                ),
+                FilterNode::Group(inner) => group_editor(
+                    fields.clone(),
+                    inner.clone(),
+                    Callback::new(move |inner| update.run(FilterNode::Group(inner))),
+                    Some(remove),
+                ),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let set_combinator = {
+        let edit = edit.clone();
+        move |ev| {
+            let combinator = match event_target_value(&ev).as_str() {
+                "Or" => FilterCombinator::Or,
+                _ => FilterCombinator::And,
+            };
+            edit(&|group| group.combinator = combinator);
+        }
+    };
+    let first_field = fields.first().map(|field| field.name.clone()).unwrap_or_default();
+    let add_condition = {
+        let edit = edit.clone();
+        move |_| {
+            let filter = Filter { field: first_field.clone(), ..Default::default() };
+            edit(&|group| group.filters.push(FilterNode::Condition(filter.clone())));
+        }
+    };
+    let add_group = move |_| edit(&|group| group.filters.push(FilterNode::Group(FilterGroup::default())));
+
+    let container = if on_remove.is_some() {
+        "flex flex-col gap-2 p-2 border border-base-300 rounded-lg"
+    } else {
+        "flex flex-col gap-2"
+    };
+    let combinator = group.combinator;
+
+    view! {
+        <div class=container>
+            {(!group.is_empty() || on_remove.is_some()).then(|| view! {
+                <div class="flex items-center gap-2">
+                    <span class="text-xs text-base-content/60">"Match"</span>
+                    <select class="select select-bordered select-xs" on:change=set_combinator>
+                        <option value="And" selected=combinator == FilterCombinator::And>"all"</option>
+                        <option value="Or" selected=combinator == FilterCombinator::Or>"any"</option>
+                    </select>
+                    <span class="text-xs text-base-content/60 flex-1">"of these conditions"</span>
+                    {on_remove.map(|remove| view! {
+                        <button
+                            class="text-base-content/40 hover:text-error"
+                            title="Remove group"
+                            on:click=move |_| remove.run(())
+                        >
+                            <Icon name=IconName::Delete class="w-4 h-4" />
+                        </button>
+                    })}
+                </div>
+            })}
+            {members}
+            <div class="flex gap-1">
+                <button class="btn btn-xs btn-ghost gap-1" on:click=add_condition>
+                    <Icon name=IconName::Add class="w-3 h-3" />
+                    "Condition"
+                </button>
+                <button class="btn btn-xs btn-ghost gap-1" on:click=add_group>
+                    <Icon name=IconName::Add class="w-3 h-3" />
+                    "Group"
+                </button>
+            </div>
+        </div>
+    }
+    .into_any()
+}
+
+/// Editor of one condition: field, operator and operands
+fn condition_editor(
+    fields: &[Field],
+    filter: Filter,
+    on_change: Callback<Filter>,
+    on_remove: Callback<()>,
+) -> AnyView {
+    let field_type = fields
+        .iter()
+        .find(|field| field.name == filter.field)
+        .map(|field| field.field_type);
+    let input_type = match (filter.operator, field_type) {
+        (FilterOperator::DateRange, _) | (_, Some(FieldType::Date)) => "date",
+        (FilterOperator::In | FilterOperator::NotIn, _) => "text",
+        (_, Some(FieldType::Numeric)) => "number",
+        _ => "text",
+    };
+
+    let edit = {
+        let filter = filter.clone();
+        move |change: &dyn Fn(&mut Filter)| {
+            let mut filter = filter.clone();
+            change(&mut filter);
+            on_change.run(filter);
+        }
+    };
+    let set_field = {
+        let edit = edit.clone();
+        move |ev| {
+            let field = event_target_value(&ev);
+            edit(&|filter| filter.field = field.clone());
+        }
+    };
+    let set_operator = {
+        let edit = edit.clone();
+        move |ev| {
+            let Some(operator) = event_target_value(&ev)
+                .parse::<usize>()
+                .ok()
+                .and_then(|idx| FilterOperator::all().get(idx).copied())
+            else {
+                return;
+            };
+            edit(&|filter| {
+                filter.operator = operator;
+                match operator.arity() {
+                    FilterArity::None => filter.values.clear(),
+                    FilterArity::One => filter.values.truncate(1),
+                    FilterArity::Two => filter.values.truncate(2),
+                    FilterArity::Many => {}
+                }
+            });
+        }
+    };
+    // Operand `idx`, or all of them from a comma-separated list
+    let operand_input = |idx: Option<usize>, placeholder: &'static str| {
+        let edit = edit.clone();
+        let text = match idx {
+            Some(idx) => filter.values.get(idx).map(value_text).unwrap_or_default(),
+            None => filter.values.iter().map(value_text).collect::<Vec<_>>().join(", "),
+        };
+        view! {
+            <input
+                class="input input-bordered input-xs flex-1 min-w-0"
+                type=if idx.is_some() { input_type } else { "text" }
+                placeholder=placeholder
+                value=text
+                on:change=move |ev| {
+                    let text = event_target_value(&ev);
+                    edit(&|filter| match idx {
+                        Some(idx) => {
+                            if filter.values.len() <= idx {
+                                filter.values.resize(idx + 1, Value::Null);
+                            }
+                            filter.values[idx] = if text.trim().is_empty() { Value::Null } else { value_from_text(&text, filter.operator) };
+                        }
+                        None => {
+                            let operator = filter.operator;
+                            filter.values = text
+                                .split(',')
+                                .filter(|item| !item.trim().is_empty())
+                                .map(|item| value_from_text(item, operator))
+                                .collect();
+                        }
+                    });
+                }
+            />
+        }
+    };
+    let operands = match filter.operator.arity() {
+        FilterArity::None => None,
+        FilterArity::One => Some(operand_input(Some(0), "Value").into_any()),
+        FilterArity::Two => Some(
+            view! {
+                <div class="flex items-center gap-1">
+                    {operand_input(Some(0), "From")}
+                    <span class="text-xs text-base-content/50">"to"</span>
+                    {operand_input(Some(1), "To")}
+                </div>
+            }
+            .into_any(),
+        ),
+        FilterArity::Many => Some(operand_input(None, "Values, comma-separated").into_any()),
+    };
+
+    view! {
+        <div class="flex flex-col gap-1 p-2 bg-base-200 rounded-lg">
+            <div class="flex items-center gap-1">
+                <select class="select select-bordered select-xs flex-1 min-w-0" on:change=set_field>
+                    {fields
+                        .iter()
+                        .map(|field| {
+                            view! {
+                                <option value=field.name.clone() selected=field.name == filter.field>
+                                    {field.name.clone()}
+                                </option>
+                            }
+                        })
+                        .collect::<Vec<_>>()}
+                </select>
+                <select class="select select-bordered select-xs" on:change=set_operator>
+                    {FilterOperator::all()
+                        .into_iter()
+                        .enumerate()
+                        .map(|(idx, operator)| {
+                            view! {
+                                <option value=idx.to_string() selected=operator == filter.operator>
+                                    {operator.label()}
+                                </option>
+                            }
+                        })
+                        .collect::<Vec<_>>()}
+                </select>
+                <button
+                    class="text-base-content/40 hover:text-error"
+                    title="Remove condition"
+                    on:click=move |_| on_remove.run(())
+                >
+                    <Icon name=IconName::Close class="w-4 h-4" />
+                </button>
+            </div>
+            {operands}
+        </div>
+    }
+    .into_any()
+}
+
+/// Operand as shown in an input
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
@@ -1,4 +1,4 @@
-use crate::features::dashboard::{calculate_kpi, analyze_dataset_for_kpis, DashboardContext, KpiAggregation};
+use crate::features::dashboard::{calculate_kpi, analyze_dataset_for_kpis, data::AggregationFunction, DashboardContext};
 use crate::ui::atoms::{Icon, IconName};
 use leptos::ev::MouseEvent;
 use leptos::prelude::*;
@@ -29,7 +29,7 @@ pub fn KpiQuickActions(
     /// Callback when modal is closed
     on_close: Callback<MouseEvent>,
     /// Callback when a KPI option is selected: (field_name, aggregation, formatted_label)
-    on_select: Callback<(String, KpiAggregation, String)>,
+    on_select: Callback<(String, AggregationFunction, String)>,
 ) -> impl IntoView {
     let dashboard = DashboardContext::use_context();
 
@@ -43,20 +43,17 @@ pub fn KpiQuickActions(
     });
 
     // Select a KPI configuration
-    let handle_select = {
-        let on_select = on_select.clone();
-        move |field_name: String, aggregation: KpiAggregation, label: String| {
-            log::info!("KPI selected: {} - {:?}", label, aggregation);
+    let handle_select = move |field_name: String, aggregation: AggregationFunction, label: String| {
+        log::info!("KPI selected: {} - {:?}", label, aggregation);
 
-            // Calculate actual value for preview
-            if let Some(dataset) = dashboard.get_active_dataset() {
-                if let Some(kpi_value) = calculate_kpi(&dataset, &field_name, aggregation) {
-                    log::info!("KPI value: {}", kpi_value.formatted);
-                }
-            }
-
-            on_select.run((field_name, aggregation, label));
+        // Calculate actual value for preview
+        if let Some(dataset) = dashboard.get_active_dataset()
+            && let Some(kpi_value) = calculate_kpi(&dataset, &field_name, aggregation.clone())
+        {
+            log::info!("KPI value: {}", kpi_value.formatted);
         }
+
+        on_select.run((field_name, aggregation, label));
     };
 
     view! {
@@ -111,17 +108,17 @@ pub fn KpiQuickActions(
                                         suggestions.into_iter().map(move |(aggregation, label)| {
                                             let field_clone = field_name.clone();
                                             let label_clone = label.clone();
-                                            let aggregation_clone = aggregation;
+                                            let aggregation_clone = aggregation.clone();
 
                                             // Determine icon
                                             let icon = match aggregation {
-                                                KpiAggregation::Sum => IconName::Add,
-                                                KpiAggregation::Average => IconName::TrendingUp,
-                                                KpiAggregation::Count => IconName::Hash,
-                                                KpiAggregation::Min => IconName::ArrowDown,
-                                                KpiAggregation::Max => IconName::ArrowUp,
-                                                KpiAggregation::Last => IconName::Schedule,
-                                                KpiAggregation::First => IconName::History,
+                                                AggregationFunction::Sum => IconName::Add,
+                                                AggregationFunction::Count | AggregationFunction::CountDistinct => IconName::Hash,
+                                                AggregationFunction::Min => IconName::ArrowDown,
+                                                AggregationFunction::Max => IconName::ArrowUp,
+                                                AggregationFunction::Last => IconName::Schedule,
+                                                AggregationFunction::First => IconName::History,
+                                                _ => IconName::TrendingUp,
                                             };
 
                                             view! {
@@ -130,7 +127,7 @@ pub fn KpiQuickActions(
                                                     on:click=move |_| {
                                                         handle_select(
                                                             field_clone.clone(),
-                                                            aggregation_clone,
+                                                            aggregation_clone.clone(),
                                                             label_clone.clone(),
                                                         );
                                                     }
@@ -143,7 +140,7 @@ pub fn KpiQuickActions(
                                                             {label_clone.clone()}
                                                         </div>
                                                         <div class="text-xs text-base-content/60 mt-0.5">
-                                                            {aggregation_clone.display_name()}
+                                                            {aggregation.label()}
                                                             " of "
                                                             {field_clone.clone()}
                                                         </div>
@@ -10,6 +10,7 @@ pub mod drop_zone;
 pub mod echarts_wrapper;
 pub mod field_item;
 pub mod field_selector;
+pub mod filter_editor;
 pub mod kpi_card;
 pub mod kpi_quick_actions;
 pub mod layer_item;
@@ -41,6 +42,7 @@ pub use drop_zone::*;
 pub use echarts_wrapper::*;
 pub use field_item::*;
 pub use field_selector::*;
+pub use filter_editor::*;
 pub use kpi_card::*;
 pub use kpi_quick_actions::*;
 pub use layer_item::*;
@@ -48,10 +48,10 @@ pub fn Modal(
     let title = StoredValue::new(title);
 
     // Clone callback for backdrop
-    let on_close_backdrop = on_close.clone();
+    let on_close_backdrop = on_close;
 
     // ESC key handler - needs to capture on_close
-    let on_close_esc = on_close.clone();
+    let on_close_esc = on_close;
     let on_keydown = move |ev: KeyboardEvent| {
         if ev.key() == "Escape" && show.get() {
             // Convert KeyboardEvent to MouseEvent for callback
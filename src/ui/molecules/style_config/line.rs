@@ -122,6 +122,17 @@ impl StyleConfigUI for LineStyleConfig {
                         "Data Points"
                     </h5>
 
+                    <Toggle
+                        label="Stacked"
+                        description="Stack lines on top of each other"
+                        checked=Signal::derive(move || options.get().stacked)
+                        on_change=Callback::new(move |val| {
+                            let mut opts = options.get();
+                            opts.stacked = val;
+                            on_change.run(opts);
+                        })
+                    />
+
                     <Toggle
                         label="Show Points"
                         description="Display data points on the line"
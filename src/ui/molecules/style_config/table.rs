@@ -1,7 +1,8 @@
 //! Table widget style configuration UI
 
 use crate::features::dashboard::config::style::TableStyleOptions;
-use crate::ui::atoms::{Slider, Toggle};
+use crate::features::dashboard::data::AggregationFunction;
+use crate::ui::atoms::{Select, SelectOption, Slider, Toggle};
 use super::StyleConfigUI;
 use leptos::prelude::*;
 
@@ -16,6 +17,16 @@ impl StyleConfigUI for TableStyleConfig {
         options: Signal<Self::Options>,
         on_change: Callback<Self::Options>,
     ) -> impl IntoView {
+        // Options are keyed by display name; an empty value means no summary
+        let summary_options = Signal::from(
+            std::iter::once(SelectOption { label: "None".to_string(), value: String::new() })
+                .chain(AggregationFunction::common().into_iter().map(|agg| SelectOption {
+                    label: agg.label(),
+                    value: agg.display_name(),
+                }))
+                .collect::<Vec<_>>(),
+        );
+
         view! {
             <div class="flex flex-col gap-4">
                 // Table Features Section
@@ -69,6 +80,28 @@ impl StyleConfigUI for TableStyleConfig {
                     />
                 </div>
 
+                // Summary Section
+                <div class="flex flex-col gap-3 border-t border-base-content/10 pt-3">
+                    <h5 class="text-xs font-semibold text-base-content/70 uppercase tracking-wide">
+                        "Summary"
+                    </h5>
+
+                    <Select
+                        label="Summary Row"
+                        options=summary_options
+                        selected=Signal::derive(move || {
+                            options.get().summary.map(|agg| agg.display_name()).unwrap_or_default()
+                        })
+                        on_change=Callback::new(move |val: String| {
+                            let mut opts = options.get();
+                            opts.summary = AggregationFunction::common()
+                                .into_iter()
+                                .find(|agg| agg.display_name() == val);
+                            on_change.run(opts);
+                        })
+                    />
+                </div>
+
                 // Pagination Options Section
                 <div class="flex flex-col gap-3 border-t border-base-content/10 pt-3">
                     <h5 class="text-xs font-semibold text-base-content/70 uppercase tracking-wide">
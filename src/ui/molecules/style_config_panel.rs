@@ -87,6 +87,15 @@ pub fn StyleConfigPanel(
         crate::features::dashboard::models::WidgetType::Treemap => {
             render_style_config::<TreemapStyleConfig>(selected_widget).into_any()
         }
+        crate::features::dashboard::models::WidgetType::FilterDropdown
+        | crate::features::dashboard::models::WidgetType::FilterDateRange
+        | crate::features::dashboard::models::WidgetType::FilterRange
+        | crate::features::dashboard::models::WidgetType::FilterSearch => {
+            view! {
+                <p class="text-xs text-base-content/60">"Filter controls have no style options."</p>
+            }
+            .into_any()
+        }
     };
 
     view! {
@@ -26,6 +26,10 @@ impl WidgetType {
             Self::Treemap => IconName::Treemap,
             Self::Kpi => IconName::TrendingUp,
             Self::Table => IconName::TableChart,
+            Self::FilterDropdown => IconName::ListFilter,
+            Self::FilterDateRange => IconName::Calendar,
+            Self::FilterRange => IconName::Sliders,
+            Self::FilterSearch => IconName::Search,
         }
     }
 
@@ -39,6 +43,9 @@ impl WidgetType {
                 WidgetCategory::Advanced
             }
             Self::Kpi | Self::Table => WidgetCategory::Data,
+            Self::FilterDropdown | Self::FilterDateRange | Self::FilterRange | Self::FilterSearch => {
+                WidgetCategory::Filters
+            }
         }
     }
 }
@@ -49,6 +56,7 @@ pub enum WidgetCategory {
     Basic,    // Line, Bar, Pie, Scatter, Area
     Advanced, // Radar, Candlestick, Heatmap, Treemap
     Data,     // KPI, Table
+    Filters,  // Dropdown, Date Range, Range Slider, Search
 }
 
 impl WidgetCategory {
@@ -58,6 +66,7 @@ impl WidgetCategory {
             Self::Basic => "Basic Charts",
             Self::Advanced => "Advanced Charts",
             Self::Data => "Data Widgets",
+            Self::Filters => "Filter Controls",
         }
     }
 }
@@ -107,6 +116,13 @@ pub fn WidgetSelector(
 
     let data_widgets = [WidgetType::Kpi, WidgetType::Table];
 
+    let filter_widgets = [
+        WidgetType::FilterDropdown,
+        WidgetType::FilterDateRange,
+        WidgetType::FilterRange,
+        WidgetType::FilterSearch,
+    ];
+
     view! {
         <div class="flex flex-col gap-3">
             // Section Header
@@ -237,6 +253,47 @@ pub fn WidgetSelector(
                     }}
                 </div>
             </div>
+
+            // Filter Controls Section
+            <div>
+                <p class="text-[10px] text-base-content/50 font-semibold mb-1.5 ml-1 uppercase tracking-wider">
+                    {WidgetCategory::Filters.label()}
+                </p>
+                <div class="grid grid-cols-4 gap-1.5">
+                    {move || {
+                        filter_widgets
+                            .iter()
+                            .map(|&widget_type| {
+                                let is_selected = selected.get() == widget_type;
+                                let click_cb: Callback<MouseEvent> = Callback::new(move |_| {
+                                    if let Some(handler) = &on_change {
+                                        handler.run(widget_type);
+                                    }
+                                });
+                                view! {
+                                    <Button
+                                        variant=if is_selected {
+                                            ButtonVariant::Primary
+                                        } else {
+                                            ButtonVariant::Secondary
+                                        }
+                                        size=ButtonSize::Small
+                                        on_click=click_cb
+                                        class=if is_selected {
+                                            "shadow-md shadow-primary/30 ring-2 ring-offset-1 ring-offset-base-100 ring-primary"
+                                        } else {
+                                            ""
+                                        }
+                                        title=widget_type.display_name()
+                                    >
+                                        <Icon name=widget_type.icon() class="w-5 h-5" />
+                                    </Button>
+                                }
+                            })
+                            .collect::<Vec<_>>()
+                    }}
+                </div>
+            </div>
         </div>
     }
 }
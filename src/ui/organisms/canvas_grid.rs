@@ -46,6 +46,43 @@ fn WidgetPlaceholder(widget_type: WidgetType, _widget_id: WidgetId) -> impl Into
     }
 }
 
+/// Badge on a widget whose chart elements cross-filter the dashboard
+///
+/// Shows how many elements are selected, with a button clearing them.
+#[component]
+fn SelectionBadge(widget_id: WidgetId) -> impl IntoView {
+    let dashboard = DashboardContext::use_context();
+    let count = Memo::new(move |_| {
+        dashboard.chart_selection.with(|selection| {
+            selection
+                .as_ref()
+                .filter(|selection| selection.source == widget_id)
+                .map(|selection| selection.elements.len())
+        })
+    });
+
+    move || {
+        count.get().map(|count| {
+            view! {
+                <div class="absolute top-2 left-2 z-30 badge badge-primary badge-sm gap-1 shadow-sm">
+                    <Icon name=IconName::ListFilter class="w-3 h-3" />
+                    {format!("{} selected", count)}
+                    <button
+                        class="hover:opacity-70"
+                        title="Clear selection"
+                        on:click=move |ev| {
+                            ev.stop_propagation();
+                            dashboard.clear_chart_selection();
+                        }
+                    >
+                        <Icon name=IconName::Close class="w-3 h-3" />
+                    </button>
+                </div>
+            }
+        })
+    }
+}
+
 /// Canvas grid organism component
 ///
 /// Main widget display area using CSS Grid layout.
@@ -306,6 +343,13 @@ pub fn CanvasGrid() -> impl IntoView {
                                 view! { <TreemapWidget widget_id=widget_id_label.clone() /> }
                                     .into_any()
                             }
+                            WidgetType::FilterDropdown
+                            | WidgetType::FilterDateRange
+                            | WidgetType::FilterRange
+                            | WidgetType::FilterSearch => {
+                                view! { <FilterControlWidget widget_id=widget_id_label.clone() /> }
+                                    .into_any()
+                            }
                         };
 
                         view! {
@@ -393,6 +437,7 @@ pub fn CanvasGrid() -> impl IntoView {
                                     ></div>
 
                                     {content}
+                                    <SelectionBadge widget_id=widget_id_label.clone() />
                                 </div>
                             </div>
                         }
@@ -0,0 +1,780 @@
+//! Import wizard organism
+//!
+//! Multi-step dialog shown after a file has been read: layout (delimiter,
+//! number format, skipped rows, header row; flattened records for JSON;
+//! sheets for workbooks), columns (rename, exclude, override type), then a
+//! final review before the dataset is added to the dashboard (or replaces
+//! the data of an existing one).
+
+use crate::features::dashboard::csv_upload::{
+    delimiter_candidates, preview_with_options, CsvUploadManager, ImportOptions, ImportPreview,
+    PendingImport, SourceFormat, UploadState, PREVIEW_ROWS,
+};
+use crate::features::dashboard::{CsvDialect, DashboardContext, FieldType, NumberFormat};
+use crate::ui::molecules::Modal;
+use leptos::ev::MouseEvent;
+use leptos::prelude::*;
+
+/// Wizard step titles
+const STEPS: [&str; 3] = ["Layout", "Columns", "Review"];
+
+/// Import wizard component
+///
+/// Opens automatically while the upload manager holds a pending import.
+///
+/// # Example
+/// ```rust
+/// view! {
+///     <ImportWizard upload_manager=upload_manager />
+/// }
+/// ```
+#[component]
+pub fn ImportWizard(
+    /// CSV Upload Manager holding the pending import
+    upload_manager: CsvUploadManager,
+) -> impl IntoView {
+    move || {
+        upload_manager.pending_import().map(|pending| {
+            view! { <ImportWizardDialog pending=pending upload_manager=upload_manager /> }
+        })
+    }
+}
+
+/// Wizard dialog for a single pending import
+#[component]
+fn ImportWizardDialog(
+    pending: PendingImport,
+    upload_manager: CsvUploadManager,
+) -> impl IntoView {
+    let dashboard = DashboardContext::use_context();
+    // Dataset whose data the file replaces; delimited text keeps its dialect
+    let replaced = untrack(|| upload_manager.replacing()).and_then(|id| {
+        dashboard.datasets.with_untracked(|datasets| datasets.iter().find(|d| d.id == id).cloned())
+    });
+    let mut initial = pending.options.clone();
+    if let Some(dialect) = replaced.as_ref().and_then(|d| d.dialect.clone())
+        && initial.format == SourceFormat::Delimited
+    {
+        initial.dialect = dialect;
+    }
+
+    let (step, set_step) = signal(0usize);
+    let (options, set_options) = signal(initial);
+
+    let content = pending.content.clone();
+    let preview = Memo::new(move |_| {
+        options.with(|o| preview_with_options(&content, o, PREVIEW_ROWS).map_err(|e| e.to_string()))
+    });
+
+    let on_close = Callback::new(move |_: MouseEvent| {
+        upload_manager.cancel_import();
+    });
+
+    // Make sure the column overrides match the current header row
+    let sync_columns = move || {
+        if let Ok(preview) = preview.get_untracked() {
+            set_options.update(|o| {
+                if !o.overrides_match(&preview.headers) {
+                    o.columns = preview.default_columns();
+                }
+            });
+        }
+    };
+
+    let on_next = move |_| {
+        if step.get_untracked() == 0 {
+            sync_columns();
+        }
+        set_step.update(|s| *s = (*s + 1).min(STEPS.len() - 1));
+    };
+
+    let on_back = move |_| {
+        upload_manager.stop_parsing();
+        set_step.update(|s| *s = s.saturating_sub(1));
+    };
+
+    let on_import = move |_| {
+        sync_columns();
+        upload_manager.confirm_import(options.get_untracked());
+    };
+
+    let on_stop = move |_| upload_manager.stop_parsing();
+
+    let parsing = move || matches!(upload_manager.progress().state, UploadState::Parsing { .. });
+
+    let title = match &replaced {
+        Some(dataset) => format!("Replace data of {} with {}", dataset.name, pending.filename),
+        None => format!("Import {}", pending.filename),
+    };
+    let format = pending.options.format;
+    let sheets = pending.sheets.clone();
+
+    view! {
+        <Modal show=Signal::derive(|| true) on_close=on_close title=title>
+            <div class="flex flex-col gap-4">
+                // Step indicator
+                <ul class="steps w-full text-xs">
+                    {STEPS
+                        .iter()
+                        .enumerate()
+                        .map(|(idx, label)| {
+                            view! {
+                                <li class=move || {
+                                    if step.get() >= idx { "step step-primary" } else { "step" }
+                                }>{*label}</li>
+                            }
+                        })
+                        .collect::<Vec<_>>()}
+                </ul>
+
+                // Step content
+                {move || match preview.get() {
+                    Err(message) if format == SourceFormat::Spreadsheet => {
+                        view! {
+                            <div class="alert alert-error text-sm">
+                                {format!("Could not read the sheet: {}", message)}
+                            </div>
+                            <SheetsStep
+                                sheets=sheets.clone()
+                                options=options
+                                set_options=set_options
+                                preview=None
+                            />
+                        }
+                            .into_any()
+                    }
+                    Err(message) if format != SourceFormat::Delimited => {
+                        view! {
+                            <div class="alert alert-error text-sm">
+                                {format!("Could not read the file: {}", message)}
+                            </div>
+                        }
+                            .into_any()
+                    }
+                    Err(message) => {
+                        view! {
+                            <div class="alert alert-error text-sm">
+                                {format!("Could not parse the file with these settings: {}", message)}
+                            </div>
+                            <LayoutStep options=options set_options=set_options preview=None />
+                        }
+                            .into_any()
+                    }
+                    Ok(current) => match step.get() {
+                        0 if format == SourceFormat::Spreadsheet => {
+                            view! {
+                                <SheetsStep
+                                    sheets=sheets.clone()
+                                    options=options
+                                    set_options=set_options
+                                    preview=Some(current)
+                                />
+                            }
+                                .into_any()
+                        }
+                        0 if format.is_columnar() => {
+                            let note = format!(
+                                "{} file: {} columns, typed by the file's schema. Pick the columns to load in the next step.",
+                                format.display_name(),
+                                current.headers.len(),
+                            );
+                            view! { <RecordsStep note=note preview=current /> }.into_any()
+                        }
+                        0 if format != SourceFormat::Delimited => {
+                            let note = format!(
+                                "{} file: {} columns. Nested fields are flattened into dotted names.",
+                                format.display_name(),
+                                current.headers.len(),
+                            );
+                            view! { <RecordsStep note=note preview=current /> }.into_any()
+                        }
+                        0 => {
+                            view! {
+                                <LayoutStep
+                                    options=options
+                                    set_options=set_options
+                                    preview=Some(current)
+                                />
+                            }
+                                .into_any()
+                        }
+                        1 => {
+                            view! {
+                                <ColumnsStep options=options set_options=set_options preview=current />
+                            }
+                                .into_any()
+                        }
+                        _ => view! { <ReviewStep options=options preview=current /> }.into_any(),
+                    },
+                }}
+
+                // Parse progress / import error
+                {move || match upload_manager.progress().state {
+                    UploadState::Parsing { progress, rows_processed } => {
+                        Some(
+                            view! {
+                                <div class="flex flex-col gap-1">
+                                    <progress
+                                        class="progress progress-primary w-full"
+                                        value=progress
+                                        max="100"
+                                    ></progress>
+                                    <p class="text-xs text-base-content/60">
+                                        {format!("Parsing: {} rows ({:.0}%)", rows_processed, progress)}
+                                    </p>
+                                </div>
+                            }
+                                .into_any(),
+                        )
+                    }
+                    UploadState::Failed(message) => {
+                        Some(
+                            view! { <div class="alert alert-error text-sm">{message}</div> }
+                                .into_any(),
+                        )
+                    }
+                    _ => None,
+                }}
+
+                // Footer
+                <div class="flex items-center justify-between pt-2 border-t border-base-300">
+                    <button class="btn btn-ghost btn-sm" on:click=move |ev| on_close.run(ev)>
+                        "Cancel"
+                    </button>
+                    <div class="flex gap-2">
+                        <button
+                            class="btn btn-ghost btn-sm"
+                            disabled=move || step.get() == 0
+                            on:click=on_back
+                        >
+                            "Back"
+                        </button>
+                        {move || {
+                            let blocked = preview.with(|p| p.is_err());
+                            if step.get() + 1 < STEPS.len() {
+                                view! {
+                                    <button
+                                        class="btn btn-primary btn-sm"
+                                        disabled=blocked
+                                        on:click=on_next
+                                    >
+                                        "Next"
+                                    </button>
+                                }
+                                    .into_any()
+                            } else if parsing() {
+                                view! {
+                                    <button class="btn btn-warning btn-sm" on:click=on_stop>
+                                        "Stop"
+                                    </button>
+                                }
+                                    .into_any()
+                            } else {
+                                view! {
+                                    <button
+                                        class="btn btn-primary btn-sm"
+                                        disabled=blocked
+                                        on:click=on_import
+                                    >
+                                        "Import"
+                                    </button>
+                                }
+                                    .into_any()
+                            }
+                        }}
+                    </div>
+                </div>
+            </div>
+        </Modal>
+    }
+}
+
+/// Step 1: delimiter, quoting, number format, skipped rows and header row
+#[component]
+fn LayoutStep(
+    options: ReadSignal<ImportOptions>,
+    set_options: WriteSignal<ImportOptions>,
+    preview: Option<ImportPreview>,
+) -> impl IntoView {
+    let delimiters = delimiter_candidates();
+
+    let raw_rows = preview.map(|p| p.raw_rows).unwrap_or_default();
+    let width = raw_rows.iter().map(|row| row.len()).max().unwrap_or(0);
+
+    view! {
+        <div class="flex flex-col gap-4">
+            <div class="grid grid-cols-2 sm:grid-cols-5 gap-3">
+                <label class="form-control">
+                    <span class="label-text text-xs font-semibold">"Delimiter"</span>
+                    <select
+                        class="select select-bordered select-sm"
+                        on:change=move |ev| {
+                            let label = event_target_value(&ev);
+                            if let Some(delimiter) = delimiter_candidates()
+                                .into_iter()
+                                .find(|d| CsvDialect::delimiter_label(*d) == label)
+                            {
+                                set_options.update(|o| o.dialect.delimiter = delimiter);
+                            }
+                        }
+                    >
+                        {delimiters
+                            .into_iter()
+                            .map(|delimiter| {
+                                let label = CsvDialect::delimiter_label(delimiter);
+                                view! {
+                                    <option
+                                        value=label
+                                        selected=move || options.with(|o| o.dialect.delimiter == delimiter)
+                                    >
+                                        {label}
+                                    </option>
+                                }
+                            })
+                            .collect::<Vec<_>>()}
+                    </select>
+                </label>
+
+                <label class="form-control">
+                    <span class="label-text text-xs font-semibold">"Quote"</span>
+                    <select
+                        class="select select-bordered select-sm"
+                        on:change=move |ev| {
+                            let quote = if event_target_value(&ev) == "single" { '\'' } else { '"' };
+                            set_options.update(|o| o.dialect.quote = quote);
+                        }
+                    >
+                        <option value="double" selected=move || options.with(|o| o.dialect.quote == '"')>
+                            "Double (\")"
+                        </option>
+                        <option value="single" selected=move || options.with(|o| o.dialect.quote == '\'')>
+                            "Single (')"
+                        </option>
+                    </select>
+                </label>
+
+                <label class="form-control">
+                    <span class="label-text text-xs font-semibold">"Numbers"</span>
+                    <select
+                        class="select select-bordered select-sm"
+                        on:change=move |ev| {
+                            let example = event_target_value(&ev);
+                            if let Some(format) = NumberFormat::presets()
+                                .into_iter()
+                                .find(|f| f.example() == example)
+                            {
+                                set_options.update(|o| o.dialect.number_format = format);
+                            }
+                        }
+                    >
+                        {NumberFormat::presets()
+                            .into_iter()
+                            .map(|format| {
+                                let example = format.example();
+                                let value = example.clone();
+                                view! {
+                                    <option
+                                        value=value
+                                        selected=move || options.with(|o| o.dialect.number_format == format)
+                                    >
+                                        {example}
+                                    </option>
+                                }
+                            })
+                            .collect::<Vec<_>>()}
+                    </select>
+                </label>
+
+                <label class="form-control">
+                    <span class="label-text text-xs font-semibold">"Skip rows"</span>
+                    <input
+                        type="number"
+                        min="0"
+                        class="input input-bordered input-sm"
+                        prop:value=move || options.with(|o| o.skip_rows.to_string())
+                        on:change=move |ev| {
+                            let skip = event_target_value(&ev).parse::<usize>().unwrap_or(0);
+                            set_options.update(|o| o.skip_rows = skip);
+                        }
+                    />
+                </label>
+
+                <label class="label cursor-pointer justify-start gap-2 self-end">
+                    <input
+                        type="checkbox"
+                        class="checkbox checkbox-sm checkbox-primary"
+                        prop:checked=move || options.with(|o| o.dialect.has_header)
+                        on:change=move |ev| {
+                            let checked = event_target_checked(&ev);
+                            set_options.update(|o| o.dialect.has_header = checked);
+                        }
+                    />
+                    <span class="label-text text-xs">"Header row"</span>
+                </label>
+            </div>
+
+            // Raw rows: skipped rows are dimmed, the header row is highlighted
+            <div class="overflow-auto max-h-72 border border-base-300 rounded-lg">
+                <table class="table table-xs">
+                    <tbody>
+                        {raw_rows
+                            .into_iter()
+                            .enumerate()
+                            .map(|(idx, row)| {
+                                let row_class = move || {
+                                    options.with(|o| {
+                                        if idx < o.skip_rows {
+                                            "opacity-40"
+                                        } else if idx == o.skip_rows && o.dialect.has_header {
+                                            "bg-primary/10 font-semibold"
+                                        } else {
+                                            ""
+                                        }
+                                    })
+                                };
+                                view! {
+                                    <tr class=row_class>
+                                        <td class="w-20">
+                                            <button
+                                                class="btn btn-ghost btn-xs"
+                                                title="Use this row as the header"
+                                                on:click=move |_| {
+                                                    set_options.update(|o| {
+                                                        o.skip_rows = idx;
+                                                        o.dialect.has_header = true;
+                                                    });
+                                                }
+                                            >
+                                                {format!("{} ↑", idx + 1)}
+                                            </button>
+                                        </td>
+                                        {(0..width)
+                                            .map(|col| {
+                                                view! { <td>{row.get(col).cloned().unwrap_or_default()}</td> }
+                                            })
+                                            .collect::<Vec<_>>()}
+                                    </tr>
+                                }
+                            })
+                            .collect::<Vec<_>>()}
+                    </tbody>
+                </table>
+            </div>
+        </div>
+    }
+}
+
+/// Step 1 for workbooks: sheets to import, with a preview of the first one
+#[component]
+fn SheetsStep(
+    sheets: Vec<String>,
+    options: ReadSignal<ImportOptions>,
+    set_options: WriteSignal<ImportOptions>,
+    preview: Option<ImportPreview>,
+) -> impl IntoView {
+    let all_sheets = sheets.clone();
+
+    view! {
+        <div class="flex flex-col gap-4">
+            <div class="flex flex-col gap-1">
+                <span class="label-text text-xs font-semibold">"Sheets (each becomes a dataset)"</span>
+                <div class="flex flex-wrap gap-x-4 gap-y-1">
+                    {sheets
+                        .into_iter()
+                        .map(|sheet| {
+                            let checked_sheet = sheet.clone();
+                            let toggled_sheet = sheet.clone();
+                            let all_sheets = all_sheets.clone();
+                            view! {
+                                <label class="label cursor-pointer justify-start gap-2">
+                                    <input
+                                        type="checkbox"
+                                        class="checkbox checkbox-sm checkbox-primary"
+                                        prop:checked=move || options.with(|o| o.sheets.contains(&checked_sheet))
+                                        on:change=move |ev| {
+                                            let checked = event_target_checked(&ev);
+                                            set_options.update(|o| {
+                                                o.sheets.retain(|s| *s != toggled_sheet);
+                                                if checked {
+                                                    o.sheets.push(toggled_sheet.clone());
+                                                }
+                                                // Keep workbook order so the preview shows the first sheet
+                                                o.sheets.sort_by_key(|s| all_sheets.iter().position(|a| a == s));
+                                            });
+                                        }
+                                    />
+                                    <span class="label-text text-xs">{sheet}</span>
+                                </label>
+                            }
+                        })
+                        .collect::<Vec<_>>()}
+                </div>
+                <label class="label cursor-pointer justify-start gap-2">
+                    <input
+                        type="checkbox"
+                        class="checkbox checkbox-sm checkbox-primary"
+                        prop:checked=move || options.with(|o| o.dialect.has_header)
+                        on:change=move |ev| {
+                            let checked = event_target_checked(&ev);
+                            set_options.update(|o| o.dialect.has_header = checked);
+                        }
+                    />
+                    <span class="label-text text-xs">"Header row"</span>
+                </label>
+            </div>
+
+            {preview
+                .map(|preview| {
+                    let note = options.with_untracked(|o| {
+                        format!(
+                            "Preview of '{}': {} columns. Title rows above the table are skipped.",
+                            o.sheets.first().cloned().unwrap_or_default(),
+                            preview.headers.len(),
+                        )
+                    });
+                    view! { <RecordsStep note=note preview=preview /> }
+                })}
+        </div>
+    }
+}
+
+/// Step 1 for JSON / NDJSON (and the sheet preview): the rows as columns
+#[component]
+fn RecordsStep(note: String, preview: ImportPreview) -> impl IntoView {
+    view! {
+        <div class="flex flex-col gap-2">
+            <p class="text-xs text-base-content/60">{note}</p>
+            <div class="overflow-auto max-h-72 border border-base-300 rounded-lg">
+                <table class="table table-xs">
+                    <thead>
+                        <tr>
+                            {preview
+                                .headers
+                                .iter()
+                                .map(|header| view! { <th>{header.clone()}</th> })
+                                .collect::<Vec<_>>()}
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {preview
+                            .rows
+                            .iter()
+                            .map(|row| {
+                                view! {
+                                    <tr>
+                                        {row
+                                            .iter()
+                                            .map(|cell| view! { <td>{cell.clone()}</td> })
+                                            .collect::<Vec<_>>()}
+                                    </tr>
+                                }
+                            })
+                            .collect::<Vec<_>>()}
+                    </tbody>
+                </table>
+            </div>
+        </div>
+    }
+}
+
+/// Step 2: rename, exclude and retype columns
+#[component]
+fn ColumnsStep(
+    options: ReadSignal<ImportOptions>,
+    set_options: WriteSignal<ImportOptions>,
+    preview: ImportPreview,
+) -> impl IntoView {
+    let detected = preview.detected_types.clone();
+
+    view! {
+        <div class="overflow-auto max-h-96 border border-base-300 rounded-lg">
+            <table class="table table-xs">
+                <thead>
+                    <tr>
+                        <th>"Import"</th>
+                        <th>"Name"</th>
+                        <th>"Type"</th>
+                        <th>"Sample"</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    {preview
+                        .headers
+                        .iter()
+                        .enumerate()
+                        .map(|(idx, header)| {
+                            let sample = preview
+                                .rows
+                                .iter()
+                                .filter_map(|row| row.get(idx))
+                                .take(3)
+                                .cloned()
+                                .collect::<Vec<_>>()
+                                .join(", ");
+                            let detected_type = detected.get(idx).copied().unwrap_or(FieldType::Text);
+                            let column = move || options.with(|o| o.columns.get(idx).cloned());
+                            // Sampled cells each type would turn into empty values
+                            let rejected: Vec<(FieldType, usize)> = FieldType::all()
+                                .into_iter()
+                                .map(|field_type| (field_type, preview.rejected_cells(idx, field_type)))
+                                .collect();
+                            let sampled = preview.sample.len();
+                            let warning = move || {
+                                let field_type = column()?.field_type;
+                                let count = rejected.iter().find(|(t, _)| *t == field_type)?.1;
+                                (count > 0).then(|| {
+                                    format!(
+                                        "{} of {} sampled values don't read as {} and will be empty",
+                                        count,
+                                        sampled,
+                                        field_type.display_name()
+                                    )
+                                })
+                            };
+
+                            view! {
+                                <tr>
+                                    <td>
+                                        <input
+                                            type="checkbox"
+                                            class="checkbox checkbox-sm checkbox-primary"
+                                            prop:checked=move || column().is_some_and(|c| c.include)
+                                            on:change=move |ev| {
+                                                let checked = event_target_checked(&ev);
+                                                set_options.update(|o| {
+                                                    if let Some(c) = o.columns.get_mut(idx) {
+                                                        c.include = checked;
+                                                    }
+                                                });
+                                            }
+                                        />
+                                    </td>
+                                    <td>
+                                        <input
+                                            type="text"
+                                            class="input input-bordered input-xs w-full"
+                                            placeholder=header.clone()
+                                            prop:value=move || column().map(|c| c.name).unwrap_or_default()
+                                            on:change=move |ev| {
+                                                let name = event_target_value(&ev);
+                                                set_options.update(|o| {
+                                                    if let Some(c) = o.columns.get_mut(idx) {
+                                                        c.name = name;
+                                                    }
+                                                });
+                                            }
+                                        />
+                                    </td>
+                                    <td>
+                                        <select
+                                            class="select select-bordered select-xs"
+                                            on:change=move |ev| {
+                                                let label = event_target_value(&ev);
+                                                if let Some(field_type) = FieldType::all()
+                                                    .into_iter()
+                                                    .find(|t| t.display_name() == label)
+                                                {
+                                                    set_options.update(|o| {
+                                                        if let Some(c) = o.columns.get_mut(idx) {
+                                                            c.field_type = field_type;
+                                                        }
+                                                    });
+                                                }
+                                            }
+                                        >
+                                            {FieldType::all()
+                                                .into_iter()
+                                                .map(|field_type| {
+                                                    let label = if field_type == detected_type {
+                                                        format!("{} (detected)", field_type.display_name())
+                                                    } else {
+                                                        field_type.display_name().to_string()
+                                                    };
+                                                    view! {
+                                                        <option
+                                                            value=field_type.display_name()
+                                                            selected=move || {
+                                                                column().is_some_and(|c| c.field_type == field_type)
+                                                            }
+                                                        >
+                                                            {label}
+                                                        </option>
+                                                    }
+                                                })
+                                                .collect::<Vec<_>>()}
+                                        </select>
+                                        {move || {
+                                            warning()
+                                                .map(|text| view! { <p class="text-xs text-warning mt-1">{text}</p> })
+                                        }}
+                                    </td>
+                                    <td class="text-base-content/60 truncate max-w-40">{sample}</td>
+                                </tr>
+                            }
+                        })
+                        .collect::<Vec<_>>()}
+                </tbody>
+            </table>
+        </div>
+    }
+}
+
+/// Step 3: preview of the dataset as it will be imported
+#[component]
+fn ReviewStep(options: ReadSignal<ImportOptions>, preview: ImportPreview) -> impl IntoView {
+    let columns = options.with_untracked(|o| o.resolve_columns(&preview.headers, &preview.detected_types));
+
+    let summary = format!(
+        "{} of {} columns will be imported",
+        columns.len(),
+        preview.headers.len()
+    );
+
+    view! {
+        <div class="flex flex-col gap-2">
+            <p class="text-xs text-base-content/60">{summary}</p>
+            <div class="overflow-auto max-h-96 border border-base-300 rounded-lg">
+                <table class="table table-xs">
+                    <thead>
+                        <tr>
+                            {columns
+                                .iter()
+                                .map(|(_, field)| {
+                                    view! {
+                                        <th>
+                                            <div class="flex flex-col">
+                                                <span>{field.name.clone()}</span>
+                                                <span class=format!(
+                                                    "text-[10px] font-normal {}",
+                                                    field.field_type.icon_color(),
+                                                )>{field.field_type.display_name()}</span>
+                                            </div>
+                                        </th>
+                                    }
+                                })
+                                .collect::<Vec<_>>()}
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {preview
+                            .rows
+                            .iter()
+                            .map(|row| {
+                                view! {
+                                    <tr>
+                                        {columns
+                                            .iter()
+                                            .map(|(idx, _)| {
+                                                view! { <td>{row.get(*idx).cloned().unwrap_or_default()}</td> }
+                                            })
+                                            .collect::<Vec<_>>()}
+                                    </tr>
+                                }
+                            })
+                            .collect::<Vec<_>>()}
+                    </tbody>
+                </table>
+            </div>
+        </div>
+    }
+}
@@ -8,6 +8,7 @@ pub mod canvas_grid;
 pub mod right_sidebar;
 pub mod widget_configuration_panel;
 pub mod activity_sidebar;
+pub mod import_wizard;
 
 // Re-export for convenience
 pub use data::*;
@@ -17,6 +18,7 @@ pub use canvas_grid::*;
 pub use right_sidebar::*;
 pub use widget_configuration_panel::*;
 pub use activity_sidebar::*;
+pub use import_wizard::*;
 
 // Note: LeftSidebar has been moved to src/features/dashboard/components/sidebar/
 // This improves code organization and reduces coupling
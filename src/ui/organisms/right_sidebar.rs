@@ -139,6 +139,10 @@ pub fn RightSidebar(
             crate::features::dashboard::models::WidgetType::Candlestick => "{\"animation\":true,\"show_values\":true,\"bar_width\":0.6}",
             crate::features::dashboard::models::WidgetType::Heatmap => "{\"animation\":true,\"show_values\":true,\"cell_size\":20}",
             crate::features::dashboard::models::WidgetType::Treemap => "{\"animation\":true,\"show_labels\":true,\"leaf_depth\":1}",
+            crate::features::dashboard::models::WidgetType::FilterDropdown
+            | crate::features::dashboard::models::WidgetType::FilterDateRange
+            | crate::features::dashboard::models::WidgetType::FilterRange
+            | crate::features::dashboard::models::WidgetType::FilterSearch => "{}",
         };
 
         // Count existing widgets of this type to generate sequential number
@@ -159,6 +163,7 @@ pub fn RightSidebar(
             widget_type,
             chart_config: ChartConfig {
                 chart_type: Some(widget_type),
+                dataset_id: None,
                 data_mapping: DataMapping::default(),
                 style_options: default_style.to_string(),
             },